@@ -787,6 +787,15 @@ def external_crates_repository(name, cargo_lockfile, lockfile, sanitizers_enable
                 version = "^0.20.0",
                 features = [
                     "metrics",
+                    "rt-tokio",
+                    "trace",
+                ],
+            ),
+            "opentelemetry-otlp": crate.spec(
+                version = "^0.13.0",
+                features = [
+                    "grpc-tonic",
+                    "trace",
                 ],
             ),
             "opentelemetry-prometheus": crate.spec(
@@ -1030,6 +1039,9 @@ def external_crates_repository(name, cargo_lockfile, lockfile, sanitizers_enable
             "scraper": crate.spec(
                 version = "^0.17.1",
             ),
+            "scrypt": crate.spec(
+                version = "^0.11.0",
+            ),
             "semver": crate.spec(
                 version = "^1.0.9",
                 features = [
@@ -91,6 +91,7 @@ function read_nns_variables() {
     while IFS="=" read -r key value; do
         case "$key" in
             "nns_url") nns_url="${value}" ;;
+            "node_reward_type") node_reward_type="${value}" ;;
         esac
     done <"$1"
 }
@@ -205,6 +206,9 @@ IPV6_ADDRESS="${IPV6_ADDRESS:-$(get_if_address_retries 6 ${INTERFACE} 12)}"
 IPV4_ADDRESS="${ipv4_address:-}"
 IPV4_GATEWAY="${ipv4_gateway:-}"
 NNS_URL="${nns_url:-http://[::1]:8080}"
+# Default is null (None)
+NODE_REWARD_TYPE="${node_reward_type:+\"${node_reward_type}\"}"
+NODE_REWARD_TYPE="${NODE_REWARD_TYPE:-null}"
 NODE_INDEX="${node_index:-0}"
 # Default value is 24h
 BACKUP_RETENTION_TIME_SECS="${backup_retention_time_secs:-86400}"
@@ -240,6 +244,7 @@ sed -e "s@{{ ipv6_address }}@${IPV6_ADDRESS}@" \
     -e "s@{{ ipv4_address }}@${IPV4_ADDRESS}@" \
     -e "s@{{ ipv4_gateway }}@${IPV4_GATEWAY}@" \
     -e "s@{{ nns_url }}@${NNS_URL}@" \
+    -e "s@{{ node_reward_type }}@${NODE_REWARD_TYPE}@" \
     -e "s@{{ node_index }}@${NODE_INDEX}@" \
     -e "s@{{ backup_retention_time_secs }}@${BACKUP_RETENTION_TIME_SECS}@" \
     -e "s@{{ backup_purging_interval_secs }}@${BACKUP_PURGING_INTERVAL_SECS}@" \
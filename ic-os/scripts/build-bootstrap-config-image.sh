@@ -74,6 +74,10 @@ options may be specified:
   --nns_public_key path
     NNS public key file.
 
+  --node_reward_type type
+    Node reward type of the node, as in the rewardable nodes of its node
+    operator (e.g. "type3.1"). It is included in the node's registration.
+
   --accounts_ssh_authorized_keys path
     Should point to a directory with files containing the authorized ssh
     keys for specific user accounts on the machine. The name of the
@@ -188,6 +192,9 @@ function build_ic_bootstrap_tar() {
             --nns_public_key)
                 NNS_PUBLIC_KEY="$2"
                 ;;
+            --node_reward_type)
+                NODE_REWARD_TYPE="$2"
+                ;;
             --accounts_ssh_authorized_keys)
                 ACCOUNTS_SSH_AUTHORIZED_KEYS="$2"
                 ;;
@@ -255,6 +262,9 @@ EOF
     if [ "${NNS_URL}" != "" ]; then
         echo "nns_url=${NNS_URL}" >"${BOOTSTRAP_TMPDIR}/nns.conf"
     fi
+    if [ "${NODE_REWARD_TYPE}" != "" ]; then
+        echo "node_reward_type=${NODE_REWARD_TYPE}" >>"${BOOTSTRAP_TMPDIR}/nns.conf"
+    fi
     if [ "${BACKUP_RETENTION_TIME_SECS}" != "" ] || [ "${BACKUP_PURGING_INTERVAL_SECS}" != "" ]; then
         echo "backup_retention_time_secs=${BACKUP_RETENTION_TIME_SECS}" >"${BOOTSTRAP_TMPDIR}/backup.conf"
         echo "backup_puging_interval_secs=${BACKUP_PURGING_INTERVAL_SECS}" >>"${BOOTSTRAP_TMPDIR}/backup.conf"
@@ -0,0 +1,477 @@
+//! Analysis of the chain of blocks in a consensus pool or in the backup
+//! written by [`crate::backup`], to help investigating subnet slowdowns.
+//!
+//! For every height, the analysis reports the rank and size of the notarized
+//! (or finalized) block, how long it took to notarize and finalize it, and
+//! which nodes did not contribute to the aggregated notarization and
+//! finalization signatures.
+//!
+//! Latencies are computed from the times the artifacts were added to the pool
+//! or, for backups, from the modification times of the backup files. The
+//! latter are only accurate up to the batching of backup writes.
+
+use ic_interfaces::consensus_pool::{PoolSection, ValidatedConsensusArtifact};
+use ic_protobuf::types::v1 as pb;
+use ic_types::{
+    consensus::{
+        Block, BlockProposal, ConsensusMessageHashable, Finalization, HasHeight, Notarization,
+    },
+    crypto::CryptoHashOf,
+    CountBytes, Height, NodeId, Time,
+};
+use prost::Message;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// An artifact and the time it was observed, if known.
+#[derive(Clone, Debug)]
+pub struct Timed<T> {
+    pub artifact: T,
+    pub timestamp: Option<Time>,
+}
+
+/// The artifacts of the chain that are subject to the analysis.
+#[derive(Clone, Debug, Default)]
+pub struct ChainArtifacts {
+    pub block_proposals: Vec<Timed<BlockProposal>>,
+    pub notarizations: Vec<Timed<Notarization>>,
+    pub finalizations: Vec<Timed<Finalization>>,
+}
+
+impl ChainArtifacts {
+    /// Collects the artifacts of a pool section with their insertion times.
+    pub fn from_pool(pool: &dyn PoolSection<ValidatedConsensusArtifact>) -> Self {
+        fn timed<T: ConsensusMessageHashable>(
+            pool: &dyn PoolSection<ValidatedConsensusArtifact>,
+            artifacts: Box<dyn Iterator<Item = T>>,
+        ) -> Vec<Timed<T>> {
+            artifacts
+                .map(|artifact| Timed {
+                    timestamp: pool.get_timestamp(&artifact.get_id()),
+                    artifact,
+                })
+                .collect()
+        }
+        Self {
+            block_proposals: timed(pool, pool.block_proposal().get_all()),
+            notarizations: timed(pool, pool.notarization().get_all()),
+            finalizations: timed(pool, pool.finalization().get_all()),
+        }
+    }
+
+    /// Collects the artifacts of the backup below `dir`, which may be the root
+    /// of the backup or the directory of a subnet, replica version or height.
+    pub fn from_backup(dir: &Path) -> io::Result<Self> {
+        let mut artifacts = Self::default();
+        artifacts.read_backup_dir(dir)?;
+        Ok(artifacts)
+    }
+
+    fn read_backup_dir(&mut self, dir: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                self.read_backup_dir(&path)?;
+                continue;
+            }
+            let timestamp = entry
+                .metadata()?
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(
+                    |since_epoch| Time::from_nanos_since_unix_epoch(since_epoch.as_nanos() as u64),
+                );
+            match entry.file_name().to_str() {
+                Some("block_proposal.bin") => self.block_proposals.push(Timed {
+                    artifact: decode::<pb::BlockProposal, _>(&path)?,
+                    timestamp,
+                }),
+                Some("notarization.bin") => self.notarizations.push(Timed {
+                    artifact: decode::<pb::Notarization, _>(&path)?,
+                    timestamp,
+                }),
+                Some("finalization.bin") => self.finalizations.push(Timed {
+                    artifact: decode::<pb::Finalization, _>(&path)?,
+                    timestamp,
+                }),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the protobuf `P` from `path` and converts it into `T`.
+fn decode<P, T>(path: &Path) -> io::Result<T>
+where
+    P: Message + Default,
+    T: TryFrom<P>,
+    T::Error: std::fmt::Display,
+{
+    let invalid_data = |err: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), err),
+        )
+    };
+    let proto =
+        P::decode(fs::read(path)?.as_slice()).map_err(|err| invalid_data(err.to_string()))?;
+    T::try_from(proto).map_err(|err| invalid_data(err.to_string()))
+}
+
+/// The statistics of a single height.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct HeightStats {
+    pub height: u64,
+    /// The number of block proposals seen at this height.
+    pub block_proposals: usize,
+    pub notarized: bool,
+    pub finalized: bool,
+    /// The rank of the finalized block, or of a notarized block if there is
+    /// no finalization.
+    pub rank: Option<u64>,
+    pub block_hash: Option<String>,
+    /// The time in the block's validation context.
+    pub block_time_ms: Option<u64>,
+    /// The size of the encoded block proposal.
+    pub block_size_bytes: Option<usize>,
+    pub ingress_messages: Option<usize>,
+    pub ingress_bytes: Option<usize>,
+    /// Time from observing the block proposal to observing its notarization.
+    pub notarization_latency_ms: Option<u64>,
+    /// Time from observing the block proposal to observing its finalization.
+    pub finalization_latency_ms: Option<u64>,
+    /// Time since the finalization of the previous height was observed.
+    pub finalization_interval_ms: Option<u64>,
+    /// Committee members that did not sign the notarization.
+    pub missing_notarization_signers: Vec<String>,
+    /// Committee members that did not sign the finalization.
+    pub missing_finalization_signers: Vec<String>,
+}
+
+fn millis_between(from: Option<Time>, to: Option<Time>) -> Option<u64> {
+    Some(
+        to?.as_millis_since_unix_epoch()
+            .saturating_sub(from?.as_millis_since_unix_epoch()),
+    )
+}
+
+fn missing_signers(committee: &BTreeSet<NodeId>, signers: &[NodeId]) -> Vec<String> {
+    committee
+        .iter()
+        .filter(|node_id| !signers.contains(node_id))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Computes the statistics of every height with a block proposal, a
+/// notarization or a finalization.
+///
+/// Missing signers are reported relative to `committee` or, if it is not
+/// given, to the set of all nodes that signed any notarization or finalization.
+pub fn analyze(
+    artifacts: &ChainArtifacts,
+    committee: Option<BTreeSet<NodeId>>,
+) -> Vec<HeightStats> {
+    let committee = committee.unwrap_or_else(|| {
+        let notarization_signers = artifacts
+            .notarizations
+            .iter()
+            .flat_map(|n| n.artifact.signature.signers.iter());
+        let finalization_signers = artifacts
+            .finalizations
+            .iter()
+            .flat_map(|f| f.artifact.signature.signers.iter());
+        notarization_signers
+            .chain(finalization_signers)
+            .cloned()
+            .collect()
+    });
+
+    #[derive(Default)]
+    struct AtHeight<'a> {
+        block_proposals: Vec<&'a Timed<BlockProposal>>,
+        notarizations: Vec<&'a Timed<Notarization>>,
+        finalization: Option<&'a Timed<Finalization>>,
+    }
+    let mut heights: BTreeMap<Height, AtHeight> = BTreeMap::new();
+    for proposal in &artifacts.block_proposals {
+        heights
+            .entry(proposal.artifact.height())
+            .or_default()
+            .block_proposals
+            .push(proposal);
+    }
+    for notarization in &artifacts.notarizations {
+        heights
+            .entry(notarization.artifact.height())
+            .or_default()
+            .notarizations
+            .push(notarization);
+    }
+    for finalization in &artifacts.finalizations {
+        heights
+            .entry(finalization.artifact.height())
+            .or_default()
+            .finalization = Some(finalization);
+    }
+
+    let mut previous_finalization: Option<(Height, Option<Time>)> = None;
+    let mut stats = Vec::with_capacity(heights.len());
+    for (height, at_height) in heights {
+        let finalized_hash = at_height
+            .finalization
+            .map(|f| f.artifact.content.block.clone());
+        let notarization = at_height
+            .notarizations
+            .iter()
+            .find(|n| Some(&n.artifact.content.block) == finalized_hash.as_ref())
+            .or_else(|| at_height.notarizations.first())
+            .copied();
+        let block_hash: Option<CryptoHashOf<Block>> =
+            finalized_hash.or_else(|| notarization.map(|n| n.artifact.content.block.clone()));
+        let proposal = at_height
+            .block_proposals
+            .iter()
+            .find(|p| Some(p.artifact.content.get_hash()) == block_hash.as_ref())
+            .copied();
+
+        let mut height_stats = HeightStats {
+            height: height.get(),
+            block_proposals: at_height.block_proposals.len(),
+            notarized: notarization.is_some(),
+            finalized: at_height.finalization.is_some(),
+            block_hash: block_hash.map(|hash| hex::encode(&hash.get_ref().0)),
+            ..HeightStats::default()
+        };
+        if let Some(proposal) = proposal {
+            let block = proposal.artifact.as_ref();
+            height_stats.rank = Some(block.rank.0);
+            height_stats.block_time_ms = Some(block.context.time.as_millis_since_unix_epoch());
+            height_stats.block_size_bytes =
+                Some(pb::BlockProposal::from(&proposal.artifact).encoded_len());
+            let payload = block.payload.as_ref();
+            if !payload.is_summary() {
+                let ingress = &payload.as_data().batch.ingress;
+                height_stats.ingress_messages = Some(ingress.message_count());
+                height_stats.ingress_bytes = Some(ingress.count_bytes());
+            }
+        }
+        let proposal_timestamp = proposal.and_then(|p| p.timestamp);
+        if let Some(notarization) = notarization {
+            height_stats.notarization_latency_ms =
+                millis_between(proposal_timestamp, notarization.timestamp);
+            height_stats.missing_notarization_signers =
+                missing_signers(&committee, &notarization.artifact.signature.signers);
+        }
+        if let Some(finalization) = at_height.finalization {
+            height_stats.finalization_latency_ms =
+                millis_between(proposal_timestamp, finalization.timestamp);
+            height_stats.missing_finalization_signers =
+                missing_signers(&committee, &finalization.artifact.signature.signers);
+            if let Some((previous_height, previous_timestamp)) = previous_finalization {
+                if previous_height.increment() == height {
+                    height_stats.finalization_interval_ms =
+                        millis_between(previous_timestamp, finalization.timestamp);
+                }
+            }
+            previous_finalization = Some((height, finalization.timestamp));
+        }
+        stats.push(height_stats);
+    }
+    stats
+}
+
+/// Writes `stats` as CSV with a header line. Lists of nodes are separated by
+/// semicolons.
+pub fn write_csv<W: Write>(stats: &[HeightStats], mut writer: W) -> io::Result<()> {
+    fn opt<T: ToString>(value: &Option<T>) -> String {
+        value.as_ref().map(ToString::to_string).unwrap_or_default()
+    }
+    writeln!(
+        writer,
+        "height,block_proposals,notarized,finalized,rank,block_hash,block_time_ms,\
+         block_size_bytes,ingress_messages,ingress_bytes,notarization_latency_ms,\
+         finalization_latency_ms,finalization_interval_ms,missing_notarization_signers,\
+         missing_finalization_signers"
+    )?;
+    for s in stats {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            s.height,
+            s.block_proposals,
+            s.notarized,
+            s.finalized,
+            opt(&s.rank),
+            opt(&s.block_hash),
+            opt(&s.block_time_ms),
+            opt(&s.block_size_bytes),
+            opt(&s.ingress_messages),
+            opt(&s.ingress_bytes),
+            opt(&s.notarization_latency_ms),
+            opt(&s.finalization_latency_ms),
+            opt(&s.finalization_interval_ms),
+            s.missing_notarization_signers.join(";"),
+            s.missing_finalization_signers.join(";"),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::BackupArtifact;
+    use ic_test_utilities::{consensus::fake::*, mock_time, types::ids::node_test_id};
+    use ic_types::{
+        batch::ValidationContext,
+        consensus::{dkg, FinalizationContent, NotarizationContent, Payload, Rank},
+        crypto::{crypto_hash, CombinedMultiSig, CombinedMultiSigOf, CryptoHash},
+        signature::MultiSignature,
+        RegistryVersion,
+    };
+    use std::time::Duration;
+
+    fn block_proposal(height: u64, rank: u64) -> BlockProposal {
+        BlockProposal::fake(
+            Block::new(
+                CryptoHashOf::from(CryptoHash(vec![rank as u8])),
+                Payload::new(crypto_hash, (dkg::Summary::fake(), None).into()),
+                Height::from(height),
+                Rank(rank),
+                ValidationContext {
+                    registry_version: RegistryVersion::from(1),
+                    certified_height: Height::from(0),
+                    time: mock_time(),
+                },
+            ),
+            node_test_id(rank),
+        )
+    }
+
+    fn multi_signature<T>(signers: &[u64]) -> MultiSignature<T> {
+        MultiSignature {
+            signature: CombinedMultiSigOf::new(CombinedMultiSig(vec![])),
+            signers: signers.iter().copied().map(node_test_id).collect(),
+        }
+    }
+
+    fn notarization(proposal: &BlockProposal, signers: &[u64]) -> Notarization {
+        Notarization {
+            content: NotarizationContent::new(
+                proposal.height(),
+                proposal.content.get_hash().clone(),
+            ),
+            signature: multi_signature(signers),
+        }
+    }
+
+    fn finalization(proposal: &BlockProposal, signers: &[u64]) -> Finalization {
+        Finalization {
+            content: FinalizationContent::new(
+                proposal.height(),
+                proposal.content.get_hash().clone(),
+            ),
+            signature: multi_signature(signers),
+        }
+    }
+
+    fn at<T>(artifact: T, millis: u64) -> Timed<T> {
+        Timed {
+            artifact,
+            timestamp: Some(mock_time() + Duration::from_millis(millis)),
+        }
+    }
+
+    #[test]
+    fn analysis_reports_rank_latencies_and_missing_signers() {
+        let proposal_1 = block_proposal(1, 0);
+        let proposal_2_rank_0 = block_proposal(2, 0);
+        let proposal_2_rank_1 = block_proposal(2, 1);
+        let artifacts = ChainArtifacts {
+            block_proposals: vec![
+                at(proposal_1.clone(), 0),
+                at(proposal_2_rank_0, 1000),
+                at(proposal_2_rank_1.clone(), 1500),
+            ],
+            notarizations: vec![
+                at(notarization(&proposal_1, &[0, 1, 2, 3]), 300),
+                at(notarization(&proposal_2_rank_1, &[0, 1, 2]), 1800),
+            ],
+            finalizations: vec![
+                at(finalization(&proposal_1, &[0, 1, 3]), 500),
+                at(finalization(&proposal_2_rank_1, &[0, 2, 3]), 2500),
+            ],
+        };
+
+        let stats = analyze(&artifacts, None);
+        assert_eq!(stats.len(), 2);
+
+        assert_eq!(stats[0].height, 1);
+        assert_eq!(stats[0].rank, Some(0));
+        assert_eq!(stats[0].notarization_latency_ms, Some(300));
+        assert_eq!(stats[0].finalization_latency_ms, Some(500));
+        assert_eq!(stats[0].finalization_interval_ms, None);
+        assert!(stats[0].missing_notarization_signers.is_empty());
+        assert_eq!(
+            stats[0].missing_finalization_signers,
+            vec![node_test_id(2).to_string()]
+        );
+
+        assert_eq!(stats[1].height, 2);
+        assert_eq!(stats[1].block_proposals, 2);
+        assert_eq!(stats[1].rank, Some(1));
+        assert_eq!(stats[1].notarization_latency_ms, Some(300));
+        assert_eq!(stats[1].finalization_latency_ms, Some(1000));
+        assert_eq!(stats[1].finalization_interval_ms, Some(2000));
+        assert_eq!(
+            stats[1].missing_notarization_signers,
+            vec![node_test_id(3).to_string()]
+        );
+        assert_eq!(
+            stats[1].missing_finalization_signers,
+            vec![node_test_id(1).to_string()]
+        );
+    }
+
+    #[test]
+    fn backup_artifacts_are_read_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        let proposal = block_proposal(7, 2);
+        let subnet_dir = dir.path().join("subnet").join("version");
+        for artifact in [
+            BackupArtifact::BlockProposal(Box::new(proposal.clone())),
+            BackupArtifact::Notarization(Box::new(notarization(&proposal, &[0, 1]))),
+            BackupArtifact::Finalization(Box::new(finalization(&proposal, &[1]))),
+        ] {
+            artifact.write_to_disk(&subnet_dir).unwrap();
+        }
+
+        let artifacts = ChainArtifacts::from_backup(dir.path()).unwrap();
+        let stats = analyze(&artifacts, None);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].height, 7);
+        assert_eq!(stats[0].rank, Some(2));
+        assert!(stats[0].finalized);
+        assert!(stats[0].notarization_latency_ms.is_some());
+        assert_eq!(
+            stats[0].missing_finalization_signers,
+            vec![node_test_id(0).to_string()]
+        );
+
+        let mut csv = vec![];
+        write_csv(&stats, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.lines().nth(1).unwrap().starts_with("7,1,true,true,2,"));
+    }
+}
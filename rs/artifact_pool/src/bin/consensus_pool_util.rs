@@ -1,5 +1,6 @@
 use clap::{arg, Arg, Command};
 use ic_artifact_pool::{
+    analytics::{analyze, write_csv, ChainArtifacts},
     certification_pool::CertificationPoolImpl,
     consensus_pool::{PoolSectionOps, UncachedConsensusPoolImpl},
 };
@@ -16,10 +17,12 @@ use prost::Message;
 use serde::{Deserialize, Serialize};
 use serde_bytes_repr::{ByteFmtDeserializer, ByteFmtSerializer};
 use serde_json::{Deserializer, Serializer};
+use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::io::BufRead;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 fn main() {
     let mut app = Command::new("ic-consensus-pool-util")
@@ -51,6 +54,40 @@ fn main() {
                         .takes_value(true),
                 ),
         )
+        .subcommand(
+            Command::new("analyze")
+                .about(
+                    "Report per-height block rank, notarization/finalization latency, \
+                     missing signers and block sizes",
+                )
+                .arg(
+                    Arg::new("backup")
+                        .long("backup")
+                        .help("PATH is a backup directory rather than a consensus pool"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format")
+                        .possible_values(["csv", "json"])
+                        .default_value("csv")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("committee")
+                        .long("committee")
+                        .value_name("NODE_ID")
+                        .help(
+                            "Nodes expected to sign notarizations and finalizations \
+                             (defaults to all nodes that signed any)",
+                        )
+                        .multiple_occurrences(true)
+                        .multiple_values(true)
+                        .takes_value(true),
+                ),
+        )
         .arg(arg!(<PATH>       "PATH to the consensus pool directory"));
     let mut help = Vec::new();
     app.write_help(&mut help)
@@ -65,6 +102,8 @@ fn main() {
         import(path)
     } else if let Some(matches) = matches.subcommand_matches("export-cup-proto") {
         export_cup_proto(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("analyze") {
+        analyze_chain(path, matches)
     } else {
         eprintln!(
             "{}",
@@ -274,3 +313,29 @@ fn export_cup_proto(path: &str, matches: &clap::ArgMatches) {
     file.write_all(&buf)
         .unwrap_or_else(|err| panic!("Cannot write to file {}: {:?}", filename, err));
 }
+
+fn analyze_chain(path: &str, matches: &clap::ArgMatches) {
+    let artifacts = if matches.is_present("backup") {
+        ChainArtifacts::from_backup(Path::new(path))
+            .unwrap_or_else(|err| panic!("Cannot read backup {}: {}", path, err))
+    } else {
+        let consensus_pool = open_consensus_pool(path, true);
+        ChainArtifacts::from_pool(consensus_pool.validated())
+    };
+    let committee = matches.values_of("committee").map(|node_ids| {
+        node_ids
+            .map(|node_id| {
+                PrincipalId::from_str(node_id)
+                    .map(NodeId::from)
+                    .unwrap_or_else(|err| panic!("Invalid node id {}: {}", node_id, err))
+            })
+            .collect::<BTreeSet<_>>()
+    });
+    let stats = analyze(&artifacts, committee);
+    let stdout = std::io::stdout();
+    match matches.value_of("format") {
+        Some("json") => serde_json::to_writer_pretty(stdout.lock(), &stats)
+            .expect("Failed to write JSON to stdout"),
+        _ => write_csv(&stats, stdout.lock()).expect("Failed to write CSV to stdout"),
+    }
+}
@@ -1,3 +1,4 @@
+pub mod analytics;
 pub mod canister_http_pool;
 pub mod certification_pool;
 pub mod consensus_pool;
@@ -1,5 +1,6 @@
 use crate::{
-    notification_client::NotificationClient,
+    cold_storage::{packed_state_name, ColdStorageKind, SubnetColdStorage},
+    notification_client::{NotificationClient, NotificationEvent},
     util::{block_on, sleep_secs},
 };
 use chrono::{DateTime, Utc};
@@ -14,7 +15,7 @@ use slog::{debug, error, info, warn, Logger};
 use std::{
     collections::BTreeMap,
     ffi::OsStr,
-    fs::{create_dir_all, read_dir, remove_dir_all, DirEntry, File},
+    fs::{create_dir_all, read_dir, remove_dir_all, remove_file, DirEntry, File},
     io::Write,
     net::IpAddr,
     path::{Path, PathBuf},
@@ -41,7 +42,7 @@ pub(crate) struct BackupHelper {
     pub(crate) downloads_guard: Arc<Mutex<bool>>,
     pub(crate) hot_disk_resource_threshold_percentage: u32,
     pub(crate) cold_disk_resource_threshold_percentage: u32,
-    pub(crate) cold_storage_dir: PathBuf,
+    pub(crate) cold_storage: SubnetColdStorage,
     pub(crate) versions_hot: usize,
     pub(crate) artifacts_guard: Mutex<bool>,
     pub(crate) daily_replays: usize,
@@ -54,6 +55,7 @@ pub(crate) struct BackupHelper {
 enum ReplayResult {
     Done,
     UpgradeRequired(ReplicaVersion),
+    Diverged(u64),
 }
 
 enum DiskStats {
@@ -110,20 +112,6 @@ impl BackupHelper {
         create_if_not_exists(self.root_dir.join(format!("work_dir/{}", self.subnet_id)))
     }
 
-    fn cold_storage_artifacts_dir(&self) -> PathBuf {
-        create_if_not_exists(
-            self.cold_storage_dir
-                .join(format!("{}/artifacts", self.subnet_id)),
-        )
-    }
-
-    fn cold_storage_states_dir(&self) -> PathBuf {
-        create_if_not_exists(
-            self.cold_storage_dir
-                .join(format!("{}/states", self.subnet_id)),
-        )
-    }
-
     fn trash_dir(&self) -> PathBuf {
         create_if_not_exists(self.root_dir.join("trash"))
     }
@@ -215,7 +203,10 @@ impl BackupHelper {
         }
         // Without the binaries we can't replay...
         self.notification_client
-            .report_failure_slack(format!("Couldn't download: {}", binary_name));
+            .report(NotificationEvent::ReplayFailed {
+                height: self.last_state_checkpoint(),
+                reason: format!("Couldn't download: {}", binary_name),
+            });
         Err(format!(
             "Binary {} is required for the replica {}",
             binary_name, replica_version
@@ -287,7 +278,9 @@ impl BackupHelper {
         }
         warn!(self.log, "Didn't sync any config from host: {}", node_ip);
         self.notification_client
-            .report_failure_slack("Couldn't pull ic.json5 from the nodes!".to_string());
+            .report(NotificationEvent::SyncFailed {
+                reason: "Couldn't pull ic.json5 from the nodes!".to_string(),
+            });
     }
 
     fn rsync_remote_cmd(
@@ -327,7 +320,13 @@ impl BackupHelper {
             self.notification_client.push_metrics_sync_time(minutes);
         } else {
             self.notification_client
-                .report_failure_slack("Couldn't pull artifacts from the nodes!".to_string());
+                .report(NotificationEvent::SyncFailed {
+                    reason: format!(
+                        "Couldn't pull artifacts from the nodes! Succeeded: {}/{}",
+                        total_succeeded,
+                        nodes.len()
+                    ),
+                });
         }
     }
 
@@ -396,14 +395,20 @@ impl BackupHelper {
             match self.replay_current_version(&current_replica_version) {
                 Ok(ReplayResult::UpgradeRequired(upgrade_version)) => {
                     // replayed the current version, but if there is upgrade try to do it again
-                    self.notification_client.message_slack(format!(
-                        "Replica version upgrade detected (current: {} new: {}): \
-                        upgrading the ic-replay tool to retry... 🤞",
-                        current_replica_version, upgrade_version
-                    ));
+                    self.notification_client
+                        .report(NotificationEvent::ReplicaVersionUpgraded {
+                            from: current_replica_version.to_string(),
+                            to: upgrade_version.to_string(),
+                        });
                     current_replica_version = upgrade_version;
                 }
-                Ok(_) => break,
+                Ok(ReplayResult::Diverged(height)) => {
+                    self.notification_client
+                        .report(NotificationEvent::ReplayDiverged { height });
+                    // a diverged state must not be archived
+                    return;
+                }
+                Ok(ReplayResult::Done) => break,
                 Err(err) => {
                     error!(self.log, "[#{}] Error replaying: {}", self.thread_id, err);
                     break;
@@ -416,10 +421,10 @@ impl BackupHelper {
             info!(self.log, "[#{}] Replay was successful!", self.thread_id);
 
             if self.archive_state(finish_height).is_ok() {
-                self.notification_client.message_slack(format!(
-                    "✅ Successfully restored the state at height *{}*",
-                    finish_height
-                ));
+                self.notification_client
+                    .report(NotificationEvent::StateRestored {
+                        height: finish_height,
+                    });
                 let duration = start_time.elapsed();
                 let minutes = duration.as_secs() / 60;
                 self.notification_client.push_metrics_replay_time(minutes);
@@ -428,9 +433,11 @@ impl BackupHelper {
             }
         } else {
             warn!(self.log, "[#{}] No progress in the replay!", self.thread_id);
-            self.notification_client.report_failure_slack(
-                "No height progress after the last replay detected!".to_string(),
-            );
+            self.notification_client
+                .report(NotificationEvent::ReplayFailed {
+                    height: start_height,
+                    reason: "No height progress after the last replay detected!".to_string(),
+                });
         }
 
         match self.maybe_cold_store_states() {
@@ -478,12 +485,18 @@ impl BackupHelper {
                 error!(self.log, "[#{}] Error: {}", self.thread_id, e.to_string());
                 if let RecoveryError::CommandError(_, ref out_str) = e {
                     self.dump_log_file(start_height, out_str)?;
+                    if let Some(height) = check_state_divergence(out_str) {
+                        return Ok(ReplayResult::Diverged(height));
+                    }
                 }
                 Err(e.to_string())
             }
             Ok(Some(stdout)) => {
                 self.dump_log_file(start_height, &stdout)?;
 
+                if let Some(height) = check_state_divergence(&stdout) {
+                    return Ok(ReplayResult::Diverged(height));
+                }
                 if let Some(upgrade_version) = self.check_upgrade_request(stdout) {
                     debug!(
                         self.log,
@@ -584,12 +597,14 @@ impl BackupHelper {
                                 DiskStats::Inodes => "inodes",
                                 DiskStats::Space => "space",
                             };
-                            self.notification_client.report_warning_slack(format!(
-                                "[{}] {} usage is at {}%",
-                                dir.to_str().unwrap_or_default(),
-                                resource,
-                                n
-                            ))
+                            self.notification_client.report(
+                                NotificationEvent::DiskThresholdExceeded {
+                                    dir: dir.to_str().unwrap_or_default().to_string(),
+                                    resource: resource.to_string(),
+                                    usage_percentage: n,
+                                    threshold_percentage: threshold,
+                                },
+                            )
                         }
                         Ok(n)
                     } else {
@@ -623,7 +638,10 @@ impl BackupHelper {
         if let Err(e) = exec_cmd(&mut cmd) {
             error!(self.log, "Error: {}", e);
             self.notification_client
-                .report_failure_slack("Couldn't archive the replayed state!".to_string());
+                .report(NotificationEvent::ReplayFailed {
+                    height: last_height,
+                    reason: "Couldn't archive the replayed state!".to_string(),
+                });
             return Err(e.to_string());
         }
         // leave only one archived checkpoint
@@ -660,20 +678,25 @@ impl BackupHelper {
 
     fn log_disk_stats(&self) -> Result<(), String> {
         let mut stats = Vec::new();
-        for (dir, threshold) in &[
-            (&self.root_dir, self.hot_disk_resource_threshold_percentage),
-            (
-                &self.cold_storage_dir,
+        let mut dirs = vec![(
+            self.root_dir.as_path(),
+            self.hot_disk_resource_threshold_percentage,
+        )];
+        // Remote cold storage backends don't use the local disk.
+        if let Some(cold_storage_dir) = self.cold_storage.backend.local_dir() {
+            dirs.push((
+                cold_storage_dir,
                 self.cold_disk_resource_threshold_percentage,
-            ),
-        ] {
-            let space = self.get_disk_stats(dir, *threshold, DiskStats::Space)?;
-            let inodes = self.get_disk_stats(dir, *threshold, DiskStats::Inodes)?;
+            ));
+        }
+        for (dir, threshold) in dirs {
+            let space = self.get_disk_stats(dir, threshold, DiskStats::Space)?;
+            let inodes = self.get_disk_stats(dir, threshold, DiskStats::Inodes)?;
             debug!(
                 self.log,
                 "[{:?}] Space: {}% Inodes: {}%", dir, space, inodes
             );
-            stats.push((dir.as_path(), space, inodes));
+            stats.push((dir, space, inodes));
         }
         self.notification_client
             .push_metrics_disk_stats(stats.as_slice());
@@ -700,6 +723,24 @@ impl BackupHelper {
         Ok(())
     }
 
+    /// Moves the artifacts and states of the subnet which are still kept in the legacy layout
+    /// of the local `cold_storage_dir` into the cold storage.
+    pub fn migrate_legacy_cold_storage(&self, cold_storage_dir: &Path) -> Result<(), String> {
+        let migrated = self.cold_storage.migrate_legacy_layout(
+            &cold_storage_dir.join(self.subnet_id.to_string()),
+            &self.work_dir(),
+        )?;
+        if migrated > 0 {
+            info!(
+                self.log,
+                "Migrated {} files of subnet {:?} from the legacy cold storage layout",
+                migrated,
+                self.subnet_id
+            );
+        }
+        Ok(())
+    }
+
     fn cold_store_artifacts(&self) -> Result<u64, String> {
         let guard = self
             .artifacts_guard
@@ -745,7 +786,6 @@ impl BackupHelper {
 
         if self.do_cold_storage {
             // process moved artifact directories
-            let cold_storage_artifacts_dir = self.cold_storage_artifacts_dir();
             let work_dir_str = work_dir
                 .clone()
                 .into_os_string()
@@ -772,18 +812,19 @@ impl BackupHelper {
                 debug!(self.log, "Will execute: {:?}", cmd);
                 exec_cmd(&mut cmd).map_err(|err| format!("Error packing artifacts: {:?}", err))?;
 
-                info!(self.log, "Copy packed file of {}", replica_version);
-                let mut cmd2 = Command::new("cp");
-                cmd2.arg(packed_file).arg(&cold_storage_artifacts_dir);
-                debug!(self.log, "Will execute: {:?}", cmd2);
-                exec_cmd(&mut cmd2).map_err(|err| format!("Error copying artifacts: {:?}", err))?;
+                info!(self.log, "Upload packed file of {}", replica_version);
+                let manifest = self
+                    .cold_storage
+                    .upload(ColdStorageKind::Artifacts, Path::new(&packed_file))
+                    .map_err(|err| format!("Error uploading artifacts: {}", err))?;
+                self.notification_client
+                    .report(NotificationEvent::ColdStorageMoved {
+                        kind: "artifacts".to_string(),
+                        name: manifest.name,
+                        size_bytes: manifest.size_bytes,
+                        sha256: manifest.sha256,
+                    });
             }
-            ls_path(
-                &self.log,
-                self.cold_storage_dir
-                    .join(format!("{}", self.subnet_id))
-                    .as_path(),
-            )?;
         }
 
         info!(
@@ -866,14 +907,31 @@ impl BackupHelper {
         });
 
         if self.do_cold_storage {
+            let work_dir = self.work_dir();
             let mut reversed = old_state_dirs.iter().rev();
-            while let Some(dir) = reversed.next() {
-                info!(self.log, "Will copy to cold storage: {:?}", dir.1);
-                let mut cmd = Command::new("rsync");
-                cmd.arg("-a");
-                cmd.arg(dir.1).arg(self.cold_storage_states_dir());
+            while let Some((height, dir)) = reversed.next() {
+                info!(self.log, "Will pack and upload to cold storage: {:?}", dir);
+                let packed_file = work_dir.join(packed_state_name(*height));
+                let mut cmd = Command::new("tar");
+                cmd.arg("czf");
+                cmd.arg(&packed_file);
+                cmd.arg("-C").arg(self.archive_dir());
+                cmd.arg(height.to_string());
                 debug!(self.log, "Will execute: {:?}", cmd);
-                exec_cmd(&mut cmd).map_err(|err| format!("Error copying states: {:?}", err))?;
+                exec_cmd(&mut cmd).map_err(|err| format!("Error packing state: {:?}", err))?;
+                let manifest = self
+                    .cold_storage
+                    .upload(ColdStorageKind::States, &packed_file)
+                    .map_err(|err| format!("Error uploading state: {}", err))?;
+                self.notification_client
+                    .report(NotificationEvent::ColdStorageMoved {
+                        kind: "state".to_string(),
+                        name: manifest.name,
+                        size_bytes: manifest.size_bytes,
+                        sha256: manifest.sha256,
+                    });
+                remove_file(&packed_file)
+                    .map_err(|err| format!("Error deleting packed state: {:?}", err))?;
                 // skip some of the states if we replay more than one per day
                 if self.daily_replays > 1 {
                     // one element is consumed in the next() call above,
@@ -881,12 +939,6 @@ impl BackupHelper {
                     reversed.nth(self.daily_replays - 2);
                 }
             }
-            ls_path(
-                &self.log,
-                self.cold_storage_dir
-                    .join(format!("{}", self.subnet_id))
-                    .as_path(),
-            )?;
         }
 
         let trash_dir = self.trash_dir();
@@ -904,6 +956,23 @@ impl BackupHelper {
     }
 }
 
+/// Returns the height of the CUP whose state hash differs from the replayed state, if
+/// `ic-replay` reported one.
+fn check_state_divergence(stdout: &str) -> Option<u64> {
+    let prefix = "The state hash of the CUP at height";
+    let suffix = "differs from the local state's hash";
+    let line = stdout
+        .lines()
+        .find(|line| line.contains(prefix) && line.contains(suffix))?;
+    let height = line[line.find(prefix)? + prefix.len()..line.find(suffix)?].trim();
+    // The height is debug-formatted, e.g. `Height(100)`.
+    height
+        .trim_start_matches("Height(")
+        .trim_end_matches(')')
+        .parse()
+        .ok()
+}
+
 pub fn ls_path(log: &Logger, dir: &Path) -> Result<(), String> {
     let mut cmd = Command::new("ls");
     cmd.arg(dir);
@@ -1045,6 +1114,7 @@ mod tests {
     use ic_types::PrincipalId;
 
     use super::*;
+    use crate::cold_storage::LocalColdStorage;
 
     const FAKE_SUBNET_ID: &str = "gpvux-2ejnk-3hgmh-cegwf-iekfc-b7rzs-hrvep-5euo2-3ywz3-k3hcb-cqe";
 
@@ -1089,6 +1159,14 @@ mod tests {
         assert!(!need_cold_storage_move);
     }
 
+    #[test]
+    fn check_state_divergence_test() {
+        let stdout = "Replaying...\n\
+            The state hash of the CUP at height Height(500) differs from the local state's hash\n";
+        assert_eq!(check_state_divergence(stdout), Some(500));
+        assert_eq!(check_state_divergence("Replay done"), None);
+    }
+
     #[test]
     fn cold_store_artifacts_test() {
         let dir = tmpdir("test_dir");
@@ -1118,16 +1196,16 @@ mod tests {
 
         assert_eq!(max_height, 150);
 
-        let cold_storage_dirs = collect_and_sort_dir_entries(
-            &backup_helper
-                .cold_storage_dir
-                .join(FAKE_SUBNET_ID)
-                .join("artifacts"),
-        );
+        let cold_storage_artifacts = backup_helper
+            .cold_storage
+            .list(ColdStorageKind::Artifacts)
+            .unwrap();
 
         // Only the artifacts from the earliest replica version are moved to the cold storage.
-        assert_eq!(cold_storage_dirs.len(), 1);
-        assert!(cold_storage_dirs[0].ends_with("_000000000150_replica_version_1.tgz"));
+        assert_eq!(cold_storage_artifacts.len(), 1);
+        assert!(cold_storage_artifacts[0]
+            .name
+            .ends_with("_000000000150_replica_version_1.tgz"));
 
         let artifacts_dirs = collect_and_sort_dir_entries(&backup_helper.spool_dir());
 
@@ -1156,17 +1234,11 @@ mod tests {
             .cold_store_states(30)
             .expect("should execute successfully");
 
-        let cold_storage_dirs = collect_and_sort_dir_entries(
-            &backup_helper
-                .cold_storage_dir
-                .join(FAKE_SUBNET_ID)
-                .join("states"),
+        assert_eq!(
+            cold_storage_state_names(&backup_helper),
+            vec!["10.tgz".to_string(), "30.tgz".to_string()]
         );
 
-        assert_eq!(cold_storage_dirs.len(), 2);
-        assert!(cold_storage_dirs.contains(&"30".to_string()));
-        assert!(cold_storage_dirs.contains(&"10".to_string()));
-
         let archives_dirs = collect_and_sort_dir_entries(&backup_helper.archive_dir());
 
         // The artifacts from the earliest replica version are removed from the hot storage.
@@ -1195,15 +1267,11 @@ mod tests {
 
         assert!(cold_stored_states);
 
-        let cold_storage_dirs = collect_and_sort_dir_entries(
-            &backup_helper
-                .cold_storage_dir
-                .join(FAKE_SUBNET_ID)
-                .join("states"),
+        assert_eq!(
+            cold_storage_state_names(&backup_helper),
+            vec!["10.tgz".to_string(), "30.tgz".to_string()]
         );
 
-        assert_eq!(cold_storage_dirs, vec!["10".to_string(), "30".to_string()]);
-
         assert_eq!(
             collect_and_sort_dir_entries(&backup_helper.archive_dir()),
             vec![
@@ -1285,15 +1353,17 @@ mod tests {
             metrics_urls: vec![],
             network_name: "fake_network_name".into(),
             backup_instance: "fake_backup_instance".into(),
-            slack_token: "fake_slack_token".into(),
+            sinks: vec![],
             subnet: "fake_subnet".into(),
             log: ic_recovery::util::make_logger(),
         };
 
+        let subnet_id = PrincipalId::from_str(FAKE_SUBNET_ID)
+            .map(SubnetId::from)
+            .unwrap();
+
         BackupHelper {
-            subnet_id: PrincipalId::from_str(FAKE_SUBNET_ID)
-                .map(SubnetId::from)
-                .unwrap(),
+            subnet_id,
             initial_replica_version: ReplicaVersion::try_from("fake_replica_version").unwrap(),
             root_dir: temp_dir.join("backup"),
             excluded_dirs: vec![],
@@ -1303,7 +1373,11 @@ mod tests {
             downloads_guard: Mutex::new(true).into(),
             hot_disk_resource_threshold_percentage: 75,
             cold_disk_resource_threshold_percentage: 95,
-            cold_storage_dir: temp_dir.join("cold_storage"),
+            cold_storage: SubnetColdStorage {
+                backend: Arc::new(LocalColdStorage::new(temp_dir.join("cold_storage"))),
+                subnet_id,
+                log: ic_recovery::util::make_logger(),
+            },
             versions_hot,
             artifacts_guard: Mutex::new(true),
             daily_replays,
@@ -1314,6 +1388,16 @@ mod tests {
         }
     }
 
+    fn cold_storage_state_names(backup_helper: &BackupHelper) -> Vec<String> {
+        backup_helper
+            .cold_storage
+            .list(ColdStorageKind::States)
+            .unwrap()
+            .into_iter()
+            .map(|manifest| manifest.name)
+            .collect()
+    }
+
     fn collect_and_sort_dir_entries(dir: &Path) -> Vec<String> {
         let mut dirs = std::fs::read_dir(dir)
             .unwrap()
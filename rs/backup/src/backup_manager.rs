@@ -22,9 +22,10 @@ use tokio::runtime::Handle;
 
 use crate::{
     backup_helper::{retrieve_replica_version_last_replayed, BackupHelper},
-    cmd::BackupArgs,
+    cmd::{BackupArgs, ColdStorageSubCommand},
+    cold_storage::{make_cold_storage_backend, ColdStorageKind, SubnetColdStorage},
     config::{ColdStorage, Config, SubnetConfig},
-    notification_client::NotificationClient,
+    notification_client::{make_notification_sinks, NotificationClient, NotificationEvent},
     util::{block_on, sleep_secs},
 };
 
@@ -49,6 +50,7 @@ pub struct BackupManager {
     pub registry_client: Arc<RegistryClientImpl>,
     pub registry_replicator: Arc<RegistryReplicator>,
     subnet_backups: Vec<SubnetBackup>,
+    cold_storage_dir: PathBuf,
     pub log: Logger,
 }
 
@@ -62,6 +64,7 @@ impl BackupManager {
         let ColdStorage {
             cold_storage_dir,
             versions_hot,
+            backend,
         } = match config.cold_storage {
             Some(cs) => cs,
             None => panic!("Cold storage and cleanup are not configured"),
@@ -107,6 +110,12 @@ impl BackupManager {
             );
         }
 
+        let cold_storage_backend =
+            make_cold_storage_backend(&backend, cold_storage_dir.clone(), log.clone());
+
+        let notification_sinks =
+            make_notification_sinks(&config.notification_sinks, &config.slack_token, &log);
+
         let mut backups = Vec::new();
 
         let downloads = Arc::new(Mutex::new(true));
@@ -120,7 +129,7 @@ impl BackupManager {
                 metrics_urls: config.metrics_urls.clone(),
                 network_name: config.network_name.clone(),
                 backup_instance: config.backup_instance.clone(),
-                slack_token: config.slack_token.clone(),
+                sinks: notification_sinks.clone(),
                 subnet: subnet_config.subnet_id.to_string(),
                 log: subnet_log.clone(),
             };
@@ -140,7 +149,11 @@ impl BackupManager {
                     .hot_disk_resource_threshold_percentage,
                 cold_disk_resource_threshold_percentage: config
                     .cold_disk_resource_threshold_percentage,
-                cold_storage_dir: cold_storage_dir.clone(),
+                cold_storage: SubnetColdStorage {
+                    backend: cold_storage_backend.clone(),
+                    subnet_id: subnet_config.subnet_id,
+                    log: subnet_log.clone(),
+                },
                 versions_hot,
                 artifacts_guard: Mutex::new(true),
                 daily_replays,
@@ -165,6 +178,7 @@ impl BackupManager {
             registry_client,
             registry_replicator, // it will be used as a background task, so keep it
             subnet_backups: backups,
+            cold_storage_dir,
             log,
         }
     }
@@ -178,6 +192,69 @@ impl BackupManager {
         println!("{}", replica_version)
     }
 
+    pub fn cold_storage(
+        log: Logger,
+        config_file: PathBuf,
+        subnet_id: SubnetId,
+        subcmd: ColdStorageSubCommand,
+    ) {
+        let config = Config::load_config(config_file).expect("Config file can't be loaded");
+        let ColdStorage {
+            cold_storage_dir,
+            backend,
+            ..
+        } = config.cold_storage.expect("Cold storage is not configured");
+        let cold_storage = SubnetColdStorage {
+            backend: make_cold_storage_backend(&backend, cold_storage_dir, log.clone()),
+            subnet_id,
+            log: log.clone(),
+        };
+        let work_dir = config
+            .root_dir
+            .join(format!("work_dir/{}_cold_storage", subnet_id));
+        match subcmd {
+            ColdStorageSubCommand::List => {
+                for kind in [ColdStorageKind::Artifacts, ColdStorageKind::States] {
+                    for manifest in cold_storage
+                        .list(kind)
+                        .expect("Cold storage couldn't be listed")
+                    {
+                        println!(
+                            "{:?}\t{}\t{}\t{}\t{}",
+                            kind,
+                            manifest.name,
+                            manifest.size_bytes,
+                            manifest.sha256,
+                            manifest.uploaded_at
+                        );
+                    }
+                }
+            }
+            ColdStorageSubCommand::Verify => {
+                let mut failed = Vec::new();
+                for kind in [ColdStorageKind::Artifacts, ColdStorageKind::States] {
+                    failed.extend(
+                        cold_storage
+                            .verify_all(kind, &work_dir)
+                            .expect("Cold storage couldn't be verified"),
+                    );
+                }
+                if !failed.is_empty() {
+                    panic!("Integrity check failed for: {:?}", failed);
+                }
+                info!(log, "All files in the cold storage are intact");
+            }
+            ColdStorageSubCommand::Restore { height } => {
+                let data_dir = config.root_dir.join(format!("data/{}", subnet_id));
+                let spool_dir = config.root_dir.join("spool").join(subnet_id.to_string());
+                cold_storage
+                    .restore_for_replay(height, &work_dir, &data_dir, &spool_dir)
+                    .expect("Restoring from the cold storage failed");
+            }
+        }
+        let _ = fs::remove_dir_all(work_dir);
+    }
+
     pub fn upgrade(log: Logger, config_file: PathBuf) {
         let config = Config::load_config(config_file.clone()).expect("Config file can't be loaded");
         config
@@ -313,6 +390,7 @@ impl BackupManager {
         config.cold_storage = Some(ColdStorage {
             cold_storage_dir,
             versions_hot,
+            backend: Default::default(),
         });
 
         config
@@ -456,6 +534,25 @@ fn replay_subnets(m: Arc<BackupManager>, thread_id: u32) {
 fn cold_store(m: Arc<BackupManager>) {
     info!(m.log, "Spawned cold storage thread...");
     let size = m.subnet_backups.len();
+    for b in m
+        .subnet_backups
+        .iter()
+        .filter(|b| b.backup_helper.do_cold_storage)
+    {
+        if let Err(err) = b
+            .backup_helper
+            .migrate_legacy_cold_storage(&m.cold_storage_dir)
+        {
+            let msg = format!(
+                "Error migrating the legacy cold storage of subnet {}: {:?}",
+                b.backup_helper.subnet_id, err
+            );
+            error!(m.log, "{}", msg);
+            b.backup_helper
+                .notification_client
+                .report(NotificationEvent::ColdStorageFailed { reason: msg });
+        }
+    }
     loop {
         for i in 0..size {
             let b = &m.subnet_backups[i];
@@ -483,7 +580,7 @@ fn cold_store(m: Arc<BackupManager>) {
                 error!(m.log, "{}", msg);
                 b.backup_helper
                     .notification_client
-                    .report_failure_slack(msg);
+                    .report(NotificationEvent::ColdStorageFailed { reason: msg });
             }
         }
 
@@ -569,6 +666,7 @@ mod tests {
             cold_storage: Some(ColdStorage {
                 cold_storage_dir: fake_cold_storage_path,
                 versions_hot: FAKE_VERSIONS_HOT,
+                backend: Default::default(),
             }),
             slack_token: FAKE_SLACK_TOKEN.to_string(),
             ..serde_json::from_str(&fake_input_config).unwrap()
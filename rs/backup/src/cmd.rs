@@ -42,4 +42,25 @@ pub enum SubCommand {
         /// The ID of the target subnet
         subnet_id: ClapSubnetId,
     },
+    /// Inspect or restore from the cold storage of a subnet
+    ColdStorage {
+        /// The ID of the target subnet
+        subnet_id: ClapSubnetId,
+
+        #[clap(subcommand)]
+        subcmd: ColdStorageSubCommand,
+    },
+}
+
+#[derive(Parser)]
+pub enum ColdStorageSubCommand {
+    /// List all artifacts and states in the cold storage
+    List,
+    /// Download all artifacts and states and verify their integrity
+    Verify,
+    /// Restore the state at the given height and all artifacts above it for replay
+    Restore {
+        /// The height of the archived state
+        height: u64,
+    },
 }
@@ -0,0 +1,921 @@
+//! The cold storage keeps the artifacts and states which are no longer needed in the hot
+//! storage of the backup pod.
+//!
+//! All files are stored content-addressed, i.e. under `objects/<sha256 of the content>`, so
+//! that identical uploads are stored only once and the integrity of every object can be
+//! verified. For every uploaded file, a small JSON manifest is stored under
+//! `<subnet_id>/<kind>/<name>.json`, mapping the human readable name of the file to its
+//! content hash.
+//!
+//! The objects and manifests are stored by a [ColdStorageBackend], which is either a local
+//! directory or a bucket of an S3-compatible object storage (e.g. AWS S3 or MinIO).
+//!
+//! Before, the packed artifacts were kept as `<subnet_id>/artifacts/<name>.tgz` and the
+//! states unpacked as `<subnet_id>/states/<height>/` in the local cold storage directory.
+//! Files in this legacy layout are moved into the cold storage by
+//! [SubnetColdStorage::migrate_legacy_layout].
+use crate::config::{ColdStorageBackendConfig, S3Config};
+use chrono::Utc;
+use ic_crypto_sha2::Sha256;
+use ic_recovery::command_helper::exec_cmd;
+use ic_types::SubnetId;
+use serde::{Deserialize, Serialize};
+use slog::{debug, info, Logger};
+use std::{
+    fs::{copy, create_dir_all, read_dir, remove_dir_all, remove_file, rename, File},
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+};
+
+const OBJECTS_DIR: &str = "objects";
+const MANIFEST_SUFFIX: &str = ".json";
+/// The exit code of the `aws` CLI if the service returned an error.
+const AWS_CLI_SERVICE_ERROR_EXIT_CODE: i32 = 254;
+
+/// A store of opaque objects, addressed by slash-separated keys.
+pub trait ColdStorageBackend: Send + Sync {
+    /// Stores the content of `local_file` under `key`, overwriting any existing object.
+    fn put(&self, local_file: &Path, key: &str) -> Result<(), String>;
+
+    /// Fetches the object stored under `key` into `local_file`.
+    fn get(&self, key: &str, local_file: &Path) -> Result<(), String>;
+
+    /// Returns true iff an object is stored under `key`.
+    fn exists(&self, key: &str) -> Result<bool, String>;
+
+    /// Returns the names of the objects stored directly under the `dir` prefix.
+    fn list(&self, dir: &str) -> Result<Vec<String>, String>;
+
+    /// Returns the directory holding the objects, if they are stored on a local disk.
+    fn local_dir(&self) -> Option<&Path>;
+}
+
+/// Stores the objects in a directory of the local file system.
+pub struct LocalColdStorage {
+    root: PathBuf,
+}
+
+impl LocalColdStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+impl ColdStorageBackend for LocalColdStorage {
+    fn put(&self, local_file: &Path, key: &str) -> Result<(), String> {
+        let target = self.root.join(key);
+        let parent = target
+            .parent()
+            .ok_or_else(|| format!("Invalid cold storage key: {}", key))?;
+        create_dir_all(parent)
+            .map_err(|err| format!("Error creating directory {:?}: {:?}", parent, err))?;
+        // Copy to a temporary file first, so that a crash never leaves a partial object behind.
+        let tmp_file = parent.join(format!(".{}.tmp", rand::random::<u64>()));
+        copy(local_file, &tmp_file)
+            .map_err(|err| format!("Error copying {:?} to cold storage: {:?}", local_file, err))?;
+        rename(&tmp_file, &target).map_err(|err| {
+            let _ = remove_file(&tmp_file);
+            format!("Error moving {:?} to {:?}: {:?}", tmp_file, target, err)
+        })
+    }
+
+    fn get(&self, key: &str, local_file: &Path) -> Result<(), String> {
+        copy(self.root.join(key), local_file)
+            .map(|_| ())
+            .map_err(|err| format!("Error copying {} from cold storage: {:?}", key, err))
+    }
+
+    fn exists(&self, key: &str) -> Result<bool, String> {
+        Ok(self.root.join(key).exists())
+    }
+
+    fn list(&self, dir: &str) -> Result<Vec<String>, String> {
+        let path = self.root.join(dir);
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        let mut names: Vec<_> = read_dir(&path)
+            .map_err(|err| format!("Error reading directory {:?}: {:?}", path, err))?
+            .flatten()
+            .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    fn local_dir(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+/// Stores the objects in a bucket of an S3-compatible object storage, using the `aws` CLI.
+///
+/// Credentials are picked up by the CLI as usual, i.e. from the environment or from the
+/// configured profile.
+pub struct S3ColdStorage {
+    config: S3Config,
+    log: Logger,
+}
+
+impl S3ColdStorage {
+    pub fn new(config: S3Config, log: Logger) -> Self {
+        Self { config, log }
+    }
+
+    fn full_key(&self, key: &str) -> String {
+        let prefix = self.config.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", prefix, key)
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("s3://{}/{}", self.config.bucket, self.full_key(key))
+    }
+
+    fn aws_cmd(&self) -> Command {
+        let mut cmd = Command::new("aws");
+        if let Some(endpoint_url) = &self.config.endpoint_url {
+            cmd.arg("--endpoint-url").arg(endpoint_url.as_str());
+        }
+        if let Some(region) = &self.config.region {
+            cmd.arg("--region").arg(region);
+        }
+        if let Some(profile) = &self.config.profile {
+            cmd.arg("--profile").arg(profile);
+        }
+        cmd
+    }
+
+    fn exec(&self, mut cmd: Command) -> Result<Option<String>, String> {
+        debug!(self.log, "Will execute: {:?}", cmd);
+        exec_cmd(&mut cmd).map_err(|err| err.to_string())
+    }
+}
+
+impl ColdStorageBackend for S3ColdStorage {
+    fn put(&self, local_file: &Path, key: &str) -> Result<(), String> {
+        let mut cmd = self.aws_cmd();
+        cmd.args(["s3", "cp", "--only-show-errors"])
+            .arg(local_file)
+            .arg(self.url(key));
+        self.exec(cmd)
+            .map(|_| ())
+            .map_err(|err| format!("Error uploading {:?} to S3: {}", local_file, err))
+    }
+
+    fn get(&self, key: &str, local_file: &Path) -> Result<(), String> {
+        let mut cmd = self.aws_cmd();
+        cmd.args(["s3", "cp", "--only-show-errors"])
+            .arg(self.url(key))
+            .arg(local_file);
+        self.exec(cmd)
+            .map(|_| ())
+            .map_err(|err| format!("Error downloading {} from S3: {}", key, err))
+    }
+
+    fn exists(&self, key: &str) -> Result<bool, String> {
+        let mut cmd = self.aws_cmd();
+        cmd.args(["s3api", "head-object", "--bucket"])
+            .arg(&self.config.bucket)
+            .arg("--key")
+            .arg(self.full_key(key));
+        debug!(self.log, "Will execute: {:?}", cmd);
+        let output = cmd
+            .output()
+            .map_err(|err| format!("Error executing {:?}: {:?}", cmd, err))?;
+        if output.status.success() {
+            return Ok(true);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match (output.status.code(), aws_error_code(&stderr)) {
+            (Some(AWS_CLI_SERVICE_ERROR_EXIT_CODE), Some("404" | "NoSuchKey")) => Ok(false),
+            (code, _) => Err(format!(
+                "Error checking {} in S3 (exit code {:?}): {}",
+                key,
+                code,
+                stderr.trim()
+            )),
+        }
+    }
+
+    fn list(&self, dir: &str) -> Result<Vec<String>, String> {
+        let prefix = format!("{}/", self.full_key(dir.trim_end_matches('/')));
+        let mut cmd = self.aws_cmd();
+        cmd.args(["s3api", "list-objects-v2", "--bucket"])
+            .arg(&self.config.bucket)
+            .arg("--prefix")
+            .arg(&prefix)
+            .args([
+                "--delimiter",
+                "/",
+                "--query",
+                "Contents[].Key",
+                "--output",
+                "text",
+            ]);
+        let output = self
+            .exec(cmd)
+            .map_err(|err| format!("Error listing {} in S3: {}", dir, err))?;
+        Ok(parse_s3_list_output(&output.unwrap_or_default(), &prefix))
+    }
+
+    fn local_dir(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// Extracts the object names from the textual output of `aws s3api list-objects-v2`, which
+/// lists the full keys separated by whitespace, or `None` if there are no objects.
+fn parse_s3_list_output(output: &str, prefix: &str) -> Vec<String> {
+    let mut names: Vec<_> = output
+        .split_whitespace()
+        .filter(|key| *key != "None")
+        .filter_map(|key| key.strip_prefix(prefix))
+        .map(String::from)
+        .collect();
+    names.sort();
+    names
+}
+
+/// Extracts the error code returned by the service from the error output of the `aws` CLI,
+/// e.g. `404` from `An error occurred (404) when calling the HeadObject operation: Not Found`.
+fn aws_error_code(stderr: &str) -> Option<&str> {
+    let (_, rest) = stderr.split_once("An error occurred (")?;
+    let (code, _) = rest.split_once(')')?;
+    Some(code)
+}
+
+pub fn make_cold_storage_backend(
+    config: &ColdStorageBackendConfig,
+    cold_storage_dir: PathBuf,
+    log: Logger,
+) -> Arc<dyn ColdStorageBackend> {
+    match config {
+        ColdStorageBackendConfig::Local => Arc::new(LocalColdStorage::new(cold_storage_dir)),
+        ColdStorageBackendConfig::S3(s3_config) => {
+            Arc::new(S3ColdStorage::new(s3_config.clone(), log))
+        }
+    }
+}
+
+/// The kinds of files kept in the cold storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColdStorageKind {
+    /// Packed artifacts of a single replica version.
+    Artifacts,
+    /// Packed archived states.
+    States,
+}
+
+impl ColdStorageKind {
+    fn dir_name(&self) -> &'static str {
+        match self {
+            ColdStorageKind::Artifacts => "artifacts",
+            ColdStorageKind::States => "states",
+        }
+    }
+}
+
+/// Returns the name of the packed archive of the state at the given height.
+pub fn packed_state_name(height: u64) -> String {
+    format!("{}.tgz", height)
+}
+
+/// Returns the top height of a packed artifacts archive, named
+/// `<timestamp>_<top_height>_<replica_version>.tgz`.
+fn packed_artifacts_top_height(name: &str) -> Option<u64> {
+    name.split('_').nth(1)?.parse().ok()
+}
+
+/// Describes a file uploaded to the cold storage.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColdStorageManifest {
+    pub name: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub uploaded_at: String,
+}
+
+impl ColdStorageManifest {
+    fn object_key(&self) -> String {
+        format!("{}/{}", OBJECTS_DIR, self.sha256)
+    }
+}
+
+/// The cold storage of a single subnet.
+pub struct SubnetColdStorage {
+    pub backend: Arc<dyn ColdStorageBackend>,
+    pub subnet_id: SubnetId,
+    pub log: Logger,
+}
+
+impl SubnetColdStorage {
+    fn manifests_dir(&self, kind: ColdStorageKind) -> String {
+        format!("{}/{}", self.subnet_id, kind.dir_name())
+    }
+
+    fn manifest_key(&self, kind: ColdStorageKind, name: &str) -> String {
+        format!("{}/{}{}", self.manifests_dir(kind), name, MANIFEST_SUFFIX)
+    }
+
+    /// Uploads `local_file` content-addressed and records it under its file name. The
+    /// content is only uploaded if no identical object is stored yet.
+    pub fn upload(
+        &self,
+        kind: ColdStorageKind,
+        local_file: &Path,
+    ) -> Result<ColdStorageManifest, String> {
+        let name = local_file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| format!("Invalid file name: {:?}", local_file))?
+            .to_string();
+        let (sha256, size_bytes) = hash_file(local_file)?;
+        let manifest = ColdStorageManifest {
+            name,
+            sha256,
+            size_bytes,
+            uploaded_at: Utc::now().to_rfc3339(),
+        };
+
+        let object_key = manifest.object_key();
+        if self.backend.exists(&object_key)? {
+            info!(
+                self.log,
+                "Object {} is already in the cold storage, skipping upload", object_key
+            );
+        } else {
+            info!(
+                self.log,
+                "Uploading {:?} ({} bytes) to the cold storage as {}",
+                local_file,
+                size_bytes,
+                object_key
+            );
+            self.backend.put(local_file, &object_key)?;
+        }
+
+        // The manifest is written last, so that it only ever points to complete objects.
+        let manifest_file =
+            local_file.with_file_name(format!("{}{}", manifest.name, MANIFEST_SUFFIX));
+        let json = serde_json::to_string_pretty(&manifest)
+            .map_err(|err| format!("Error serializing manifest: {:?}", err))?;
+        File::create(&manifest_file)
+            .and_then(|mut file| file.write_all(json.as_bytes()))
+            .map_err(|err| format!("Error writing manifest {:?}: {:?}", manifest_file, err))?;
+        let result = self
+            .backend
+            .put(&manifest_file, &self.manifest_key(kind, &manifest.name));
+        let _ = remove_file(&manifest_file);
+        result.map(|_| manifest)
+    }
+
+    /// Returns the manifests of all files of the given kind, ordered by name.
+    pub fn list(&self, kind: ColdStorageKind) -> Result<Vec<ColdStorageManifest>, String> {
+        self.backend
+            .list(&self.manifests_dir(kind))?
+            .iter()
+            .filter_map(|file_name| file_name.strip_suffix(MANIFEST_SUFFIX))
+            .map(|name| self.manifest(kind, name))
+            .collect()
+    }
+
+    pub fn manifest(
+        &self,
+        kind: ColdStorageKind,
+        name: &str,
+    ) -> Result<ColdStorageManifest, String> {
+        let tmp_file =
+            std::env::temp_dir().join(format!("cold_storage_manifest_{}", rand::random::<u64>()));
+        self.backend
+            .get(&self.manifest_key(kind, name), &tmp_file)?;
+        let manifest = File::open(&tmp_file)
+            .map_err(|err| format!("Error opening manifest of {}: {:?}", name, err))
+            .and_then(|file| {
+                serde_json::from_reader(BufReader::new(file))
+                    .map_err(|err| format!("Error parsing manifest of {}: {:?}", name, err))
+            });
+        let _ = remove_file(&tmp_file);
+        manifest
+    }
+
+    /// Downloads the file with the given name into `target_dir` and verifies that its content
+    /// matches the hash recorded in the manifest. Returns the path of the downloaded file.
+    pub fn download_verified(
+        &self,
+        kind: ColdStorageKind,
+        name: &str,
+        target_dir: &Path,
+    ) -> Result<PathBuf, String> {
+        let manifest = self.manifest(kind, name)?;
+        create_dir_all(target_dir)
+            .map_err(|err| format!("Error creating directory {:?}: {:?}", target_dir, err))?;
+        let local_file = target_dir.join(&manifest.name);
+        self.backend.get(&manifest.object_key(), &local_file)?;
+        let (sha256, size_bytes) = hash_file(&local_file)?;
+        if sha256 != manifest.sha256 || size_bytes != manifest.size_bytes {
+            let _ = remove_file(&local_file);
+            return Err(format!(
+                "Integrity check of {} failed: expected sha256 {} ({} bytes), got {} ({} bytes)",
+                name, manifest.sha256, manifest.size_bytes, sha256, size_bytes
+            ));
+        }
+        Ok(local_file)
+    }
+
+    /// Downloads, verifies and unpacks the archive with the given name into `target_dir`.
+    pub fn restore(
+        &self,
+        kind: ColdStorageKind,
+        name: &str,
+        work_dir: &Path,
+        target_dir: &Path,
+    ) -> Result<(), String> {
+        let packed_file = self.download_verified(kind, name, work_dir)?;
+        create_dir_all(target_dir)
+            .map_err(|err| format!("Error creating directory {:?}: {:?}", target_dir, err))?;
+        let mut cmd = Command::new("tar");
+        cmd.arg("xzf").arg(&packed_file).arg("-C").arg(target_dir);
+        debug!(self.log, "Will execute: {:?}", cmd);
+        let result = exec_cmd(&mut cmd)
+            .map(|_| ())
+            .map_err(|err| format!("Error unpacking {}: {:?}", name, err));
+        let _ = remove_file(&packed_file);
+        result
+    }
+
+    /// Restores the state archived at `height` into `data_dir`, together with all the
+    /// artifacts above that height into `spool_dir`, so that the replay can be resumed.
+    pub fn restore_for_replay(
+        &self,
+        height: u64,
+        work_dir: &Path,
+        data_dir: &Path,
+        spool_dir: &Path,
+    ) -> Result<(), String> {
+        self.restore(
+            ColdStorageKind::States,
+            &packed_state_name(height),
+            work_dir,
+            work_dir,
+        )?;
+        create_dir_all(data_dir)
+            .map_err(|err| format!("Error creating directory {:?}: {:?}", data_dir, err))?;
+        let mut cmd = Command::new("rsync");
+        cmd.arg("-a").arg("--delete");
+        cmd.arg(work_dir.join(format!("{}/", height))).arg(data_dir);
+        debug!(self.log, "Will execute: {:?}", cmd);
+        exec_cmd(&mut cmd).map_err(|err| format!("Error restoring state: {:?}", err))?;
+        remove_dir_all(work_dir.join(height.to_string()))
+            .map_err(|err| format!("Error deleting unpacked state: {:?}", err))?;
+        info!(
+            self.log,
+            "Restored state at height {} to {:?}", height, data_dir
+        );
+
+        for manifest in self.list(ColdStorageKind::Artifacts)? {
+            match packed_artifacts_top_height(&manifest.name) {
+                Some(top_height) if top_height > height => {
+                    self.restore(
+                        ColdStorageKind::Artifacts,
+                        &manifest.name,
+                        work_dir,
+                        spool_dir,
+                    )?;
+                    info!(
+                        self.log,
+                        "Restored artifacts {} to {:?}", manifest.name, spool_dir
+                    );
+                }
+                Some(_) => {}
+                None => info!(self.log, "Skipping unknown archive {}", manifest.name),
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the files of the legacy layout in `legacy_dir`, the directory of the subnet in
+    /// the local cold storage directory, into the cold storage. The states are packed first.
+    /// Every file is deleted from the legacy layout as soon as it is uploaded, so that an
+    /// interrupted migration is resumed by the next call. Returns the number of migrated
+    /// files.
+    pub fn migrate_legacy_layout(
+        &self,
+        legacy_dir: &Path,
+        work_dir: &Path,
+    ) -> Result<usize, String> {
+        create_dir_all(work_dir)
+            .map_err(|err| format!("Error creating directory {:?}: {:?}", work_dir, err))?;
+        let mut migrated = 0;
+
+        let artifacts_dir = legacy_dir.join(ColdStorageKind::Artifacts.dir_name());
+        for name in legacy_entries(&artifacts_dir, false)? {
+            if !name.ends_with(".tgz") {
+                continue;
+            }
+            let legacy_file = artifacts_dir.join(&name);
+            info!(self.log, "Migrating legacy artifacts {:?}", legacy_file);
+            // Upload a copy, as the manifest written next to the uploaded file would
+            // otherwise clash with the manifest in the same directory of a local backend.
+            let packed_file = work_dir.join(&name);
+            copy(&legacy_file, &packed_file)
+                .map_err(|err| format!("Error copying {:?}: {:?}", legacy_file, err))?;
+            let result = self.upload(ColdStorageKind::Artifacts, &packed_file);
+            let _ = remove_file(&packed_file);
+            result?;
+            remove_file(&legacy_file)
+                .map_err(|err| format!("Error deleting {:?}: {:?}", legacy_file, err))?;
+            migrated += 1;
+        }
+
+        let states_dir = legacy_dir.join(ColdStorageKind::States.dir_name());
+        for name in legacy_entries(&states_dir, true)? {
+            let Ok(height) = name.parse::<u64>() else {
+                continue;
+            };
+            info!(self.log, "Migrating legacy state at height {}", height);
+            let packed_file = work_dir.join(packed_state_name(height));
+            let mut cmd = Command::new("tar");
+            cmd.arg("czf")
+                .arg(&packed_file)
+                .arg("-C")
+                .arg(&states_dir)
+                .arg(&name);
+            debug!(self.log, "Will execute: {:?}", cmd);
+            let result = exec_cmd(&mut cmd)
+                .map_err(|err| format!("Error packing state: {:?}", err))
+                .and_then(|_| self.upload(ColdStorageKind::States, &packed_file));
+            let _ = remove_file(&packed_file);
+            result?;
+            remove_dir_all(states_dir.join(&name))
+                .map_err(|err| format!("Error deleting legacy state {}: {:?}", height, err))?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    /// Verifies the integrity of all files of the given kind. Returns the names of the files
+    /// which are missing or corrupted.
+    pub fn verify_all(
+        &self,
+        kind: ColdStorageKind,
+        work_dir: &Path,
+    ) -> Result<Vec<String>, String> {
+        let mut failed = vec![];
+        for manifest in self.list(kind)? {
+            match self.download_verified(kind, &manifest.name, work_dir) {
+                Ok(local_file) => {
+                    let _ = remove_file(local_file);
+                }
+                Err(err) => {
+                    info!(self.log, "{}", err);
+                    failed.push(manifest.name);
+                }
+            }
+        }
+        Ok(failed)
+    }
+}
+
+/// Returns the sorted names of the directories (if `dirs`) or files in `dir`, if it exists.
+fn legacy_entries(dir: &Path, dirs: bool) -> Result<Vec<String>, String> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut names: Vec<_> = read_dir(dir)
+        .map_err(|err| format!("Error reading directory {:?}: {:?}", dir, err))?
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_type()
+                .map(|t| if dirs { t.is_dir() } else { t.is_file() })
+                .unwrap_or(false)
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Returns the hex encoded SHA-256 hash and the size of the given file.
+fn hash_file(path: &Path) -> Result<(String, u64), String> {
+    let mut file =
+        File::open(path).map_err(|err| format!("Error opening {:?}: {:?}", path, err))?;
+    let mut hasher = Sha256::new();
+    let size_bytes = std::io::copy(&mut file, &mut hasher)
+        .map_err(|err| format!("Error reading {:?}: {:?}", path, err))?;
+    Ok((hex::encode(hasher.finish()), size_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_tmpdir::tmpdir;
+    use ic_types::PrincipalId;
+
+    fn fake_cold_storage(root: &Path) -> SubnetColdStorage {
+        SubnetColdStorage {
+            backend: Arc::new(LocalColdStorage::new(root.join("cold_storage"))),
+            subnet_id: SubnetId::from(PrincipalId::new_subnet_test_id(1)),
+            log: ic_recovery::util::make_logger(),
+        }
+    }
+
+    fn create_file(path: &Path, content: &str) {
+        create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(path)
+            .unwrap()
+            .write_all(content.as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn identical_content_is_stored_once_test() {
+        let dir = tmpdir("test_dir");
+        let cold_storage = fake_cold_storage(dir.as_ref());
+
+        create_file(&dir.as_ref().join("a/10.tgz"), "same content");
+        create_file(&dir.as_ref().join("b/20.tgz"), "same content");
+
+        let manifest_1 = cold_storage
+            .upload(ColdStorageKind::States, &dir.as_ref().join("a/10.tgz"))
+            .unwrap();
+        let manifest_2 = cold_storage
+            .upload(ColdStorageKind::States, &dir.as_ref().join("b/20.tgz"))
+            .unwrap();
+
+        assert_eq!(manifest_1.sha256, manifest_2.sha256);
+        assert_eq!(manifest_1.size_bytes, 12);
+        assert_eq!(
+            cold_storage.backend.list(OBJECTS_DIR).unwrap(),
+            vec![manifest_1.sha256.clone()]
+        );
+        assert_eq!(
+            cold_storage
+                .list(ColdStorageKind::States)
+                .unwrap()
+                .into_iter()
+                .map(|manifest| manifest.name)
+                .collect::<Vec<_>>(),
+            vec!["10.tgz".to_string(), "20.tgz".to_string()]
+        );
+        assert!(cold_storage
+            .list(ColdStorageKind::Artifacts)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn corrupted_objects_are_detected_test() {
+        let dir = tmpdir("test_dir");
+        let cold_storage = fake_cold_storage(dir.as_ref());
+
+        create_file(&dir.as_ref().join("10.tgz"), "good content");
+        create_file(&dir.as_ref().join("20.tgz"), "other content");
+        let manifest = cold_storage
+            .upload(ColdStorageKind::States, &dir.as_ref().join("10.tgz"))
+            .unwrap();
+        cold_storage
+            .upload(ColdStorageKind::States, &dir.as_ref().join("20.tgz"))
+            .unwrap();
+
+        let work_dir = dir.as_ref().join("work_dir");
+        assert_eq!(
+            cold_storage
+                .verify_all(ColdStorageKind::States, &work_dir)
+                .unwrap(),
+            Vec::<String>::new()
+        );
+
+        create_file(
+            &dir.as_ref()
+                .join("cold_storage")
+                .join(manifest.object_key()),
+            "bad content!",
+        );
+
+        assert_eq!(
+            cold_storage
+                .verify_all(ColdStorageKind::States, &work_dir)
+                .unwrap(),
+            vec!["10.tgz".to_string()]
+        );
+        let err = cold_storage
+            .download_verified(ColdStorageKind::States, "10.tgz", &work_dir)
+            .unwrap_err();
+        assert!(err.contains("Integrity check of 10.tgz failed"));
+        assert!(!work_dir.join("10.tgz").exists());
+    }
+
+    #[test]
+    fn restore_unpacks_archive_test() {
+        let dir = tmpdir("test_dir");
+        let cold_storage = fake_cold_storage(dir.as_ref());
+
+        let state_dir = dir.as_ref().join("archive");
+        create_file(&state_dir.join("30/ic_state/checkpoints/1e/file"), "state");
+        let packed_file = dir.as_ref().join("30.tgz");
+        let mut cmd = Command::new("tar");
+        cmd.arg("czf")
+            .arg(&packed_file)
+            .arg("-C")
+            .arg(&state_dir)
+            .arg("30");
+        exec_cmd(&mut cmd).unwrap();
+        cold_storage
+            .upload(ColdStorageKind::States, &packed_file)
+            .unwrap();
+
+        let target_dir = dir.as_ref().join("restored");
+        cold_storage
+            .restore(
+                ColdStorageKind::States,
+                "30.tgz",
+                &dir.as_ref().join("work_dir"),
+                &target_dir,
+            )
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(target_dir.join("30/ic_state/checkpoints/1e/file")).unwrap(),
+            "state"
+        );
+    }
+
+    #[test]
+    fn legacy_layout_is_migrated_test() {
+        let dir = tmpdir("test_dir");
+        let cold_storage = fake_cold_storage(dir.as_ref());
+        let legacy_dir = dir
+            .as_ref()
+            .join("cold_storage")
+            .join(cold_storage.subnet_id.to_string());
+        let artifacts_name = "1672531200_000000000150_replica_version_1.tgz";
+        create_file(
+            &legacy_dir.join("artifacts").join(artifacts_name),
+            "artifacts",
+        );
+        create_file(
+            &legacy_dir.join("states/30/ic_state/checkpoints/1e/file"),
+            "state",
+        );
+        // Files already in the new layout are left alone.
+        create_file(&dir.as_ref().join("10.tgz"), "new state");
+        cold_storage
+            .upload(ColdStorageKind::States, &dir.as_ref().join("10.tgz"))
+            .unwrap();
+
+        let work_dir = dir.as_ref().join("work_dir");
+        assert_eq!(
+            cold_storage
+                .migrate_legacy_layout(&legacy_dir, &work_dir)
+                .unwrap(),
+            2
+        );
+
+        assert!(!legacy_dir.join("artifacts").join(artifacts_name).exists());
+        assert!(!legacy_dir.join("states/30").exists());
+        let names = |kind| {
+            cold_storage
+                .list(kind)
+                .unwrap()
+                .into_iter()
+                .map(|manifest| manifest.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(ColdStorageKind::Artifacts),
+            vec![artifacts_name.to_string()]
+        );
+        assert_eq!(
+            names(ColdStorageKind::States),
+            vec!["10.tgz".to_string(), "30.tgz".to_string()]
+        );
+
+        let target_dir = dir.as_ref().join("restored");
+        cold_storage
+            .restore(ColdStorageKind::States, "30.tgz", &work_dir, &target_dir)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(target_dir.join("30/ic_state/checkpoints/1e/file")).unwrap(),
+            "state"
+        );
+
+        // There is nothing left to migrate.
+        assert_eq!(
+            cold_storage
+                .migrate_legacy_layout(&legacy_dir, &work_dir)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn aws_error_code_test() {
+        assert_eq!(
+            aws_error_code(
+                "\nAn error occurred (404) when calling the HeadObject operation: Not Found\n"
+            ),
+            Some("404")
+        );
+        assert_eq!(
+            aws_error_code(
+                "An error occurred (AccessDenied) when calling the HeadObject operation: Forbidden"
+            ),
+            Some("AccessDenied")
+        );
+        assert_eq!(
+            aws_error_code("Could not connect to the endpoint URL"),
+            None
+        );
+    }
+
+    /// Runs against a live S3-compatible object storage, e.g. a local MinIO server started
+    /// with `docker run -p 9000:9000 minio/minio server /data`. It needs the `aws` CLI with
+    /// credentials for the server, an existing bucket, and the environment variables
+    /// `COLD_STORAGE_TEST_S3_ENDPOINT` and `COLD_STORAGE_TEST_S3_BUCKET`.
+    #[test]
+    #[ignore] // requires an S3-compatible object storage
+    fn s3_cold_storage_test() {
+        let endpoint_url = std::env::var("COLD_STORAGE_TEST_S3_ENDPOINT")
+            .expect("COLD_STORAGE_TEST_S3_ENDPOINT is not set");
+        let bucket = std::env::var("COLD_STORAGE_TEST_S3_BUCKET")
+            .expect("COLD_STORAGE_TEST_S3_BUCKET is not set");
+        let dir = tmpdir("test_dir");
+        let log = ic_recovery::util::make_logger();
+        let cold_storage = SubnetColdStorage {
+            backend: Arc::new(S3ColdStorage::new(
+                S3Config {
+                    bucket,
+                    prefix: format!("test_{}", rand::random::<u64>()),
+                    endpoint_url: Some(endpoint_url.parse().unwrap()),
+                    ..Default::default()
+                },
+                log.clone(),
+            )),
+            subnet_id: SubnetId::from(PrincipalId::new_subnet_test_id(1)),
+            log,
+        };
+
+        assert!(!cold_storage.backend.exists("objects/missing").unwrap());
+
+        create_file(&dir.as_ref().join("a/10.tgz"), "same content");
+        create_file(&dir.as_ref().join("b/20.tgz"), "same content");
+        let manifest = cold_storage
+            .upload(ColdStorageKind::States, &dir.as_ref().join("a/10.tgz"))
+            .unwrap();
+        cold_storage
+            .upload(ColdStorageKind::States, &dir.as_ref().join("b/20.tgz"))
+            .unwrap();
+
+        assert!(cold_storage.backend.exists(&manifest.object_key()).unwrap());
+        assert_eq!(
+            cold_storage.backend.list(OBJECTS_DIR).unwrap(),
+            vec![manifest.sha256.clone()]
+        );
+        assert_eq!(
+            cold_storage
+                .list(ColdStorageKind::States)
+                .unwrap()
+                .into_iter()
+                .map(|manifest| manifest.name)
+                .collect::<Vec<_>>(),
+            vec!["10.tgz".to_string(), "20.tgz".to_string()]
+        );
+        assert_eq!(
+            cold_storage
+                .verify_all(ColdStorageKind::States, &dir.as_ref().join("work_dir"))
+                .unwrap(),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn packed_artifacts_top_height_test() {
+        assert_eq!(
+            packed_artifacts_top_height("1672531200_000000000150_replica_version_1.tgz"),
+            Some(150)
+        );
+        assert_eq!(packed_artifacts_top_height("10.tgz"), None);
+    }
+
+    #[test]
+    fn parse_s3_list_output_test() {
+        let prefix = "backup/subnet/states/";
+        assert_eq!(
+            parse_s3_list_output(
+                "backup/subnet/states/20.tgz.json\tbackup/subnet/states/10.tgz.json\n",
+                prefix
+            ),
+            vec!["10.tgz.json".to_string(), "20.tgz.json".to_string()]
+        );
+        assert!(parse_s3_list_output("None\n", prefix).is_empty());
+    }
+}
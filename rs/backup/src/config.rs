@@ -1,7 +1,7 @@
 use ic_config::{ConfigSource, ConfigValidate};
 use ic_types::{ReplicaVersion, SubnetId};
 use serde::{Deserialize, Serialize};
-use std::{fs::File, io::Write, net::IpAddr, path::PathBuf};
+use std::{collections::BTreeMap, fs::File, io::Write, net::IpAddr, path::PathBuf};
 use url::Url;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -21,8 +21,62 @@ pub struct SubnetConfig {
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ColdStorage {
+    /// The root directory of the local cold storage backend.
     pub cold_storage_dir: PathBuf,
     pub versions_hot: usize,
+    #[serde(default)]
+    pub backend: ColdStorageBackendConfig,
+}
+
+/// Where the cold storage keeps its objects.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ColdStorageBackendConfig {
+    /// In the `cold_storage_dir` on the local disk.
+    #[default]
+    Local,
+    /// In a bucket of an S3-compatible object storage.
+    S3(S3Config),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct S3Config {
+    pub bucket: String,
+    /// The prefix of all keys in the bucket, e.g. the name of the backup instance.
+    #[serde(default)]
+    pub prefix: String,
+    /// The endpoint of the object storage, if it is not AWS S3 (e.g. a MinIO server).
+    #[serde(default)]
+    pub endpoint_url: Option<Url>,
+    #[serde(default)]
+    pub region: Option<String>,
+    /// The profile of the `aws` CLI holding the credentials.
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+/// Where the notifications about backup events are sent to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationSinkConfig {
+    /// The Slack channel of the backup pods, using the `slack_token`.
+    Slack,
+    /// POST every event as a JSON object to the given URL.
+    Webhook {
+        url: Url,
+        /// Additional HTTP headers, e.g. for authorization.
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+    /// Append every event as a line of JSON to the given file, or print it to stdout.
+    File {
+        #[serde(default)]
+        path: Option<PathBuf>,
+    },
+}
+
+fn default_notification_sinks() -> Vec<NotificationSinkConfig> {
+    vec![NotificationSinkConfig::Slack]
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -39,6 +93,8 @@ pub struct Config {
     pub hot_disk_resource_threshold_percentage: u32,
     pub cold_disk_resource_threshold_percentage: u32,
     pub slack_token: String,
+    #[serde(default = "default_notification_sinks")]
+    pub notification_sinks: Vec<NotificationSinkConfig>,
     pub cold_storage: Option<ColdStorage>,
     pub blacklisted_nodes: Option<Vec<IpAddr>>,
     pub subnets: Vec<SubnetConfig>,
@@ -61,6 +117,22 @@ impl ConfigValidate for Config {
         if self.cold_disk_resource_threshold_percentage >= 100 {
             return Err("Cold disk threshold warning should be below 100%".to_string());
         }
+        if let Some(ColdStorage {
+            backend: ColdStorageBackendConfig::S3(s3_config),
+            ..
+        }) = &self.cold_storage
+        {
+            if s3_config.bucket.is_empty() {
+                return Err("S3 cold storage requires a bucket".to_string());
+            }
+        }
+        for sink in &self.notification_sinks {
+            if let NotificationSinkConfig::File { path: Some(path) } = sink {
+                if path.is_dir() {
+                    return Err(format!("Notification file {:?} is a directory", path));
+                }
+            }
+        }
         // we accept no subnets in the config at the initial stage only
         if self.subnets.is_empty() && self.slack_token != "<INSERT SLACK TOKEN>" {
             return Err("No subnet configured for backup!".to_string());
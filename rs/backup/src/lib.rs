@@ -1,6 +1,7 @@
 pub mod backup_helper;
 pub mod backup_manager;
 pub mod cmd;
+pub mod cold_storage;
 pub mod config;
 pub mod notification_client;
 pub mod util;
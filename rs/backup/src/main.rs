@@ -27,9 +27,24 @@ use tokio::{runtime::Handle, task::spawn_blocking};
 //     "hot_disk_resource_threshold_percentage": 75,
 //     "cold_disk_resource_threshold_percentage": 95,
 //     "slack_token": "ABCD1234",
+//     "notification_sinks": [
+//         { "type": "slack" },
+//         {
+//             "type": "webhook",
+//             "url": "https://incidents.example.com/api/events",
+//             "headers": { "Authorization": "Bearer XYZ" }
+//         },
+//         { "type": "file", "path": "/var/log/ic-backup/events.jsonl" }
+//     ],
 //     "cold_storage": {
 //         "cold_storage_dir": "/var/cold_storage",
-//         "versions_hot": 2
+//         "versions_hot": 2,
+//         "backend": {
+//             "type": "s3",
+//             "bucket": "ic-backup",
+//             "prefix": "mainnet",
+//             "endpoint_url": "https://s3.eu-central-1.amazonaws.com"
+//         }
 //     },
 //     "subnets": [
 //       {
@@ -80,6 +95,9 @@ async fn main() {
             Some(SubCommand::GetReplicaVersion { subnet_id }) => {
                 BackupManager::get_version(log, args.config_file, subnet_id.0)
             }
+            Some(SubCommand::ColdStorage { subnet_id, subcmd }) => {
+                BackupManager::cold_storage(log, args.config_file, subnet_id.0, subcmd)
+            }
             _ => {
                 let bm = BackupManager::new(log, args, &rt);
                 Arc::new(bm).do_backups();
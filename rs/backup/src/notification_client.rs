@@ -1,60 +1,317 @@
-use std::path::Path;
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
-use crate::util::block_on;
-use slog::{error, info, Logger};
+use crate::{config::NotificationSinkConfig, util::block_on};
+use chrono::Utc;
+use serde::Serialize;
+use slog::{error, info, warn, Logger};
 use std::time::Duration;
 use url::Url;
 
-pub struct NotificationClient {
-    pub push_metrics: bool,
-    pub metrics_urls: Vec<Url>,
-    pub network_name: String,
-    pub backup_instance: String,
+/// How urgent a [NotificationEvent] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Failure,
+}
+
+/// The events the backup pod reports to its notification sinks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum NotificationEvent {
+    /// Artifacts or configuration couldn't be pulled from the nodes.
+    SyncFailed { reason: String },
+    /// The replayed state doesn't match the state hash of a CUP.
+    ReplayDiverged { height: u64 },
+    /// The replay couldn't be completed for some other reason.
+    ReplayFailed { height: u64, reason: String },
+    /// A new replica version is used to continue the replay.
+    ReplicaVersionUpgraded { from: String, to: String },
+    /// The replayed state was archived.
+    StateRestored { height: u64 },
+    /// The usage of a disk resource reached the configured threshold.
+    DiskThresholdExceeded {
+        dir: String,
+        resource: String,
+        usage_percentage: u32,
+        threshold_percentage: u32,
+    },
+    /// An archive of artifacts or of a state was moved to the cold storage.
+    ColdStorageMoved {
+        kind: String,
+        name: String,
+        size_bytes: u64,
+        sha256: String,
+    },
+    /// Moving to the cold storage failed.
+    ColdStorageFailed { reason: String },
+}
+
+impl NotificationEvent {
+    pub fn severity(&self) -> Severity {
+        match self {
+            NotificationEvent::SyncFailed { .. }
+            | NotificationEvent::ReplayDiverged { .. }
+            | NotificationEvent::ReplayFailed { .. }
+            | NotificationEvent::ColdStorageFailed { .. } => Severity::Failure,
+            NotificationEvent::DiskThresholdExceeded { .. } => Severity::Warning,
+            NotificationEvent::ReplicaVersionUpgraded { .. }
+            | NotificationEvent::StateRestored { .. }
+            | NotificationEvent::ColdStorageMoved { .. } => Severity::Info,
+        }
+    }
+
+    /// A human readable description of the event.
+    pub fn message(&self) -> String {
+        match self {
+            NotificationEvent::SyncFailed { reason } => reason.clone(),
+            NotificationEvent::ReplayDiverged { height } => {
+                format!("The replayed state diverged at height *{}*!", height)
+            }
+            NotificationEvent::ReplayFailed { height, reason } => {
+                format!("Replay from height {} failed: {}", height, reason)
+            }
+            NotificationEvent::ReplicaVersionUpgraded { from, to } => format!(
+                "Replica version upgrade detected (current: {} new: {}): \
+                upgrading the ic-replay tool to retry... 🤞",
+                from, to
+            ),
+            NotificationEvent::StateRestored { height } => {
+                format!("✅ Successfully restored the state at height *{}*", height)
+            }
+            NotificationEvent::DiskThresholdExceeded {
+                dir,
+                resource,
+                usage_percentage,
+                ..
+            } => format!("[{}] {} usage is at {}%", dir, resource, usage_percentage),
+            NotificationEvent::ColdStorageMoved {
+                kind,
+                name,
+                size_bytes,
+                ..
+            } => format!(
+                "Moved {} {} ({} bytes) to the cold storage",
+                kind, name, size_bytes
+            ),
+            NotificationEvent::ColdStorageFailed { reason } => reason.clone(),
+        }
+    }
+}
+
+/// A [NotificationEvent] together with the context of the backup pod reporting it.
+#[derive(Clone, Debug, Serialize)]
+pub struct NotificationRecord<'a> {
+    pub timestamp: String,
+    pub network: &'a str,
+    pub backup_instance: &'a str,
+    pub subnet: &'a str,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(flatten)]
+    pub event: &'a NotificationEvent,
+}
+
+/// A destination of notifications, e.g. a chat channel or an incident management system.
+pub trait NotificationSink: Send + Sync {
+    fn notify(&self, record: &NotificationRecord);
+}
+
+/// Posts a message to the Slack channel of the backup pods.
+pub struct SlackSink {
     pub slack_token: String,
-    pub subnet: String,
     pub log: Logger,
 }
 
-impl NotificationClient {
-    fn http_post_request(&self, url: String, content_type: String, data_str: String) {
+impl NotificationSink for SlackSink {
+    fn notify(&self, record: &NotificationRecord) {
+        let url = format!(
+            "https://hooks.slack.com/services/T43F9UHS5/B027BHAQ1HQ/{}",
+            self.slack_token
+        );
+        let data_str = serde_json::json!({ "text": slack_text(record) }).to_string();
+        let content_type = "Content-type: application/json".to_string();
+        http_post_request(&self.log, url, content_type, data_str)
+    }
+}
+
+fn slack_text(record: &NotificationRecord) -> String {
+    let message = match record.severity {
+        Severity::Info => record.message.clone(),
+        Severity::Warning => format!("⚠️ {}", record.message),
+        Severity::Failure => format!("<!channel> ❌ {}", record.message),
+    };
+    format!(
+        "[{}, *{}*] {}",
+        record.backup_instance,
+        record.subnet.get(0..5).unwrap_or(record.subnet),
+        message
+    )
+}
+
+/// Posts every record as a JSON object to an arbitrary URL.
+pub struct WebhookSink {
+    pub url: Url,
+    pub headers: BTreeMap<String, String>,
+    pub log: Logger,
+}
+
+impl NotificationSink for WebhookSink {
+    fn notify(&self, record: &NotificationRecord) {
+        let data_str = match serde_json::to_string(record) {
+            Ok(data_str) => data_str,
+            Err(err) => {
+                error!(self.log, "Error serializing notification: {}", err);
+                return;
+            }
+        };
         block_on(async {
             let client = reqwest::Client::new();
-            match client
-                .post(url)
+            let mut request = client
+                .post(self.url.clone())
                 .timeout(Duration::from_secs(60))
-                .header(reqwest::header::CONTENT_TYPE, content_type)
-                .body(data_str)
-                .send()
-                .await
-            {
+                .header(reqwest::header::CONTENT_TYPE, "application/json");
+            for (name, value) in &self.headers {
+                request = request.header(name, value);
+            }
+            match request.body(data_str).send().await {
+                Ok(response) if !response.status().is_success() => error!(
+                    self.log,
+                    "Webhook {} responded with: {}",
+                    self.url,
+                    response.status()
+                ),
                 Ok(_) => {}
                 Err(err) => error!(self.log, "Http POST failed: {}", err),
             }
         });
     }
+}
 
-    pub fn message_slack(&self, message: String) {
-        info!(self.log, "{}", message);
-        let url = format!(
-            "https://hooks.slack.com/services/T43F9UHS5/B027BHAQ1HQ/{}",
-            self.slack_token
-        );
-        let data_str = format!(
-            "{{\"text\":\"[{}, *{}*] {}\"}}",
-            self.backup_instance,
-            &self.subnet[0..5],
-            message
-        );
-        let content_type = "Content-type: application/json".to_string();
-        self.http_post_request(url, content_type, data_str)
-    }
+/// Appends every record as a line of JSON to a file, or prints it to stdout.
+pub struct FileSink {
+    pub path: Option<PathBuf>,
+    // Serializes the writes of the subnet threads.
+    pub lock: Mutex<()>,
+    pub log: Logger,
+}
 
-    pub fn report_failure_slack(&self, message: String) {
-        self.message_slack(format!("<!channel> ❌ {}", message))
+impl NotificationSink for FileSink {
+    fn notify(&self, record: &NotificationRecord) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(err) => {
+                error!(self.log, "Error serializing notification: {}", err);
+                return;
+            }
+        };
+        let _guard = self
+            .lock
+            .lock()
+            .expect("notification file mutex lock failed");
+        let path = match &self.path {
+            Some(path) => path,
+            None => {
+                println!("{}", line);
+                return;
+            }
+        };
+        if let Err(err) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+        {
+            error!(
+                self.log,
+                "Error writing notification to {:?}: {}", path, err
+            );
+        }
     }
+}
+
+/// Creates the configured notification sinks, shared by all subnets.
+pub fn make_notification_sinks(
+    configs: &[NotificationSinkConfig],
+    slack_token: &str,
+    log: &Logger,
+) -> Vec<Arc<dyn NotificationSink>> {
+    configs
+        .iter()
+        .map(|config| -> Arc<dyn NotificationSink> {
+            match config {
+                NotificationSinkConfig::Slack => Arc::new(SlackSink {
+                    slack_token: slack_token.to_string(),
+                    log: log.clone(),
+                }),
+                NotificationSinkConfig::Webhook { url, headers } => Arc::new(WebhookSink {
+                    url: url.clone(),
+                    headers: headers.clone(),
+                    log: log.clone(),
+                }),
+                NotificationSinkConfig::File { path } => Arc::new(FileSink {
+                    path: path.clone(),
+                    lock: Mutex::new(()),
+                    log: log.clone(),
+                }),
+            }
+        })
+        .collect()
+}
 
-    pub fn report_warning_slack(&self, message: String) {
-        self.message_slack(format!("⚠️ {}", message))
+fn http_post_request(log: &Logger, url: String, content_type: String, data_str: String) {
+    block_on(async {
+        let client = reqwest::Client::new();
+        match client
+            .post(url)
+            .timeout(Duration::from_secs(60))
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(data_str)
+            .send()
+            .await
+        {
+            Ok(_) => {}
+            Err(err) => error!(log, "Http POST failed: {}", err),
+        }
+    });
+}
+
+pub struct NotificationClient {
+    pub push_metrics: bool,
+    pub metrics_urls: Vec<Url>,
+    pub network_name: String,
+    pub backup_instance: String,
+    pub sinks: Vec<Arc<dyn NotificationSink>>,
+    pub subnet: String,
+    pub log: Logger,
+}
+
+impl NotificationClient {
+    /// Logs the event and forwards it to all notification sinks.
+    pub fn report(&self, event: NotificationEvent) {
+        let record = NotificationRecord {
+            timestamp: Utc::now().to_rfc3339(),
+            network: &self.network_name,
+            backup_instance: &self.backup_instance,
+            subnet: &self.subnet,
+            severity: event.severity(),
+            message: event.message(),
+            event: &event,
+        };
+        match record.severity {
+            Severity::Info => info!(self.log, "{}", record.message),
+            Severity::Warning | Severity::Failure => warn!(self.log, "{}", record.message),
+        }
+        for sink in &self.sinks {
+            sink.notify(&record);
+        }
     }
 
     fn push_metrics(&self, message: String) {
@@ -69,7 +326,7 @@ impl NotificationClient {
                 self.subnet
             );
             let content_type = "Content-type: application/octet-stream".to_string();
-            self.http_post_request(url_str, content_type, message.clone());
+            http_post_request(&self.log, url_str, content_type, message.clone());
         }
     }
 
@@ -162,3 +419,76 @@ impl NotificationClient {
         self.push_metrics(message)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_tmpdir::tmpdir;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn file_sink_appends_json_lines_test() {
+        let dir = tmpdir("test_dir");
+        let path = dir.path().join("notifications.jsonl");
+        let log = ic_recovery::util::make_logger();
+        let client = NotificationClient {
+            push_metrics: false,
+            metrics_urls: vec![],
+            network_name: "fake_network_name".into(),
+            backup_instance: "fake_backup_instance".into(),
+            sinks: make_notification_sinks(
+                &[NotificationSinkConfig::File {
+                    path: Some(path.clone()),
+                }],
+                "fake_slack_token",
+                &log,
+            ),
+            subnet: "fake_subnet".into(),
+            log,
+        };
+
+        client.report(NotificationEvent::ReplayDiverged { height: 100 });
+        client.report(NotificationEvent::DiskThresholdExceeded {
+            dir: "/var/backup".into(),
+            resource: "space".into(),
+            usage_percentage: 80,
+            threshold_percentage: 75,
+        });
+
+        let lines: Vec<serde_json::Value> = read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "replay_diverged");
+        assert_eq!(lines[0]["severity"], "failure");
+        assert_eq!(lines[0]["height"], 100);
+        assert_eq!(lines[0]["subnet"], "fake_subnet");
+        assert_eq!(lines[0]["backup_instance"], "fake_backup_instance");
+        assert_eq!(lines[1]["event"], "disk_threshold_exceeded");
+        assert_eq!(lines[1]["severity"], "warning");
+        assert_eq!(lines[1]["usage_percentage"], 80);
+        assert_eq!(lines[1]["message"], "[/var/backup] space usage is at 80%");
+    }
+
+    #[test]
+    fn slack_text_test() {
+        let event = NotificationEvent::SyncFailed {
+            reason: "Couldn't pull artifacts from the nodes!".into(),
+        };
+        let record = NotificationRecord {
+            timestamp: "".into(),
+            network: "fake_network_name",
+            backup_instance: "fake_backup_instance",
+            subnet: "gpvux-2ejnk",
+            severity: event.severity(),
+            message: event.message(),
+            event: &event,
+        };
+        assert_eq!(
+            slack_text(&record),
+            "[fake_backup_instance, *gpvux*] <!channel> ❌ Couldn't pull artifacts from the nodes!"
+        );
+    }
+}
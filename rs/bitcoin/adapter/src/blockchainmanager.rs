@@ -1,7 +1,9 @@
 use crate::{
     blockchainstate::{AddHeaderError, BlockchainState},
     common::{BlockHeight, MINIMUM_VERSION_NUMBER},
+    dogecoin::{self, AuxPow},
     metrics::RouterMetrics,
+    network::{DogecoinNetwork, Network},
     Channel, Command, ProcessBitcoinNetworkMessageError,
 };
 use bitcoin::{
@@ -373,7 +375,8 @@ impl BlockchainManager {
             }
 
             match maybe_err {
-                Some(AddHeaderError::InvalidHeader(_, _)) => {
+                Some(AddHeaderError::InvalidHeader(_, _))
+                | Some(AddHeaderError::InvalidDogecoinHeader(_, _)) => {
                     return Err(ReceivedHeadersMessageError::ReceivedInvalidHeader)
                 }
                 Some(AddHeaderError::PrevHeaderNotCached(stop_hash)) => {
@@ -404,11 +407,13 @@ impl BlockchainManager {
         Ok(())
     }
 
-    /// This function processes "block" messages received from Bitcoin nodes
+    /// This function processes "block" messages received from Bitcoin nodes.
+    /// Merge-mined Dogecoin blocks come with their AuxPoW, which is cached with the block.
     async fn received_block_message(
         &mut self,
         addr: &SocketAddr,
         block: &Block,
+        aux_pow: Option<&AuxPow>,
     ) -> Result<(), ReceivedBlockMessageError> {
         if !self.peer_info.contains_key(addr) {
             return Err(ReceivedBlockMessageError::UnknownPeer);
@@ -433,7 +438,14 @@ impl BlockchainManager {
             block_hash
         );
 
-        match self.blockchain.lock().await.add_block(block.clone()) {
+        let result = {
+            let mut blockchain = self.blockchain.lock().await;
+            match aux_pow {
+                Some(aux_pow) => blockchain.add_block_with_aux_pow(block.clone(), aux_pow.clone()),
+                None => blockchain.add_block(block.clone()),
+            }
+        };
+        match result {
             Ok(()) => Ok(()),
             Err(err) => {
                 warn!(
@@ -649,15 +661,98 @@ impl BlockchainManager {
                 }
             }
             NetworkMessage::Block(block) => {
-                if self.received_block_message(&addr, block).await.is_err() {
+                if self
+                    .received_block_message(&addr, block, None)
+                    .await
+                    .is_err()
+                {
                     return Err(ProcessBitcoinNetworkMessageError::InvalidMessage);
                 }
             }
+            NetworkMessage::Unknown { command, payload } => {
+                let network = self.blockchain.lock().await.network();
+                if let Network::Dogecoin(network) = network {
+                    self.received_dogecoin_message(
+                        channel,
+                        &addr,
+                        network,
+                        command.as_ref(),
+                        payload,
+                    )
+                    .await?;
+                }
+            }
             _ => {}
         };
         Ok(())
     }
 
+    /// This function processes the Dogecoin "headers" and "block" messages, which the
+    /// stream leaves undecoded as they may carry an AuxPoW. The proof of work of every
+    /// header is checked here, before the headers reach the blockchain state.
+    async fn received_dogecoin_message(
+        &mut self,
+        channel: &mut impl Channel,
+        addr: &SocketAddr,
+        network: DogecoinNetwork,
+        command: &str,
+        payload: &[u8],
+    ) -> Result<(), ProcessBitcoinNetworkMessageError> {
+        match command {
+            "headers" => {
+                let headers = dogecoin::decode_headers(payload)
+                    .map_err(|_| ProcessBitcoinNetworkMessageError::InvalidMessage)?;
+                // Check the size before the (expensive) proof of work.
+                if headers.len() > MAX_HEADERS_SIZE {
+                    return Err(ProcessBitcoinNetworkMessageError::InvalidMessage);
+                }
+                // Hashing up to `MAX_HEADERS_SIZE` headers with scrypt takes long
+                // enough to stall the runtime, so it is done on a blocking thread.
+                let block_headers = tokio::task::spawn_blocking(move || {
+                    headers
+                        .into_iter()
+                        .map(|(header, aux_pow)| {
+                            dogecoin::check_proof_of_work(network, &header, aux_pow.as_ref())
+                                .map(|()| header)
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .await
+                .expect("Checking the proof of work of headers panicked");
+                let block_headers = match block_headers {
+                    Ok(block_headers) => block_headers,
+                    Err(err) => {
+                        warn!(
+                            self.logger,
+                            "Received a header with invalid proof of work from {}: {}", addr, err
+                        );
+                        return Err(ProcessBitcoinNetworkMessageError::InvalidMessage);
+                    }
+                };
+                self.received_headers_message(channel, addr, &block_headers)
+                    .await
+                    .map_err(|_| ProcessBitcoinNetworkMessageError::InvalidMessage)
+            }
+            "block" => {
+                let (block, aux_pow) = dogecoin::decode_block(payload)
+                    .map_err(|_| ProcessBitcoinNetworkMessageError::InvalidMessage)?;
+                if let Err(err) =
+                    dogecoin::check_proof_of_work(network, &block.header, aux_pow.as_ref())
+                {
+                    warn!(
+                        self.logger,
+                        "Received a block with invalid proof of work from {}: {}", addr, err
+                    );
+                    return Err(ProcessBitcoinNetworkMessageError::InvalidMessage);
+                }
+                self.received_block_message(addr, &block, aux_pow.as_ref())
+                    .await
+                    .map_err(|_| ProcessBitcoinNetworkMessageError::InvalidMessage)
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// This heartbeat method is called periodically by the adapter.
     /// This method is used to send messages to Bitcoin peers.
     pub async fn tick(&mut self, channel: &mut impl Channel) {
@@ -1096,7 +1191,7 @@ pub mod test {
 
         // Ensure there is now 1 request.
         let result = blockchain_manager
-            .received_block_message(&peer_addr, &block_1)
+            .received_block_message(&peer_addr, &block_1, None)
             .await;
         assert!(result.is_ok());
         {
@@ -1109,7 +1204,7 @@ pub mod test {
         }
 
         let result = blockchain_manager
-            .received_block_message(&peer_addr, &block_2)
+            .received_block_message(&peer_addr, &block_2, None)
             .await;
         assert!(result.is_ok());
         blockchain_manager.sync_blocks(&mut channel).await;
@@ -1,10 +1,19 @@
 //! The module is responsible for keeping track of the blockchain state.
 //!
-use crate::{common::BlockHeight, config::Config, metrics::BlockchainStateMetrics};
-use bitcoin::{blockdata::constants::genesis_block, Block, BlockHash, BlockHeader, Network};
+use crate::{
+    common::BlockHeight,
+    config::Config,
+    dogecoin::{self, AuxPow},
+    metrics::BlockchainStateMetrics,
+    network::Network,
+};
+use bitcoin::{Block, BlockHash, BlockHeader};
 use ic_btc_validation::{validate_header, HeaderStore, ValidateHeaderError};
 use ic_metrics::MetricsRegistry;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use thiserror::Error;
 
 /// This field contains the datatype used to store "work" of a Bitcoin blockchain
@@ -65,6 +74,9 @@ pub enum AddHeaderError {
     /// (eg: not of the right format)
     #[error("Received an invalid block header: {0}")]
     InvalidHeader(BlockHash, ValidateHeaderError),
+    /// This variant is used when the input header violates the Dogecoin consensus rules.
+    #[error("Received an invalid Dogecoin block header: {0}")]
+    InvalidDogecoinHeader(BlockHash, dogecoin::ValidateHeaderError),
     /// This variant is used when the predecessor of the input header is not part of header_cache.
     #[error("Received a block header where we do not have the previous header in the cache: {0}")]
     PrevHeaderNotCached(BlockHash),
@@ -94,6 +106,11 @@ pub struct BlockchainState {
     /// This field stores a hashmap containing BlockHash and the corresponding Block.
     block_cache: HashMap<BlockHash, Block>,
 
+    /// This field stores the AuxPoW of the merge-mined Dogecoin blocks in `block_cache`.
+    /// The AuxPoW is needed to serve the blocks, but not to validate further headers,
+    /// so it is only kept as long as the block itself.
+    aux_pow_cache: HashMap<BlockHash, AuxPow>,
+
     /// This field contains the known tips of the header cache.
     tips: Vec<Tip>,
 
@@ -106,7 +123,7 @@ impl BlockchainState {
     /// This function is used to create a new BlockChainState object.  
     pub fn new(config: &Config, metrics_registry: &MetricsRegistry) -> Self {
         // Create a header cache and inserting dummy header corresponding the `adapter_genesis_hash`.
-        let genesis_block_header = genesis_block(config.network).header;
+        let genesis_block_header = config.network.genesis_block_header();
         let header_cache = init_cache_with_genesis(genesis_block_header);
         let block_cache = HashMap::new();
         let tips = vec![Tip {
@@ -119,12 +136,18 @@ impl BlockchainState {
             genesis_block_header,
             header_cache,
             block_cache,
+            aux_pow_cache: HashMap::new(),
             tips,
             network: config.network,
             metrics: BlockchainStateMetrics::new(metrics_registry),
         }
     }
 
+    /// Returns the network the state follows.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
     /// Returns the genesis header that the store is initialized with.
     pub fn genesis(&self) -> &BlockHeader {
         &self.genesis_block_header
@@ -179,8 +202,17 @@ impl BlockchainState {
             return Ok(AddHeaderResult::HeaderAlreadyExists(block_hash));
         }
 
-        if let Err(err) = validate_header(&self.network, self, &header) {
-            return Err(AddHeaderError::InvalidHeader(block_hash, err));
+        match self.network {
+            Network::Bitcoin(network) => validate_header(&network, self, &header)
+                .map_err(|err| AddHeaderError::InvalidHeader(block_hash, err))?,
+            Network::Dogecoin(network) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                dogecoin::validate_header(network, self, &header, now)
+                    .map_err(|err| AddHeaderError::InvalidDogecoinHeader(block_hash, err))?
+            }
         }
 
         let parent = self
@@ -248,6 +280,19 @@ impl BlockchainState {
         Ok(())
     }
 
+    /// This method adds a new merge-mined Dogecoin block to the `block_cache`
+    /// and keeps its AuxPoW alongside it.
+    pub fn add_block_with_aux_pow(
+        &mut self,
+        block: Block,
+        aux_pow: AuxPow,
+    ) -> Result<(), AddBlockError> {
+        let block_hash = block.block_hash();
+        self.add_block(block)?;
+        self.aux_pow_cache.insert(block_hash, aux_pow);
+        Ok(())
+    }
+
     /// This method returns the tip header with the highest cumulative work.
     #[allow(clippy::indexing_slicing)]
     pub fn get_active_chain_tip(&self) -> &Tip {
@@ -262,6 +307,7 @@ impl BlockchainState {
     pub fn prune_blocks(&mut self, block_hashes: &[BlockHash]) {
         for block_hash in block_hashes {
             self.block_cache.remove(block_hash);
+            self.aux_pow_cache.remove(block_hash);
         }
     }
 
@@ -321,9 +367,15 @@ impl BlockchainState {
         self.block_cache.get(block_hash)
     }
 
+    /// Returns the AuxPoW of a cached merge-mined Dogecoin block.
+    pub fn get_aux_pow(&self, block_hash: &BlockHash) -> Option<&AuxPow> {
+        self.aux_pow_cache.get(block_hash)
+    }
+
     /// Used when the adapter is shutdown and no longer requires holding on to blocks.
     pub fn clear_blocks(&mut self) {
         self.block_cache = HashMap::new();
+        self.aux_pow_cache = HashMap::new();
     }
 
     /// Returns the current size of the block cache.
@@ -354,6 +406,7 @@ mod test {
 
     use super::*;
     use crate::{common::test_common::TestState, config::test::ConfigBuilder};
+    use bitcoin::Network;
     use ic_btc_adapter_test_utils::{block_1, block_2, generate_header, generate_headers};
     use std::collections::HashSet;
 
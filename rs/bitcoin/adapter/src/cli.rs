@@ -34,6 +34,10 @@ impl Cli {
         // Set the address limits based on the specified network.
         config.address_limits = address_limits(config.network);
 
+        if config.dns_seeds.is_empty() {
+            config.dns_seeds = config.network.default_dns_seeds();
+        }
+
         // Validate proxy URL.
         // Check for general validation errors.
         if let Some(socks_proxy) = &config.socks_proxy {
@@ -55,7 +59,7 @@ impl Cli {
 pub mod test {
     use super::*;
     use crate::config::IncomingSource;
-    use bitcoin::Network;
+    use crate::network::{DogecoinNetwork, Network};
     use std::io::Write;
     use std::path::PathBuf;
     use std::str::FromStr;
@@ -94,6 +98,10 @@ pub mod test {
         "ipv6_only": true    
     }"#;
 
+    const DOGECOIN_CONFIG: &str = r#"{
+        "network": "dogecoin"
+    }"#;
+
     const TESTNET_BAD_SOCKS_CONFIG: &str = r#"{
         "network": "testnet",
         "socks_proxy": "socks5.notaproxy.com"        
@@ -155,7 +163,7 @@ pub mod test {
         };
         let result = cli.get_config();
         let config = result.unwrap();
-        assert_eq!(config.network, Network::Bitcoin);
+        assert_eq!(config.network, Network::Bitcoin(bitcoin::Network::Bitcoin));
         assert_eq!(config.address_limits, (500, 2000));
         assert_eq!(config.dns_seeds.len(), 9);
         assert_eq!(config.socks_proxy, None);
@@ -171,7 +179,7 @@ pub mod test {
         };
         let result = cli.get_config();
         let config = result.unwrap();
-        assert_eq!(config.network, Network::Testnet);
+        assert_eq!(config.network, Network::Bitcoin(bitcoin::Network::Testnet));
         assert_eq!(config.address_limits, (100, 1000));
         assert_eq!(config.dns_seeds.len(), 4);
         assert_eq!(config.socks_proxy, None);
@@ -180,4 +188,21 @@ pub mod test {
             IncomingSource::Path(PathBuf::from("/tmp/ic-btc-adapter.socket"))
         );
     }
+
+    #[test]
+    fn test_cli_get_config_dogecoin_json() {
+        let mut tmpfile = NamedTempFile::new().expect("Failed to create tmp file");
+        writeln!(tmpfile, "{}", DOGECOIN_CONFIG).expect("Failed to write to tmp file");
+        let cli = Cli {
+            config: tmpfile.path().to_owned(),
+        };
+        let config = cli.get_config().unwrap();
+        assert_eq!(config.network, Network::Dogecoin(DogecoinNetwork::Mainnet));
+        assert_eq!(config.address_limits, (500, 2000));
+        assert_eq!(config.network_port(), 22556);
+        assert_eq!(
+            config.dns_seeds,
+            vec!["seed.multidoge.org", "seed2.multidoge.org"]
+        );
+    }
 }
@@ -1,4 +1,5 @@
-use bitcoin::Network;
+use crate::network::{DogecoinNetwork, Network};
+use bitcoin::Network as BitcoinNetwork;
 use ic_config::logger::Config as LoggerConfig;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
@@ -18,9 +19,11 @@ pub enum IncomingSource {
 /// This struct contains configuration options for the BTC Adapter.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
-    /// The type of Bitcoin network we plan to communicate to (e.g. Mainnet, Testnet, etc.).
+    /// The type of network we plan to communicate to (e.g. Bitcoin Mainnet, Bitcoin Testnet,
+    /// Dogecoin Mainnet, etc.).
     pub network: Network,
-    /// A list of DNS seeds for address discovery.
+    /// A list of DNS seeds for address discovery. Dogecoin networks fall back to
+    /// their well-known seeds if the list is empty.
     #[serde(default)]
     pub dns_seeds: Vec<String>,
     /// Addresses of nodes to connect to (in case discovery from seeds is not possible/sufficient)
@@ -60,21 +63,22 @@ fn default_idle_seconds() -> u64 {
 /// based on the provided `Network`.
 pub(crate) fn address_limits(network: Network) -> (usize, usize) {
     match network {
-        Network::Bitcoin => (500, 2000),
-        Network::Testnet => (100, 1000),
-        Network::Signet => (1, 1),
-        Network::Regtest => (1, 1),
+        Network::Bitcoin(BitcoinNetwork::Bitcoin) | Network::Dogecoin(DogecoinNetwork::Mainnet) => {
+            (500, 2000)
+        }
+        Network::Bitcoin(BitcoinNetwork::Testnet) | Network::Dogecoin(DogecoinNetwork::Testnet) => {
+            (100, 1000)
+        }
+        Network::Bitcoin(BitcoinNetwork::Signet)
+        | Network::Bitcoin(BitcoinNetwork::Regtest)
+        | Network::Dogecoin(DogecoinNetwork::Regtest) => (1, 1),
     }
 }
 
 impl Config {
-    /// This function returns the port to use based on the Bitcoin network provided.
+    /// This function returns the port to use based on the network provided.
     pub fn network_port(&self) -> u16 {
-        match self.network {
-            Network::Bitcoin => 8333,
-            Network::Testnet => 18333,
-            _ => 8333,
-        }
+        self.network.default_port()
     }
 }
 
@@ -82,14 +86,14 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             dns_seeds: Default::default(),
-            network: Network::Bitcoin,
+            network: Network::Bitcoin(BitcoinNetwork::Bitcoin),
             socks_proxy: Default::default(),
             nodes: vec![],
             idle_seconds: default_idle_seconds(),
             ipv6_only: false,
             logger: LoggerConfig::default(),
             incoming_source: Default::default(),
-            address_limits: address_limits(Network::Bitcoin), // Address limits used for Bitcoin mainnet
+            address_limits: address_limits(Network::Bitcoin(BitcoinNetwork::Bitcoin)), // Address limits used for Bitcoin mainnet
         }
     }
 }
@@ -121,7 +125,8 @@ pub mod test {
             self
         }
 
-        pub fn with_network(mut self, network: Network) -> Self {
+        pub fn with_network(mut self, network: impl Into<Network>) -> Self {
+            let network = network.into();
             self.config.network = network;
             self.config.address_limits = address_limits(network);
             self
@@ -27,6 +27,7 @@ use crate::{
     config::Config,
     connection::{Connection, ConnectionConfig, ConnectionState, PingState},
     metrics::RouterMetrics,
+    network::Network,
     stream::{StreamConfig, StreamEvent, StreamEventKind},
     Channel, ChannelError, Command, ProcessBitcoinNetworkMessage,
     ProcessBitcoinNetworkMessageError, ProcessEvent,
@@ -82,6 +83,8 @@ pub struct ConnectionManager {
     /// This field is used to provide the magic value to the raw network message.
     /// The magic number is used to identity the type of Bitcoin network being accessed.
     magic: u32,
+    /// This field is used to select the message decoding and the announced protocol version.
+    network: Network,
     /// This field contains the number of connections the connection manager can manage at one time.
     max_connections: usize,
     /// This field contains the number of connections the connection manager must have in order to send messages.
@@ -123,6 +126,7 @@ impl ConnectionManager {
             address_book,
             logger,
             magic: config.network.magic(),
+            network: config.network,
             max_connections,
             min_connections,
             current_height: 0,
@@ -308,6 +312,7 @@ impl ConnectionManager {
             address,
             logger: self.logger.clone(),
             magic: self.magic,
+            network: self.network,
             network_message_receiver,
             socks_proxy: self.socks_proxy.clone(),
             stream_event_sender,
@@ -348,7 +353,7 @@ impl ConnectionManager {
         let receiver = Address::new(addr, ServiceFlags::NETWORK | ServiceFlags::NETWORK_LIMITED);
         let nonce: u64 = self.rng.gen();
         let user_agent = String::from(USER_AGENT);
-        let message = NetworkMessage::Version(VersionMessage::new(
+        let mut version = VersionMessage::new(
             services,
             timestamp as i64,
             receiver,
@@ -357,9 +362,10 @@ impl ConnectionManager {
             user_agent,
             // The height the adapter believes is the active tip.
             self.current_height as i32,
-        ));
+        );
+        version.version = self.network.protocol_version();
 
-        self.send_to(addr, message)
+        self.send_to(addr, NetworkMessage::Version(version))
     }
 
     /// This function is used to send a `verack` message to a specified connection.
@@ -0,0 +1,970 @@
+//! Dogecoin specific parts of the protocol: merge-mined headers (AuxPoW), scrypt
+//! proof of work and the Dogecoin difficulty adjustment.
+//!
+//! Dogecoin reuses the Bitcoin block header layout, but a header whose version
+//! has the AuxPoW flag set is followed on the wire by an [AuxPow] that proves the
+//! work was done on a parent chain (usually Litecoin). The `bitcoin` crate cannot
+//! decode such headers, so `headers` and `block` messages are decoded here.
+use crate::{common::BlockHeight, network::DogecoinNetwork};
+use bitcoin::{
+    consensus::encode::{self, CheckedData, Decodable, Encodable, VarInt},
+    hashes::{hex::FromHex, sha256d, Hash},
+    network::message::{CommandString, NetworkMessage, RawNetworkMessage},
+    util::uint::Uint256,
+    Block, BlockHash, BlockHeader, Transaction, TxMerkleNode,
+};
+use ic_btc_validation::HeaderStore;
+use std::io;
+use thiserror::Error;
+
+/// The chain ID of Dogecoin, encoded in the upper 16 bits of the block version.
+const AUXPOW_CHAIN_ID: i32 = 0x0062;
+
+/// The version bit signalling that a header is followed by an AuxPoW.
+const VERSION_AUXPOW: i32 = 1 << 8;
+
+/// The bit offset of the chain ID in the block version.
+const VERSION_CHAIN_START: u32 = 16;
+
+/// The maximum length of the merkle branch linking the block to the merge-mining root.
+const MAX_BLOCKCHAIN_BRANCH_LENGTH: usize = 30;
+
+/// Marks the start of the merge-mining root in the parent coinbase script.
+const MERGED_MINING_HEADER: [u8; 4] = [0xfa, 0xbe, b'm', b'm'];
+
+/// The maximum offset of the merge-mining root in the parent coinbase script
+/// when the merge-mining header is absent.
+const MAX_LEGACY_ROOT_OFFSET: usize = 20;
+
+/// The target spacing between two blocks, in seconds.
+const TARGET_SPACING: i64 = 60;
+
+/// The retarget timespan before Digishield, in seconds.
+const LEGACY_TARGET_TIMESPAN: i64 = 4 * 60 * 60;
+
+/// The retarget timespan with Digishield, in seconds.
+const DIGISHIELD_TARGET_TIMESPAN: i64 = 60;
+
+/// The height from which the Digishield difficulty adjustment is used.
+const DIGISHIELD_HEIGHT: BlockHeight = 145_000;
+
+/// The height from which testnet allows minimum difficulty blocks again after
+/// the Digishield switch.
+const TESTNET_MIN_DIFFICULTY_HEIGHT: BlockHeight = 157_500;
+
+/// The number of blocks used to compute the median time past.
+const MEDIAN_TIME_SPAN: usize = 11;
+
+/// How far in the future a block timestamp may be, in seconds.
+const MAX_FUTURE_BLOCK_TIME: u64 = 2 * 60 * 60;
+
+/// The errors that may occur when validating a Dogecoin header.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ValidateHeaderError {
+    /// The previous header is not known.
+    #[error("The previous header was not found")]
+    PrevHeaderNotFound,
+    /// A legacy (non merge-mining aware) block was received after AuxPoW activation.
+    #[error("Legacy blocks are not allowed at height {0}")]
+    LegacyBlockNotAllowed(BlockHeight),
+    /// The version of the header does not carry the Dogecoin chain ID.
+    #[error("The header has the wrong chain ID {0}")]
+    WrongChainId(i32),
+    /// The version signals an AuxPoW but none was provided.
+    #[error("The header version signals an AuxPoW but none was provided")]
+    MissingAuxPow,
+    /// An AuxPoW was provided but the version does not signal one.
+    #[error("An AuxPoW was provided but the header version does not signal one")]
+    UnexpectedAuxPow,
+    /// The AuxPoW does not commit to the header.
+    #[error("Invalid AuxPoW: {0}")]
+    InvalidAuxPow(&'static str),
+    /// The target encoded in the header is zero or above the network's limit.
+    #[error("The target is out of range")]
+    TargetOutOfRange,
+    /// The scrypt hash of the (parent) header is above the target.
+    #[error("The header does not satisfy its target")]
+    InsufficientProofOfWork,
+    /// The timestamp is not above the median of the previous blocks.
+    #[error("The timestamp {0} is not above the median time past {1}")]
+    TimestampTooOld(u32, u32),
+    /// The timestamp is too far in the future.
+    #[error("The timestamp {0} is too far in the future")]
+    TimestampTooFarInFuture(u32),
+    /// The header is at the height of a checkpoint but has another hash.
+    #[error("The header does not match the checkpoint at height {0}")]
+    DoesNotMatchCheckpoint(BlockHeight),
+    /// The header forks off the chain below a checkpoint the chain has passed.
+    #[error("The header at height {0} forks off below the checkpoint at height {1}")]
+    ForksBelowCheckpoint(BlockHeight, BlockHeight),
+    /// The header does not use the difficulty required at its height.
+    #[error("The header has difficulty bits {actual:#x} instead of {expected:#x}")]
+    WrongDifficulty {
+        /// The difficulty bits required by the consensus rules.
+        expected: u32,
+        /// The difficulty bits found in the header.
+        actual: u32,
+    },
+}
+
+/// Proof that a Dogecoin block was merge-mined as part of a parent chain block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuxPow {
+    /// The coinbase transaction of the parent block.
+    pub coinbase_tx: Transaction,
+    /// The hash of the parent block. Unused by the consensus rules.
+    pub parent_hash: BlockHash,
+    /// The merkle branch linking the coinbase transaction to the parent block's merkle root.
+    pub coinbase_branch: Vec<TxMerkleNode>,
+    /// The index of the coinbase transaction in the parent block; always 0.
+    pub coinbase_index: i32,
+    /// The merkle branch linking the Dogecoin block hash to the merge-mining root.
+    pub blockchain_branch: Vec<TxMerkleNode>,
+    /// The index of the Dogecoin block hash in the merge-mining tree.
+    pub blockchain_index: i32,
+    /// The header of the parent block, carrying the proof of work.
+    pub parent_block_header: BlockHeader,
+}
+
+impl Encodable for AuxPow {
+    fn consensus_encode<W: io::Write>(&self, mut writer: W) -> Result<usize, io::Error> {
+        let mut len = self.coinbase_tx.consensus_encode(&mut writer)?;
+        len += self.parent_hash.consensus_encode(&mut writer)?;
+        len += self.coinbase_branch.consensus_encode(&mut writer)?;
+        len += self.coinbase_index.consensus_encode(&mut writer)?;
+        len += self.blockchain_branch.consensus_encode(&mut writer)?;
+        len += self.blockchain_index.consensus_encode(&mut writer)?;
+        len += self.parent_block_header.consensus_encode(&mut writer)?;
+        Ok(len)
+    }
+}
+
+impl Decodable for AuxPow {
+    fn consensus_decode<D: io::Read>(mut d: D) -> Result<Self, encode::Error> {
+        Ok(Self {
+            coinbase_tx: Decodable::consensus_decode(&mut d)?,
+            parent_hash: Decodable::consensus_decode(&mut d)?,
+            coinbase_branch: Decodable::consensus_decode(&mut d)?,
+            coinbase_index: Decodable::consensus_decode(&mut d)?,
+            blockchain_branch: Decodable::consensus_decode(&mut d)?,
+            blockchain_index: Decodable::consensus_decode(&mut d)?,
+            parent_block_header: Decodable::consensus_decode(&mut d)?,
+        })
+    }
+}
+
+impl AuxPow {
+    /// Checks that the AuxPoW commits to the block with the given hash and
+    /// chain ID. This mirrors `CAuxPow::check` of the Dogecoin reference client.
+    fn check(&self, block_hash: &BlockHash, chain_id: i32) -> Result<(), ValidateHeaderError> {
+        if self.coinbase_index != 0 {
+            return Err(ValidateHeaderError::InvalidAuxPow(
+                "the parent transaction is not a coinbase",
+            ));
+        }
+        if chain_id_of(self.parent_block_header.version) == chain_id {
+            return Err(ValidateHeaderError::InvalidAuxPow(
+                "the parent block has our chain ID",
+            ));
+        }
+        if self.blockchain_branch.len() > MAX_BLOCKCHAIN_BRANCH_LENGTH {
+            return Err(ValidateHeaderError::InvalidAuxPow(
+                "the blockchain merkle branch is too long",
+            ));
+        }
+
+        let coinbase_root = merkle_root_from_branch(
+            self.coinbase_tx.txid().into_inner(),
+            &self.coinbase_branch,
+            self.coinbase_index,
+        );
+        if coinbase_root != self.parent_block_header.merkle_root.into_inner() {
+            return Err(ValidateHeaderError::InvalidAuxPow(
+                "the coinbase is not part of the parent block",
+            ));
+        }
+
+        // The merge-mining root is stored in the coinbase in display (reversed) order.
+        let mut root = merkle_root_from_branch(
+            block_hash.into_inner(),
+            &self.blockchain_branch,
+            self.blockchain_index,
+        );
+        root.reverse();
+
+        let script = self
+            .coinbase_tx
+            .input
+            .first()
+            .ok_or(ValidateHeaderError::InvalidAuxPow(
+                "the coinbase has no inputs",
+            ))?
+            .script_sig
+            .as_bytes();
+        let root_pos = find(script, &root).ok_or(ValidateHeaderError::InvalidAuxPow(
+            "the merge-mining root is missing from the coinbase",
+        ))?;
+        match find(script, &MERGED_MINING_HEADER) {
+            Some(header_pos) => {
+                if find(&script[header_pos + 1..], &MERGED_MINING_HEADER).is_some() {
+                    return Err(ValidateHeaderError::InvalidAuxPow(
+                        "multiple merge-mining headers in the coinbase",
+                    ));
+                }
+                if header_pos + MERGED_MINING_HEADER.len() != root_pos {
+                    return Err(ValidateHeaderError::InvalidAuxPow(
+                        "the merge-mining header does not precede the root",
+                    ));
+                }
+            }
+            None => {
+                if root_pos > MAX_LEGACY_ROOT_OFFSET {
+                    return Err(ValidateHeaderError::InvalidAuxPow(
+                        "the merge-mining root does not start early in the coinbase",
+                    ));
+                }
+            }
+        }
+
+        // The root is followed by the size of the merge-mining tree and a nonce that
+        // determines where the block must be placed in the tree.
+        let trailer = script
+            .get(root_pos + root.len()..root_pos + root.len() + 8)
+            .ok_or(ValidateHeaderError::InvalidAuxPow(
+                "the merge-mining tree size and nonce are missing",
+            ))?;
+        let size = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let nonce = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        let height = self.blockchain_branch.len() as u32;
+        if size != 1 << height {
+            return Err(ValidateHeaderError::InvalidAuxPow(
+                "the merge-mining tree size does not match the branch",
+            ));
+        }
+        if self.blockchain_index as u32 != expected_index(nonce, chain_id, height) {
+            return Err(ValidateHeaderError::InvalidAuxPow(
+                "the block is at the wrong index of the merge-mining tree",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Computes the merkle root from a leaf and the branch leading to the root.
+fn merkle_root_from_branch(leaf: [u8; 32], branch: &[TxMerkleNode], index: i32) -> [u8; 32] {
+    if index == -1 {
+        return [0; 32];
+    }
+    let mut index = index;
+    let mut hash = leaf;
+    for node in branch {
+        let mut data = [0; 64];
+        if index & 1 == 1 {
+            data[..32].copy_from_slice(node.as_inner());
+            data[32..].copy_from_slice(&hash);
+        } else {
+            data[..32].copy_from_slice(&hash);
+            data[32..].copy_from_slice(node.as_inner());
+        }
+        hash = sha256d::Hash::hash(&data).into_inner();
+        index >>= 1;
+    }
+    hash
+}
+
+/// Returns the index a chain must occupy in a merge-mining tree of the given
+/// height, so that a block cannot be committed to at several positions.
+fn expected_index(nonce: u32, chain_id: i32, height: u32) -> u32 {
+    let mut rand = nonce;
+    rand = rand.wrapping_mul(1103515245).wrapping_add(12345);
+    rand = rand.wrapping_add(chain_id as u32);
+    rand = rand.wrapping_mul(1103515245).wrapping_add(12345);
+    rand % (1 << height)
+}
+
+fn chain_id_of(version: i32) -> i32 {
+    version >> VERSION_CHAIN_START
+}
+
+fn is_aux_pow(header: &BlockHeader) -> bool {
+    header.version & VERSION_AUXPOW != 0
+}
+
+/// Legacy blocks predate merge-mining and carry no chain ID.
+fn is_legacy(header: &BlockHeader) -> bool {
+    header.version == 1 || (header.version == 2 && chain_id_of(header.version) == 0)
+}
+
+/// Decodes a header, followed by its AuxPoW if the version signals one.
+fn decode_header<D: io::Read>(mut d: D) -> Result<(BlockHeader, Option<AuxPow>), encode::Error> {
+    let header = BlockHeader::consensus_decode(&mut d)?;
+    let aux_pow = if is_aux_pow(&header) {
+        Some(AuxPow::consensus_decode(&mut d)?)
+    } else {
+        None
+    };
+    Ok((header, aux_pow))
+}
+
+fn ensure_consumed(cursor: &io::Cursor<&[u8]>) -> Result<(), encode::Error> {
+    if cursor.position() as usize != cursor.get_ref().len() {
+        return Err(encode::Error::ParseFailed(
+            "data not consumed entirely when explicitly deserializing",
+        ));
+    }
+    Ok(())
+}
+
+/// The envelope shared by all messages: magic, command and checksummed payload.
+struct MessageEnvelope {
+    magic: u32,
+    command: CommandString,
+    payload: Vec<u8>,
+}
+
+impl Decodable for MessageEnvelope {
+    fn consensus_decode<D: io::Read>(mut d: D) -> Result<Self, encode::Error> {
+        Ok(Self {
+            magic: Decodable::consensus_decode(&mut d)?,
+            command: Decodable::consensus_decode(&mut d)?,
+            payload: CheckedData::consensus_decode(&mut d)?.0,
+        })
+    }
+}
+
+/// Deserializes a message from the start of `data`, returning it with the number
+/// of bytes consumed. `headers` and `block` messages cannot be decoded by the
+/// `bitcoin` crate when they carry an AuxPoW, so they are returned as
+/// [NetworkMessage::Unknown] and decoded with [decode_headers] and [decode_block].
+pub fn deserialize_partial_message(
+    data: &[u8],
+) -> Result<(RawNetworkMessage, usize), encode::Error> {
+    let (envelope, consumed) = encode::deserialize_partial::<MessageEnvelope>(data)?;
+    match envelope.command.as_ref() {
+        "headers" | "block" => Ok((
+            RawNetworkMessage {
+                magic: envelope.magic,
+                payload: NetworkMessage::Unknown {
+                    command: envelope.command,
+                    payload: envelope.payload,
+                },
+            },
+            consumed,
+        )),
+        _ => encode::deserialize(&data[..consumed]).map(|message| (message, consumed)),
+    }
+}
+
+/// Decodes the payload of a Dogecoin `headers` message. Every header is
+/// followed by its AuxPoW, if any, and an empty transaction count.
+pub fn decode_headers(payload: &[u8]) -> Result<Vec<(BlockHeader, Option<AuxPow>)>, encode::Error> {
+    let mut cursor = io::Cursor::new(payload);
+    let VarInt(count) = VarInt::consensus_decode(&mut cursor)?;
+    let mut headers = vec![];
+    for _ in 0..count {
+        headers.push(decode_header(&mut cursor)?);
+        if VarInt::consensus_decode(&mut cursor)?.0 != 0 {
+            return Err(encode::Error::ParseFailed(
+                "Headers message should not contain transactions",
+            ));
+        }
+    }
+    ensure_consumed(&cursor)?;
+    Ok(headers)
+}
+
+/// Decodes the payload of a Dogecoin `block` message.
+pub fn decode_block(payload: &[u8]) -> Result<(Block, Option<AuxPow>), encode::Error> {
+    let mut cursor = io::Cursor::new(payload);
+    let (header, aux_pow) = decode_header(&mut cursor)?;
+    let txdata = Vec::<Transaction>::consensus_decode(&mut cursor)?;
+    ensure_consumed(&cursor)?;
+    Ok((Block { header, txdata }, aux_pow))
+}
+
+/// Encodes a block the way Dogecoin nodes do, with the AuxPoW following the header.
+pub fn serialize_block(block: &Block, aux_pow: Option<&AuxPow>) -> Vec<u8> {
+    let mut bytes = encode::serialize(&block.header);
+    if let Some(aux_pow) = aux_pow {
+        bytes.extend(encode::serialize(aux_pow));
+    }
+    bytes.extend(encode::serialize(&block.txdata));
+    bytes
+}
+
+/// Returns the highest target allowed on the network.
+fn pow_limit(network: DogecoinNetwork) -> Uint256 {
+    let max = !Uint256::default();
+    match network {
+        DogecoinNetwork::Mainnet | DogecoinNetwork::Testnet => max >> 20,
+        DogecoinNetwork::Regtest => max >> 1,
+    }
+}
+
+/// Returns the height from which blocks must be merge-mining aware.
+fn auxpow_start_height(network: DogecoinNetwork) -> BlockHeight {
+    match network {
+        DogecoinNetwork::Mainnet => 371_337,
+        DogecoinNetwork::Testnet => 158_100,
+        DogecoinNetwork::Regtest => 20,
+    }
+}
+
+/// Returns the heights and hashes of known blocks of the network, taken from
+/// `chainparams.cpp` of the Dogecoin reference client.
+fn checkpoints(network: DogecoinNetwork) -> &'static [(BlockHeight, &'static str)] {
+    match network {
+        DogecoinNetwork::Mainnet => &[
+            (
+                0,
+                "1a91e3dace36e2be3bf030a65679fe821aa1d6ef92e7c9902eb318182c355691",
+            ),
+            (
+                104_679,
+                "35eb87ae90d44b98898fec8c39577b76cb1eb08e1261cfc10706c8ce9a1d01cf",
+            ),
+            (
+                145_000,
+                "cc47cae70d7c5c92828d3214a266331dde59087d4a39071fa76ddfff9b7bde72",
+            ),
+            (
+                371_337,
+                "60323982f9c5ff1b5a954eac9dc1269352835f47c2c5222691d80f0d50dcf053",
+            ),
+            (
+                450_000,
+                "d279277f8f846a224d776450aa04da3cf978991a182c6f3075db4c48b173bbd7",
+            ),
+        ],
+        DogecoinNetwork::Testnet => &[
+            (
+                0,
+                "bb0a78264637406b6360aad926284d544d7049f45189db5664f3c4d07350559e",
+            ),
+            (
+                483_173,
+                "a804201ca0aceb7e937ef7a3c613a9b7589245b10cc095148c4ce4965b0b73b5",
+            ),
+        ],
+        DogecoinNetwork::Regtest => &[(
+            0,
+            "3d2160a3b5dc4a9d62e7e66a295f70313ac808440ef7400d6c0772171ce973a5",
+        )],
+    }
+}
+
+fn checkpoint_hash(hash: &str) -> BlockHash {
+    BlockHash::from_hex(hash).expect("checkpoint hashes are valid hex")
+}
+
+/// Returns whether a header chain with its tip at `height` has passed the
+/// last checkpoint of the network. Blocks are only synced once it has, to
+/// make sure the adapter follows the right chain.
+pub fn is_beyond_last_checkpoint(network: DogecoinNetwork, height: BlockHeight) -> bool {
+    checkpoints(network)
+        .last()
+        .map_or(true, |(checkpoint_height, _)| height >= *checkpoint_height)
+}
+
+/// Checks that a header at `height` matches the checkpoint at that height, if
+/// any, and does not fork off below the last checkpoint the chain, with its
+/// tip at `tip_height`, has passed.
+fn check_checkpoints(
+    network: DogecoinNetwork,
+    header: &BlockHeader,
+    height: BlockHeight,
+    tip_height: BlockHeight,
+) -> Result<(), ValidateHeaderError> {
+    let checkpoints = checkpoints(network);
+    if let Some((_, hash)) = checkpoints.iter().find(|(h, _)| *h == height) {
+        if header.block_hash() != checkpoint_hash(hash) {
+            return Err(ValidateHeaderError::DoesNotMatchCheckpoint(height));
+        }
+    }
+    let passed = checkpoints
+        .iter()
+        .map(|(h, _)| *h)
+        .filter(|h| *h <= tip_height)
+        .max()
+        .unwrap_or_default();
+    if height < passed {
+        return Err(ValidateHeaderError::ForksBelowCheckpoint(height, passed));
+    }
+    Ok(())
+}
+
+/// Computes the scrypt hash Dogecoin (and Litecoin) use as proof of work.
+fn scrypt_hash(header: &BlockHeader) -> Uint256 {
+    let bytes = encode::serialize(header);
+    let params = scrypt::Params::new(10, 1, 1, 32).expect("scrypt parameters are valid");
+    let mut hash = [0u8; 32];
+    scrypt::scrypt(&bytes, &bytes, &params, &mut hash).expect("output length is valid");
+    // The hash is interpreted as a little-endian number.
+    hash.reverse();
+    Uint256::from_be_bytes(hash)
+}
+
+/// Checks the proof of work of a header without any chain context. For
+/// merge-mined headers, the AuxPoW must commit to the header and the parent
+/// header must satisfy the target of the Dogecoin header.
+pub fn check_proof_of_work(
+    network: DogecoinNetwork,
+    header: &BlockHeader,
+    aux_pow: Option<&AuxPow>,
+) -> Result<(), ValidateHeaderError> {
+    let chain_id = chain_id_of(header.version);
+    if !is_legacy(header) && chain_id != AUXPOW_CHAIN_ID {
+        return Err(ValidateHeaderError::WrongChainId(chain_id));
+    }
+
+    let pow_header = match aux_pow {
+        None if is_aux_pow(header) => return Err(ValidateHeaderError::MissingAuxPow),
+        None => header,
+        Some(_) if !is_aux_pow(header) => return Err(ValidateHeaderError::UnexpectedAuxPow),
+        Some(aux_pow) => {
+            aux_pow.check(&header.block_hash(), chain_id)?;
+            &aux_pow.parent_block_header
+        }
+    };
+
+    let target = header.target();
+    if target == Uint256::default() || target > pow_limit(network) {
+        return Err(ValidateHeaderError::TargetOutOfRange);
+    }
+    if scrypt_hash(pow_header) > target {
+        return Err(ValidateHeaderError::InsufficientProofOfWork);
+    }
+    Ok(())
+}
+
+/// Validates a header against the chain it extends: checkpoints, merge-mining
+/// activation, timestamps and difficulty. The proof of work is checked separately by
+/// [check_proof_of_work] when the header is decoded, as it needs the AuxPoW.
+pub fn validate_header(
+    network: DogecoinNetwork,
+    store: &impl HeaderStore,
+    header: &BlockHeader,
+    current_time: u64,
+) -> Result<(), ValidateHeaderError> {
+    let (prev_header, prev_height) = store
+        .get_header(&header.prev_blockhash)
+        .ok_or(ValidateHeaderError::PrevHeaderNotFound)?;
+    let height = prev_height + 1;
+
+    check_checkpoints(network, header, height, store.get_height())?;
+
+    if is_legacy(header) && height >= auxpow_start_height(network) {
+        return Err(ValidateHeaderError::LegacyBlockNotAllowed(height));
+    }
+
+    let median_time_past = median_time_past(store, prev_header);
+    if header.time <= median_time_past {
+        return Err(ValidateHeaderError::TimestampTooOld(
+            header.time,
+            median_time_past,
+        ));
+    }
+    if header.time as u64 > current_time + MAX_FUTURE_BLOCK_TIME {
+        return Err(ValidateHeaderError::TimestampTooFarInFuture(header.time));
+    }
+
+    let expected = next_work_required(network, store, &prev_header, prev_height, header)?;
+    if header.bits != expected {
+        return Err(ValidateHeaderError::WrongDifficulty {
+            expected,
+            actual: header.bits,
+        });
+    }
+    Ok(())
+}
+
+fn median_time_past(store: &impl HeaderStore, mut header: BlockHeader) -> u32 {
+    let mut times = vec![header.time];
+    while times.len() < MEDIAN_TIME_SPAN {
+        match store.get_header(&header.prev_blockhash) {
+            Some((prev, _)) => {
+                times.push(prev.time);
+                header = prev;
+            }
+            None => break,
+        }
+    }
+    times.sort_unstable();
+    times[times.len() / 2]
+}
+
+/// The consensus parameters relevant to the difficulty adjustment at a given height.
+struct DifficultyParams {
+    target_timespan: i64,
+    digishield: bool,
+    allow_min_difficulty_blocks: bool,
+}
+
+impl DifficultyParams {
+    fn at(network: DogecoinNetwork, height: BlockHeight) -> Self {
+        let digishield = height >= DIGISHIELD_HEIGHT;
+        let allow_min_difficulty_blocks = match network {
+            DogecoinNetwork::Mainnet => false,
+            DogecoinNetwork::Testnet => !digishield || height >= TESTNET_MIN_DIFFICULTY_HEIGHT,
+            DogecoinNetwork::Regtest => true,
+        };
+        Self {
+            target_timespan: if digishield {
+                DIGISHIELD_TARGET_TIMESPAN
+            } else {
+                LEGACY_TARGET_TIMESPAN
+            },
+            digishield,
+            allow_min_difficulty_blocks,
+        }
+    }
+
+    fn adjustment_interval(&self) -> BlockHeight {
+        (self.target_timespan / TARGET_SPACING) as BlockHeight
+    }
+}
+
+/// Returns the difficulty bits required for the header following `last`.
+/// This mirrors `GetNextWorkRequired` of the Dogecoin reference client.
+fn next_work_required(
+    network: DogecoinNetwork,
+    store: &impl HeaderStore,
+    last: &BlockHeader,
+    last_height: BlockHeight,
+    header: &BlockHeader,
+) -> Result<u32, ValidateHeaderError> {
+    // Regtest never retargets.
+    if network == DogecoinNetwork::Regtest {
+        return Ok(last.bits);
+    }
+
+    let pow_limit_bits = BlockHeader::compact_target_from_u256(&pow_limit(network));
+    let params = DifficultyParams::at(network, last_height + 1);
+    let min_difficulty_timeout = last.time as i64 + 2 * TARGET_SPACING;
+
+    if params.allow_min_difficulty_blocks
+        && last_height >= TESTNET_MIN_DIFFICULTY_HEIGHT
+        && header.time as i64 > min_difficulty_timeout
+    {
+        return Ok(pow_limit_bits);
+    }
+
+    let interval = if last_height >= DIGISHIELD_HEIGHT {
+        1
+    } else {
+        params.adjustment_interval()
+    };
+
+    if (last_height + 1) % interval != 0 {
+        if !params.allow_min_difficulty_blocks {
+            return Ok(last.bits);
+        }
+        if header.time as i64 > min_difficulty_timeout {
+            return Ok(pow_limit_bits);
+        }
+        // Return the bits of the last block not mined under the minimum difficulty rule.
+        let mut current = *last;
+        let mut height = last_height;
+        while height % params.adjustment_interval() != 0 && current.bits == pow_limit_bits {
+            match store.get_header(&current.prev_blockhash) {
+                Some((prev, prev_height)) => {
+                    current = prev;
+                    height = prev_height;
+                }
+                None => break,
+            }
+        }
+        return Ok(current.bits);
+    }
+
+    // Go back the full period unless it's the first retarget after genesis.
+    let blocks_to_go_back = if last_height + 1 != interval {
+        interval
+    } else {
+        interval - 1
+    };
+    let mut first = *last;
+    for _ in 0..blocks_to_go_back {
+        first = store
+            .get_header(&first.prev_blockhash)
+            .ok_or(ValidateHeaderError::PrevHeaderNotFound)?
+            .0;
+    }
+
+    Ok(calculate_next_work_required(
+        network,
+        &params,
+        last,
+        last_height + 1,
+        first.time,
+    ))
+}
+
+/// Computes the retargeted difficulty bits. This mirrors
+/// `CalculateDogecoinNextWorkRequired` of the Dogecoin reference client.
+fn calculate_next_work_required(
+    network: DogecoinNetwork,
+    params: &DifficultyParams,
+    last: &BlockHeader,
+    height: BlockHeight,
+    first_block_time: u32,
+) -> u32 {
+    let target_timespan = params.target_timespan;
+    let actual_timespan = last.time as i64 - first_block_time as i64;
+
+    let (timespan, min_timespan, max_timespan) = if params.digishield {
+        // Amplitude filter.
+        (
+            target_timespan + (actual_timespan - target_timespan) / 8,
+            target_timespan - target_timespan / 4,
+            target_timespan + target_timespan / 2,
+        )
+    } else if height > 10_000 {
+        (actual_timespan, target_timespan / 4, target_timespan * 4)
+    } else if height > 5_000 {
+        (actual_timespan, target_timespan / 8, target_timespan * 4)
+    } else {
+        (actual_timespan, target_timespan / 16, target_timespan * 4)
+    };
+    let timespan = timespan.clamp(min_timespan, max_timespan);
+
+    let target = last.target().mul_u32(timespan as u32)
+        / Uint256::from_u64(target_timespan as u64).expect("fits in a Uint256");
+    let target = target.min(pow_limit(network));
+    BlockHeader::compact_target_from_u256(&target)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::network::Network;
+    use bitcoin::{blockdata::script::Builder, OutPoint, Script, TxIn};
+
+    const MAINNET_GENESIS_SCRYPT_HASH: &str =
+        "0000026f3f7874ca0c251314eaed2d2fcf83d7da3acfaacf59417d485310b448";
+
+    #[test]
+    fn genesis_block_satisfies_scrypt_proof_of_work() {
+        let genesis = Network::from(DogecoinNetwork::Mainnet).genesis_block_header();
+        let mut expected = [0u8; 32];
+        expected.copy_from_slice(&hex::decode(MAINNET_GENESIS_SCRYPT_HASH).unwrap());
+        assert_eq!(scrypt_hash(&genesis), Uint256::from_be_bytes(expected));
+        assert_eq!(
+            check_proof_of_work(DogecoinNetwork::Mainnet, &genesis, None),
+            Ok(())
+        );
+
+        let mut tampered = genesis;
+        tampered.nonce += 1;
+        assert_eq!(
+            check_proof_of_work(DogecoinNetwork::Mainnet, &tampered, None),
+            Err(ValidateHeaderError::InsufficientProofOfWork)
+        );
+    }
+
+    #[test]
+    fn expected_index_matches_reference() {
+        assert_eq!(expected_index(0, AUXPOW_CHAIN_ID, 3), 0);
+        assert_eq!(expected_index(7, AUXPOW_CHAIN_ID, 5), 23);
+        assert_eq!(expected_index(0xdeadbeef, AUXPOW_CHAIN_ID, 30), 220410431);
+    }
+
+    /// Builds a merge-mined regtest header whose AuxPoW commits to it directly,
+    /// with a single-leaf merge-mining tree.
+    fn merge_mined_header() -> (BlockHeader, AuxPow) {
+        let genesis = Network::from(DogecoinNetwork::Regtest).genesis_block_header();
+        let header = BlockHeader {
+            version: (AUXPOW_CHAIN_ID << VERSION_CHAIN_START) | VERSION_AUXPOW | 4,
+            prev_blockhash: genesis.block_hash(),
+            merkle_root: TxMerkleNode::default(),
+            time: genesis.time + 60,
+            bits: genesis.bits,
+            nonce: 0,
+        };
+        let mut root = header.block_hash().into_inner();
+        root.reverse();
+        let mut script = MERGED_MINING_HEADER.to_vec();
+        script.extend(root);
+        script.extend(1u32.to_le_bytes());
+        script.extend(0u32.to_le_bytes());
+        let coinbase_tx = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: Builder::new().push_slice(&script).into_script(),
+                sequence: u32::MAX,
+                witness: Default::default(),
+            }],
+            output: vec![],
+        };
+        let mut parent_block_header = BlockHeader {
+            version: 2,
+            prev_blockhash: BlockHash::default(),
+            merkle_root: TxMerkleNode::from_inner(coinbase_tx.txid().into_inner()),
+            time: header.time,
+            bits: header.bits,
+            nonce: 0,
+        };
+        while scrypt_hash(&parent_block_header) > header.target() {
+            parent_block_header.nonce += 1;
+        }
+        let aux_pow = AuxPow {
+            coinbase_tx,
+            parent_hash: parent_block_header.block_hash(),
+            coinbase_branch: vec![],
+            coinbase_index: 0,
+            blockchain_branch: vec![],
+            blockchain_index: 0,
+            parent_block_header,
+        };
+        (header, aux_pow)
+    }
+
+    #[test]
+    fn aux_pow_is_checked() {
+        let (header, aux_pow) = merge_mined_header();
+        assert_eq!(
+            check_proof_of_work(DogecoinNetwork::Regtest, &header, Some(&aux_pow)),
+            Ok(())
+        );
+        assert_eq!(
+            check_proof_of_work(DogecoinNetwork::Regtest, &header, None),
+            Err(ValidateHeaderError::MissingAuxPow)
+        );
+
+        // The AuxPoW does not commit to a different header.
+        let mut other = header;
+        other.time += 1;
+        assert_eq!(
+            check_proof_of_work(DogecoinNetwork::Regtest, &other, Some(&aux_pow)),
+            Err(ValidateHeaderError::InvalidAuxPow(
+                "the merge-mining root is missing from the coinbase"
+            ))
+        );
+
+        let mut wrong_script = aux_pow.clone();
+        wrong_script.coinbase_tx.input[0].script_sig = Script::new();
+        assert!(matches!(
+            check_proof_of_work(DogecoinNetwork::Regtest, &header, Some(&wrong_script)),
+            Err(ValidateHeaderError::InvalidAuxPow(_))
+        ));
+    }
+
+    #[test]
+    fn block_with_aux_pow_roundtrips() {
+        let (header, aux_pow) = merge_mined_header();
+        let block = Block {
+            header,
+            txdata: vec![aux_pow.coinbase_tx.clone()],
+        };
+        let bytes = serialize_block(&block, Some(&aux_pow));
+        assert_eq!(
+            decode_block(&bytes).unwrap(),
+            (block, Some(aux_pow.clone()))
+        );
+
+        let mut headers = encode::serialize(&VarInt(1));
+        headers.extend(encode::serialize(&header));
+        headers.extend(encode::serialize(&aux_pow));
+        headers.extend(encode::serialize(&VarInt(0)));
+        assert_eq!(
+            decode_headers(&headers).unwrap(),
+            vec![(header, Some(aux_pow))]
+        );
+    }
+
+    #[test]
+    fn digishield_difficulty_adjustment() {
+        let params = DifficultyParams::at(DogecoinNetwork::Mainnet, 200_000);
+        let last = BlockHeader {
+            version: 1,
+            prev_blockhash: BlockHash::default(),
+            merkle_root: TxMerkleNode::default(),
+            time: 1_000_000,
+            bits: 0x1c0d3b29,
+            nonce: 0,
+        };
+        // Blocks on schedule keep the difficulty.
+        assert_eq!(
+            calculate_next_work_required(
+                DogecoinNetwork::Mainnet,
+                &params,
+                &last,
+                200_000,
+                last.time - 60
+            ),
+            0x1c0d3b29
+        );
+        // Slow blocks lower the difficulty by at most 50%.
+        assert_eq!(
+            calculate_next_work_required(
+                DogecoinNetwork::Mainnet,
+                &params,
+                &last,
+                200_000,
+                last.time - 6000
+            ),
+            0x1c13d8bd
+        );
+
+        let params = DifficultyParams::at(DogecoinNetwork::Mainnet, 2400);
+        assert_eq!(
+            calculate_next_work_required(
+                DogecoinNetwork::Mainnet,
+                &params,
+                &last,
+                2400,
+                last.time - 7200
+            ),
+            0x1c069d94
+        );
+    }
+
+    #[test]
+    fn first_checkpoint_is_the_genesis_block() {
+        for network in [
+            DogecoinNetwork::Mainnet,
+            DogecoinNetwork::Testnet,
+            DogecoinNetwork::Regtest,
+        ] {
+            let (height, hash) = checkpoints(network)[0];
+            assert_eq!(height, 0);
+            assert_eq!(
+                checkpoint_hash(hash),
+                Network::from(network).genesis_block_header().block_hash()
+            );
+        }
+    }
+
+    #[test]
+    fn headers_are_checked_against_checkpoints() {
+        let network = DogecoinNetwork::Mainnet;
+        let header = Network::from(network).genesis_block_header();
+        assert!(!is_beyond_last_checkpoint(network, 449_999));
+        assert!(is_beyond_last_checkpoint(network, 450_000));
+
+        // A header at a checkpoint height must have the checkpoint's hash.
+        assert_eq!(check_checkpoints(network, &header, 0, 0), Ok(()));
+        assert_eq!(
+            check_checkpoints(network, &header, 145_000, 144_999),
+            Err(ValidateHeaderError::DoesNotMatchCheckpoint(145_000))
+        );
+        // Forks are allowed above the last checkpoint the chain has passed.
+        assert_eq!(
+            check_checkpoints(network, &header, 145_001, 150_000),
+            Ok(())
+        );
+        assert_eq!(
+            check_checkpoints(network, &header, 144_999, 150_000),
+            Err(ValidateHeaderError::ForksBelowCheckpoint(144_999, 145_000))
+        );
+    }
+}
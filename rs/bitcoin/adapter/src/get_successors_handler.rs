@@ -1,17 +1,21 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     sync::Arc,
 };
 
-use bitcoin::{Block, BlockHash, BlockHeader, Network};
+use bitcoin::{Block, BlockHash, BlockHeader, Network as BitcoinNetwork};
 use ic_btc_validation::is_beyond_last_checkpoint;
 use ic_metrics::MetricsRegistry;
 use tokio::sync::{mpsc::Sender, Mutex};
 use tonic::{Code, Status};
 
 use crate::{
-    common::BlockHeight, config::Config, metrics::GetSuccessorMetrics, BlockchainManagerRequest,
-    BlockchainState,
+    common::BlockHeight,
+    config::Config,
+    dogecoin::{self, AuxPow},
+    metrics::GetSuccessorMetrics,
+    network::Network,
+    BlockchainManagerRequest, BlockchainState,
 };
 
 // Max size of the `GetSuccessorsResponse` message.
@@ -54,7 +58,11 @@ pub struct GetSuccessorsRequest {
 pub struct GetSuccessorsResponse {
     /// Blocks found in the block cache.
     pub blocks: Vec<Block>,
-    /// Next set of headers to be sent to the canister.
+    /// The AuxPoW of the merge-mined Dogecoin blocks in `blocks`, which must be
+    /// sent along with the block.
+    pub aux_pows: HashMap<BlockHash, AuxPow>,
+    /// Next set of headers to be sent to the canister. Dogecoin headers are sent
+    /// without their AuxPoW.
     pub next: Vec<BlockHeader>,
 }
 /// Contains the functionality to respond to GetSuccessorsRequests via the RPC
@@ -105,7 +113,14 @@ impl GetSuccessorsHandler {
 
             // Wait with downloading blocks until we synced the header chain above the last checkpoint
             // to make sure we are following the correct chain.
-            if !is_beyond_last_checkpoint(&self.network, state.get_active_chain_tip().height) {
+            let tip_height = state.get_active_chain_tip().height;
+            let beyond_last_checkpoint = match self.network {
+                Network::Bitcoin(network) => is_beyond_last_checkpoint(&network, tip_height),
+                Network::Dogecoin(network) => {
+                    dogecoin::is_beyond_last_checkpoint(network, tip_height)
+                }
+            };
+            if !beyond_last_checkpoint {
                 return Err(Status::new(
                     Code::Unavailable,
                     "Header chain not yet synced past last checkpoint",
@@ -125,7 +140,20 @@ impl GetSuccessorsHandler {
                 &request.processed_block_hashes,
                 &blocks,
             );
-            GetSuccessorsResponse { blocks, next }
+            let aux_pows = blocks
+                .iter()
+                .filter_map(|block| {
+                    let block_hash = block.block_hash();
+                    state
+                        .get_aux_pow(&block_hash)
+                        .map(|aux_pow| (block_hash, aux_pow.clone()))
+                })
+                .collect();
+            GetSuccessorsResponse {
+                blocks,
+                aux_pows,
+                next,
+            }
         };
         self.metrics
             .response_blocks
@@ -248,8 +276,10 @@ fn get_next_headers(
 /// Helper used to determine if multiple blocks should be returned.
 fn are_multiple_blocks_allowed(network: Network, anchor_height: BlockHeight) -> bool {
     match network {
-        Network::Bitcoin => anchor_height <= MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT,
-        Network::Testnet | Network::Signet | Network::Regtest => true,
+        Network::Bitcoin(BitcoinNetwork::Bitcoin) => {
+            anchor_height <= MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT
+        }
+        Network::Bitcoin(_) | Network::Dogecoin(_) => true,
     }
 }
 
@@ -259,6 +289,7 @@ mod test {
 
     use std::sync::Arc;
 
+    use crate::network::DogecoinNetwork;
     use bitcoin::Network;
     use ic_metrics::MetricsRegistry;
     use tokio::sync::{mpsc::channel, Mutex};
@@ -799,37 +830,47 @@ mod test {
     fn test_are_multiple_blocks_allowed() {
         // Mainnet
         assert!(
-            are_multiple_blocks_allowed(Network::Bitcoin, 100_500),
+            are_multiple_blocks_allowed(Network::Bitcoin.into(), 100_500),
             "Multiple blocks are allowed at 100_500"
         );
         assert!(
-            are_multiple_blocks_allowed(Network::Bitcoin, MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT),
+            are_multiple_blocks_allowed(
+                Network::Bitcoin.into(),
+                MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT
+            ),
             "Multiple blocks are allowed at {}",
             MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT
         );
         assert!(
-            !are_multiple_blocks_allowed(Network::Bitcoin, 900_000),
+            !are_multiple_blocks_allowed(Network::Bitcoin.into(), 900_000),
             "Multiple blocks are not allowed at 900_000"
         );
 
         // Testnet
         assert!(
-            are_multiple_blocks_allowed(Network::Testnet, 1_000_000),
+            are_multiple_blocks_allowed(Network::Testnet.into(), 1_000_000),
             "Multiple blocks are allowed at 1_000_000"
         );
         assert!(
-            are_multiple_blocks_allowed(Network::Testnet, u32::MAX),
+            are_multiple_blocks_allowed(Network::Testnet.into(), u32::MAX),
             "Multiple blocks are allowed at {}",
             u32::MAX
         );
 
         // Regtest
         assert!(
-            are_multiple_blocks_allowed(Network::Regtest, 1),
+            are_multiple_blocks_allowed(Network::Regtest.into(), 1),
             "Multiple blocks are allowed at 1"
         );
         assert!(
-            are_multiple_blocks_allowed(Network::Regtest, u32::MAX),
+            are_multiple_blocks_allowed(Network::Regtest.into(), u32::MAX),
+            "Multiple blocks are allowed at {}",
+            u32::MAX
+        );
+
+        // Dogecoin
+        assert!(
+            are_multiple_blocks_allowed(DogecoinNetwork::Mainnet.into(), u32::MAX),
             "Multiple blocks are allowed at {}",
             u32::MAX
         );
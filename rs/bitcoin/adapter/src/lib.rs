@@ -34,7 +34,11 @@ mod connection;
 /// This module contains code that is used to manage multiple connections to
 /// BTC nodes.
 mod connectionmanager;
+/// This module contains the Dogecoin specific message decoding and header validation.
+mod dogecoin;
 mod metrics;
+/// This module contains the networks the adapter can connect to.
+pub mod network;
 /// The module is responsible for awaiting messages from bitcoin peers and dispaching them
 /// to the correct component.
 mod router;
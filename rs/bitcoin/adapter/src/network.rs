@@ -0,0 +1,165 @@
+//! The networks the adapter can connect to.
+use bitcoin::{
+    blockdata::constants::genesis_block, hash_types::TxMerkleNode, hashes::hex::FromHex,
+    network::constants::PROTOCOL_VERSION, BlockHash, BlockHeader, Network as BitcoinNetwork,
+};
+use serde::{Deserialize, Serialize};
+
+/// The protocol version announced to Dogecoin peers. Dogecoin nodes reject
+/// peers that announce the version used for Bitcoin.
+const DOGECOIN_PROTOCOL_VERSION: u32 = 70015;
+
+/// The merkle root of the Dogecoin genesis block, shared by all Dogecoin networks.
+const DOGECOIN_GENESIS_MERKLE_ROOT: &str =
+    "5b2a3f53f605d62c53e62932dac6925e3d74afa5a4b459745c36d42d0ed26a69";
+
+/// The Dogecoin networks the adapter can connect to.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum DogecoinNetwork {
+    /// Dogecoin mainnet.
+    #[serde(rename = "dogecoin")]
+    Mainnet,
+    /// Dogecoin testnet.
+    #[serde(rename = "dogecoin_testnet")]
+    Testnet,
+    /// Dogecoin regtest.
+    #[serde(rename = "dogecoin_regtest")]
+    Regtest,
+}
+
+/// The network the adapter connects to. Bitcoin networks keep the names used by
+/// the `bitcoin` crate ("bitcoin", "testnet", "signet", "regtest"), so existing
+/// configurations keep working.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum Network {
+    /// One of the Bitcoin networks.
+    Bitcoin(BitcoinNetwork),
+    /// One of the Dogecoin networks.
+    Dogecoin(DogecoinNetwork),
+}
+
+impl From<BitcoinNetwork> for Network {
+    fn from(network: BitcoinNetwork) -> Self {
+        Self::Bitcoin(network)
+    }
+}
+
+impl From<DogecoinNetwork> for Network {
+    fn from(network: DogecoinNetwork) -> Self {
+        Self::Dogecoin(network)
+    }
+}
+
+impl Network {
+    /// Returns true if the network is one of the Dogecoin networks.
+    pub fn is_dogecoin(&self) -> bool {
+        matches!(self, Self::Dogecoin(_))
+    }
+
+    /// Returns the magic value that prefixes every message on the network.
+    pub fn magic(&self) -> u32 {
+        match self {
+            Self::Bitcoin(network) => network.magic(),
+            Self::Dogecoin(DogecoinNetwork::Mainnet) => 0xc0c0c0c0,
+            Self::Dogecoin(DogecoinNetwork::Testnet) => 0xdcb7c1fc,
+            Self::Dogecoin(DogecoinNetwork::Regtest) => 0xdab5bffa,
+        }
+    }
+
+    /// Returns the protocol version announced in `version` messages.
+    pub fn protocol_version(&self) -> u32 {
+        match self {
+            Self::Bitcoin(_) => PROTOCOL_VERSION,
+            Self::Dogecoin(_) => DOGECOIN_PROTOCOL_VERSION,
+        }
+    }
+
+    /// Returns the default P2P port of the network.
+    pub fn default_port(&self) -> u16 {
+        match self {
+            Self::Bitcoin(BitcoinNetwork::Testnet) => 18333,
+            Self::Bitcoin(_) => 8333,
+            Self::Dogecoin(DogecoinNetwork::Mainnet) => 22556,
+            Self::Dogecoin(DogecoinNetwork::Testnet) => 44556,
+            Self::Dogecoin(DogecoinNetwork::Regtest) => 18444,
+        }
+    }
+
+    /// Returns the DNS seeds used when the configuration does not provide any.
+    /// Bitcoin configurations are expected to list their seeds explicitly.
+    pub fn default_dns_seeds(&self) -> Vec<String> {
+        let seeds: &[&str] = match self {
+            Self::Bitcoin(_) | Self::Dogecoin(DogecoinNetwork::Regtest) => &[],
+            Self::Dogecoin(DogecoinNetwork::Mainnet) => {
+                &["seed.multidoge.org", "seed2.multidoge.org"]
+            }
+            Self::Dogecoin(DogecoinNetwork::Testnet) => &["testseed.jrn.me.uk"],
+        };
+        seeds.iter().map(|seed| seed.to_string()).collect()
+    }
+
+    /// Returns the header of the genesis block of the network.
+    pub fn genesis_block_header(&self) -> BlockHeader {
+        let (time, bits, nonce) = match self {
+            Self::Bitcoin(network) => return genesis_block(*network).header,
+            Self::Dogecoin(DogecoinNetwork::Mainnet) => (1386325540, 0x1e0ffff0, 99943),
+            Self::Dogecoin(DogecoinNetwork::Testnet) => (1391503289, 0x1e0ffff0, 997879),
+            Self::Dogecoin(DogecoinNetwork::Regtest) => (1296688602, 0x207fffff, 2),
+        };
+        BlockHeader {
+            version: 1,
+            prev_blockhash: BlockHash::default(),
+            merkle_root: TxMerkleNode::from_hex(DOGECOIN_GENESIS_MERKLE_ROOT)
+                .expect("the genesis merkle root is valid hex"),
+            time,
+            bits,
+            nonce,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dogecoin_genesis_block_hashes() {
+        for (network, hash) in [
+            (
+                DogecoinNetwork::Mainnet,
+                "1a91e3dace36e2be3bf030a65679fe821aa1d6ef92e7c9902eb318182c355691",
+            ),
+            (
+                DogecoinNetwork::Testnet,
+                "bb0a78264637406b6360aad926284d544d7049f45189db5664f3c4d07350559e",
+            ),
+            (
+                DogecoinNetwork::Regtest,
+                "3d2160a3b5dc4a9d62e7e66a295f70313ac808440ef7400d6c0772171ce973a5",
+            ),
+        ] {
+            assert_eq!(
+                Network::from(network).genesis_block_header().block_hash(),
+                BlockHash::from_hex(hash).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn network_names_are_backwards_compatible() {
+        for (name, network) in [
+            ("\"bitcoin\"", Network::Bitcoin(BitcoinNetwork::Bitcoin)),
+            ("\"testnet\"", Network::Bitcoin(BitcoinNetwork::Testnet)),
+            ("\"regtest\"", Network::Bitcoin(BitcoinNetwork::Regtest)),
+            ("\"dogecoin\"", Network::Dogecoin(DogecoinNetwork::Mainnet)),
+            (
+                "\"dogecoin_testnet\"",
+                Network::Dogecoin(DogecoinNetwork::Testnet),
+            ),
+        ] {
+            assert_eq!(serde_json::from_str::<Network>(name).unwrap(), network);
+            assert_eq!(serde_json::to_string(&network).unwrap(), name);
+        }
+    }
+}
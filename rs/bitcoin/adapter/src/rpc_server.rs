@@ -1,5 +1,6 @@
 use crate::{
     config::{Config, IncomingSource},
+    dogecoin,
     get_successors_handler::{GetSuccessorsRequest, GetSuccessorsResponse},
     metrics::{ServiceMetrics, LABEL_GET_SUCCESSOR, LABEL_SEND_TRANSACTION},
     AdapterState, GetSuccessorsHandler, TransactionManagerRequest,
@@ -53,6 +54,11 @@ impl TryFrom<GetSuccessorsResponse> for BtcServiceGetSuccessorsResponse {
     fn try_from(response: GetSuccessorsResponse) -> Result<Self, Self::Error> {
         let mut blocks = vec![];
         for block in response.blocks.iter() {
+            // Merge-mined Dogecoin blocks are sent with their AuxPoW, as Dogecoin nodes do.
+            if let Some(aux_pow) = response.aux_pows.get(&block.block_hash()) {
+                blocks.push(dogecoin::serialize_block(block, Some(aux_pow)));
+                continue;
+            }
             let mut encoded_block = vec![];
             block
                 .consensus_encode(&mut encoded_block)
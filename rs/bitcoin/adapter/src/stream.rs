@@ -1,3 +1,4 @@
+use crate::{dogecoin, network::Network};
 use bitcoin::{
     consensus::serialize,
     network::message::RawNetworkMessage,
@@ -70,6 +71,8 @@ pub struct StreamConfig {
     /// This field is used to provide the magic value to the raw network message.
     /// The magic number is used to identity the type of Bitcoin network being accessed.
     pub magic: u32,
+    /// This field is used to determine how incoming messages are decoded.
+    pub network: Network,
     /// This field is used to receive network messages to send out to the connected
     /// BTC node.
     pub network_message_receiver: UnboundedReceiver<NetworkMessage>,
@@ -118,6 +121,8 @@ pub struct Stream {
     /// This field is used to provide the magic value to the raw network message.
     /// The magic number is used to identity the type of Bitcoin network being accessed.
     magic: u32,
+    /// This field is used to determine how incoming messages are decoded.
+    network: Network,
     /// This field contains the receiver used to intake messages that are to be
     /// sent to the connected node.
     network_message_receiver: UnboundedReceiver<NetworkMessage>,
@@ -134,6 +139,7 @@ impl Stream {
             address,
             socks_proxy,
             magic,
+            network,
             network_message_receiver,
             network_message_sender,
             ..
@@ -203,6 +209,7 @@ impl Stream {
             read_half,
             write_half,
             magic,
+            network,
             network_message_receiver,
             network_message_sender,
             unparsed,
@@ -220,7 +227,12 @@ impl Stream {
             }
             // The stream may only a message partial from the Bitcoin node.
             // Due to this, the stream must attempt to deserialize partial messages.
-            match encode::deserialize_partial::<RawNetworkMessage>(&self.unparsed) {
+            let result = if self.network.is_dogecoin() {
+                dogecoin::deserialize_partial_message(&self.unparsed)
+            } else {
+                encode::deserialize_partial::<RawNetworkMessage>(&self.unparsed)
+            };
+            match result {
                 // If there was an I/O error found in the unparsed message and it was an unexpected
                 // end-of-file, then the stream should try to read again. If the read fails, the stream
                 // exits the read message with the error. The stream later looks at this error, if the
@@ -390,6 +402,7 @@ pub mod test {
             address,
             logger: no_op_logger(),
             magic: network.magic(),
+            network: network.into(),
             network_message_receiver: adapter_rx,
             socks_proxy: None,
             stream_event_sender: stream_tx,
@@ -446,6 +459,7 @@ pub mod test {
             address,
             logger: no_op_logger(),
             magic: network.magic(),
+            network: network.into(),
             network_message_receiver: adapter_rx,
             socks_proxy: None,
             stream_event_sender: stream_tx,
@@ -471,6 +485,7 @@ pub mod test {
             address,
             logger: no_op_logger(),
             magic: network.magic(),
+            network: network.into(),
             network_message_receiver: adapter_rx,
             socks_proxy: None,
             stream_event_sender: stream_tx,
@@ -6,7 +6,7 @@ use ic_btc_adapter::{
     start_grpc_server_and_router, AdapterState,
 };
 use ic_btc_adapter_client::setup_bitcoin_adapter_clients;
-use ic_btc_interface::Network;
+use ic_btc_types_internal::Network;
 use ic_btc_types_internal::{
     BitcoinAdapterRequestWrapper, BitcoinAdapterResponseWrapper, GetSuccessorsRequestInitial,
     SendTransactionRequest,
@@ -94,7 +94,7 @@ async fn start_adapter(
     network: bitcoin::Network,
 ) {
     let config = Config {
-        network,
+        network: network.into(),
         incoming_source: IncomingSource::Path(uds_path.to_path_buf()),
         nodes,
         ipv6_only: true,
@@ -127,11 +127,7 @@ async fn start_client(
 ) -> BitcoinAdapterClient {
     let adapters_config = AdaptersConfig {
         bitcoin_mainnet_uds_path: Some(uds_path.into()),
-        bitcoin_mainnet_uds_metrics_path: None,
-        bitcoin_testnet_uds_path: None,
-        bitcoin_testnet_uds_metrics_path: None,
-        https_outcalls_uds_path: None,
-        https_outcalls_uds_metrics_path: None,
+        ..Default::default()
     };
 
     setup_bitcoin_adapter_clients(
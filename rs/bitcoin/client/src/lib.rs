@@ -209,6 +209,18 @@ pub struct BitcoinAdapterClients {
             Response = BitcoinAdapterResponseWrapper,
         >,
     >,
+    pub doge_testnet_client: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
+    pub doge_mainnet_client: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
 }
 
 pub fn setup_bitcoin_adapter_clients(
@@ -234,6 +246,20 @@ pub fn setup_bitcoin_adapter_clients(
             rt_handle.clone(),
         ));
     }
+    if let Some(metrics_uds_path) = adapters_config.dogecoin_testnet_uds_metrics_path {
+        metrics_registry.register_adapter(AdapterMetrics::new(
+            "dogetestnet",
+            metrics_uds_path,
+            rt_handle.clone(),
+        ));
+    }
+    if let Some(metrics_uds_path) = adapters_config.dogecoin_mainnet_uds_metrics_path {
+        metrics_registry.register_adapter(AdapterMetrics::new(
+            "dogemainnet",
+            metrics_uds_path,
+            rt_handle.clone(),
+        ));
+    }
 
     BitcoinAdapterClients {
         btc_testnet_client: setup_bitcoin_adapter_client(
@@ -243,10 +269,22 @@ pub fn setup_bitcoin_adapter_clients(
             adapters_config.bitcoin_testnet_uds_path,
         ),
         btc_mainnet_client: setup_bitcoin_adapter_client(
+            log.clone(),
+            metrics.clone(),
+            rt_handle.clone(),
+            adapters_config.bitcoin_mainnet_uds_path,
+        ),
+        doge_testnet_client: setup_bitcoin_adapter_client(
+            log.clone(),
+            metrics.clone(),
+            rt_handle.clone(),
+            adapters_config.dogecoin_testnet_uds_path,
+        ),
+        doge_mainnet_client: setup_bitcoin_adapter_client(
             log,
             metrics,
             rt_handle,
-            adapters_config.bitcoin_mainnet_uds_path,
+            adapters_config.dogecoin_mainnet_uds_path,
         ),
     }
 }
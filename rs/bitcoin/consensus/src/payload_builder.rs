@@ -8,10 +8,9 @@ mod tests;
 mod proptests;
 
 use crate::metrics::BitcoinPayloadBuilderMetrics;
-use ic_btc_interface::Network;
 use ic_btc_types_internal::{
     BitcoinAdapterRequestWrapper, BitcoinAdapterResponse, BitcoinAdapterResponseWrapper,
-    BitcoinReject,
+    BitcoinReject, Network,
 };
 use ic_config::bitcoin_payload_builder_config::Config;
 use ic_error_types::RejectCode;
@@ -81,6 +80,18 @@ pub struct BitcoinPayloadBuilder {
             Response = BitcoinAdapterResponseWrapper,
         >,
     >,
+    dogecoin_mainnet_adapter_client: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
+    dogecoin_testnet_adapter_client: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
     subnet_id: SubnetId,
     registry: Arc<dyn RegistryClient + Send + Sync>,
     config: Config,
@@ -103,6 +114,18 @@ impl BitcoinPayloadBuilder {
                 Response = BitcoinAdapterResponseWrapper,
             >,
         >,
+        dogecoin_mainnet_adapter_client: Box<
+            dyn RpcAdapterClient<
+                BitcoinAdapterRequestWrapper,
+                Response = BitcoinAdapterResponseWrapper,
+            >,
+        >,
+        dogecoin_testnet_adapter_client: Box<
+            dyn RpcAdapterClient<
+                BitcoinAdapterRequestWrapper,
+                Response = BitcoinAdapterResponseWrapper,
+            >,
+        >,
         subnet_id: SubnetId,
         registry: Arc<dyn RegistryClient + Send + Sync>,
         config: Config,
@@ -113,6 +136,8 @@ impl BitcoinPayloadBuilder {
             metrics: Arc::new(BitcoinPayloadBuilderMetrics::new(metrics_registry)),
             bitcoin_mainnet_adapter_client,
             bitcoin_testnet_adapter_client,
+            dogecoin_mainnet_adapter_client,
+            dogecoin_testnet_adapter_client,
             subnet_id,
             registry,
             config,
@@ -146,6 +171,10 @@ impl BitcoinPayloadBuilder {
             let adapter_client = match request.network() {
                 Network::Mainnet => &self.bitcoin_mainnet_adapter_client,
                 Network::Testnet | Network::Regtest => &self.bitcoin_testnet_adapter_client,
+                Network::DogecoinMainnet => &self.dogecoin_mainnet_adapter_client,
+                Network::DogecoinTestnet | Network::DogecoinRegtest => {
+                    &self.dogecoin_testnet_adapter_client
+                }
             };
 
             // Send request to the adapter.
@@ -5,7 +5,6 @@
 //! only for serialization/deserialization of the ReplicatedState.
 
 use candid::CandidType;
-use ic_btc_interface::Network;
 use ic_error_types::{RejectCode, TryFromError};
 use ic_protobuf::{
     bitcoin::v1,
@@ -15,6 +14,65 @@ use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 use std::mem::size_of_val;
 
+/// The network an adapter request is for. The Bitcoin networks mirror
+/// [`ic_btc_interface::Network`], with the same Candid and serde encoding; the
+/// Dogecoin networks are served by dedicated Dogecoin adapters.
+#[derive(
+    CandidType, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum Network {
+    #[serde(rename = "mainnet")]
+    Mainnet,
+    #[serde(rename = "testnet")]
+    Testnet,
+    #[serde(rename = "regtest")]
+    Regtest,
+    #[serde(rename = "dogecoin_mainnet")]
+    DogecoinMainnet,
+    #[serde(rename = "dogecoin_testnet")]
+    DogecoinTestnet,
+    #[serde(rename = "dogecoin_regtest")]
+    DogecoinRegtest,
+}
+
+impl From<ic_btc_interface::Network> for Network {
+    fn from(network: ic_btc_interface::Network) -> Self {
+        match network {
+            ic_btc_interface::Network::Mainnet => Network::Mainnet,
+            ic_btc_interface::Network::Testnet => Network::Testnet,
+            ic_btc_interface::Network::Regtest => Network::Regtest,
+        }
+    }
+}
+
+impl From<Network> for i32 {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Testnet => 1,
+            Network::Mainnet => 2,
+            Network::Regtest => 3,
+            Network::DogecoinMainnet => 4,
+            Network::DogecoinTestnet => 5,
+            Network::DogecoinRegtest => 6,
+        }
+    }
+}
+
+impl Network {
+    /// Decodes the network from its protobuf representation.
+    fn try_from_proto(network: i32) -> Option<Self> {
+        match network {
+            1 => Some(Network::Testnet),
+            2 => Some(Network::Mainnet),
+            3 => Some(Network::Regtest),
+            4 => Some(Network::DogecoinMainnet),
+            5 => Some(Network::DogecoinTestnet),
+            6 => Some(Network::DogecoinRegtest),
+            _ => None,
+        }
+    }
+}
+
 #[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SendTransactionRequest {
     pub network: Network,
@@ -25,11 +83,7 @@ pub struct SendTransactionRequest {
 impl From<&SendTransactionRequest> for v1::SendTransactionRequest {
     fn from(request: &SendTransactionRequest) -> Self {
         Self {
-            network: match request.network {
-                Network::Testnet => 1,
-                Network::Mainnet => 2,
-                Network::Regtest => 3,
-            },
+            network: request.network.into(),
             transaction: request.transaction.clone(),
         }
     }
@@ -39,16 +93,9 @@ impl TryFrom<v1::SendTransactionRequest> for SendTransactionRequest {
     type Error = ProxyDecodeError;
     fn try_from(request: v1::SendTransactionRequest) -> Result<Self, Self::Error> {
         Ok(SendTransactionRequest {
-            network: match request.network {
-                1 => Network::Testnet,
-                2 => Network::Mainnet,
-                3 => Network::Regtest,
-                _ => {
-                    return Err(ProxyDecodeError::MissingField(
-                        "SendTransactionRequest::network",
-                    ))
-                }
-            },
+            network: Network::try_from_proto(request.network).ok_or(
+                ProxyDecodeError::MissingField("SendTransactionRequest::network"),
+            )?,
             transaction: request.transaction,
         })
     }
@@ -68,7 +115,7 @@ impl BitcoinAdapterRequestWrapper {
         }
     }
 
-    /// Returns which bitcoin network the request is for.
+    /// Returns which network the request is for.
     pub fn network(&self) -> Network {
         match self {
             BitcoinAdapterRequestWrapper::GetSuccessorsRequest(GetSuccessorsRequestInitial {
@@ -640,11 +687,7 @@ pub struct GetSuccessorsRequestInitial {
 impl From<&GetSuccessorsRequestInitial> for v1::GetSuccessorsRequestInitial {
     fn from(request: &GetSuccessorsRequestInitial) -> Self {
         Self {
-            network: match request.network {
-                Network::Testnet => 1,
-                Network::Mainnet => 2,
-                Network::Regtest => 3,
-            },
+            network: request.network.into(),
             anchor: request.anchor.clone(),
             processed_block_hashes: request.processed_block_hashes.clone(),
         }
@@ -655,16 +698,9 @@ impl TryFrom<v1::GetSuccessorsRequestInitial> for GetSuccessorsRequestInitial {
     type Error = ProxyDecodeError;
     fn try_from(request: v1::GetSuccessorsRequestInitial) -> Result<Self, Self::Error> {
         Ok(GetSuccessorsRequestInitial {
-            network: match request.network {
-                1 => Network::Testnet,
-                2 => Network::Mainnet,
-                3 => Network::Regtest,
-                _ => {
-                    return Err(ProxyDecodeError::MissingField(
-                        "GetSuccessorsRequestInitial::network",
-                    ))
-                }
-            },
+            network: Network::try_from_proto(request.network).ok_or(
+                ProxyDecodeError::MissingField("GetSuccessorsRequestInitial::network"),
+            )?,
             anchor: request.anchor,
             processed_block_hashes: request.processed_block_hashes,
         })
@@ -781,4 +817,32 @@ mod test {
             12
         );
     }
+
+    #[test]
+    fn network_encodings() {
+        for network in [
+            Network::Mainnet,
+            Network::Testnet,
+            Network::Regtest,
+            Network::DogecoinMainnet,
+            Network::DogecoinTestnet,
+            Network::DogecoinRegtest,
+        ] {
+            assert_eq!(Network::try_from_proto(network.into()), Some(network));
+        }
+        assert_eq!(Network::try_from_proto(0), None);
+
+        // The Bitcoin networks are interchangeable with the ones of the interface.
+        for network in [
+            ic_btc_interface::Network::Mainnet,
+            ic_btc_interface::Network::Testnet,
+            ic_btc_interface::Network::Regtest,
+        ] {
+            let bytes = candid::encode_one(network).unwrap();
+            assert_eq!(
+                candid::decode_one::<Network>(&bytes).unwrap(),
+                Network::from(network)
+            );
+        }
+    }
 }
@@ -0,0 +1,501 @@
+use std::{
+    collections::VecDeque,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use tokio::task::JoinHandle;
+use tracing::warn;
+use url::Url;
+
+use crate::{
+    api_nodes_discovery::{ApiNodeRegistryFetchError, Fetch},
+    route_provider::{RouteProvider, RouteProviderError},
+};
+
+const STATUS_PATH: &str = "/api/v2/status";
+
+/// Configuration of the [`DynamicRouteProvider`].
+#[derive(Clone, Debug)]
+pub struct DynamicRouteProviderConfig {
+    /// How often the list of API boundary nodes is refreshed from the registry.
+    pub fetch_period: Duration,
+    /// How often every API boundary node is health-checked.
+    pub health_check_period: Duration,
+    /// The number of latency samples the moving average is computed over.
+    pub latency_window: usize,
+    /// The number of healthy nodes with the lowest latency that requests are routed to.
+    pub max_routes: usize,
+}
+
+impl Default for DynamicRouteProviderConfig {
+    fn default() -> Self {
+        Self {
+            fetch_period: Duration::from_secs(300),
+            health_check_period: Duration::from_secs(5),
+            latency_window: 10,
+            max_routes: 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthCheckStatus {
+    /// The node responded successfully within the given time.
+    Healthy(Duration),
+    Unhealthy,
+}
+
+#[async_trait]
+pub trait HealthCheck {
+    async fn check(&self, domain: &str) -> HealthCheckStatus;
+}
+
+/// Checks the health of a node by querying its `/api/v2/status` endpoint.
+pub struct HttpHealthChecker {
+    client: reqwest::Client,
+}
+
+impl HttpHealthChecker {
+    pub fn new(timeout: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("failed to build the HTTP client");
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for HttpHealthChecker {
+    async fn check(&self, domain: &str) -> HealthCheckStatus {
+        let start = Instant::now();
+        match self
+            .client
+            .get(format!("https://{domain}{STATUS_PATH}"))
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => {
+                HealthCheckStatus::Healthy(start.elapsed())
+            }
+            _ => HealthCheckStatus::Unhealthy,
+        }
+    }
+}
+
+/// The state of an API boundary node as observed by the health checks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteInfo {
+    pub domain: String,
+    pub url: Url,
+    /// Nodes that haven't been checked yet are considered healthy.
+    pub healthy: bool,
+    /// The moving average over the latencies of the last successful health checks.
+    pub average_latency: Option<Duration>,
+}
+
+/// A point-in-time view of all known API boundary nodes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RoutesSnapshot {
+    pub routes: Vec<RouteInfo>,
+}
+
+impl RoutesSnapshot {
+    /// Returns the URLs of up to `count` healthy nodes, ordered by their average latency.
+    /// Nodes without latency samples come last.
+    pub fn best_routes(&self, count: usize) -> Vec<Url> {
+        let mut healthy: Vec<&RouteInfo> = self.routes.iter().filter(|r| r.healthy).collect();
+        healthy.sort_by_key(|r| (r.average_latency.is_none(), r.average_latency));
+        healthy
+            .into_iter()
+            .take(count)
+            .map(|r| r.url.clone())
+            .collect()
+    }
+}
+
+struct NodeState {
+    domain: String,
+    url: Url,
+    healthy: bool,
+    latencies: VecDeque<Duration>,
+}
+
+impl NodeState {
+    /// Returns `None` and logs a warning if the domain doesn't make up a valid URL.
+    fn new(domain: String) -> Option<Self> {
+        let url = match Url::from_str(&format!("https://{domain}")) {
+            Ok(url)
+                if url
+                    .host_str()
+                    .is_some_and(|host| host.eq_ignore_ascii_case(&domain)) =>
+            {
+                url
+            }
+            _ => {
+                warn!("Skipping API boundary node with invalid domain {domain:?}");
+                return None;
+            }
+        };
+        Some(Self {
+            domain,
+            url,
+            healthy: true,
+            latencies: VecDeque::new(),
+        })
+    }
+
+    fn update(&mut self, status: HealthCheckStatus, latency_window: usize) {
+        match status {
+            HealthCheckStatus::Healthy(latency) => {
+                self.healthy = true;
+                self.latencies.push_back(latency);
+                while self.latencies.len() > latency_window {
+                    self.latencies.pop_front();
+                }
+            }
+            HealthCheckStatus::Unhealthy => self.healthy = false,
+        }
+    }
+
+    fn average_latency(&self) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        Some(self.latencies.iter().sum::<Duration>() / self.latencies.len() as u32)
+    }
+}
+
+/// Routes requests to the healthy API boundary nodes with the lowest latency.
+///
+/// The list of nodes is periodically refreshed from the registry and every node is
+/// periodically health-checked, see [`DynamicRouteProvider::start`]. Requests are distributed
+/// round-robin among the `max_routes` best nodes.
+pub struct DynamicRouteProvider<F, H> {
+    fetcher: F,
+    checker: H,
+    config: DynamicRouteProviderConfig,
+    nodes: RwLock<Vec<NodeState>>,
+    current_idx: AtomicUsize,
+}
+
+impl<F, H> DynamicRouteProvider<F, H>
+where
+    F: Fetch + Send + Sync + 'static,
+    H: HealthCheck + Send + Sync + 'static,
+{
+    /// Creates a provider that routes to the given domains until the first refresh from the
+    /// registry.
+    pub fn new<T: AsRef<str>>(
+        fetcher: F,
+        checker: H,
+        config: DynamicRouteProviderConfig,
+        seed_domains: Vec<T>,
+    ) -> Self {
+        let nodes = seed_domains
+            .iter()
+            .filter_map(|domain| NodeState::new(domain.as_ref().to_string()))
+            .collect();
+        Self {
+            fetcher,
+            checker,
+            config,
+            nodes: RwLock::new(nodes),
+            current_idx: AtomicUsize::new(0),
+        }
+    }
+
+    /// Replaces the known nodes with the ones currently in the registry, keeping the health
+    /// state of nodes that are still present. Invalid domains are skipped. A list from the
+    /// registry without any valid domain is ignored, so the provider never runs out of nodes to
+    /// try.
+    pub async fn refresh_nodes(&self) -> Result<(), ApiNodeRegistryFetchError> {
+        let mut fetched: Vec<NodeState> = self
+            .fetcher
+            .api_node_domains_from_registry()
+            .await?
+            .into_iter()
+            .filter_map(NodeState::new)
+            .collect();
+        if fetched.is_empty() {
+            return Ok(());
+        }
+        let mut nodes = self.nodes.write().unwrap();
+        for node in fetched.iter_mut() {
+            if let Some(previous) = nodes.iter_mut().find(|n| n.domain == node.domain) {
+                std::mem::swap(node, previous);
+            }
+        }
+        *nodes = fetched;
+        Ok(())
+    }
+
+    /// Health-checks all known nodes concurrently and records the results.
+    pub async fn check_health(&self) {
+        let domains: Vec<String> = self
+            .nodes
+            .read()
+            .unwrap()
+            .iter()
+            .map(|n| n.domain.clone())
+            .collect();
+        let statuses = join_all(domains.iter().map(|domain| self.checker.check(domain))).await;
+
+        let mut nodes = self.nodes.write().unwrap();
+        for (domain, status) in domains.iter().zip(statuses) {
+            // The node may have been removed by a refresh while it was checked.
+            if let Some(node) = nodes.iter_mut().find(|n| &n.domain == domain) {
+                node.update(status, self.config.latency_window);
+            }
+        }
+    }
+
+    /// Returns the current state of all known nodes.
+    pub fn snapshot(&self) -> RoutesSnapshot {
+        RoutesSnapshot {
+            routes: self
+                .nodes
+                .read()
+                .unwrap()
+                .iter()
+                .map(|n| RouteInfo {
+                    domain: n.domain.clone(),
+                    url: n.url.clone(),
+                    healthy: n.healthy,
+                    average_latency: n.average_latency(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Spawns a task that refreshes the nodes and checks their health at the configured
+    /// periods. The task runs until the returned handle is aborted.
+    pub fn start(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut fetch_interval = tokio::time::interval(self.config.fetch_period);
+            let mut health_check_interval = tokio::time::interval(self.config.health_check_period);
+            loop {
+                tokio::select! {
+                    _ = fetch_interval.tick() => {
+                        // On failure, keep routing to the nodes we already know.
+                        if self.refresh_nodes().await.is_ok() {
+                            self.check_health().await;
+                        }
+                    }
+                    _ = health_check_interval.tick() => self.check_health().await,
+                }
+            }
+        })
+    }
+}
+
+impl<F, H> RouteProvider for DynamicRouteProvider<F, H>
+where
+    F: Fetch + Send + Sync + 'static,
+    H: HealthCheck + Send + Sync + 'static,
+{
+    fn route(&self) -> Result<Url, RouteProviderError> {
+        let snapshot = self.snapshot();
+        if snapshot.routes.is_empty() {
+            return Err(RouteProviderError::NoExistingRoutesFound);
+        }
+        let routes = snapshot.best_routes(self.config.max_routes);
+        if routes.is_empty() {
+            return Err(RouteProviderError::NoHealthyRoutesFound);
+        }
+        let prev_idx = self.current_idx.fetch_add(1, Ordering::Relaxed);
+        Ok(routes[prev_idx % routes.len()].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use super::*;
+
+    struct FakeFetcher(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl Fetch for FakeFetcher {
+        async fn api_node_domains_from_registry(
+            &self,
+        ) -> Result<Vec<String>, ApiNodeRegistryFetchError> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+    }
+
+    struct FakeChecker(Mutex<HashMap<String, HealthCheckStatus>>);
+
+    #[async_trait]
+    impl HealthCheck for FakeChecker {
+        async fn check(&self, domain: &str) -> HealthCheckStatus {
+            self.0.lock().unwrap()[domain]
+        }
+    }
+
+    fn provider(
+        domains: &[&str],
+        statuses: &[(&str, HealthCheckStatus)],
+        max_routes: usize,
+    ) -> DynamicRouteProvider<FakeFetcher, FakeChecker> {
+        let fetcher = FakeFetcher(Mutex::new(domains.iter().map(|d| d.to_string()).collect()));
+        let checker = FakeChecker(Mutex::new(
+            statuses.iter().map(|(d, s)| (d.to_string(), *s)).collect(),
+        ));
+        let config = DynamicRouteProviderConfig {
+            latency_window: 2,
+            max_routes,
+            ..Default::default()
+        };
+        DynamicRouteProvider::new(fetcher, checker, config, vec!["seed.com"])
+    }
+
+    fn healthy(millis: u64) -> HealthCheckStatus {
+        HealthCheckStatus::Healthy(Duration::from_millis(millis))
+    }
+
+    fn url(domain: &str) -> Url {
+        Url::parse(&format!("https://{domain}")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_routes_to_seed_until_refreshed() {
+        let provider = provider(&[], &[("seed.com", healthy(10))], 2);
+        provider.refresh_nodes().await.unwrap();
+        assert_eq!(provider.route().unwrap(), url("seed.com"));
+    }
+
+    #[tokio::test]
+    async fn test_skips_unhealthy_nodes() {
+        let provider = provider(
+            &["api1.com", "api2.com"],
+            &[
+                ("api1.com", HealthCheckStatus::Unhealthy),
+                ("api2.com", healthy(30)),
+            ],
+            2,
+        );
+        provider.refresh_nodes().await.unwrap();
+        provider.check_health().await;
+        for _ in 0..3 {
+            assert_eq!(provider.route().unwrap(), url("api2.com"));
+        }
+
+        provider
+            .checker
+            .0
+            .lock()
+            .unwrap()
+            .insert("api2.com".to_string(), HealthCheckStatus::Unhealthy);
+        provider.check_health().await;
+        assert_eq!(
+            provider.route().unwrap_err(),
+            RouteProviderError::NoHealthyRoutesFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefers_lowest_average_latency() {
+        let provider = provider(
+            &["api1.com", "api2.com", "api3.com"],
+            &[
+                ("api1.com", healthy(50)),
+                ("api2.com", healthy(10)),
+                ("api3.com", healthy(30)),
+            ],
+            2,
+        );
+        provider.refresh_nodes().await.unwrap();
+        provider.check_health().await;
+        assert_eq!(
+            provider.snapshot().best_routes(2),
+            vec![url("api2.com"), url("api3.com")]
+        );
+        let routes: Vec<Url> = (0..4).map(|_| provider.route().unwrap()).collect();
+        assert_eq!(
+            routes,
+            vec![
+                url("api2.com"),
+                url("api3.com"),
+                url("api2.com"),
+                url("api3.com")
+            ]
+        );
+
+        // The average over the window of two samples is (10 + 70) / 2 = 40ms.
+        provider
+            .checker
+            .0
+            .lock()
+            .unwrap()
+            .insert("api2.com".to_string(), healthy(70));
+        provider.check_health().await;
+        let snapshot = provider.snapshot();
+        assert_eq!(
+            snapshot.routes[1].average_latency,
+            Some(Duration::from_millis(40))
+        );
+        assert_eq!(
+            snapshot.best_routes(2),
+            vec![url("api3.com"), url("api2.com")]
+        );
+
+        // Samples older than the window are dropped: (70 + 70) / 2 = 70ms.
+        provider.check_health().await;
+        assert_eq!(
+            provider.snapshot().best_routes(3),
+            vec![url("api3.com"), url("api1.com"), url("api2.com")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_keeps_state_of_remaining_nodes() {
+        let provider = provider(
+            &["api1.com", "api2.com"],
+            &[
+                ("api1.com", HealthCheckStatus::Unhealthy),
+                ("api2.com", healthy(10)),
+                ("api3.com", healthy(20)),
+            ],
+            3,
+        );
+        provider.refresh_nodes().await.unwrap();
+        provider.check_health().await;
+
+        *provider.fetcher.0.lock().unwrap() = vec!["api1.com".into(), "api3.com".into()];
+        provider.refresh_nodes().await.unwrap();
+        let snapshot = provider.snapshot();
+        let domains: Vec<&str> = snapshot.routes.iter().map(|r| r.domain.as_str()).collect();
+        assert_eq!(domains, vec!["api1.com", "api3.com"]);
+        assert!(!snapshot.routes[0].healthy);
+        // Unchecked nodes are healthy, but rank behind nodes with known latency.
+        assert!(snapshot.routes[1].healthy);
+        assert_eq!(snapshot.routes[1].average_latency, None);
+    }
+
+    #[tokio::test]
+    async fn test_skips_invalid_domains() {
+        let provider = provider(
+            &["api1.com", "not a domain", "api2.com/path", "api2.com"],
+            &[("api1.com", healthy(10)), ("api2.com", healthy(20))],
+            3,
+        );
+        provider.refresh_nodes().await.unwrap();
+        let snapshot = provider.snapshot();
+        let domains: Vec<&str> = snapshot.routes.iter().map(|r| r.domain.as_str()).collect();
+        assert_eq!(domains, vec!["api1.com", "api2.com"]);
+
+        // Without any valid domain, the known nodes are kept.
+        *provider.fetcher.0.lock().unwrap() = vec!["not a domain".into()];
+        provider.refresh_nodes().await.unwrap();
+        assert_eq!(provider.snapshot(), snapshot);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod api_nodes_discovery;
+pub mod dynamic_route_provider;
 pub mod route_provider;
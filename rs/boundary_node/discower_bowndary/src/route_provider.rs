@@ -9,6 +9,8 @@ use url::Url;
 pub enum RouteProviderError {
     #[error("No existing routes found")]
     NoExistingRoutesFound,
+    #[error("No healthy routes found")]
+    NoHealthyRoutesFound,
 }
 
 pub trait RouteProvider {
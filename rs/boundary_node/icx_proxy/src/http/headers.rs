@@ -2,3 +2,6 @@ pub static REQUIRE_CERTIFICATION_HEADER_NAME: &str = "x-icx-require-certificatio
 pub static IC_CERTIFICATE_HEADER_NAME: &str = "ic-certificate";
 pub static CACHE_HEADER_NAME: &str = "cache-control";
 pub static ACCEPT_ENCODING_HEADER_NAME: &str = "accept-encoding";
+pub static RANGE_HEADER_NAME: &str = "range";
+pub static CONTENT_RANGE_HEADER_NAME: &str = "content-range";
+pub static CONTENT_LENGTH_HEADER_NAME: &str = "content-length";
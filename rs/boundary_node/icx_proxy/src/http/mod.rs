@@ -1,4 +1,5 @@
 pub mod body;
 pub mod headers;
+pub mod range;
 pub mod request;
 pub mod response;
@@ -0,0 +1,132 @@
+//! Parsing of the `Range` and `Content-Range` headers, as far as needed to request and verify
+//! the body of a response chunk by chunk. Only single byte ranges are supported.
+use std::fmt;
+
+/// A byte range as requested with `Range: bytes=<start>-[<end>]`. Both ends are inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+impl ByteRange {
+    /// Parses the value of a `Range` header. Returns `None` for suffix ranges (`bytes=-500`),
+    /// multiple ranges and malformed values.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+        let start = start.trim().parse().ok()?;
+        let end = match end.trim() {
+            "" => None,
+            end => Some(end.parse().ok()?),
+        };
+        match end {
+            Some(end) if end < start => None,
+            _ => Some(ByteRange { start, end }),
+        }
+    }
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "bytes={}-{}", self.start, end),
+            None => write!(f, "bytes={}-", self.start),
+        }
+    }
+}
+
+/// The part of the body returned in a partial response, given by
+/// `Content-Range: bytes <start>-<end>/<total>`. Both ends are inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContentRange {
+    pub start: u64,
+    pub end: u64,
+    pub total: u64,
+}
+
+impl ContentRange {
+    /// Parses the value of a `Content-Range` header. Returns `None` for unknown total lengths
+    /// (`bytes 0-99/*`), unsatisfied ranges and malformed values.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+        let (start, end) = range.split_once('-')?;
+        let content_range = ContentRange {
+            start: start.trim().parse().ok()?,
+            end: end.trim().parse().ok()?,
+            total: total.trim().parse().ok()?,
+        };
+        if content_range.start > content_range.end || content_range.end >= content_range.total {
+            return None;
+        }
+        Some(content_range)
+    }
+
+    /// The number of bytes in the range.
+    pub fn size(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+impl fmt::Display for ContentRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bytes {}-{}/{}", self.start, self.end, self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::range::{ByteRange, ContentRange};
+
+    #[test]
+    fn parse_byte_range() {
+        assert_eq!(
+            ByteRange::parse("bytes=100-"),
+            Some(ByteRange {
+                start: 100,
+                end: None
+            })
+        );
+        assert_eq!(
+            ByteRange::parse("bytes=0-499"),
+            Some(ByteRange {
+                start: 0,
+                end: Some(499)
+            })
+        );
+        assert_eq!(ByteRange::parse("bytes=-500"), None);
+        assert_eq!(ByteRange::parse("bytes=0-10, 20-30"), None);
+        assert_eq!(ByteRange::parse("bytes=10-5"), None);
+        assert_eq!(ByteRange::parse("items=0-5"), None);
+    }
+
+    #[test]
+    fn byte_range_round_trip() {
+        for value in ["bytes=100-", "bytes=0-499"] {
+            assert_eq!(ByteRange::parse(value).unwrap().to_string(), value);
+        }
+    }
+
+    #[test]
+    fn parse_content_range() {
+        let content_range = ContentRange::parse("bytes 0-1023/4096").unwrap();
+
+        assert_eq!(
+            content_range,
+            ContentRange {
+                start: 0,
+                end: 1023,
+                total: 4096
+            }
+        );
+        assert_eq!(content_range.size(), 1024);
+        assert_eq!(content_range.to_string(), "bytes 0-1023/4096");
+    }
+
+    #[test]
+    fn reject_invalid_content_range() {
+        assert_eq!(ContentRange::parse("bytes 0-1023/*"), None);
+        assert_eq!(ContentRange::parse("bytes */4096"), None);
+        assert_eq!(ContentRange::parse("bytes 100-99/4096"), None);
+        assert_eq!(ContentRange::parse("bytes 0-4096/4096"), None);
+    }
+}
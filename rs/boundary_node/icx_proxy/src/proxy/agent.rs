@@ -29,18 +29,27 @@ use ic_utils::{
 use tokio_util::task::LocalPoolHandle;
 use tracing::{debug, enabled, info, trace, warn, Level};
 
+use crate::http::range::ByteRange;
 use crate::http::request::HttpRequest;
 use crate::http::response::{AgentResponseAny, HttpResponse};
 use crate::http_client::{HEADERS_IN, HEADERS_OUT, HEADER_X_REQUEST_ID};
 use crate::metrics::RequestContext;
 use crate::{
     canister_id,
-    proxy::{AppState, HandleError, HyperService},
+    proxy::{
+        verified_stream::{
+            should_verify_chunks, ChunkVerificationError, QueryChunkFetcher, VerifiedRangeStream,
+        },
+        AppState, HandleError, HyperService,
+    },
     validate::Validate,
 };
 use crate::{
     error::ErrorFactory,
-    http::headers::{ACCEPT_ENCODING_HEADER_NAME, CACHE_HEADER_NAME},
+    http::headers::{
+        ACCEPT_ENCODING_HEADER_NAME, CACHE_HEADER_NAME, CONTENT_LENGTH_HEADER_NAME,
+        CONTENT_RANGE_HEADER_NAME, RANGE_HEADER_NAME,
+    },
 };
 
 // The maximum length of a body we should log as tracing.
@@ -153,7 +162,7 @@ fn remove_hop_headers(headers: &HeaderMap<HeaderValue>) -> HeaderMap<HeaderValue
 // This function wraps Axum handler.
 // The local thread pool is used to pin all async calls in the handler to a single thread
 // which is needed to use TLS (Thread Local Storage)
-pub async fn handler_wrapper<V: Validate + 'static, C: HyperService<Body> + 'static>(
+pub async fn handler_wrapper<V: Validate + Clone + 'static, C: HyperService<Body> + 'static>(
     State(args): State<Args<V, C>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     uri_canister_id: Option<canister_id::UriHost>,
@@ -192,7 +201,7 @@ pub async fn handler_wrapper<V: Validate + 'static, C: HyperService<Body> + 'sta
     rx.await.unwrap()
 }
 
-pub async fn handler<V: Validate, C: HyperService<Body>>(
+pub async fn handler<V: Validate + Clone + 'static, C: HyperService<Body>>(
     State(mut args): State<Args<V, C>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     uri_canister_id: Option<canister_id::UriHost>,
@@ -231,7 +240,7 @@ async fn process_request_inner(
     addr: SocketAddr,
     agent: &Agent,
     replica_uri: &Uri,
-    validator: &impl Validate,
+    validator: &(impl Validate + Clone + 'static),
     client: &mut impl HyperService<Body>,
     canister_id: Option<Principal>,
 ) -> Result<Response<Body>, anyhow::Error> {
@@ -313,25 +322,7 @@ async fn process_request_inner(
     }
 
     let canister = HttpRequestCanister::create(agent, canister_id);
-    let header_fields = http_request
-        .headers
-        .iter()
-        .filter(|(name, _)| name != "x-request-id")
-        .map(|(name, value)| {
-            if name.eq_ignore_ascii_case(ACCEPT_ENCODING_HEADER_NAME) {
-                let mut encodings = value.split(',').map(|s| s.trim()).collect::<Vec<_>>();
-                if !encodings.iter().any(|s| s.eq_ignore_ascii_case("identity")) {
-                    encodings.push("identity");
-                };
-
-                let value = encodings.join(", ");
-                return HeaderField(name.into(), value.into());
-            }
-
-            HeaderField(name.into(), value.into())
-        })
-        .collect::<Vec<_>>() // it needs to be an ExactSizeIterator
-        .into_iter();
+    let header_fields = header_fields(&http_request).into_iter();
 
     let query_result = canister
         .http_request_custom(
@@ -369,12 +360,22 @@ async fn process_request_inner(
     };
 
     let http_response = HttpResponse::create(agent, &agent_response).await?;
+
+    // Certified bodies that are too large to be collected and verified at once are requested in
+    // ranges instead of through the streaming callback, so every chunk can be verified. Bodies of
+    // canisters that don't serve certified ranges are streamed as before.
+    if !is_update_call && should_verify_chunks(&http_request, &http_response) {
+        if let Some(response) =
+            process_verified_stream(agent, validator, canister_id, &http_request).await?
+        {
+            return Ok(response);
+        }
+    }
+
     let mut response_builder =
         Response::builder().status(StatusCode::from_u16(http_response.status_code)?);
 
-    // At the moment verification is only performed if the response is not using a streaming
-    // strategy. Performing verification for those requests would required to join all the chunks
-    // and this could cause memory issues and possibly create DOS attack vectors.
+    // Streamed responses are passed through as-is unless they were verified chunk by chunk above.
     let should_validate = !http_response.has_streaming_body && !is_update_call;
     let validation_info = if should_validate {
         let validation_result =
@@ -499,6 +500,125 @@ async fn process_request_inner(
     Ok(response)
 }
 
+/// Serves the body chunk by chunk, verifying each chunk before it is sent to the client. A client
+/// requesting a range of the body gets a partial response, everyone else the whole body. Returns
+/// `None` if the canister doesn't serve certified ranges, in which case the body is streamed
+/// through the streaming callback instead.
+async fn process_verified_stream(
+    agent: &Agent,
+    validator: &(impl Validate + Clone + 'static),
+    canister_id: Principal,
+    http_request: &HttpRequest,
+) -> Result<Option<Response<Body>>, anyhow::Error> {
+    let requested_range = http_request
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(RANGE_HEADER_NAME))
+        .and_then(|(_, value)| ByteRange::parse(value));
+    let request_size = http_request.body.len() as u64;
+
+    let stream = match VerifiedRangeStream::start(
+        agent.clone(),
+        canister_id,
+        validator.clone(),
+        QueryChunkFetcher,
+        http_request,
+        requested_range.unwrap_or(ByteRange {
+            start: 0,
+            end: None,
+        }),
+    )
+    .await
+    {
+        Ok(stream) => stream,
+        Err(ChunkVerificationError::RangeNotSatisfiable) => {
+            return Ok(Some(
+                Response::builder()
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .body(Body::empty())
+                    .unwrap(),
+            ));
+        }
+        Err(err) if err.is_unsupported() => {
+            warn!("Streaming the body without verifying chunks: {}", err);
+            return Ok(None);
+        }
+        Err(err) => {
+            warn!("Streamed body does not pass verification: {}", err);
+            return Ok(Some(
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body("Body does not pass verification".into())
+                    .unwrap(),
+            ));
+        }
+    };
+
+    let content_range = stream.content_range;
+    let mut response_builder = Response::builder();
+    for (name, value) in stream.headers() {
+        response_builder = response_builder.header(name, value);
+    }
+    response_builder = match requested_range {
+        Some(_) => response_builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(CONTENT_RANGE_HEADER_NAME, content_range.to_string()),
+        None => response_builder.status(StatusCode::OK),
+    };
+
+    let mut response = response_builder
+        .header(CONTENT_LENGTH_HEADER_NAME, content_range.size())
+        .header("X-IC-Streaming-Response", "true")
+        .body(stream.into_body())?;
+
+    HEADERS_IN.with(|f| {
+        for (k, v) in (*f.borrow()).iter() {
+            response.headers_mut().insert(k, v.clone());
+        }
+    });
+
+    response.extensions_mut().insert(RequestContext {
+        request_size,
+        streaming_request: true,
+    });
+
+    info!(
+        ">> {:?} {} {}",
+        &response.version(),
+        response.status().as_u16(),
+        response.status().to_string(),
+    );
+    info!(
+        ">> verified streaming body of {} bytes",
+        content_range.size()
+    );
+
+    Ok(Some(response))
+}
+
+/// Returns the request headers to pass on to the canister.
+pub(super) fn header_fields(http_request: &HttpRequest) -> Vec<HeaderField<'_>> {
+    http_request
+        .headers
+        .iter()
+        .filter(|(name, _)| name != "x-request-id")
+        .map(|(name, value)| {
+            if name.eq_ignore_ascii_case(ACCEPT_ENCODING_HEADER_NAME) {
+                let mut encodings = value.split(',').map(|s| s.trim()).collect::<Vec<_>>();
+                if !encodings.iter().any(|s| s.eq_ignore_ascii_case("identity")) {
+                    encodings.push("identity");
+                };
+
+                let value = encodings.join(", ");
+                return HeaderField(name.into(), value.into());
+            }
+
+            HeaderField(name.into(), value.into())
+        })
+        // it needs to be an ExactSizeIterator
+        .collect()
+}
+
 fn handle_result(
     result: Result<(AgentResponseAny,), AgentError>,
 ) -> Result<AgentResponseAny, Result<Response<Body>, anyhow::Error>> {
@@ -43,6 +43,7 @@ pub struct ProxyOpts {
 }
 
 pub mod agent;
+pub mod verified_stream;
 
 use agent::{handler_wrapper as agent_handler, Pool};
 
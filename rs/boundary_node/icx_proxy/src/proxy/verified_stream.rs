@@ -0,0 +1,605 @@
+//! Chunk-wise verification of streamed responses.
+//!
+//! The chunks a canister returns from its streaming callback carry no certificate, so they can't
+//! be verified on their own. Instead, certified streamed bodies are requested in byte ranges:
+//! with response verification v2, the canister certifies every `206 Partial Content` response,
+//! including its `Content-Range` header and the chunk it contains. Each chunk is verified as it
+//! arrives and only then forwarded to the client. The stream is aborted on the first chunk that
+//! doesn't pass verification or doesn't continue where the previous one ended. Canisters that
+//! don't return a certified `206` for the first range are left to the streaming callback.
+use async_trait::async_trait;
+use candid::Principal;
+use futures::StreamExt;
+use hyper::{Body, StatusCode};
+use ic_agent::{Agent, AgentError};
+use ic_response_verification::MAX_VERIFICATION_VERSION;
+use ic_utils::{call::SyncCall, interfaces::http_request::HttpRequestCanister};
+use tracing::warn;
+
+use super::agent::header_fields;
+use crate::{
+    http::{
+        headers::{CONTENT_LENGTH_HEADER_NAME, CONTENT_RANGE_HEADER_NAME, RANGE_HEADER_NAME},
+        range::{ByteRange, ContentRange},
+        request::HttpRequest,
+        response::HttpResponse,
+    },
+    validate::Validate,
+};
+
+// Limit the number of range requests per response, like the number of streaming callbacks.
+const MAX_RANGE_REQUEST_COUNT: usize = 1000;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ChunkVerificationError {
+    #[error("Failed to request the chunk: {0}")]
+    Agent(#[from] AgentError),
+    #[error(
+        "The chunk is streamed itself, which means the canister doesn't support range requests"
+    )]
+    Streamed,
+    #[error("The requested range is not satisfiable")]
+    RangeNotSatisfiable,
+    #[error("The chunk has status code {0} instead of 206")]
+    NotPartial(u16),
+    #[error("The chunk has no valid Content-Range header")]
+    MissingContentRange,
+    #[error("The chunk covers {actual} instead of starting at byte {expected_start}")]
+    UnexpectedRange {
+        expected_start: u64,
+        actual: ContentRange,
+    },
+    #[error("The chunk has {actual} bytes instead of {expected}")]
+    UnexpectedLength { expected: u64, actual: usize },
+    #[error("The chunk does not pass verification: {0}")]
+    VerificationFailed(String),
+    #[error("The chunk is not certified")]
+    NotCertified,
+    #[error(
+        "The chunk is certified with response verification v{0}, which doesn't certify ranges"
+    )]
+    UnsupportedVersion(u16),
+    #[error("The body requires more than {0} range requests")]
+    TooManyRequests(usize),
+}
+
+impl ChunkVerificationError {
+    /// Whether the error means that the canister doesn't serve certified ranges, as opposed to
+    /// serving a range that doesn't pass verification.
+    pub fn is_unsupported(&self) -> bool {
+        matches!(
+            self,
+            Self::Streamed | Self::NotPartial(_) | Self::NotCertified | Self::UnsupportedVersion(_)
+        )
+    }
+}
+
+/// Sends the request for a chunk to the canister.
+#[async_trait]
+pub trait FetchChunk: Sync + Send {
+    async fn fetch(
+        &self,
+        agent: &Agent,
+        canister_id: &Principal,
+        request: &HttpRequest,
+    ) -> Result<HttpResponse, AgentError>;
+}
+
+/// Fetches chunks with query calls to the canister's `http_request` method.
+#[derive(Clone, Copy)]
+pub struct QueryChunkFetcher;
+
+#[async_trait]
+impl FetchChunk for QueryChunkFetcher {
+    async fn fetch(
+        &self,
+        agent: &Agent,
+        canister_id: &Principal,
+        request: &HttpRequest,
+    ) -> Result<HttpResponse, AgentError> {
+        let canister = HttpRequestCanister::create(agent, *canister_id);
+        let (agent_response,) = canister
+            .http_request_custom(
+                &request.method,
+                request.uri.to_string().as_str(),
+                header_fields(request).into_iter(),
+                &request.body,
+                Some(&u16::from(MAX_VERIFICATION_VERSION)),
+            )
+            .call()
+            .await?;
+        Ok(HttpResponse {
+            status_code: agent_response.status_code,
+            headers: agent_response
+                .headers
+                .iter()
+                .map(|field| (field.0.to_string(), field.1.to_string()))
+                .collect(),
+            body: agent_response.body,
+            streaming_body: None,
+            has_streaming_body: agent_response.streaming_strategy.is_some(),
+        })
+    }
+}
+
+/// A chunk that passed verification.
+pub struct VerifiedChunk {
+    pub content_range: ContentRange,
+    /// The certified headers of the chunk, or all of them if the canister certifiably skipped
+    /// verification.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Everything needed to request and verify the chunks of a body.
+struct ChunkSource<V, F> {
+    agent: Agent,
+    canister_id: Principal,
+    validator: V,
+    fetcher: F,
+    /// The request of the client, without its `Range` header.
+    request: HttpRequest,
+}
+
+impl<V: Validate, F: FetchChunk> ChunkSource<V, F> {
+    /// Requests the given range of the body and verifies the returned chunk.
+    async fn fetch_verified_chunk(
+        &self,
+        range: ByteRange,
+    ) -> Result<VerifiedChunk, ChunkVerificationError> {
+        let chunk_request = HttpRequest {
+            uri: self.request.uri.clone(),
+            method: self.request.method.clone(),
+            headers: self
+                .request
+                .headers
+                .iter()
+                .cloned()
+                .chain(std::iter::once((
+                    RANGE_HEADER_NAME.to_string(),
+                    range.to_string(),
+                )))
+                .collect(),
+            body: self.request.body.clone(),
+        };
+        let chunk_response = self
+            .fetcher
+            .fetch(&self.agent, &self.canister_id, &chunk_request)
+            .await?;
+        verify_chunk(
+            &self.agent,
+            &self.canister_id,
+            &self.validator,
+            &chunk_request,
+            chunk_response,
+            range,
+        )
+    }
+}
+
+/// Requests a range of the body and fetches the remaining chunks on demand, verifying each.
+pub struct VerifiedRangeStream<V, F = QueryChunkFetcher> {
+    source: ChunkSource<V, F>,
+    /// The first chunk, whose headers are used for the response.
+    pub first_chunk: VerifiedChunk,
+    /// The range of the body that is returned to the client.
+    pub content_range: ContentRange,
+}
+
+impl<V, F> VerifiedRangeStream<V, F>
+where
+    V: Validate + 'static,
+    F: FetchChunk + 'static,
+{
+    /// Fetches and verifies the first chunk of the given range of the body. A range that ends
+    /// after the end of the body is truncated.
+    pub async fn start(
+        agent: Agent,
+        canister_id: Principal,
+        validator: V,
+        fetcher: F,
+        request: &HttpRequest,
+        range: ByteRange,
+    ) -> Result<Self, ChunkVerificationError> {
+        let source = ChunkSource {
+            agent,
+            canister_id,
+            validator,
+            fetcher,
+            request: HttpRequest {
+                uri: request.uri.clone(),
+                method: request.method.clone(),
+                headers: request
+                    .headers
+                    .iter()
+                    .filter(|(name, _)| !name.eq_ignore_ascii_case(RANGE_HEADER_NAME))
+                    .cloned()
+                    .collect(),
+                body: request.body.clone(),
+            },
+        };
+        let first_chunk = source.fetch_verified_chunk(range).await?;
+
+        let total = first_chunk.content_range.total;
+        let content_range = ContentRange {
+            start: range.start,
+            end: range.end.map_or(total - 1, |end| end.min(total - 1)),
+            total,
+        };
+
+        Ok(Self {
+            source,
+            first_chunk,
+            content_range,
+        })
+    }
+
+    /// The headers of the response to the client, i.e. the headers of the first chunk without
+    /// those describing the chunk itself.
+    pub fn headers(&self) -> impl Iterator<Item = &(String, String)> {
+        self.first_chunk.headers.iter().filter(|(name, _)| {
+            !name.eq_ignore_ascii_case(CONTENT_RANGE_HEADER_NAME)
+                && !name.eq_ignore_ascii_case(CONTENT_LENGTH_HEADER_NAME)
+        })
+    }
+
+    /// Turns the stream into a body, starting with the first chunk. Subsequent chunks are only
+    /// requested once the previous one has been sent.
+    pub fn into_body(self) -> Body {
+        let Self {
+            source,
+            first_chunk,
+            content_range,
+        } = self;
+        let next = first_chunk.content_range.end + 1;
+
+        let chunks = futures::stream::try_unfold(
+            (source, next, 1),
+            move |(source, next, request_count)| async move {
+                if next > content_range.end {
+                    return Ok(None);
+                }
+                if request_count >= MAX_RANGE_REQUEST_COUNT {
+                    warn!(
+                        "Aborting verified stream after {} range requests",
+                        request_count
+                    );
+                    return Err(ChunkVerificationError::TooManyRequests(
+                        MAX_RANGE_REQUEST_COUNT,
+                    ));
+                }
+                let range = ByteRange {
+                    start: next,
+                    end: Some(content_range.end),
+                };
+                let chunk = source.fetch_verified_chunk(range).await.map_err(|err| {
+                    warn!("Aborting verified stream: {}", err);
+                    err
+                })?;
+                let next = chunk.content_range.end + 1;
+                Ok(Some((chunk.body, (source, next, request_count + 1))))
+            },
+        );
+
+        Body::wrap_stream(
+            futures::stream::once(async move { Ok::<_, ChunkVerificationError>(first_chunk.body) })
+                .chain(chunks),
+        )
+    }
+}
+
+/// Verifies the chunk returned for the given range of the body. Every chunk has to be certified
+/// with response verification v2 or later, which covers the status code and `Content-Range`.
+fn verify_chunk(
+    agent: &Agent,
+    canister_id: &Principal,
+    validator: &impl Validate,
+    chunk_request: &HttpRequest,
+    chunk_response: HttpResponse,
+    range: ByteRange,
+) -> Result<VerifiedChunk, ChunkVerificationError> {
+    if chunk_response.has_streaming_body {
+        return Err(ChunkVerificationError::Streamed);
+    }
+    // Nothing of the body is returned, so there is nothing to verify.
+    if chunk_response.status_code == StatusCode::RANGE_NOT_SATISFIABLE.as_u16() {
+        return Err(ChunkVerificationError::RangeNotSatisfiable);
+    }
+
+    let validation_info = validator
+        .validate(agent, canister_id, chunk_request, &chunk_response)
+        .map_err(|err| ChunkVerificationError::VerificationFailed(err.to_string()))?;
+    let headers = match validation_info {
+        None => return Err(ChunkVerificationError::NotCertified),
+        // Status codes and headers are only certified from v2 on.
+        Some(info) if info.verification_version < 2 => {
+            return Err(ChunkVerificationError::UnsupportedVersion(
+                info.verification_version,
+            ))
+        }
+        // The canister certifiably skipped verification.
+        Some(info) => match info.response {
+            None => chunk_response.headers,
+            Some(certified_response) => certified_response.headers,
+        },
+    };
+
+    if chunk_response.status_code != StatusCode::PARTIAL_CONTENT.as_u16() {
+        return Err(ChunkVerificationError::NotPartial(
+            chunk_response.status_code,
+        ));
+    }
+    let content_range = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(CONTENT_RANGE_HEADER_NAME))
+        .and_then(|(_, value)| ContentRange::parse(value))
+        .ok_or(ChunkVerificationError::MissingContentRange)?;
+    if content_range.start != range.start || range.end.is_some_and(|end| content_range.end > end) {
+        return Err(ChunkVerificationError::UnexpectedRange {
+            expected_start: range.start,
+            actual: content_range,
+        });
+    }
+    if content_range.size() != chunk_response.body.len() as u64 {
+        return Err(ChunkVerificationError::UnexpectedLength {
+            expected: content_range.size(),
+            actual: chunk_response.body.len(),
+        });
+    }
+
+    Ok(VerifiedChunk {
+        content_range,
+        headers,
+        body: chunk_response.body,
+    })
+}
+
+/// Returns whether the body of the given response should be verified chunk by chunk rather than
+/// be streamed as returned by the canister's streaming callback.
+pub fn should_verify_chunks(request: &HttpRequest, response: &HttpResponse) -> bool {
+    response.has_streaming_body
+        && (request.is_certification_required() || response.has_ic_certificate())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use async_trait::async_trait;
+    use candid::Principal;
+    use hyper::Uri;
+    use ic_agent::{
+        agent::http_transport::hyper_transport::{hyper::Body, HyperReplicaV2Transport},
+        Agent, AgentError,
+    };
+    use ic_response_verification::types::VerificationInfo;
+
+    use super::{ChunkVerificationError, FetchChunk, VerifiedRangeStream};
+    use crate::{
+        http::{
+            headers::{CONTENT_RANGE_HEADER_NAME, RANGE_HEADER_NAME},
+            range::{ByteRange, ContentRange},
+            request::HttpRequest,
+            response::HttpResponse,
+        },
+        validate::Validate,
+    };
+
+    const BODY: &[u8] = b"0123456789";
+
+    fn range_of(request: &HttpRequest) -> ByteRange {
+        request
+            .headers
+            .iter()
+            .find(|(name, _)| name == RANGE_HEADER_NAME)
+            .and_then(|(_, value)| ByteRange::parse(value))
+            .unwrap()
+    }
+
+    /// Certifies every chunk, unless it is not certified at all or rejected.
+    struct TestValidator {
+        certified: bool,
+        reject_from: Option<u64>,
+    }
+
+    impl Validate for TestValidator {
+        fn validate(
+            &self,
+            _agent: &Agent,
+            _canister_id: &Principal,
+            request: &HttpRequest,
+            _response: &HttpResponse,
+        ) -> Result<Option<VerificationInfo>, Cow<'static, str>> {
+            if self
+                .reject_from
+                .is_some_and(|start| range_of(request).start >= start)
+            {
+                return Err("Body does not pass verification".into());
+            }
+            if !self.certified {
+                return Ok(None);
+            }
+            Ok(Some(VerificationInfo {
+                response: None,
+                verification_version: 2,
+            }))
+        }
+    }
+
+    /// Returns chunks of at most `chunk_size` bytes of `BODY`, starting one byte late from
+    /// `shift_from` on.
+    struct TestFetcher {
+        chunk_size: u64,
+        shift_from: Option<u64>,
+    }
+
+    #[async_trait]
+    impl FetchChunk for TestFetcher {
+        async fn fetch(
+            &self,
+            _agent: &Agent,
+            _canister_id: &Principal,
+            request: &HttpRequest,
+        ) -> Result<HttpResponse, AgentError> {
+            let range = range_of(request);
+            let total = BODY.len() as u64;
+            let mut start = range.start;
+            if self.shift_from.is_some_and(|from| start >= from) {
+                start += 1;
+            }
+            if start >= total {
+                return Ok(HttpResponse {
+                    status_code: 416,
+                    headers: vec![],
+                    body: vec![],
+                    streaming_body: None,
+                    has_streaming_body: false,
+                });
+            }
+            let end = (start + self.chunk_size - 1)
+                .min(range.end.unwrap_or(u64::MAX))
+                .min(total - 1);
+            let content_range = ContentRange { start, end, total };
+            Ok(HttpResponse {
+                status_code: 206,
+                headers: vec![(
+                    CONTENT_RANGE_HEADER_NAME.to_string(),
+                    content_range.to_string(),
+                )],
+                body: BODY[start as usize..=end as usize].to_vec(),
+                streaming_body: None,
+                has_streaming_body: false,
+            })
+        }
+    }
+
+    async fn start(
+        validator: TestValidator,
+        fetcher: TestFetcher,
+        range: ByteRange,
+    ) -> Result<VerifiedRangeStream<TestValidator, TestFetcher>, ChunkVerificationError> {
+        let url = "http://www.example.com";
+        let transport = HyperReplicaV2Transport::<Body>::create(url).unwrap();
+        let agent = Agent::builder().with_transport(transport).build().unwrap();
+        let canister_id = Principal::from_text("wwc2m-2qaaa-aaaac-qaaaa-cai").unwrap();
+        let request = HttpRequest {
+            uri: Uri::from_static(url),
+            method: String::from("GET"),
+            body: Vec::new(),
+            headers: Vec::new(),
+        };
+        VerifiedRangeStream::start(agent, canister_id, validator, fetcher, &request, range).await
+    }
+
+    fn certifying() -> TestValidator {
+        TestValidator {
+            certified: true,
+            reject_from: None,
+        }
+    }
+
+    fn in_order() -> TestFetcher {
+        TestFetcher {
+            chunk_size: 4,
+            shift_from: None,
+        }
+    }
+
+    const WHOLE_BODY: ByteRange = ByteRange {
+        start: 0,
+        end: None,
+    };
+
+    #[tokio::test]
+    async fn verified_chunks_make_up_the_body() {
+        let Ok(stream) = start(certifying(), in_order(), WHOLE_BODY).await else {
+            panic!("the first chunk should pass verification");
+        };
+        assert_eq!(
+            stream.content_range,
+            ContentRange {
+                start: 0,
+                end: 9,
+                total: 10
+            }
+        );
+        let body = hyper::body::to_bytes(stream.into_body()).await.unwrap();
+        assert_eq!(body, BODY);
+    }
+
+    #[tokio::test]
+    async fn requested_range_is_truncated_to_the_body() {
+        let range = ByteRange {
+            start: 3,
+            end: Some(100),
+        };
+        let Ok(stream) = start(certifying(), in_order(), range).await else {
+            panic!("the first chunk should pass verification");
+        };
+        assert_eq!(stream.content_range.size(), 7);
+        let body = hyper::body::to_bytes(stream.into_body()).await.unwrap();
+        assert_eq!(body, BODY[3..]);
+    }
+
+    #[tokio::test]
+    async fn stream_is_aborted_on_chunk_that_fails_verification() {
+        let validator = TestValidator {
+            certified: true,
+            reject_from: Some(4),
+        };
+        let Ok(stream) = start(validator, in_order(), WHOLE_BODY).await else {
+            panic!("the first chunk should pass verification");
+        };
+        assert!(hyper::body::to_bytes(stream.into_body()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn stream_is_aborted_on_chunk_out_of_order() {
+        let fetcher = TestFetcher {
+            chunk_size: 4,
+            shift_from: Some(4),
+        };
+        let Ok(stream) = start(certifying(), fetcher, WHOLE_BODY).await else {
+            panic!("the first chunk should pass verification");
+        };
+        assert!(hyper::body::to_bytes(stream.into_body()).await.is_err());
+
+        let fetcher = TestFetcher {
+            chunk_size: 4,
+            shift_from: Some(0),
+        };
+        assert!(matches!(
+            start(certifying(), fetcher, WHOLE_BODY).await,
+            Err(ChunkVerificationError::UnexpectedRange {
+                expected_start: 0,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn uncertified_first_chunk_is_unsupported() {
+        let validator = TestValidator {
+            certified: false,
+            reject_from: None,
+        };
+        match start(validator, in_order(), WHOLE_BODY).await {
+            Err(err @ ChunkVerificationError::NotCertified) => assert!(err.is_unsupported()),
+            _ => panic!("an uncertified first chunk should be rejected"),
+        }
+    }
+
+    #[tokio::test]
+    async fn unsatisfiable_range_is_reported() {
+        let range = ByteRange {
+            start: 10,
+            end: None,
+        };
+        match start(certifying(), in_order(), range).await {
+            Err(err @ ChunkVerificationError::RangeNotSatisfiable) => {
+                assert!(!err.is_unsupported())
+            }
+            _ => panic!("a range after the end of the body should not be satisfiable"),
+        }
+    }
+}
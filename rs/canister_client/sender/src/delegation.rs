@@ -0,0 +1,214 @@
+//! Local validation of delegation chains, mirroring the checks performed by
+//! the ingress validator on the replica side.
+use crate::{ed25519_public_key_to_der, SigKeys};
+use ic_types::crypto::Signable;
+use ic_types::messages::SignedDelegation;
+use ic_types::{CanisterId, Time};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Maximum number of delegations accepted by the replica in a single request.
+pub const MAXIMUM_NUMBER_OF_DELEGATIONS: usize = 20;
+
+/// Maximum number of targets accepted by the replica in a single delegation.
+pub const MAXIMUM_NUMBER_OF_TARGETS_PER_DELEGATION: usize = 1_000;
+
+/// Length of the DER prefix of an Ed25519 `SubjectPublicKeyInfo`.
+const ED25519_DER_PREFIX_LEN: usize = 12;
+const ED25519_PUBLIC_KEY_LEN: usize = 32;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DelegationError {
+    /// The sender carries no delegations.
+    EmptyChain,
+    /// The chain is longer than what the replica accepts.
+    TooManyDelegations { length: usize, maximum: usize },
+    /// A delegation lists more targets than what the replica accepts.
+    TooManyTargets {
+        index: usize,
+        length: usize,
+        maximum: usize,
+    },
+    /// The targets of a delegation could not be parsed.
+    InvalidTargets { index: usize, reason: String },
+    /// The canister the request is addressed to is not among the targets.
+    CanisterNotInTargets {
+        index: usize,
+        canister_id: CanisterId,
+    },
+    /// A delegation has expired.
+    Expired {
+        index: usize,
+        expiration: Time,
+        current_time: Time,
+    },
+    /// The same public key appears more than once in the chain.
+    Cycle { index: usize },
+    /// The signature of a delegation does not verify under the key of its
+    /// delegator.
+    InvalidSignature { index: usize },
+    /// The last delegation is not issued to the session key.
+    SessionKeyMismatch,
+}
+
+impl fmt::Display for DelegationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyChain => write!(f, "the delegation chain is empty"),
+            Self::TooManyDelegations { length, maximum } => write!(
+                f,
+                "the delegation chain has {} delegations, at most {} are allowed",
+                length, maximum
+            ),
+            Self::TooManyTargets {
+                index,
+                length,
+                maximum,
+            } => write!(
+                f,
+                "delegation {} has {} targets, at most {} are allowed",
+                index, length, maximum
+            ),
+            Self::InvalidTargets { index, reason } => {
+                write!(f, "delegation {} has invalid targets: {}", index, reason)
+            }
+            Self::CanisterNotInTargets { index, canister_id } => write!(
+                f,
+                "canister {} is not among the targets of delegation {}",
+                canister_id, index
+            ),
+            Self::Expired {
+                index,
+                expiration,
+                current_time,
+            } => write!(
+                f,
+                "delegation {} expired at {}, current time is {}",
+                index, expiration, current_time
+            ),
+            Self::Cycle { index } => write!(
+                f,
+                "the public key of delegation {} already appears earlier in the chain",
+                index
+            ),
+            Self::InvalidSignature { index } => {
+                write!(f, "the signature of delegation {} is invalid", index)
+            }
+            Self::SessionKeyMismatch => write!(
+                f,
+                "the last delegation is not issued to the session key of the sender"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DelegationError {}
+
+/// Validates `delegations`, rooted at `delegator_pub_key` and ending at
+/// `session_keys`, for a request addressed to `canister_id` (if any) at
+/// `current_time`.
+///
+/// Signatures are verified for Ed25519 and secp256k1 delegators. Other key
+/// types (notably canister signatures, as used by Internet Identity) can only
+/// be verified against the state tree of the issuing subnet and are left to
+/// the replica.
+pub fn validate_delegation_chain(
+    delegator_pub_key: &[u8],
+    delegations: &[SignedDelegation],
+    session_keys: &SigKeys,
+    canister_id: Option<&CanisterId>,
+    current_time: Time,
+) -> Result<(), DelegationError> {
+    if delegations.is_empty() {
+        return Err(DelegationError::EmptyChain);
+    }
+    if delegations.len() > MAXIMUM_NUMBER_OF_DELEGATIONS {
+        return Err(DelegationError::TooManyDelegations {
+            length: delegations.len(),
+            maximum: MAXIMUM_NUMBER_OF_DELEGATIONS,
+        });
+    }
+
+    let mut seen_keys: BTreeSet<&[u8]> = BTreeSet::new();
+    seen_keys.insert(delegator_pub_key);
+    let mut signer = delegator_pub_key;
+    for (index, signed_delegation) in delegations.iter().enumerate() {
+        let delegation = signed_delegation.delegation();
+
+        if let Some(length) = delegation.number_of_targets() {
+            if length > MAXIMUM_NUMBER_OF_TARGETS_PER_DELEGATION {
+                return Err(DelegationError::TooManyTargets {
+                    index,
+                    length,
+                    maximum: MAXIMUM_NUMBER_OF_TARGETS_PER_DELEGATION,
+                });
+            }
+        }
+        let targets = delegation
+            .targets()
+            .map_err(|reason| DelegationError::InvalidTargets { index, reason })?;
+        if let (Some(targets), Some(canister_id)) = (targets, canister_id) {
+            if !targets.contains(canister_id) {
+                return Err(DelegationError::CanisterNotInTargets {
+                    index,
+                    canister_id: *canister_id,
+                });
+            }
+        }
+
+        if delegation.expiration() < current_time {
+            return Err(DelegationError::Expired {
+                index,
+                expiration: delegation.expiration(),
+                current_time,
+            });
+        }
+
+        if !seen_keys.insert(delegation.pubkey().as_slice()) {
+            return Err(DelegationError::Cycle { index });
+        }
+
+        if verify_signature(
+            signer,
+            &delegation.as_signed_bytes(),
+            &signed_delegation.signature().0,
+        ) == Some(false)
+        {
+            return Err(DelegationError::InvalidSignature { index });
+        }
+        signer = delegation.pubkey();
+    }
+
+    if signer != session_keys.public_key_der().as_slice() {
+        return Err(DelegationError::SessionKeyMismatch);
+    }
+    Ok(())
+}
+
+/// Verifies `signature` on `msg` under the DER encoded `pub_key_der`.
+///
+/// Returns `None` if the key type cannot be verified locally.
+fn verify_signature(pub_key_der: &[u8], msg: &[u8], signature: &[u8]) -> Option<bool> {
+    if pub_key_der.len() == ED25519_DER_PREFIX_LEN + ED25519_PUBLIC_KEY_LEN
+        && pub_key_der[..ED25519_DER_PREFIX_LEN]
+            == ed25519_public_key_to_der(vec![])[..ED25519_DER_PREFIX_LEN]
+    {
+        let verified =
+            <[u8; ED25519_PUBLIC_KEY_LEN]>::try_from(&pub_key_der[ED25519_DER_PREFIX_LEN..])
+                .ok()
+                .and_then(|key| ed25519_consensus::VerificationKey::try_from(key).ok())
+                .zip(
+                    <[u8; 64]>::try_from(signature)
+                        .ok()
+                        .map(ed25519_consensus::Signature::from),
+                )
+                .map_or(false, |(key, signature)| {
+                    key.verify(&signature, msg).is_ok()
+                });
+        return Some(verified);
+    }
+    if let Ok(key) = ic_crypto_ecdsa_secp256k1::PublicKey::deserialize_der(pub_key_der) {
+        return Some(key.verify_signature(msg, signature));
+    }
+    None
+}
@@ -1,7 +1,13 @@
+mod delegation;
 mod secp256k1_conversions;
 #[cfg(test)]
 mod tests;
 
+pub use delegation::{
+    validate_delegation_chain, DelegationError, MAXIMUM_NUMBER_OF_DELEGATIONS,
+    MAXIMUM_NUMBER_OF_TARGETS_PER_DELEGATION,
+};
+
 use ic_base_types::PrincipalId;
 use ic_crypto_internal_types::sign::eddsa::ed25519::{
     PublicKey as Ed25519PublicKey, SecretKey as Ed25519SecretKey,
@@ -9,7 +15,8 @@ use ic_crypto_internal_types::sign::eddsa::ed25519::{
 use ic_crypto_utils_basic_sig::conversions::Ed25519PemParseError;
 use ic_crypto_utils_basic_sig::conversions::Ed25519SecretKeyConversions;
 use ic_types::crypto::DOMAIN_IC_REQUEST;
-use ic_types::messages::MessageId;
+use ic_types::messages::{MessageId, SignedDelegation};
+use ic_types::{CanisterId, Time};
 use rand::{CryptoRng, Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 use std::{error::Error, sync::Arc};
@@ -96,6 +103,22 @@ impl SigKeys {
             Err("unsupported or malformed secret key pem")
         }
     }
+
+    /// Returns the DER encoded public key.
+    pub fn public_key_der(&self) -> Vec<u8> {
+        match self {
+            SigKeys::Ed25519(key_pair) => ed25519_public_key_to_der(key_pair.public_key.to_vec()),
+            SigKeys::EcdsaSecp256k1(key_pair) => key_pair.pk.serialize_der(),
+        }
+    }
+
+    /// Signs `msg` as is, without adding a domain separator.
+    pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            SigKeys::Ed25519(key_pair) => key_pair.sign(msg).to_vec(),
+            SigKeys::EcdsaSecp256k1(key_pair) => key_pair.sign(msg),
+        }
+    }
 }
 
 /// Represents the identity of the sender.
@@ -125,6 +148,18 @@ pub enum Sender {
         /// Function that signs the message id
         sign: SignMessageId,
     },
+    /// A session key acting on behalf of another identity through a chain of
+    /// signed delegations, as e.g. produced by Internet Identity.
+    Delegated {
+        /// DER encoded public key of the identity at the root of the chain,
+        /// which determines the principal of the sender.
+        delegator_pub_key: Vec<u8>,
+        /// The delegations, starting with the one signed by the delegator and
+        /// ending with the one issued to the session key.
+        delegations: Vec<SignedDelegation>,
+        /// The session key signing the requests.
+        session_keys: SigKeys,
+    },
 }
 
 impl Sender {
@@ -156,17 +191,27 @@ impl Sender {
         Sender::PrincipalId(principal_id)
     }
 
+    pub fn from_delegation_chain(
+        delegator_pub_key: Vec<u8>,
+        delegations: Vec<SignedDelegation>,
+        session_keys: SigKeys,
+    ) -> Self {
+        Sender::Delegated {
+            delegator_pub_key,
+            delegations,
+            session_keys,
+        }
+    }
+
     pub fn get_principal_id(&self) -> PrincipalId {
         match self {
-            Self::SigKeys(sig_keys) => match sig_keys {
-                SigKeys::Ed25519(key_pair) => PrincipalId::new_self_authenticating(
-                    &ed25519_public_key_to_der(key_pair.public_key.to_vec()),
-                ),
-                SigKeys::EcdsaSecp256k1(key_pair) => {
-                    PrincipalId::new_self_authenticating(&key_pair.pk.serialize_der())
-                }
-            },
+            Self::SigKeys(sig_keys) => {
+                PrincipalId::new_self_authenticating(&sig_keys.public_key_der())
+            }
             Self::ExternalHsm { pub_key, .. } => PrincipalId::new_self_authenticating(pub_key),
+            Self::Delegated {
+                delegator_pub_key, ..
+            } => PrincipalId::new_self_authenticating(delegator_pub_key),
             Self::Anonymous => PrincipalId::new_anonymous(),
             Self::PrincipalId(id) => *id,
             Self::Node { pub_key, .. } => {
@@ -191,13 +236,9 @@ impl Sender {
         msg.extend_from_slice(DOMAIN_IC_REQUEST);
         msg.extend_from_slice(raw_msg);
         match self {
-            Self::SigKeys(sig_keys) => match sig_keys {
-                SigKeys::Ed25519(key_pair) => Ok(Some(key_pair.sign(&msg).to_vec())),
-                SigKeys::EcdsaSecp256k1(key_pair) => {
-                    Ok(Some(key_pair.sk.sign_message(&msg).to_vec()))
-                }
-            },
+            Self::SigKeys(sig_keys) => Ok(Some(sig_keys.sign(&msg))),
             Self::ExternalHsm { sign, .. } => sign(&msg).map(Some),
+            Self::Delegated { session_keys, .. } => Ok(Some(session_keys.sign(&msg))),
             Self::Anonymous => Ok(None),
             Self::PrincipalId(_) => Ok(None),
             Self::Node { .. } => unreachable!("Wrong case of agent.sign()"),
@@ -206,16 +247,46 @@ impl Sender {
 
     pub fn sender_pubkey_der(&self) -> Option<Vec<u8>> {
         match self {
-            Self::SigKeys(sig_keys) => match sig_keys {
-                SigKeys::Ed25519(key_pair) => {
-                    Some(ed25519_public_key_to_der(key_pair.public_key.to_vec()))
-                }
-                SigKeys::EcdsaSecp256k1(key_pair) => Some(key_pair.pk.serialize_der()),
-            },
+            Self::SigKeys(sig_keys) => Some(sig_keys.public_key_der()),
             Self::ExternalHsm { pub_key, .. } => Some(pub_key.clone()),
             Self::Anonymous => None,
             Self::PrincipalId(_) => None,
             Self::Node { pub_key, .. } => Some(ed25519_public_key_to_der(pub_key.clone())),
+            Self::Delegated {
+                delegator_pub_key, ..
+            } => Some(delegator_pub_key.clone()),
+        }
+    }
+
+    /// Returns the delegation chain to put in the request envelope, if any.
+    pub fn sender_delegation(&self) -> Option<Vec<SignedDelegation>> {
+        match self {
+            Self::Delegated { delegations, .. } => Some(delegations.clone()),
+            _ => None,
+        }
+    }
+
+    /// Checks that the delegation chain of the sender, if any, authorizes a
+    /// request to `canister_id` at `current_time`. `canister_id` is `None`
+    /// for requests not addressed to a canister, e.g. `read_state`.
+    pub fn validate_delegation(
+        &self,
+        canister_id: Option<&CanisterId>,
+        current_time: Time,
+    ) -> Result<(), DelegationError> {
+        match self {
+            Self::Delegated {
+                delegator_pub_key,
+                delegations,
+                session_keys,
+            } => validate_delegation_chain(
+                delegator_pub_key,
+                delegations,
+                session_keys,
+                canister_id,
+                current_time,
+            ),
+            _ => Ok(()),
         }
     }
 }
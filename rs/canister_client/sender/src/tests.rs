@@ -1,4 +1,12 @@
-use super::SigKeys;
+use super::{
+    ed25519_public_key_to_der, validate_delegation_chain, DelegationError, Ed25519KeyPair, SigKeys,
+};
+use ic_types::crypto::Signable;
+use ic_types::messages::{Delegation, SignedDelegation};
+use ic_types::{CanisterId, Time};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::time::Duration;
 
 pub mod vectors {
     /// A valid secp256k1 key.
@@ -64,3 +72,104 @@ fn should_fail_to_read_key_for_wrong_curve() {
         .map(|_| ())
         .expect_err("The base64 payload should be a secp256k1 key");
 }
+
+fn sign_delegation(signer: &Ed25519KeyPair, delegation: Delegation) -> SignedDelegation {
+    let signature = signer.sign(&delegation.as_signed_bytes()).to_vec();
+    SignedDelegation::new(delegation, signature)
+}
+
+/// Returns a delegator, a session key and a two-step chain between them
+/// expiring at `expiration` and restricted to canister 1.
+fn delegation_chain(expiration: Time) -> (Vec<u8>, SigKeys, Vec<SignedDelegation>) {
+    let mut rng = ChaCha20Rng::seed_from_u64(42);
+    let delegator = Ed25519KeyPair::generate(&mut rng);
+    let intermediate = Ed25519KeyPair::generate(&mut rng);
+    let session_keys = SigKeys::Ed25519(Ed25519KeyPair::generate(&mut rng));
+    let delegations = vec![
+        sign_delegation(
+            &delegator,
+            Delegation::new(
+                ed25519_public_key_to_der(intermediate.public_key.to_vec()),
+                expiration,
+            ),
+        ),
+        sign_delegation(
+            &intermediate,
+            Delegation::new_with_targets(
+                session_keys.public_key_der(),
+                expiration,
+                vec![CanisterId::from(1)],
+            ),
+        ),
+    ];
+    (
+        ed25519_public_key_to_der(delegator.public_key.to_vec()),
+        session_keys,
+        delegations,
+    )
+}
+
+#[test]
+fn should_accept_valid_delegation_chain() {
+    let now = Time::from_nanos_since_unix_epoch(1_000_000);
+    let (delegator, session_keys, delegations) = delegation_chain(now + Duration::from_secs(60));
+    assert_eq!(
+        validate_delegation_chain(
+            &delegator,
+            &delegations,
+            &session_keys,
+            Some(&CanisterId::from(1)),
+            now
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        validate_delegation_chain(&delegator, &delegations, &session_keys, None, now),
+        Ok(())
+    );
+}
+
+#[test]
+fn should_reject_invalid_delegation_chains() {
+    let now = Time::from_nanos_since_unix_epoch(1_000_000);
+    let (delegator, session_keys, delegations) = delegation_chain(now + Duration::from_secs(60));
+
+    assert_eq!(
+        validate_delegation_chain(
+            &delegator,
+            &delegations,
+            &session_keys,
+            Some(&CanisterId::from(2)),
+            now
+        ),
+        Err(DelegationError::CanisterNotInTargets {
+            index: 1,
+            canister_id: CanisterId::from(2)
+        })
+    );
+    assert!(matches!(
+        validate_delegation_chain(
+            &delegator,
+            &delegations,
+            &session_keys,
+            None,
+            now + Duration::from_secs(61)
+        ),
+        Err(DelegationError::Expired { index: 0, .. })
+    ));
+    assert_eq!(
+        validate_delegation_chain(&delegator, &delegations[..1], &session_keys, None, now),
+        Err(DelegationError::SessionKeyMismatch)
+    );
+    assert_eq!(
+        validate_delegation_chain(&delegator, &[], &session_keys, None, now),
+        Err(DelegationError::EmptyChain)
+    );
+
+    let mut tampered = delegations.clone();
+    tampered[0] = SignedDelegation::new(tampered[0].delegation().clone(), vec![0; 64]);
+    assert_eq!(
+        validate_delegation_chain(&delegator, &tampered, &session_keys, None, now),
+        Err(DelegationError::InvalidSignature { index: 0 })
+    );
+}
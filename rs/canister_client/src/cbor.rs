@@ -8,7 +8,7 @@ use ic_types::{
         HttpReadStateContent, HttpReadStateResponse, HttpRequestEnvelope, HttpUserQuery, MessageId,
         SignedRequestBytes,
     },
-    time::expiry_time_from_now,
+    time::{current_time, expiry_time_from_now},
     CanisterId, SubnetId, Time,
 };
 use serde::Deserialize;
@@ -209,6 +209,7 @@ pub fn prepare_update<S: ToString>(
         },
     };
 
+    sender.validate_delegation(Some(canister_id), current_time())?;
     let (submit_request, request_id) = sign_submit(content, sender)?;
     let signed_request_bytes = SignedRequestBytes::try_from(submit_request)?;
     Ok((signed_request_bytes, request_id))
@@ -233,6 +234,7 @@ pub fn prepare_query(
         },
     };
 
+    sender.validate_delegation(Some(canister_id), current_time())?;
     let request = sign_query(content, sender)?;
     Ok(SignedRequestBytes::try_from(request)?)
 }
@@ -252,6 +254,7 @@ pub fn prepare_read_state(
         },
     };
 
+    sender.validate_delegation(None, current_time())?;
     let request = sign_read_state(content, sender)?;
     Ok(SignedRequestBytes::try_from(request)?)
 }
@@ -283,7 +286,7 @@ fn sign_submit(
         content,
         sender_pubkey: pub_key_der,
         sender_sig,
-        sender_delegation: None,
+        sender_delegation: sender.sender_delegation(),
     };
     Ok((envelope, message_id))
 }
@@ -305,7 +308,7 @@ fn sign_read_state(
         content,
         sender_pubkey: pub_key_der,
         sender_sig,
-        sender_delegation: None,
+        sender_delegation: sender.sender_delegation(),
     })
 }
 
@@ -326,21 +329,23 @@ fn sign_query(
         content,
         sender_pubkey: pub_key_der,
         sender_sig,
-        sender_delegation: None,
+        sender_delegation: sender.sender_delegation(),
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ic_canister_client_sender::{ed25519_public_key_to_der, Ed25519KeyPair};
+    use ic_canister_client_sender::{ed25519_public_key_to_der, Ed25519KeyPair, SigKeys};
     use ic_certification_test_utils::{CertificateBuilder, CertificateData};
     use ic_crypto_test_utils_root_of_trust::MockRootOfTrustProvider;
     use ic_crypto_tree_hash::{Digest, Label, MixedHashTree};
     use ic_test_utilities::crypto::temp_crypto_component_with_fake_registry;
     use ic_test_utilities::types::ids::node_test_id;
+    use ic_types::crypto::Signable;
     use ic_types::messages::{
-        HttpCanisterUpdate, HttpReadStateResponse, HttpRequest, HttpUserQuery, UserQuery,
+        Delegation, HttpCanisterUpdate, HttpReadStateResponse, HttpRequest, HttpUserQuery,
+        SignedDelegation, UserQuery,
     };
     use ic_types::time::current_time;
     use ic_types::{PrincipalId, UserId};
@@ -402,6 +407,63 @@ mod tests {
             .contains(&request.content().canister_id()));
     }
 
+    /// Create an HttpRequest signed by a session key under a delegation chain
+    /// and then verify that `validate_message` manages to authenticate it.
+    #[test]
+    fn sign_and_verify_submit_content_with_delegation() {
+        let test_start_time = current_time();
+        let expiry_time = test_start_time + Duration::from_secs(4 * 60);
+        let mut rng = ChaChaRng::seed_from_u64(789_u64);
+        let delegator = Ed25519KeyPair::generate(&mut rng);
+        let session_keys = SigKeys::Ed25519(Ed25519KeyPair::generate(&mut rng));
+        let canister_id = CanisterId::from(51);
+        let delegation = Delegation::new_with_targets(
+            session_keys.public_key_der(),
+            expiry_time,
+            vec![canister_id],
+        );
+        let signature = delegator.sign(&delegation.as_signed_bytes()).to_vec();
+        let sender = Sender::from_delegation_chain(
+            ed25519_public_key_to_der(delegator.public_key.to_vec()),
+            vec![SignedDelegation::new(delegation, signature)],
+            session_keys,
+        );
+
+        let (bytes, id) = prepare_update(
+            &sender,
+            &canister_id,
+            "foo",
+            vec![12, 13, 99],
+            vec![],
+            expiry_time,
+            Blob(sender.get_principal_id().into_vec()),
+        )
+        .unwrap();
+        let envelope: HttpRequestEnvelope<HttpCallContent> =
+            serde_cbor::from_slice(bytes.as_ref()).unwrap();
+        assert_eq!(envelope.sender_delegation, sender.sender_delegation());
+
+        let request = HttpRequest::try_from(envelope).unwrap();
+        assert_eq!(id, request.id());
+        let targets = request_validator()
+            .validate_request(&request, test_start_time, &MockRootOfTrustProvider::new())
+            .unwrap();
+        assert!(targets.contains(&canister_id));
+        assert!(!targets.contains(&CanisterId::from(52)));
+
+        // Requests to canisters outside of the targets are rejected locally.
+        assert!(prepare_update(
+            &sender,
+            &CanisterId::from(52),
+            "foo",
+            vec![],
+            vec![],
+            expiry_time,
+            Blob(sender.get_principal_id().into_vec()),
+        )
+        .is_err());
+    }
+
     /// Create an HttpRequest with a non-anonymous user and then verify
     /// that `validate_message` manages to authenticate it.
     #[test]
@@ -2,6 +2,8 @@ mod dts;
 pub mod logging;
 pub mod sandbox_manager;
 pub mod sandbox_server;
+#[cfg(target_os = "linux")]
+mod syscall_filter;
 
 use ic_canister_sandbox_common::{
     child_process_initialization, controller_client_stub, protocol, rpc,
@@ -73,6 +75,8 @@ pub fn run_canister_sandbox(
     };
     let (log, _log_guard) = new_replica_logger_from_config(&logger_config);
 
+    #[cfg(target_os = "linux")]
+    let syscall_filter = syscall_filter::SyscallFilter::new(embedder_config.sandbox_syscall_filter);
     let socket = Arc::new(socket);
 
     let out_stream =
@@ -92,7 +96,7 @@ pub fn run_canister_sandbox(
     // Construct RPC server for the  service offered by this binary,
     // namely access to the sandboxed canister runner functions.
     let svc = Arc::new(sandbox_server::SandboxServer::new(
-        sandbox_manager::SandboxManager::new(controller, embedder_config, log),
+        sandbox_manager::SandboxManager::new(controller, embedder_config, log.clone()),
     ));
 
     // Wrap it all up to handle frames received on socket -- either
@@ -113,6 +117,15 @@ pub fn run_canister_sandbox(
         });
     }
 
+    // Setup is complete: from now on the process only serves RPCs, so it is
+    // confined to the system calls needed for that.
+    #[cfg(target_os = "linux")]
+    if let Some(filter) = syscall_filter {
+        if let Err(err) = filter.install() {
+            ic_logger::warn!(log, "Failed to install the sandbox syscall filter: {}", err);
+        }
+    }
+
     // Run RPC operations on the stream socket.
     transport::socket_read_messages::<_, _>(
         move |message| {
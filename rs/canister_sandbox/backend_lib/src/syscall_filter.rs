@@ -0,0 +1,316 @@
+//! A seccomp-bpf allowlist confining the sandbox process to the system calls
+//! it needs once it is set up: memory management (including the signal-based
+//! memory tracker), I/O on the IPC socket and on the file descriptors received
+//! over it, threads and exiting. In particular, opening files and creating
+//! sockets is not allowed.
+//!
+//! Reads and writes are not restricted to the IPC socket because page maps are
+//! backed by checkpoint files whose descriptors are passed over that socket.
+//!
+//! The allowlist must cover everything the IPC loop in
+//! `transport::socket_read_messages` does, including setting the receive
+//! timeout and trimming malloc when the socket is idle. Paths that are rarely
+//! taken, such as formatting a panic with a backtrace, may still need calls
+//! that are not listed here, which is why the filter only logs by default.
+use ic_config::embedders::SandboxSyscallFilter;
+use std::io;
+
+// Constants from `linux/filter.h` and `linux/seccomp.h`.
+const BPF_LD: u16 = 0x00;
+const BPF_JMP: u16 = 0x05;
+const BPF_RET: u16 = 0x06;
+const BPF_W: u16 = 0x00;
+const BPF_ABS: u16 = 0x20;
+const BPF_JEQ: u16 = 0x10;
+const BPF_K: u16 = 0x00;
+
+const SECCOMP_SET_MODE_FILTER: libc::c_uint = 1;
+const SECCOMP_FILTER_FLAG_TSYNC: libc::c_ulong = 1;
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_LOG: u32 = 0x7ffc_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+// Offsets of the fields of `struct seccomp_data`.
+const SECCOMP_DATA_NR_OFFSET: u32 = 0;
+const SECCOMP_DATA_ARCH_OFFSET: u32 = 4;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// The system calls the sandbox process may make after setup.
+const ALLOWED_SYSCALLS: &[libc::c_long] = &[
+    // Memory management.
+    libc::SYS_brk,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_mprotect,
+    libc::SYS_madvise,
+    libc::SYS_msync,
+    libc::SYS_membarrier,
+    libc::SYS_memfd_create,
+    libc::SYS_ftruncate,
+    libc::SYS_fallocate,
+    // I/O on the IPC socket and received file descriptors.
+    libc::SYS_read,
+    libc::SYS_readv,
+    libc::SYS_pread64,
+    libc::SYS_write,
+    libc::SYS_writev,
+    libc::SYS_pwrite64,
+    libc::SYS_recvmsg,
+    libc::SYS_sendmsg,
+    libc::SYS_recvfrom,
+    libc::SYS_sendto,
+    libc::SYS_shutdown,
+    libc::SYS_setsockopt,
+    libc::SYS_getsockopt,
+    libc::SYS_ppoll,
+    libc::SYS_lseek,
+    libc::SYS_fstat,
+    libc::SYS_newfstatat,
+    libc::SYS_statx,
+    libc::SYS_fcntl,
+    libc::SYS_dup,
+    libc::SYS_close,
+    // Signals, used by the memory tracker and for backtraces.
+    libc::SYS_rt_sigaction,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_sigaltstack,
+    libc::SYS_tgkill,
+    libc::SYS_restart_syscall,
+    // Threads and synchronization.
+    //
+    // `clone` and `clone3` are allowed with any flags: seccomp can't inspect
+    // the arguments of `clone3`, which are passed in memory, and new threads
+    // and processes inherit the filter, so they can't do more than the caller.
+    // In particular, `execve` stays forbidden. Likewise, `prctl` is allowed
+    // with any option (the standard library uses it to name threads): with
+    // `PR_SET_NO_NEW_PRIVS` set, no option lifts the filter or grants
+    // privileges.
+    libc::SYS_clone,
+    libc::SYS_clone3,
+    libc::SYS_futex,
+    libc::SYS_set_robust_list,
+    libc::SYS_rseq,
+    libc::SYS_set_tid_address,
+    libc::SYS_sched_yield,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_prctl,
+    libc::SYS_prlimit64,
+    libc::SYS_getpid,
+    libc::SYS_gettid,
+    // Time and randomness.
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_nanosleep,
+    libc::SYS_gettimeofday,
+    libc::SYS_getrandom,
+    // Exiting.
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_poll,
+];
+
+/// A compiled filter, ready to be installed.
+pub(crate) struct SyscallFilter {
+    program: Vec<libc::sock_filter>,
+}
+
+impl SyscallFilter {
+    /// Compiles the allowlist for `mode`. Returns `None` if no filter is to
+    /// be installed.
+    pub(crate) fn new(mode: SandboxSyscallFilter) -> Option<Self> {
+        let default_action = match mode {
+            SandboxSyscallFilter::Disabled => return None,
+            SandboxSyscallFilter::LogOnly => SECCOMP_RET_LOG,
+            SandboxSyscallFilter::Enforce => SECCOMP_RET_KILL_PROCESS,
+        };
+        let allowed = ALLOWED_SYSCALLS.len();
+        assert!(allowed <= u8::MAX as usize, "Too many allowed syscalls");
+
+        let mut program = vec![
+            // System call numbers differ between architectures, so calls made
+            // under a different ABI are never allowed.
+            stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_ARCH_OFFSET),
+            jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH, 1, 0),
+            stmt(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS),
+            stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_NR_OFFSET),
+        ];
+        // Each comparison jumps to the final `ALLOW` on a match and falls
+        // through to the next one otherwise.
+        for (i, nr) in ALLOWED_SYSCALLS.iter().enumerate() {
+            program.push(jump(
+                BPF_JMP | BPF_JEQ | BPF_K,
+                *nr as u32,
+                (allowed - i) as u8,
+                0,
+            ));
+        }
+        program.push(stmt(BPF_RET | BPF_K, default_action));
+        program.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+        Some(Self { program })
+    }
+
+    /// Installs the filter on all threads of the calling process. Threads
+    /// created afterwards inherit it.
+    ///
+    /// Only makes system calls, so that it can be used after `fork()`.
+    pub(crate) fn install(&self) -> io::Result<()> {
+        let prog = libc::sock_fprog {
+            len: self.program.len() as libc::c_ushort,
+            filter: self.program.as_ptr() as *mut libc::sock_filter,
+        };
+        // SAFETY: `prog` points to a valid program that outlives the calls.
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::syscall(
+                libc::SYS_seccomp,
+                SECCOMP_SET_MODE_FILTER,
+                SECCOMP_FILTER_FLAG_TSYNC,
+                &prog as *const libc::sock_fprog,
+            ) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn stmt(code: u16, k: u32) -> libc::sock_filter {
+    jump(code, k, 0, 0)
+}
+
+fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter { code, jt, jf, k }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_canister_sandbox_common::{
+        protocol::{sbxsvc, transport::ControllerToSandbox},
+        rpc::MessageSink,
+        transport::{self, SocketReaderConfig, UnixStreamMuxWriter},
+    };
+    use std::net::Shutdown;
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Runs `f` in a child process with the filter for `mode` installed and
+    /// returns the wait status of the child.
+    fn run_confined(mode: SandboxSyscallFilter, f: fn()) -> libc::c_int {
+        let filter = SyscallFilter::new(mode).unwrap();
+        // SAFETY: glibc makes `malloc` usable in the child of a multithreaded
+        // process, which is all that `f` relies on.
+        unsafe {
+            let pid = libc::fork();
+            assert!(pid >= 0, "fork failed");
+            if pid == 0 {
+                if filter.install().is_err() {
+                    libc::_exit(2);
+                }
+                f();
+                libc::_exit(0);
+            }
+            let mut status = 0;
+            assert_eq!(libc::waitpid(pid, &mut status, 0), pid);
+            status
+        }
+    }
+
+    fn killed_by_sigsys(status: libc::c_int) -> bool {
+        libc::WIFSIGNALED(status) && libc::WTERMSIG(status) == libc::SIGSYS
+    }
+
+    fn exited_successfully(status: libc::c_int) -> bool {
+        libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0
+    }
+
+    #[test]
+    fn sandbox_opening_a_file_is_killed() {
+        let status = run_confined(SandboxSyscallFilter::Enforce, || {
+            let _ = std::fs::File::open("/dev/null");
+        });
+        assert!(killed_by_sigsys(status), "wait status {}", status);
+    }
+
+    #[test]
+    fn sandbox_creating_a_socket_is_killed() {
+        let status = run_confined(SandboxSyscallFilter::Enforce, || {
+            let _ = std::net::UdpSocket::bind("127.0.0.1:0");
+        });
+        assert!(killed_by_sigsys(status), "wait status {}", status);
+    }
+
+    #[test]
+    fn log_only_mode_permits_disallowed_calls() {
+        let status = run_confined(SandboxSyscallFilter::LogOnly, || {
+            let _ = std::fs::File::open("/dev/null");
+        });
+        assert!(exited_successfully(status), "wait status {}", status);
+    }
+
+    #[test]
+    fn sandbox_serving_ipc_messages_is_not_killed() {
+        let (controller, sandbox) = UnixStream::pair().unwrap();
+        let filter = SyscallFilter::new(SandboxSyscallFilter::Enforce).unwrap();
+        // SAFETY: glibc makes `malloc` usable in the child of a multithreaded
+        // process, so the child can run the IPC loop.
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0, "fork failed");
+        if pid == 0 {
+            drop(controller);
+            if filter.install().is_err() {
+                // SAFETY: exiting the child process is always safe.
+                unsafe { libc::_exit(2) };
+            }
+            let received = AtomicUsize::new(0);
+            transport::socket_read_messages::<ControllerToSandbox, _>(
+                |_| {
+                    received.fetch_add(1, Ordering::SeqCst);
+                },
+                Arc::new(sandbox),
+                SocketReaderConfig::for_testing(),
+            );
+            let code = if received.load(Ordering::SeqCst) == 1 {
+                0
+            } else {
+                3
+            };
+            // SAFETY: exiting the child process is always safe.
+            unsafe { libc::_exit(code) };
+        }
+        drop(sandbox);
+
+        let controller = Arc::new(controller);
+        let writer = UnixStreamMuxWriter::<ControllerToSandbox>::new(Arc::clone(&controller));
+        writer
+            .make_sink::<sbxsvc::Request>()
+            .handle(0, sbxsvc::Request::Terminate(sbxsvc::TerminateRequest {}));
+        // Let the sandbox go idle, so that it also trims its buffers and
+        // resets the socket timeout, before closing the connection.
+        std::thread::sleep(Duration::from_millis(100));
+        writer.stop();
+        controller.shutdown(Shutdown::Both).unwrap();
+
+        let mut status = 0;
+        // SAFETY: `status` is a valid pointer.
+        assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+        assert!(exited_successfully(status), "wait status {}", status);
+    }
+
+    #[test]
+    fn disabled_mode_installs_no_filter() {
+        assert!(SyscallFilter::new(SandboxSyscallFilter::Disabled).is_none());
+    }
+}
@@ -20,6 +20,7 @@ use crate::{
     registration::Config as RegistrationConfig,
     registry_client::Config as RegistryClientConfig,
     state_manager::Config as StateManagerConfig,
+    tracing::Config as TracingConfig,
     transport::TransportConfig,
 };
 use ic_types::malicious_behaviour::MaliciousBehaviour;
@@ -36,6 +37,7 @@ pub struct Config {
     pub hypervisor: HypervisorConfig,
     pub http_handler: HttpHandlerConfig,
     pub metrics: MetricsConfig,
+    pub tracing: TracingConfig,
     pub artifact_pool: ArtifactPoolTomlConfig,
     pub crypto: CryptoConfig,
     pub logger: LoggerConfig,
@@ -65,6 +67,7 @@ pub struct ConfigOptional {
     pub hypervisor: Option<HypervisorConfig>,
     pub http_handler: Option<HttpHandlerConfig>,
     pub metrics: Option<MetricsConfig>,
+    pub tracing: Option<TracingConfig>,
     pub artifact_pool: Option<ArtifactPoolTomlConfig>,
     pub crypto: Option<CryptoConfig>,
     pub logger: Option<LoggerConfig>,
@@ -95,6 +98,7 @@ impl Config {
             hypervisor: HypervisorConfig::default(),
             http_handler: HttpHandlerConfig::default(),
             metrics: MetricsConfig::default(),
+            tracing: TracingConfig::default(),
             artifact_pool: ArtifactPoolTomlConfig::new(parent_dir.join("consensus_pool"), None),
             crypto: CryptoConfig::new(parent_dir.join("crypto")),
             logger: logger.clone(),
@@ -145,6 +149,7 @@ impl Config {
             hypervisor: cfg.hypervisor.unwrap_or(default.hypervisor),
             http_handler: cfg.http_handler.unwrap_or(default.http_handler),
             metrics: cfg.metrics.unwrap_or(default.metrics),
+            tracing: cfg.tracing.unwrap_or(default.tracing),
             artifact_pool: cfg.artifact_pool.unwrap_or(default.artifact_pool),
             crypto: cfg.crypto.unwrap_or(default.crypto),
             logger,
@@ -189,6 +194,12 @@ impl ConfigValidate for ConfigOptional {
             if let Some(uds_path) = &adapters_config.bitcoin_testnet_uds_path {
                 same_uds_paths |= !uds_paths.insert(uds_path.clone());
             }
+            if let Some(uds_path) = &adapters_config.dogecoin_mainnet_uds_path {
+                same_uds_paths |= !uds_paths.insert(uds_path.clone());
+            }
+            if let Some(uds_path) = &adapters_config.dogecoin_testnet_uds_path {
+                same_uds_paths |= !uds_paths.insert(uds_path.clone());
+            }
             if let Some(uds_path) = &adapters_config.https_outcalls_uds_path {
                 same_uds_paths |= !uds_paths.insert(uds_path.clone());
             }
@@ -178,6 +178,20 @@ pub const SAMPLE_CONFIG: &str = r#"
         max_concurrent_requests: 50,
         request_timeout_seconds: 30,
     },
+    // ==========================================================
+    // Configuration of distributed tracing of ingress messages.
+    // ==========================================================
+    tracing: {
+        // The OpenTelemetry collector to export spans to over OTLP/gRPC.
+        // Tracing is disabled if omitted.
+        // EXAMPLE: otlp_endpoint: "http://127.0.0.1:4317",
+
+        // The fraction of ingress messages to trace.
+        sampling_ratio: 0.01,
+
+        // The service name attached to exported spans.
+        service_name: "ic-replica",
+    },
     // ===================================
     // Configuration of the logging setup.
     // ===================================
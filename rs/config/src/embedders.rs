@@ -101,6 +101,22 @@ pub enum MeteringType {
     None,
 }
 
+/// How the canister sandbox restricts the system calls of its own process
+/// once it is set up.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SandboxSyscallFilter {
+    /// No filter is installed.
+    Disabled,
+    /// System calls outside of the allowlist are permitted but logged by the
+    /// kernel to the audit log. This is the default until the audit log shows
+    /// that the allowlist covers everything the sandbox does in production.
+    #[default]
+    LogOnly,
+    /// The sandbox process is killed on a system call outside of the
+    /// allowlist.
+    Enforce,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Config {
     pub max_wasm_stack_size: usize,
@@ -178,6 +194,11 @@ pub struct Config {
     /// The maximum number of pages that a message dirties without optimizing dirty
     /// page copying by triggering a new execution slice for copying and using prefaulting.
     pub max_dirty_pages_without_optimization: usize,
+
+    /// The seccomp-bpf system call filter the sandbox process installs on
+    /// itself, in addition to the confinement provided by the OS.
+    #[serde(default)]
+    pub sandbox_syscall_filter: SandboxSyscallFilter,
 }
 
 impl Config {
@@ -204,6 +225,7 @@ impl Config {
             dirty_page_overhead: NumInstructions::new(0),
             trace_execution: FlagStatus::Disabled,
             max_dirty_pages_without_optimization: DEFAULT_MAX_DIRTY_PAGES_WITHOUT_OPTIMIZATION,
+            sandbox_syscall_filter: SandboxSyscallFilter::LogOnly,
         }
     }
 }
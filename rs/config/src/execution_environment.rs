@@ -5,7 +5,7 @@ use ic_types::{
     Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use serde::{Deserialize, Serialize};
-use std::{str::FromStr, time::Duration};
+use std::{path::PathBuf, str::FromStr, time::Duration};
 
 const MIB: u64 = 1024 * 1024;
 const GIB: u64 = MIB * 1024;
@@ -122,6 +122,9 @@ const BITCOIN_MAINNET_SOFT_LAUNCH_CANISTER_ID: &str = "gsvzx-syaaa-aaaan-aaabq-c
 /// The capacity of the Wasm compilation cache.
 pub const MAX_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(10 * GIB);
 
+/// The capacity of the on-disk tier of the Wasm compilation cache.
+pub const MAX_COMPILATION_CACHE_DISK_SIZE: NumBytes = NumBytes::new(10 * GIB);
+
 /// Maximum number of controllers allowed in a request (specified in the interface spec).
 pub const MAX_ALLOWED_CONTROLLERS_COUNT: usize = 10;
 
@@ -236,12 +239,25 @@ pub struct Config {
     /// The capacity of the Wasm compilation cache.
     pub max_compilation_cache_size: NumBytes,
 
+    /// The directory of the on-disk tier of the Wasm compilation cache, which
+    /// keeps compiled modules across replica restarts. The on-disk tier is
+    /// disabled if `None`. The replica places it under the state root unless
+    /// configured otherwise.
+    pub compilation_cache_dir: Option<PathBuf>,
+
+    /// The capacity of the on-disk tier of the Wasm compilation cache.
+    pub max_compilation_cache_disk_size: NumBytes,
+
     /// Indicate whether query stats should be collected or not.
     pub query_stats_aggregation: FlagStatus,
 
     /// Length of an epoch for query stats collection.
     pub query_stats_epoch_length: u64,
 
+    /// Indicates whether canisters are charged for query execution based on
+    /// the query stats aggregated at the end of each epoch.
+    pub query_stats_charging: FlagStatus,
+
     /// Indicates whether the Wasm chunk store feature has been enabled or not.
     pub wasm_chunk_store: FlagStatus,
 
@@ -314,8 +330,11 @@ impl Default for Config {
             query_cache_capacity: QUERY_CACHE_CAPACITY,
             query_cache_max_expiry_time: QUERY_CACHE_MAX_EXPIRY_TIME,
             max_compilation_cache_size: MAX_COMPILATION_CACHE_SIZE,
+            compilation_cache_dir: None,
+            max_compilation_cache_disk_size: MAX_COMPILATION_CACHE_DISK_SIZE,
             query_stats_aggregation: FlagStatus::Disabled,
             query_stats_epoch_length: QUERY_STATS_EPOCH_LENGTH,
+            query_stats_charging: FlagStatus::Disabled,
             wasm_chunk_store: FlagStatus::Disabled,
             stop_canister_timeout_duration: STOP_CANISTER_TIMEOUT_DURATION,
             canister_snapshots: FlagStatus::Disabled,
@@ -30,6 +30,12 @@ pub struct Config {
 
     /// If this Sec256k1 PEM is available, use it instead of the HSM.
     pub node_operator_pem: Option<PathBuf>,
+
+    /// The node reward type of this node, as in the rewardable nodes of its
+    /// node operator, e.g. "type3.1". It is sent along when registering the
+    /// node, so that its rewards can be matched to its performance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_reward_type: Option<String>,
 }
 
 // We allow for the operator to only specify some of the fields while the others
@@ -57,6 +63,7 @@ impl Default for Config {
             nns_url: None,
             nns_pub_key_pem: None,
             node_operator_pem: None,
+            node_reward_type: None,
         }
     }
 }
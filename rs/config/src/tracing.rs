@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Default fraction of ingress messages whose lifecycle is traced.
+const DEFAULT_SAMPLING_RATIO: f64 = 0.01;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The gRPC endpoint of the OpenTelemetry collector spans are exported to,
+    /// e.g. `http://127.0.0.1:4317`. Tracing is disabled if not set.
+    pub otlp_endpoint: Option<String>,
+
+    /// The fraction of ingress messages to trace, between 0 and 1. The
+    /// decision is derived from the message id, so all nodes and components
+    /// agree on which messages are traced.
+    pub sampling_ratio: f64,
+
+    /// The `service.name` resource attribute attached to exported spans.
+    pub service_name: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            sampling_ratio: DEFAULT_SAMPLING_RATIO,
+            service_name: "ic-replica".to_string(),
+        }
+    }
+}
@@ -24,6 +24,7 @@ use ic_protobuf::{
     log::consensus_log_entry::v1::ConsensusLogEntry,
     registry::{crypto::v1::PublicKey as PublicKeyProto, subnet::v1::InitialNiDkgTranscriptRecord},
 };
+use ic_tracing::{start_ingress_span, IngressStage};
 use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
 use ic_types::{
     batch::{Batch, BatchMessages, BlockmakerMetrics},
@@ -32,7 +33,7 @@ use ic_types::{
         Block,
     },
     crypto::threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTranscript},
-    messages::{CallbackId, Payload, RejectContext, Response},
+    messages::{CallbackId, MessageId, Payload, RejectContext, Response, SignedIngress},
     CanisterId, Cycles, Height, PrincipalId, Randomness, ReplicaVersion, SubnetId,
 };
 use std::collections::BTreeMap;
@@ -53,6 +54,9 @@ pub fn deliver_batches(
     // deliver all batches until the finalized height. If it is set to `Some(h)`, we will
     // deliver all bathes up to the height `min(h, finalized_height)`.
     max_batch_height_to_deliver: Option<Height>,
+    // Whether a checkpoint should be created at `max_batch_height_to_deliver`. This argument
+    // should only be used by the ic-replay tool.
+    persist_max_batch: bool,
     result_processor: Option<&dyn Fn(&Result<(), MessageRoutingError>, BlockStats, BatchStats)>,
 ) -> Result<Height, MessageRoutingError> {
     let finalized_height = pool.get_finalized_height();
@@ -131,8 +135,9 @@ pub fn deliver_batches(
                     generate_responses_to_subnet_calls(&block, &mut batch_stats, log);
 
                 // This flag can only be true, if we've called deliver_batches with a height
-                // limit.  In this case we also want to have a checkpoint for that last height.
-                let persist_batch = Some(h) == max_batch_height_to_deliver;
+                // limit.  In this case we also want to have a checkpoint for that last height,
+                // unless the caller only needs the in-memory state.
+                let persist_batch = persist_max_batch && Some(h) == max_batch_height_to_deliver;
                 let requires_full_state_hash = block.payload.is_summary() || persist_batch;
                 let batch_messages = if block.payload.is_summary() {
                     BatchMessages::default()
@@ -194,6 +199,17 @@ pub fn deliver_batches(
                     blockmaker_metrics,
                 };
 
+                let traced_ingress_ids: Vec<MessageId> = if ic_tracing::is_enabled() {
+                    batch
+                        .messages
+                        .signed_ingress_msgs
+                        .iter()
+                        .map(SignedIngress::id)
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+
                 debug!(
                     log,
                     "replica {:?} delivered batch {:?} for block_hash {:?}",
@@ -209,6 +225,11 @@ pub fn deliver_batches(
                     warn!(every_n_seconds => 5, log, "Batch delivery failed: {:?}", err);
                     return Err(err);
                 }
+                for message_id in traced_ingress_ids {
+                    start_ingress_span(&message_id, IngressStage::IncludedInBlock)
+                        .with_attribute("ic.height", h.get() as i64)
+                        .end();
+                }
                 last_delivered_batch_height = h;
                 h = h.increment();
             }
@@ -101,6 +101,7 @@ impl Finalizer {
             ReplicaVersion::default(),
             &self.log,
             None,
+            false,
             Some(&|result, block_stats, batch_stats| {
                 self.process_batch_delivery_result(result, block_stats, batch_stats)
             }),
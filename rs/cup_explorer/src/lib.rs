@@ -1,18 +1,21 @@
 use ic_canister_client::{Agent, Sender};
-use ic_protobuf::types::v1::CatchUpContent;
+use ic_protobuf::types::v1::{CatchUpContent, CatchUpPackage as CatchUpPackageProto};
+use ic_types::consensus::catchup::CatchUpPackage;
 use prost::Message;
 use reqwest::Url;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+mod summary;
+mod verify;
+
+pub use summary::{diff, summarize};
+pub use verify::verify_signature;
 
 /// Fetches the contents of a CatchUp package, if it's present.
 pub async fn get_catchup_content(url: &Url) -> Result<Option<CatchUpContent>, String> {
-    let agent = Agent::new(url.clone(), Sender::Anonymous);
-    let maybe_cup = agent
-        .query_cup_endpoint(None)
-        .await
-        .map_err(|e| format!("failed to get catch up package: {}", e))?;
-    match maybe_cup {
+    match get_catchup_package(url).await? {
         Some(cup) => {
-            // TODO(roman): verify signatures?
             let content = CatchUpContent::decode(&cup.content[..])
                 .map_err(|e| format!("failed to deserialize cup: {}", e))?;
             Ok(Some(content))
@@ -20,3 +23,69 @@ pub async fn get_catchup_content(url: &Url) -> Result<Option<CatchUpContent>, St
         None => Ok(None),
     }
 }
+
+/// Fetches a CatchUp package from the node at `url`, if it's present.
+pub async fn get_catchup_package(url: &Url) -> Result<Option<CatchUpPackageProto>, String> {
+    let agent = Agent::new(url.clone(), Sender::Anonymous);
+    agent
+        .query_cup_endpoint(None)
+        .await
+        .map_err(|e| format!("failed to get catch up package: {}", e))
+}
+
+/// Reads a CatchUp package in protobuf format, as persisted by the orchestrator,
+/// from `path`.
+pub fn read_catchup_package(path: &Path) -> Result<CatchUpPackageProto, String> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    CatchUpPackageProto::decode(&bytes[..])
+        .map_err(|e| format!("failed to deserialize cup {}: {}", path.display(), e))
+}
+
+/// Decodes the contents of a CatchUp package in protobuf format.
+pub fn decode_catchup_package(proto: &CatchUpPackageProto) -> Result<CatchUpPackage, String> {
+    CatchUpPackage::try_from(proto).map_err(|e| format!("failed to decode cup: {}", e))
+}
+
+/// Where to get a CatchUp package from: the public endpoint of a node or a
+/// file.
+#[derive(Clone, Debug)]
+pub enum CupSource {
+    Node(Url),
+    File(PathBuf),
+}
+
+impl CupSource {
+    pub async fn get(&self) -> Result<CatchUpPackageProto, String> {
+        match self {
+            Self::Node(url) => get_catchup_package(url)
+                .await?
+                .ok_or_else(|| format!("no cup yet on {}", url)),
+            Self::File(path) => read_catchup_package(path),
+        }
+    }
+}
+
+impl FromStr for CupSource {
+    type Err = String;
+
+    /// Parses `http(s)://` URLs as nodes and anything else as a file path.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            Url::parse(s)
+                .map(Self::Node)
+                .map_err(|e| format!("failed to parse url {}: {}", s, e))
+        } else {
+            Ok(Self::File(PathBuf::from(s)))
+        }
+    }
+}
+
+impl std::fmt::Display for CupSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Node(url) => write!(f, "{}", url),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
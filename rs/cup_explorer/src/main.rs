@@ -1,4 +1,7 @@
-use ic_cup_explorer::get_catchup_content;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use ic_cup_explorer::{
+    decode_catchup_package, diff, get_catchup_content, summarize, verify_signature, CupSource,
+};
 use ic_protobuf::registry::{node::v1::NodeRecord, subnet::v1::SubnetRecord};
 use ic_registry_keys::{make_node_record_key, make_subnet_record_key};
 use ic_registry_nns_data_provider::registry::RegistryCanister;
@@ -7,10 +10,74 @@ use prost::Message;
 use reqwest::Url;
 use std::convert::TryFrom;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::task;
 
+struct ClapSubnetId(SubnetId);
+
+impl FromStr for ClapSubnetId {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PrincipalId::from_str(s)
+            .map_err(|e| format!("failed to parse subnet id {}: {}", s, e))
+            .map(SubnetId::from)
+            .map(ClapSubnetId)
+    }
+}
+
+/// Explores, verifies and compares catch-up packages (CUPs).
+///
+/// Without a subcommand, the positional arguments of `explore` are accepted
+/// for compatibility with earlier versions.
+#[derive(Parser)]
+#[clap(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+    /// URL of the NNS registry canister, as for `explore`
+    #[clap(requires = "subnet_id")]
+    registry_url: Option<Url>,
+    /// The ID of the subnet, as for `explore`
+    subnet_id: Option<ClapSubnetId>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Fetch the CUPs of all nodes of a subnet and report the latest one
+    Explore {
+        /// URL of the NNS registry canister
+        registry_url: Url,
+        /// The ID of the subnet
+        subnet_id: ClapSubnetId,
+    },
+    /// Verify the signature of a CUP against the subnet public key found in a
+    /// local registry store, without network access to the NNS
+    Verify {
+        /// Path to the local registry store
+        #[clap(long)]
+        local_store: PathBuf,
+        /// The ID of the subnet that created the CUP
+        #[clap(long)]
+        subnet_id: ClapSubnetId,
+        /// A CUP file in protobuf format or the http(s) URL of a node
+        cup: CupSource,
+    },
+    /// Decode and print the block, DKG summary and state hash of a CUP
+    Print {
+        /// A CUP file in protobuf format or the http(s) URL of a node
+        cup: CupSource,
+    },
+    /// Print the fields in which two CUPs differ
+    Diff {
+        /// A CUP file in protobuf format or the http(s) URL of a node
+        a: CupSource,
+        /// A CUP file in protobuf format or the http(s) URL of a node
+        b: CupSource,
+    },
+}
+
 /// Returns the list of nodes assigned to the specified subnet_id.
 async fn get_nodes(
     registry_canister: &Arc<RegistryCanister>,
@@ -66,22 +133,7 @@ fn http_url(n: &NodeRecord) -> Url {
     .unwrap()
 }
 
-#[tokio::main]
-async fn main() {
-    let args: Vec<_> = std::env::args().collect();
-    if args.len() != 3 {
-        eprintln!("Usage: {} [REGISTRY_URL] [SUBNET_ID]", args[0]);
-        std::process::exit(1);
-    }
-
-    let registry_url = Url::parse(&args[1][..])
-        .unwrap_or_else(|e| panic!("failed to parse registry url {}: {}", args[1], e));
-
-    let subnet_id = SubnetId::from(
-        PrincipalId::from_str(&args[2][..])
-            .unwrap_or_else(|e| panic!("failed to parse subnet id {}: {}", args[2], e)),
-    );
-
+async fn explore(registry_url: Url, subnet_id: SubnetId) {
     let registry_canister = Arc::new(RegistryCanister::new(vec![registry_url]));
 
     println!("Fetching the list of nodes on subnet {}...", subnet_id);
@@ -131,3 +183,79 @@ async fn main() {
         println!("{:>10}: {}", "NODE", node);
     }
 }
+
+/// Fetches and decodes the CUP from `source` and returns its summary, or exits
+/// on failure.
+async fn summarize_source(source: &CupSource) -> Vec<(String, String)> {
+    let cup = match source.get().await {
+        Ok(proto) => decode_catchup_package(&proto),
+        Err(err) => Err(err),
+    };
+    match cup {
+        Ok(cup) => summarize(&cup),
+        Err(err) => {
+            eprintln!("{}: {}", source, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let command = match (cli.command, cli.registry_url, cli.subnet_id) {
+        (Some(command), _, _) => command,
+        (None, Some(registry_url), Some(subnet_id)) => Command::Explore {
+            registry_url,
+            subnet_id,
+        },
+        _ => Cli::command()
+            .error(
+                ErrorKind::MissingSubcommand,
+                "a subcommand or REGISTRY_URL and SUBNET_ID are required",
+            )
+            .exit(),
+    };
+    match command {
+        Command::Explore {
+            registry_url,
+            subnet_id,
+        } => explore(registry_url, subnet_id.0).await,
+        Command::Verify {
+            local_store,
+            subnet_id,
+            cup,
+        } => {
+            let result = match cup.get().await {
+                Ok(proto) => verify_signature(&proto, &local_store, subnet_id.0),
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(()) => println!(" ✔ {}: signature is valid", cup),
+                Err(err) => {
+                    println!(" ✘ {}: {}", cup, err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Print { cup } => {
+            for (key, value) in summarize_source(&cup).await {
+                println!("{:>50}: {}", key, value);
+            }
+        }
+        Command::Diff { a, b } => {
+            let differences = diff(&summarize_source(&a).await, &summarize_source(&b).await);
+            if differences.is_empty() {
+                println!("The CUPs are identical");
+                return;
+            }
+            println!("A: {}\nB: {}", a, b);
+            for (key, value_a, value_b) in differences {
+                println!("{}:", key);
+                println!("  A: {}", value_a.as_deref().unwrap_or("<missing>"));
+                println!("  B: {}", value_b.as_deref().unwrap_or("<missing>"));
+            }
+            std::process::exit(1);
+        }
+    }
+}
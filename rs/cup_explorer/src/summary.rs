@@ -0,0 +1,173 @@
+use ic_types::{
+    consensus::{catchup::CatchUpPackage, HasHeight},
+    crypto::CryptoHash,
+};
+use std::collections::BTreeMap;
+
+fn hash(hash: &CryptoHash) -> String {
+    hex::encode(&hash.0)
+}
+
+/// Decodes the fields of a CatchUp package that are relevant for comparing and
+/// verifying it into a list of human-readable key-value pairs: the contained
+/// block, its DKG summary and the state hash.
+pub fn summarize(cup: &CatchUpPackage) -> Vec<(String, String)> {
+    let content = &cup.content;
+    let block = content.block.get_value();
+    let mut fields = vec![
+        ("height".to_string(), cup.height().to_string()),
+        ("replica_version".to_string(), content.version.to_string()),
+        ("state_hash".to_string(), hash(content.state_hash.get_ref())),
+        (
+            "oldest_registry_version_in_use_by_replicated_state".to_string(),
+            content
+                .oldest_registry_version_in_use_by_replicated_state
+                .map_or_else(|| "none".to_string(), |v| v.to_string()),
+        ),
+        (
+            "random_beacon_hash".to_string(),
+            hash(content.random_beacon.get_hash().get_ref()),
+        ),
+        (
+            "block.hash".to_string(),
+            hash(content.block.get_hash().get_ref()),
+        ),
+        ("block.parent".to_string(), hash(block.parent.get_ref())),
+        ("block.rank".to_string(), block.rank.0.to_string()),
+        ("block.time".to_string(), block.context.time.to_string()),
+        (
+            "block.registry_version".to_string(),
+            block.context.registry_version.to_string(),
+        ),
+        (
+            "block.certified_height".to_string(),
+            block.context.certified_height.to_string(),
+        ),
+        ("signer".to_string(), cup.signature.signer.to_string()),
+        (
+            "signature".to_string(),
+            hex::encode(&cup.signature.signature.get_ref().0),
+        ),
+    ];
+
+    let dkg = &block.payload.as_ref().as_summary().dkg;
+    fields.extend([
+        (
+            "dkg.registry_version".to_string(),
+            dkg.registry_version.to_string(),
+        ),
+        ("dkg.height".to_string(), dkg.height.to_string()),
+        (
+            "dkg.interval_length".to_string(),
+            dkg.interval_length.to_string(),
+        ),
+        (
+            "dkg.next_interval_length".to_string(),
+            dkg.next_interval_length.to_string(),
+        ),
+        ("dkg.configs".to_string(), dkg.configs.len().to_string()),
+    ]);
+    for (name, transcripts) in [
+        ("current", dkg.current_transcripts()),
+        ("next", dkg.next_transcripts()),
+    ] {
+        for (tag, transcript) in transcripts {
+            fields.push((
+                format!("dkg.{}_transcripts.{:?}", name, tag),
+                format!(
+                    "{} (registry version {})",
+                    transcript.dkg_id, transcript.registry_version
+                ),
+            ));
+        }
+    }
+    fields
+}
+
+/// Compares the summaries of two CatchUp packages and returns the fields
+/// whose values differ, with `None` for fields only present in one of them.
+pub fn diff(
+    a: &[(String, String)],
+    b: &[(String, String)],
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let mut fields: BTreeMap<&str, (Option<&str>, Option<&str>)> = BTreeMap::new();
+    for (key, value) in a {
+        fields.entry(key.as_str()).or_default().0 = Some(value.as_str());
+    }
+    for (key, value) in b {
+        fields.entry(key.as_str()).or_default().1 = Some(value.as_str());
+    }
+    // Report differences in the order of the summaries.
+    let order = a.iter().chain(b).map(|(key, _)| key.as_str());
+    let mut differences = vec![];
+    for key in order {
+        if let Some((value_a, value_b)) = fields.remove(key) {
+            if value_a != value_b {
+                differences.push((
+                    key.to_string(),
+                    value_a.map(str::to_string),
+                    value_b.map(str::to_string),
+                ));
+            }
+        }
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_protobuf::types::v1::CatchUpPackage as CatchUpPackageProto;
+    use ic_test_utilities::consensus::{fake::*, make_genesis};
+    use ic_types::consensus::dkg;
+
+    fn summary(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+        fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn diff_reports_changed_and_missing_fields_in_order() {
+        let a = summary(&[("height", "100"), ("state_hash", "aa"), ("dkg.a", "1")]);
+        let b = summary(&[("height", "100"), ("state_hash", "bb"), ("dkg.b", "2")]);
+        assert_eq!(
+            diff(&a, &b),
+            vec![
+                (
+                    "state_hash".to_string(),
+                    Some("aa".to_string()),
+                    Some("bb".to_string())
+                ),
+                ("dkg.a".to_string(), Some("1".to_string()), None),
+                ("dkg.b".to_string(), None, Some("2".to_string())),
+            ]
+        );
+        assert!(diff(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn summary_covers_block_dkg_summary_and_state_hash() {
+        let cup = make_genesis(dkg::Summary::fake());
+        let fields: BTreeMap<_, _> = summarize(&cup).into_iter().collect();
+
+        assert_eq!(fields["height"], "0");
+        assert_eq!(fields["block.registry_version"], "1");
+        assert_eq!(fields["dkg.registry_version"], "1");
+        assert_eq!(
+            fields["state_hash"],
+            hex::encode(&cup.content.state_hash.get_ref().0)
+        );
+        for tag in ["LowThreshold", "HighThreshold"] {
+            assert!(fields.contains_key(&format!("dkg.current_transcripts.{}", tag)));
+        }
+    }
+
+    #[test]
+    fn summary_survives_protobuf_round_trip() {
+        let cup = make_genesis(dkg::Summary::fake());
+        let decoded = crate::decode_catchup_package(&CatchUpPackageProto::from(&cup)).unwrap();
+        assert!(diff(&summarize(&cup), &summarize(&decoded)).is_empty());
+    }
+}
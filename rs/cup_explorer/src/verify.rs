@@ -0,0 +1,165 @@
+use ic_crypto_utils_threshold_sig::verify_combined;
+use ic_interfaces_registry::RegistryClient;
+use ic_protobuf::types::v1::CatchUpPackage as CatchUpPackageProto;
+use ic_registry_client::client::RegistryClientImpl;
+use ic_registry_client_helpers::crypto::CryptoRegistry;
+use ic_registry_local_store::LocalStoreImpl;
+use ic_types::{
+    consensus::catchup::CatchUpContentProtobufBytes,
+    crypto::{CombinedThresholdSig, CombinedThresholdSigOf},
+    SubnetId,
+};
+use std::{path::Path, sync::Arc};
+
+/// Verifies the threshold signature of a CatchUp package against the public
+/// key of `subnet_id` in the registry version the CUP's block refers to, as
+/// found in the local registry store at `local_store`. Requires no network
+/// access.
+///
+/// CUPs created from the registry at genesis or during subnet recovery are not
+/// signed, so they fail verification.
+pub fn verify_signature(
+    proto: &CatchUpPackageProto,
+    local_store: &Path,
+    subnet_id: SubnetId,
+) -> Result<(), String> {
+    if proto.signature.is_empty() {
+        return Err("the cup is not signed, i.e. it was created from the registry".to_string());
+    }
+    let cup = crate::decode_catchup_package(proto)?;
+    let registry_version = cup.content.block.get_value().context.registry_version;
+
+    let registry = RegistryClientImpl::new(Arc::new(LocalStoreImpl::new(local_store)), None);
+    registry
+        .poll_once()
+        .map_err(|e| format!("failed to read the local store: {}", e))?;
+    let latest_version = registry.get_latest_version();
+    if latest_version < registry_version {
+        return Err(format!(
+            "the local store is at registry version {}, but the cup refers to version {}",
+            latest_version, registry_version
+        ));
+    }
+    let public_key = registry
+        .get_threshold_signing_public_key_for_subnet(subnet_id, registry_version)
+        .map_err(|e| {
+            format!(
+                "failed to get the public key of subnet {}: {}",
+                subnet_id, e
+            )
+        })?
+        .ok_or_else(|| {
+            format!(
+                "no public key of subnet {} at registry version {}",
+                subnet_id, registry_version
+            )
+        })?;
+
+    verify_combined(
+        &CatchUpContentProtobufBytes::from(proto),
+        &CombinedThresholdSigOf::new(CombinedThresholdSig(proto.signature.clone())),
+        &public_key,
+    )
+    .map_err(|e| format!("invalid signature: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_certification_test_utils::generate_root_of_trust;
+    use ic_crypto_internal_threshold_sig_bls12381::api::{combine_signatures, sign_message};
+    use ic_crypto_internal_threshold_sig_bls12381::types::SecretKeyBytes;
+    use ic_protobuf::registry::crypto::v1::PublicKey as PublicKeyProto;
+    use ic_registry_keys::make_crypto_threshold_signing_pubkey_key;
+    use ic_registry_local_store::{KeyMutation, LocalStoreWriter};
+    use ic_test_utilities::{
+        consensus::{fake::*, make_genesis},
+        types::ids::subnet_test_id,
+    };
+    use ic_types::{consensus::dkg, crypto::Signable, NumberOfNodes, RegistryVersion};
+    use prost::Message;
+
+    /// Returns a CUP at registry version 1 and its signature by `secret_key`.
+    fn cup_signed_with(secret_key: &SecretKeyBytes) -> CatchUpPackageProto {
+        let mut proto = CatchUpPackageProto::from(&make_genesis(dkg::Summary::fake()));
+        let message = CatchUpContentProtobufBytes::from(&proto).as_signed_bytes();
+        let signature = sign_message(&message, secret_key).unwrap();
+        proto.signature = combine_signatures(&[Some(signature)], NumberOfNodes::new(1))
+            .unwrap()
+            .0
+            .to_vec();
+        proto
+    }
+
+    /// Creates a local store at registry version 1 holding the public key of
+    /// `subnet_id` and returns its secret key.
+    fn local_store_with_key(local_store: &Path, subnet_id: SubnetId) -> SecretKeyBytes {
+        let (public_key, secret_key) = generate_root_of_trust(&mut rand::thread_rng());
+        LocalStoreImpl::new(local_store)
+            .store(
+                RegistryVersion::from(1),
+                vec![KeyMutation {
+                    key: make_crypto_threshold_signing_pubkey_key(subnet_id),
+                    value: Some(PublicKeyProto::from(public_key).encode_to_vec()),
+                }],
+            )
+            .unwrap();
+        secret_key
+    }
+
+    #[test]
+    fn cup_signed_by_the_subnet_is_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let subnet_id = subnet_test_id(0);
+        let secret_key = local_store_with_key(dir.path(), subnet_id);
+
+        let proto = cup_signed_with(&secret_key);
+        assert_eq!(verify_signature(&proto, dir.path(), subnet_id), Ok(()));
+    }
+
+    #[test]
+    fn cup_signed_with_another_key_is_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let subnet_id = subnet_test_id(0);
+        local_store_with_key(dir.path(), subnet_id);
+        let (_, other_secret_key) = generate_root_of_trust(&mut rand::thread_rng());
+
+        let proto = cup_signed_with(&other_secret_key);
+        let err = verify_signature(&proto, dir.path(), subnet_id).unwrap_err();
+        assert!(err.starts_with("invalid signature"), "{}", err);
+    }
+
+    #[test]
+    fn unsigned_cup_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let proto = CatchUpPackageProto::from(&make_genesis(dkg::Summary::fake()));
+        let err = verify_signature(&proto, dir.path(), subnet_test_id(0)).unwrap_err();
+        assert!(err.contains("not signed"), "{}", err);
+    }
+
+    #[test]
+    fn cup_of_a_newer_registry_version_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let subnet_id = subnet_test_id(0);
+        let (_, secret_key) = generate_root_of_trust(&mut rand::thread_rng());
+
+        // The local store is empty, i.e. at registry version 0.
+        let proto = cup_signed_with(&secret_key);
+        let err = verify_signature(&proto, dir.path(), subnet_id).unwrap_err();
+        assert!(
+            err.contains("local store is at registry version 0"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn cup_of_a_subnet_without_key_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret_key = local_store_with_key(dir.path(), subnet_test_id(0));
+
+        let proto = cup_signed_with(&secret_key);
+        let err = verify_signature(&proto, dir.path(), subnet_test_id(1)).unwrap_err();
+        assert!(err.contains("no public key"), "{}", err);
+    }
+}
@@ -268,6 +268,16 @@ impl CyclesAccountManager {
         )
     }
 
+    /// Returns the cycles burned per day for storing the given number of bytes,
+    /// regardless of the canister's memory allocation.
+    pub fn idle_memory_cycles_burned_rate(&self, bytes: NumBytes, subnet_size: usize) -> Cycles {
+        self.memory_cost(
+            bytes,
+            Duration::from_secs(SECONDS_PER_DAY as u64),
+            subnet_size,
+        )
+    }
+
     // Returns the total idle resource consumption rate in cycles per day.
     pub fn idle_cycles_burned_rate(
         &self,
@@ -876,7 +886,8 @@ impl CyclesAccountManager {
             | CyclesUseCase::HTTPOutcalls
             | CyclesUseCase::DeletedCanisters
             | CyclesUseCase::NonConsumed
-            | CyclesUseCase::BurnedCycles => system_state.balance(),
+            | CyclesUseCase::BurnedCycles
+            | CyclesUseCase::QueryExecution => system_state.balance(),
         };
 
         self.verify_cycles_balance_with_threshold(
@@ -1027,6 +1038,40 @@ impl CyclesAccountManager {
         )
     }
 
+    /// Returns the cost of the query calls that a canister executed during a
+    /// query stats epoch, given their totals across all nodes of the subnet.
+    /// The fees are the same as for replicated execution, see
+    /// `execution_cost()`, plus the fee per received byte for both the request
+    /// and the response payloads. Since each query is executed on a single node
+    /// rather than on all nodes of the subnet, the cost is divided by the
+    /// subnet size.
+    pub fn query_execution_cost(
+        &self,
+        num_calls: u128,
+        num_instructions: u128,
+        num_payload_bytes: u128,
+        subnet_size: usize,
+    ) -> Cycles {
+        let cost = self.config.update_message_execution_fee * num_calls
+            + self.config.ten_update_instructions_execution_fee * num_instructions / 10_u128
+            + self.config.ingress_byte_reception_fee * num_payload_bytes;
+        self.scale_cost(cost, subnet_size) / subnet_size.max(1)
+    }
+
+    /// Charges a canister for the query calls it executed during a query stats
+    /// epoch. As the queries have already been executed, charging can take the
+    /// balance all the way down to zero cycles, and the canister is charged at
+    /// most its balance.
+    ///
+    /// Returns the cycles that were actually charged.
+    pub fn charge_for_query_execution(&self, canister: &mut CanisterState, cost: Cycles) -> Cycles {
+        let cycles = min(cost, canister.system_state.balance());
+        canister
+            .system_state
+            .remove_cycles(cycles, CyclesUseCase::QueryExecution);
+        cycles
+    }
+
     /// Charges a canister for its resource allocation and usage for the
     /// duration specified. If fees were successfully charged, then returns
     /// Ok() else returns Err(CanisterOutOfCyclesError).
@@ -1085,6 +1130,29 @@ impl CyclesAccountManager {
             * (subnet_size as u64)
     }
 
+    /// Returns the fee for a http request that is performed by a single
+    /// replica. The baseline fee is the same as for a replicated request, since
+    /// the response still goes through consensus, but the per-byte fees are
+    /// only charged once.
+    pub fn non_replicated_http_request_fee(
+        &self,
+        request_size: NumBytes,
+        response_size_limit: Option<NumBytes>,
+        subnet_size: usize,
+    ) -> Cycles {
+        let response_size = match response_size_limit {
+            Some(response_size) => response_size.get(),
+            // Defaults to maximum response size.
+            None => MAX_CANISTER_HTTP_RESPONSE_BYTES,
+        };
+
+        (self.config.http_request_linear_baseline_fee
+            + self.config.http_request_quadratic_baseline_fee * (subnet_size as u64))
+            * (subnet_size as u64)
+            + self.config.http_request_per_byte_fee * request_size.get()
+            + self.config.http_response_per_byte_fee * response_size
+    }
+
     /// Returns the default value of the reserved balance limit for the case
     /// when the canister doesn't have it set in the settings.
     pub fn default_reserved_balance_limit(&self) -> Cycles {
@@ -1217,6 +1285,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn non_replicated_http_requests_fee_charges_bytes_once() {
+        let subnet_size: usize = 13;
+        let request_size = NumBytes::from(17);
+        let cycles_account_manager = create_cycles_account_manager(subnet_size);
+
+        assert_eq!(
+            cycles_account_manager.non_replicated_http_request_fee(request_size, None, subnet_size),
+            Cycles::from(3_780_000u64) * subnet_size + Cycles::from(1_600_006_800u64)
+        );
+        assert!(
+            cycles_account_manager.non_replicated_http_request_fee(request_size, None, subnet_size)
+                < cycles_account_manager.http_request_fee(request_size, None, subnet_size)
+        );
+    }
+
     #[test]
     fn test_cycles_burn() {
         let subnet_size = 13;
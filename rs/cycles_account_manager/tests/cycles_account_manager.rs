@@ -1004,3 +1004,57 @@ fn test_storage_reservation_cycles() {
         cam.storage_reservation_cycles(NumBytes::new(1000 * GB), &rs0, 13)
     )
 }
+
+#[test]
+fn query_execution_cost_is_divided_among_nodes() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+
+    let cost = cycles_account_manager.query_execution_cost(
+        subnet_size as u128,
+        subnet_size as u128 * 1_000,
+        0,
+        subnet_size,
+    );
+
+    // Executing one query with 1000 instructions on every node of the subnet
+    // costs as much as executing one update with 1000 instructions.
+    assert_eq!(
+        cost,
+        cycles_account_manager.execution_cost(NumInstructions::from(1_000), subnet_size)
+    );
+    assert_eq!(
+        cycles_account_manager.query_execution_cost(0, 0, 100, subnet_size),
+        cycles_account_manager.ingress_byte_received_fee(subnet_size) * 100_u64 / subnet_size
+    );
+}
+
+#[test]
+fn charge_for_query_execution_charges_at_most_the_balance() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let mut canister = new_canister_state(
+        canister_test_id(1),
+        canister_test_id(2).get(),
+        Cycles::new(1_000),
+        NumSeconds::from(1_000),
+    );
+
+    let charged =
+        cycles_account_manager.charge_for_query_execution(&mut canister, Cycles::new(600));
+    assert_eq!(charged, Cycles::new(600));
+    assert_eq!(canister.system_state.balance(), Cycles::new(400));
+
+    let charged =
+        cycles_account_manager.charge_for_query_execution(&mut canister, Cycles::new(600));
+    assert_eq!(charged, Cycles::new(400));
+    assert_eq!(canister.system_state.balance(), Cycles::zero());
+    assert_eq!(
+        *canister
+            .system_state
+            .canister_metrics
+            .get_consumed_cycles_since_replica_started_by_use_cases()
+            .get(&CyclesUseCase::QueryExecution)
+            .unwrap(),
+        NominalCycles::from(1_000)
+    );
+}
@@ -1,21 +1,75 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
 
-use crate::SerializedModule;
+use crate::{wasm_utils::instrumentation::INSTRUMENTATION_VERSION, SerializedModule};
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_crypto_sha2::Sha256;
 use ic_interfaces::execution_environment::HypervisorResult;
-use ic_types::NumBytes;
+use ic_logger::{warn, ReplicaLogger};
+use ic_types::{NumBytes, ReplicaVersion};
 use ic_utils_lru_cache::LruCache;
 use ic_wasm_types::{CanisterModule, WasmHash};
 
+#[cfg(test)]
+mod tests;
+
 /// Stores the serialized modules of wasm code that has already been compiled so
 /// that it can be used again without recompiling.
+///
+/// Optionally, successfully compiled modules are also written to disk, so that
+/// they survive replica restarts. Modules found on disk are promoted to the
+/// in-memory tier on first use.
 pub struct CompilationCache {
     cache: Mutex<LruCache<WasmHash, HypervisorResult<Arc<SerializedModule>>>>,
+    disk_cache: Option<DiskCache>,
 }
 
 impl CompilationCache {
     pub fn new(capacity: NumBytes) -> Self {
         Self {
             cache: Mutex::new(LruCache::new(capacity)),
+            disk_cache: None,
+        }
+    }
+
+    /// Creates a cache with an on-disk tier of `disk_capacity` under `dir`.
+    /// Modules compiled by a different replica version or with a different
+    /// `embedder_config` are never loaded and are deleted on startup.
+    ///
+    /// Falls back to an in-memory cache if `dir` cannot be used.
+    pub fn new_with_disk_cache(
+        capacity: NumBytes,
+        dir: &Path,
+        disk_capacity: NumBytes,
+        embedder_config: &EmbeddersConfig,
+        log: ReplicaLogger,
+    ) -> Self {
+        let fingerprint = fingerprint(
+            &ReplicaVersion::default(),
+            WASMTIME_VERSION,
+            INSTRUMENTATION_VERSION,
+            embedder_config,
+        );
+        let disk_cache = match DiskCache::open(dir, fingerprint, disk_capacity, log.clone()) {
+            Ok(disk_cache) => Some(disk_cache),
+            Err(err) => {
+                warn!(
+                    log,
+                    "Failed to open the compilation cache directory {}: {}",
+                    dir.display(),
+                    err
+                );
+                None
+            }
+        };
+        Self {
+            cache: Mutex::new(LruCache::new(capacity)),
+            disk_cache,
         }
     }
 
@@ -24,21 +78,38 @@ impl CompilationCache {
         canister_module: &CanisterModule,
         serialized_module: HypervisorResult<Arc<SerializedModule>>,
     ) {
+        let wasm_hash = WasmHash::from(canister_module);
+        // Compilation errors are cheap to reproduce, so only successfully
+        // compiled modules are persisted.
+        if let (Some(disk_cache), Ok(serialized_module)) = (&self.disk_cache, &serialized_module) {
+            disk_cache.insert(&wasm_hash, serialized_module);
+        }
         self.cache
             .lock()
             .unwrap()
-            .push(WasmHash::from(canister_module), serialized_module);
+            .push(wasm_hash, serialized_module);
     }
 
     pub fn get(
         &self,
         canister_module: &CanisterModule,
     ) -> Option<HypervisorResult<Arc<SerializedModule>>> {
+        let wasm_hash = WasmHash::from(canister_module);
+        let cached = self
+            .cache
+            .lock()
+            .unwrap()
+            .get(&wasm_hash)
+            .map(|o| o.as_ref().map(Arc::clone).map_err(|e| e.clone()));
+        if cached.is_some() {
+            return cached;
+        }
+        let serialized_module = Arc::new(self.disk_cache.as_ref()?.get(&wasm_hash)?);
         self.cache
             .lock()
             .unwrap()
-            .get(&WasmHash::from(canister_module))
-            .map(|o| o.as_ref().map(Arc::clone).map_err(|e| e.clone()))
+            .push(wasm_hash, Ok(Arc::clone(&serialized_module)));
+        Some(Ok(serialized_module))
     }
 
     #[doc(hidden)]
@@ -46,3 +117,286 @@ impl CompilationCache {
         self.cache.lock().unwrap().clear()
     }
 }
+
+/// The version of the `wasmtime` dependency, which must be kept in sync with
+/// `Cargo.toml`.
+const WASMTIME_VERSION: &str = "15.0.0";
+
+/// Identifies the code and configuration modules are compiled with.
+///
+/// The replica version is not enough on its own: builds for testing share a
+/// version, and the cache directory may outlive a replica that was upgraded
+/// with a different Wasmtime or instrumentation.
+fn fingerprint(
+    replica_version: &ReplicaVersion,
+    wasmtime_version: &str,
+    instrumentation_version: u32,
+    embedder_config: &EmbeddersConfig,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in [replica_version.as_ref(), wasmtime_version] {
+        hasher.write(&(part.len() as u64).to_le_bytes());
+        hasher.write(part.as_bytes());
+    }
+    hasher.write(&instrumentation_version.to_le_bytes());
+    hasher.write(
+        &bincode::serialize(embedder_config).expect("Failed to serialize the embedder config"),
+    );
+    hasher.finish()
+}
+
+/// Marks files written by the on-disk tier.
+const MAGIC: &[u8; 4] = b"ICWC";
+/// The version of the file format, to be bumped on incompatible changes.
+const FORMAT_VERSION: u32 = 1;
+/// Magic, format version, fingerprint, Wasm hash and payload checksum.
+const HEADER_LEN: usize = 4 + 4 + 32 + 32 + 32;
+const FILE_EXTENSION: &str = "bin";
+const TMP_FILE_EXTENSION: &str = "tmp";
+
+/// The on-disk tier of the compilation cache, holding one file per module in a
+/// directory named after the fingerprint. Once the total size of the files
+/// exceeds the capacity, the least recently used ones are deleted.
+///
+/// Files are written to a temporary path and renamed, so that a crash never
+/// leaves a partially written module behind. Before being deserialized, the
+/// header of a file is checked against the expected fingerprint and Wasm hash
+/// and the payload against its checksum; files failing the checks are deleted.
+struct DiskCache {
+    dir: PathBuf,
+    fingerprint: [u8; 32],
+    capacity: u64,
+    index: Mutex<DiskIndex>,
+    log: ReplicaLogger,
+}
+
+/// The files in the cache directory with their size and the tick they were
+/// last used at.
+#[derive(Default)]
+struct DiskIndex {
+    entries: HashMap<WasmHash, (u64, u64)>,
+    total_size: u64,
+    tick: u64,
+}
+
+impl DiskIndex {
+    fn touch(&mut self, wasm_hash: &WasmHash) {
+        self.tick += 1;
+        if let Some((_, last_used)) = self.entries.get_mut(wasm_hash) {
+            *last_used = self.tick;
+        }
+    }
+
+    fn add(&mut self, wasm_hash: WasmHash, size: u64) {
+        self.remove(&wasm_hash);
+        self.tick += 1;
+        self.entries.insert(wasm_hash, (size, self.tick));
+        self.total_size += size;
+    }
+
+    fn remove(&mut self, wasm_hash: &WasmHash) {
+        if let Some((size, _)) = self.entries.remove(wasm_hash) {
+            self.total_size -= size;
+        }
+    }
+
+    fn least_recently_used(&self) -> Option<WasmHash> {
+        self.entries
+            .iter()
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(wasm_hash, _)| wasm_hash.clone())
+    }
+}
+
+impl DiskCache {
+    /// Opens the directory of `fingerprint` under `root`, deleting the
+    /// directories of other fingerprints. Other entries of `root` are left
+    /// alone, so that pointing the cache at the wrong directory never deletes
+    /// unrelated data.
+    fn open(
+        root: &Path,
+        fingerprint: [u8; 32],
+        capacity: NumBytes,
+        log: ReplicaLogger,
+    ) -> std::io::Result<Self> {
+        let dir_name = hex::encode(fingerprint);
+        let dir = root.join(&dir_name);
+        fs::create_dir_all(&dir)?;
+        for entry in fs::read_dir(root)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            if file_name != dir_name.as_str()
+                && is_fingerprint(&file_name.to_string_lossy())
+                && entry.file_type()?.is_dir()
+            {
+                let path = entry.path();
+                if let Err(err) = fs::remove_dir_all(&path) {
+                    warn!(log, "Failed to delete {}: {}", path.display(), err);
+                }
+            }
+        }
+
+        let mut files = vec![];
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            match wasm_hash_of_file(&path) {
+                Some(wasm_hash) => {
+                    let metadata = fs::metadata(&path)?;
+                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    files.push((modified, wasm_hash, metadata.len()));
+                }
+                None => {
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+        // Files are considered used when they were last written.
+        files.sort_by_key(|(modified, _, _)| *modified);
+        let mut index = DiskIndex::default();
+        for (_, wasm_hash, size) in files {
+            index.add(wasm_hash, size);
+        }
+
+        let disk_cache = Self {
+            dir,
+            fingerprint,
+            capacity: capacity.get(),
+            index: Mutex::new(index),
+            log,
+        };
+        disk_cache.evict(&mut disk_cache.index.lock().unwrap());
+        Ok(disk_cache)
+    }
+
+    fn path(&self, wasm_hash: &WasmHash, extension: &str) -> PathBuf {
+        self.dir
+            .join(hex::encode(wasm_hash.to_slice()))
+            .with_extension(extension)
+    }
+
+    fn get(&self, wasm_hash: &WasmHash) -> Option<SerializedModule> {
+        let mut index = self.index.lock().unwrap();
+        if !index.entries.contains_key(wasm_hash) {
+            return None;
+        }
+        let path = self.path(wasm_hash, FILE_EXTENSION);
+        let result = fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| self.decode(wasm_hash, &bytes));
+        match result {
+            Ok(serialized_module) => {
+                index.touch(wasm_hash);
+                Some(serialized_module)
+            }
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "Discarding cached module {}: {}",
+                    path.display(),
+                    err
+                );
+                let _ = fs::remove_file(&path);
+                index.remove(wasm_hash);
+                None
+            }
+        }
+    }
+
+    fn insert(&self, wasm_hash: &WasmHash, serialized_module: &SerializedModule) {
+        let bytes = match bincode::serialize(serialized_module) {
+            Ok(payload) => self.encode(wasm_hash, &payload),
+            Err(err) => {
+                warn!(self.log, "Failed to serialize module: {}", err);
+                return;
+            }
+        };
+        let size = bytes.len() as u64;
+        if size > self.capacity {
+            return;
+        }
+
+        let mut index = self.index.lock().unwrap();
+        let tmp_path = self.path(wasm_hash, TMP_FILE_EXTENSION);
+        let path = self.path(wasm_hash, FILE_EXTENSION);
+        if let Err(err) = fs::write(&tmp_path, &bytes).and_then(|()| fs::rename(&tmp_path, &path)) {
+            warn!(
+                self.log,
+                "Failed to write cached module {}: {}",
+                path.display(),
+                err
+            );
+            let _ = fs::remove_file(&tmp_path);
+            return;
+        }
+        index.add(wasm_hash.clone(), size);
+        self.evict(&mut index);
+    }
+
+    /// Deletes the least recently used files until the cache fits into its
+    /// capacity.
+    fn evict(&self, index: &mut DiskIndex) {
+        while index.total_size > self.capacity {
+            let wasm_hash = match index.least_recently_used() {
+                Some(wasm_hash) => wasm_hash,
+                None => break,
+            };
+            let path = self.path(&wasm_hash, FILE_EXTENSION);
+            if let Err(err) = fs::remove_file(&path) {
+                warn!(
+                    self.log,
+                    "Failed to evict cached module {}: {}",
+                    path.display(),
+                    err
+                );
+            }
+            index.remove(&wasm_hash);
+        }
+    }
+
+    fn encode(&self, wasm_hash: &WasmHash, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.fingerprint);
+        bytes.extend_from_slice(&wasm_hash.to_slice());
+        bytes.extend_from_slice(&Sha256::hash(payload));
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn decode(&self, wasm_hash: &WasmHash, bytes: &[u8]) -> Result<SerializedModule, String> {
+        if bytes.len() < HEADER_LEN {
+            return Err("file is truncated".to_string());
+        }
+        let (header, payload) = bytes.split_at(HEADER_LEN);
+        if header[0..4] != MAGIC[..] || header[4..8] != FORMAT_VERSION.to_le_bytes() {
+            return Err("unknown file format".to_string());
+        }
+        if header[8..40] != self.fingerprint {
+            return Err("fingerprint mismatch".to_string());
+        }
+        if header[40..72] != wasm_hash.to_slice() {
+            return Err("Wasm hash mismatch".to_string());
+        }
+        if header[72..104] != Sha256::hash(payload) {
+            return Err("checksum mismatch".to_string());
+        }
+        bincode::deserialize(payload).map_err(|err| err.to_string())
+    }
+}
+
+/// Returns the Wasm hash a file of the cache is named after, or `None` if the
+/// file is not a (completely written) module.
+fn wasm_hash_of_file(path: &Path) -> Option<WasmHash> {
+    if path.extension()? != FILE_EXTENSION {
+        return None;
+    }
+    let bytes = hex::decode(path.file_stem()?.to_str()?).ok()?;
+    WasmHash::try_from(bytes).ok()
+}
+
+/// Returns whether `name` is the name of a directory of the cache, i.e. a
+/// hex-encoded fingerprint.
+fn is_fingerprint(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
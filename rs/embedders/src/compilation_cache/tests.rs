@@ -0,0 +1,182 @@
+use super::*;
+use crate::{wasm_utils::compile, WasmtimeEmbedder};
+use ic_interfaces::execution_environment::HypervisorError;
+use ic_logger::replica_logger::no_op_logger;
+use ic_wasm_types::{BinaryEncodedWasm, WasmValidationError};
+
+const CAPACITY: NumBytes = NumBytes::new(1 << 30);
+
+fn compile_wat(wat: &str) -> (CanisterModule, Arc<SerializedModule>) {
+    let wasm = wat::parse_str(wat).unwrap();
+    let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+    let (_, result) = compile(&embedder, &BinaryEncodedWasm::new(wasm.clone()));
+    let (_, serialized_module) = result.unwrap();
+    (CanisterModule::new(wasm), Arc::new(serialized_module))
+}
+
+fn open(dir: &Path, disk_capacity: NumBytes, config: &EmbeddersConfig) -> CompilationCache {
+    CompilationCache::new_with_disk_cache(CAPACITY, dir, disk_capacity, config, no_op_logger())
+}
+
+/// Returns the modules stored on disk under `root`.
+fn cached_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+    for dir in fs::read_dir(root).unwrap() {
+        for file in fs::read_dir(dir.unwrap().path()).unwrap() {
+            files.push(file.unwrap().path());
+        }
+    }
+    files
+}
+
+fn assert_cached(cache: &CompilationCache, module: &CanisterModule, expected: &SerializedModule) {
+    let cached = cache.get(module).unwrap().unwrap();
+    assert_eq!(cached.bytes.as_slice(), expected.bytes.as_slice());
+    assert_eq!(cached.exported_functions, expected.exported_functions);
+}
+
+#[test]
+fn compiled_module_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let (module, serialized_module) =
+        compile_wat(r#"(module (func (export "canister_update f")))"#);
+
+    let cache = open(dir.path(), CAPACITY, &config);
+    cache.insert(&module, Ok(Arc::clone(&serialized_module)));
+    drop(cache);
+
+    let cache = open(dir.path(), CAPACITY, &config);
+    assert_cached(&cache, &module, &serialized_module);
+    // The module is now served from memory.
+    fs::remove_file(&cached_files(dir.path())[0]).unwrap();
+    assert_cached(&cache, &module, &serialized_module);
+}
+
+#[test]
+fn modules_of_another_config_are_deleted() {
+    let dir = tempfile::tempdir().unwrap();
+    let (module, serialized_module) = compile_wat("(module)");
+    let cache = open(dir.path(), CAPACITY, &EmbeddersConfig::default());
+    cache.insert(&module, Ok(serialized_module));
+    drop(cache);
+
+    let other_config = EmbeddersConfig {
+        max_globals: EmbeddersConfig::default().max_globals + 1,
+        ..EmbeddersConfig::default()
+    };
+    let cache = open(dir.path(), CAPACITY, &other_config);
+    assert!(cache.get(&module).is_none());
+    assert!(cached_files(dir.path()).is_empty());
+}
+
+#[test]
+fn corrupted_module_is_discarded() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let (module, serialized_module) = compile_wat("(module)");
+    let cache = open(dir.path(), CAPACITY, &config);
+    cache.insert(&module, Ok(serialized_module));
+    drop(cache);
+
+    let path = cached_files(dir.path()).pop().unwrap();
+    let mut bytes = fs::read(&path).unwrap();
+    *bytes.last_mut().unwrap() ^= 1;
+    fs::write(&path, bytes).unwrap();
+
+    let cache = open(dir.path(), CAPACITY, &config);
+    assert!(cache.get(&module).is_none());
+    assert!(!path.exists());
+}
+
+#[test]
+fn least_recently_used_module_is_evicted() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let (module_1, serialized_module_1) =
+        compile_wat(r#"(module (func (export "canister_update f")))"#);
+    let (module_2, serialized_module_2) =
+        compile_wat(r#"(module (func (export "canister_update g")))"#);
+
+    let cache = open(dir.path(), CAPACITY, &config);
+    cache.insert(&module_1, Ok(serialized_module_1));
+    let size = fs::metadata(&cached_files(dir.path())[0]).unwrap().len();
+    drop(cache);
+
+    // Only the most recently inserted module fits on disk.
+    let disk_capacity = NumBytes::new(size + size / 2);
+    let cache = open(dir.path(), disk_capacity, &config);
+    cache.insert(&module_2, Ok(Arc::clone(&serialized_module_2)));
+    drop(cache);
+
+    let cache = open(dir.path(), disk_capacity, &config);
+    assert!(cache.get(&module_1).is_none());
+    assert_cached(&cache, &module_2, &serialized_module_2);
+}
+
+#[test]
+fn compilation_errors_are_not_persisted() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let (module, _) = compile_wat("(module)");
+    let error = HypervisorError::InvalidWasm(WasmValidationError::TooManyGlobals {
+        defined: 1,
+        allowed: 0,
+    });
+
+    let cache = open(dir.path(), CAPACITY, &config);
+    cache.insert(&module, Err(error.clone()));
+    assert_eq!(cache.get(&module).unwrap().unwrap_err(), error);
+    assert!(cached_files(dir.path()).is_empty());
+}
+
+#[test]
+fn fingerprint_covers_wasmtime_and_instrumentation_versions() {
+    let replica_version = ReplicaVersion::default();
+    let config = EmbeddersConfig::default();
+    let fingerprint_of = |wasmtime_version, instrumentation_version| {
+        fingerprint(
+            &replica_version,
+            wasmtime_version,
+            instrumentation_version,
+            &config,
+        )
+    };
+
+    let current = fingerprint_of(WASMTIME_VERSION, INSTRUMENTATION_VERSION);
+    assert_ne!(current, fingerprint_of("0.0.0", INSTRUMENTATION_VERSION));
+    assert_ne!(
+        current,
+        fingerprint_of(WASMTIME_VERSION, INSTRUMENTATION_VERSION + 1)
+    );
+}
+
+#[test]
+fn wasmtime_version_matches_the_dependency() {
+    let manifest = include_str!("../../Cargo.toml");
+    let dependency = manifest
+        .lines()
+        .find(|line| line.starts_with("wasmtime = "))
+        .unwrap();
+    assert!(
+        dependency.contains(&format!("version = \"{}\"", WASMTIME_VERSION)),
+        "WASMTIME_VERSION is out of date: {}",
+        dependency
+    );
+}
+
+#[test]
+fn only_directories_of_other_fingerprints_are_deleted() {
+    let dir = tempfile::tempdir().unwrap();
+    let stale = dir.path().join(hex::encode([0; 32]));
+    let unrelated_dir = dir.path().join("unrelated");
+    let unrelated_file = dir.path().join("notes.txt");
+    fs::create_dir(&stale).unwrap();
+    fs::create_dir(&unrelated_dir).unwrap();
+    fs::write(&unrelated_file, "keep me").unwrap();
+
+    let _cache = open(dir.path(), CAPACITY, &EmbeddersConfig::default());
+    assert!(!stale.exists());
+    assert!(unrelated_dir.exists());
+    assert!(unrelated_file.exists());
+}
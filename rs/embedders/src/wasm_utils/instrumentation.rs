@@ -455,6 +455,11 @@ pub fn instruction_to_cost_new(i: &Operator) -> u64 {
     }
 }
 
+/// The version of the instrumentation, to be bumped whenever a change to this
+/// module changes the code it produces, so that modules instrumented before
+/// the change are no longer loaded from the on-disk compilation cache.
+pub const INSTRUMENTATION_VERSION: u32 = 1;
+
 const INSTRUMENTED_FUN_MODULE: &str = "__";
 const OUT_OF_INSTRUCTIONS_FUN_NAME: &str = "out_of_instructions";
 const UPDATE_MEMORY_FUN_NAME: &str = "update_available_memory";
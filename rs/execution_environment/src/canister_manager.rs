@@ -17,8 +17,8 @@ use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
     CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterStatusResultV2,
-    CanisterStatusType, InstallChunkedCodeArgs, InstallCodeArgsV2, Method as Ic00Method,
-    StoredChunksReply, UploadChunkReply,
+    CanisterStatusType, InstallChunkedCodeArgs, InstallCodeArgsV2, MemoryMetric, MemoryMetrics,
+    Method as Ic00Method, StoredChunksReply, UploadChunkReply,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter, SubnetAvailableMemory,
@@ -34,8 +34,8 @@ use ic_replicated_state::{
     },
     metadata_state::subnet_call_context_manager::InstallCodeCallId,
     page_map::PageAllocatorFileDescriptor,
-    CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState, SchedulerState,
-    SystemState,
+    CallOrigin, CanisterState, CanisterStatus, ExecutionState, NetworkTopology, ReplicatedState,
+    SchedulerState, SystemState,
 };
 use ic_system_api::ExecutionParameters;
 use ic_types::{
@@ -1199,9 +1199,43 @@ impl CanisterManager {
                 .scheduler_state
                 .total_query_stats
                 .egress_payload_size,
+            canister.scheduler_state.total_query_stats.cycles_charged,
+            self.memory_metrics(canister, subnet_size),
         ))
     }
 
+    /// Returns the breakdown of the memory used by the canister, together with
+    /// the cycles burned per day for storing each component.
+    fn memory_metrics(&self, canister: &CanisterState, subnet_size: usize) -> MemoryMetrics {
+        let metric = |size: NumBytes| {
+            MemoryMetric::new(
+                size,
+                self.cycles_account_manager
+                    .idle_memory_cycles_burned_rate(size, subnet_size)
+                    .get(),
+            )
+        };
+        let execution_state_metric = |size: fn(&ExecutionState) -> NumBytes| {
+            metric(
+                canister
+                    .execution_state
+                    .as_ref()
+                    .map_or(NumBytes::from(0), size),
+            )
+        };
+
+        MemoryMetrics {
+            wasm_memory: execution_state_metric(ExecutionState::wasm_memory_usage),
+            stable_memory: execution_state_metric(ExecutionState::stable_memory_usage),
+            global_memory: execution_state_metric(ExecutionState::globals_memory_usage),
+            wasm_binary: execution_state_metric(ExecutionState::wasm_binary_memory_usage),
+            custom_sections: metric(canister.wasm_custom_sections_memory_usage()),
+            canister_history: metric(canister.canister_history_memory_usage()),
+            wasm_chunk_store: metric(canister.wasm_chunk_store_memory_usage()),
+            message_memory: metric(canister.message_memory_usage()),
+        }
+    }
+
     /// Permanently deletes a canister from `ReplicatedState`.
     ///
     /// The canister must be `Stopped` and only the controller of the canister
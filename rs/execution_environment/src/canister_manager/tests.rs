@@ -6746,6 +6746,57 @@ fn canister_status_contains_reserved_cycles_limit() {
     );
 }
 
+#[test]
+fn canister_status_contains_memory_metrics() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000_000);
+    let wat = r#"
+        (module
+            (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+            (func (export "canister_init")
+                (drop (call $stable_grow (i32.const 1)))
+            )
+            (memory 2)
+        )"#;
+
+    let mut test = ExecutionTestBuilder::new().build();
+
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.canister_status(canister_id);
+    let reply = get_reply(result);
+    let status = CanisterStatusResultV2::decode(&reply).unwrap();
+    let memory_metrics = status.memory_metrics();
+
+    assert_eq!(
+        memory_metrics.wasm_memory.size(),
+        NumBytes::from(2 * WASM_PAGE_SIZE_IN_BYTES)
+    );
+    assert_eq!(
+        memory_metrics.stable_memory.size(),
+        NumBytes::from(WASM_PAGE_SIZE_IN_BYTES)
+    );
+    assert_eq!(
+        memory_metrics.wasm_binary.size(),
+        test.execution_state(canister_id).wasm_binary_memory_usage()
+    );
+    assert_eq!(
+        memory_metrics.wasm_memory.size()
+            + memory_metrics.stable_memory.size()
+            + memory_metrics.global_memory.size()
+            + memory_metrics.wasm_binary.size()
+            + memory_metrics.custom_sections.size()
+            + memory_metrics.canister_history.size()
+            + memory_metrics.wasm_chunk_store.size(),
+        status.memory_size()
+    );
+    assert_eq!(
+        memory_metrics.wasm_memory.idle_cycles_burned_per_day(),
+        test.cycles_account_manager()
+            .idle_memory_cycles_burned_rate(memory_metrics.wasm_memory.size(), test.subnet_size())
+            .get()
+    );
+    assert!(memory_metrics.wasm_memory.idle_cycles_burned_per_day() > 0);
+}
+
 #[test]
 fn upload_chunk_works_from_white_list() {
     const CYCLES: Cycles = Cycles::new(1_000_000_000_000_000);
@@ -57,8 +57,9 @@ use ic_replicated_state::{
     CanisterState, CanisterStatus, ExecutionTask, NetworkTopology, ReplicatedState,
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
+use ic_tracing::{start_ingress_span, IngressStage};
 use ic_types::{
-    canister_http::CanisterHttpRequestContext,
+    canister_http::{CanisterHttpRequestContext, Replication},
     crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey},
     crypto::threshold_sig::ni_dkg::NiDkgTargetId,
     ingress::{IngressState, IngressStatus, WasmResult},
@@ -75,7 +76,7 @@ use ic_types::{messages::MessageId, methods::WasmMethod};
 use ic_wasm_types::WasmHash;
 use phantom_newtype::AmountOf;
 use prometheus::IntCounter;
-use rand::RngCore;
+use rand::{seq::IteratorRandom, RngCore};
 use std::{
     collections::{BTreeMap, HashMap},
     convert::Into,
@@ -809,19 +810,36 @@ impl ExecutionEnvironment {
                     CanisterCall::Request(request) => {
                         match CanisterHttpRequestArgs::decode(payload) {
                             Err(err) => Some((Err(err), msg.take_cycles())),
-                            Ok(args) => match CanisterHttpRequestContext::try_from((
-                                state.time(),
-                                request.as_ref(),
-                                args,
-                            )) {
-                                Err(err) => Some((Err(err.into()), msg.take_cycles())),
+                            Ok(args) => match canister_http_request_replication(&args, &state, rng)
+                                .and_then(|replication| {
+                                    CanisterHttpRequestContext::try_from((
+                                        state.time(),
+                                        request.as_ref(),
+                                        args,
+                                        replication,
+                                    ))
+                                    .map_err(UserError::from)
+                                }) {
+                                Err(err) => Some((Err(err), msg.take_cycles())),
                                 Ok(mut canister_http_request_context) => {
-                                    let http_request_fee =
-                                        self.cycles_account_manager.http_request_fee(
-                                            canister_http_request_context.variable_parts_size(),
-                                            canister_http_request_context.max_response_bytes,
-                                            registry_settings.subnet_size,
-                                        );
+                                    let http_request_fee = match canister_http_request_context
+                                        .replication
+                                    {
+                                        Replication::FullyReplicated => {
+                                            self.cycles_account_manager.http_request_fee(
+                                                canister_http_request_context.variable_parts_size(),
+                                                canister_http_request_context.max_response_bytes,
+                                                registry_settings.subnet_size,
+                                            )
+                                        }
+                                        Replication::NonReplicated(_) => self
+                                            .cycles_account_manager
+                                            .non_replicated_http_request_fee(
+                                                canister_http_request_context.variable_parts_size(),
+                                                canister_http_request_context.max_response_bytes,
+                                                registry_settings.subnet_size,
+                                            ),
+                                    };
                                     if request.payment < http_request_fee {
                                         let err = Err(UserError::new(
                                                         ErrorCode::CanisterRejectedMessage,
@@ -3145,6 +3163,18 @@ fn execute_canister_input(
     subnet_size: usize,
 ) -> ExecuteCanisterResult {
     let info = input.to_string();
+    let span = match &input {
+        CanisterMessageOrTask::Message(CanisterMessage::Ingress(ingress))
+            if ic_tracing::is_enabled() =>
+        {
+            Some(
+                start_ingress_span(&ingress.message_id, IngressStage::Executed)
+                    .with_attribute("ic.canister_id", ingress.receiver.to_string())
+                    .with_attribute("ic.method_name", ingress.method_name.clone()),
+            )
+        }
+        _ => None,
+    };
     let result = exec_env.execute_canister_input(
         canister,
         instruction_limits,
@@ -3157,6 +3187,16 @@ fn execute_canister_input(
         subnet_size,
     );
     let (canister, instructions_used, heap_delta, ingress_status) = exec_env.process_result(result);
+    if let Some(mut span) = span {
+        if let Some(instructions_used) = instructions_used {
+            span = span.with_attribute("ic.instructions_used", instructions_used.get() as i64);
+        }
+        // A paused long execution has no status yet and continues in a later round.
+        let status = ingress_status
+            .as_ref()
+            .map_or("paused", |(_, status)| status.as_str());
+        span.with_attribute("ic.ingress_status", status).end();
+    }
     ExecuteCanisterResult {
         canister,
         instructions_used,
@@ -3278,3 +3318,29 @@ fn get_master_ecdsa_public_key<'a>(
         Some(master_key) => Ok(master_key),
     }
 }
+
+/// Determines which replicas perform the http request described by `args`.
+/// A non-replicated request is assigned to a replica of the own subnet that is
+/// chosen using the deterministic randomness of the round.
+fn canister_http_request_replication(
+    args: &CanisterHttpRequestArgs,
+    state: &ReplicatedState,
+    rng: &mut dyn RngCore,
+) -> Result<Replication, UserError> {
+    if args.is_replicated() {
+        return Ok(Replication::FullyReplicated);
+    }
+    state
+        .metadata
+        .network_topology
+        .subnets
+        .get(&state.metadata.own_subnet_id)
+        .and_then(|subnet_topology| subnet_topology.nodes.iter().choose(rng))
+        .map(|node_id| Replication::NonReplicated(*node_id))
+        .ok_or_else(|| {
+            UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "No replica is available to perform a non-replicated http request.",
+            )
+        })
+}
@@ -28,7 +28,7 @@ use ic_test_utilities_execution_environment::{
     ExecutionTestBuilder,
 };
 use ic_test_utilities_metrics::{fetch_histogram_vec_count, fetch_int_counter, metric_vec};
-use ic_types::canister_http::Transform;
+use ic_types::canister_http::{Replication, Transform};
 use ic_types::{
     canister_http::CanisterHttpMethod,
     ingress::{IngressState, IngressStatus, WasmResult},
@@ -1923,6 +1923,7 @@ fn execute_canister_http_request() {
             }),
             context: transform_context.clone(),
         }),
+        is_replicated: None,
     };
 
     // Create request to HTTP_REQUEST method.
@@ -1977,6 +1978,69 @@ fn execute_canister_http_request() {
     );
 }
 
+#[test]
+fn execute_non_replicated_canister_http_request() {
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+
+    let response_size_limit = 1000u64;
+    let args = CanisterHttpRequestArgs {
+        url: "https://".to_string(),
+        max_response_bytes: Some(response_size_limit),
+        headers: BoundedHttpHeaders::new(vec![]),
+        body: Some(b"payment".to_vec()),
+        method: HttpMethod::POST,
+        transform: None,
+        is_replicated: Some(false),
+    };
+
+    // Create request to HTTP_REQUEST method.
+    let payment = Cycles::new(1_000_000_000);
+    test.inject_call_to_ic00(Method::HttpRequest, args.encode(), payment);
+    test.execute_all();
+
+    let http_request_context = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .get(&CallbackId::from(0))
+        .unwrap()
+        .clone();
+
+    // The request is assigned to a single node of the own subnet.
+    match http_request_context.replication {
+        Replication::NonReplicated(node_id) => assert!(test
+            .state()
+            .metadata
+            .network_topology
+            .subnets
+            .get(&own_subnet)
+            .unwrap()
+            .nodes
+            .contains(&node_id)),
+        Replication::FullyReplicated => panic!("Expected a non-replicated request"),
+    }
+
+    // The request is charged the lower, non-replicated fee.
+    let fee = test.non_replicated_http_request_fee(
+        http_request_context.variable_parts_size(),
+        Some(NumBytes::from(response_size_limit)),
+    );
+    assert!(
+        fee < test.http_request_fee(
+            http_request_context.variable_parts_size(),
+            Some(NumBytes::from(response_size_limit)),
+        )
+    );
+    assert_eq!(http_request_context.request.payment, payment - fee);
+}
+
 #[test]
 fn execute_canister_http_request_disabled() {
     let own_subnet = subnet_test_id(1);
@@ -2002,6 +2066,7 @@ fn execute_canister_http_request_disabled() {
             }),
             context: vec![0, 1, 2],
         }),
+        is_replicated: None,
     };
 
     // Create request to HTTP_REQUEST method.
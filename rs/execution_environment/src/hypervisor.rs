@@ -235,6 +235,17 @@ impl Hypervisor {
         embedder_config.subnet_type = own_subnet_type;
         embedder_config.dirty_page_overhead = dirty_page_overhead;
 
+        let compilation_cache = match &config.compilation_cache_dir {
+            Some(dir) => CompilationCache::new_with_disk_cache(
+                config.max_compilation_cache_size,
+                dir,
+                config.max_compilation_cache_disk_size,
+                &embedder_config,
+                log.clone(),
+            ),
+            None => CompilationCache::new(config.max_compilation_cache_size),
+        };
+
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => {
                 let executor = SandboxedExecutionController::new(
@@ -264,7 +275,7 @@ impl Hypervisor {
             own_subnet_type,
             log,
             cycles_account_manager,
-            compilation_cache: Arc::new(CompilationCache::new(config.max_compilation_cache_size)),
+            compilation_cache: Arc::new(compilation_cache),
             deterministic_time_slicing: config.deterministic_time_slicing,
             cost_to_compile_wasm_instruction: config
                 .embedders_config
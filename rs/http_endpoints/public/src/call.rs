@@ -22,6 +22,7 @@ use ic_registry_client_helpers::{
     subnet::{IngressMessageSettings, SubnetRegistry},
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
+use ic_tracing::{start_ingress_span, IngressStage};
 use ic_types::{
     artifact::UnvalidatedArtifactMutation,
     artifact_kind::IngressArtifact,
@@ -193,6 +194,11 @@ impl Service<Request<Bytes>> for CallService {
         }
 
         let message_id = msg.id();
+        let span = ic_tracing::is_enabled().then(|| {
+            start_ingress_span(&message_id, IngressStage::Received)
+                .with_attribute("ic.canister_id", msg.canister_id().to_string())
+                .with_attribute("ic.method_name", msg.method_name().to_string())
+        });
         let registry_version = self.registry_client.get_latest_version();
         let (ingress_registry_settings, provisional_whitelist) = match get_registry_data(
             &self.log,
@@ -267,6 +273,10 @@ impl Service<Request<Bytes>> for CallService {
                 );
                 make_accepted_response()
             };
+            if let Some(span) = span {
+                span.with_attribute("http.status_code", response.status().as_u16() as i64)
+                    .end();
+            }
             Ok(response)
         })
     }
@@ -328,7 +328,7 @@ mod tests {
         CanisterHttpSendRequest, CanisterHttpSendResponse,
     };
     use ic_test_utilities::{mock_time, types::messages::RequestBuilder};
-    use ic_types::canister_http::Transform;
+    use ic_types::canister_http::{Replication, Transform};
     use ic_types::{
         canister_http::CanisterHttpMethod,
         messages::{Blob, CallbackId},
@@ -423,6 +423,7 @@ mod tests {
                     context: vec![],
                 }),
                 time: mock_time(),
+                replication: Replication::FullyReplicated,
             },
         }
     }
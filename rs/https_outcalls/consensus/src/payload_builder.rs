@@ -4,7 +4,10 @@ use crate::{
     metrics::CanisterHttpPayloadBuilderMetrics,
     payload_builder::{
         parse::bytes_to_payload,
-        utils::{group_shares_by_callback_id, grouped_shares_meet_divergence_criteria},
+        utils::{
+            group_shares_by_callback_id, grouped_shares_meet_divergence_criteria,
+            non_replicated_request_nodes,
+        },
     },
 };
 use ic_consensus_utils::{
@@ -42,7 +45,7 @@ use ic_types::{
     CanisterId, CountBytes, Cycles, Height, NodeId, NumBytes, RegistryVersion, SubnetId,
 };
 use std::{
-    collections::{BTreeSet, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     mem::size_of,
     sync::{Arc, RwLock},
 };
@@ -197,6 +200,8 @@ impl CanisterHttpPayloadBuilderImpl {
         let mut candidates = vec![];
         let mut timeouts = vec![];
         let mut divergence_responses = vec![];
+        // Requests performed by a single node, which need only that node's share.
+        let mut non_replicated_nodes = BTreeMap::new();
 
         // Metrics counters
         let mut unique_includable_responses = 0;
@@ -215,14 +220,15 @@ impl CanisterHttpPayloadBuilderImpl {
             .state_reader
             .get_state_at(validation_context.certified_height)
         {
-            // Iterate over all outstanding canister http requests
-            for (callback_id, request) in state
+            let http_contexts = &state
                 .get_ref()
                 .metadata
                 .subnet_call_context_manager
-                .canister_http_request_contexts
-                .iter()
-            {
+                .canister_http_request_contexts;
+            non_replicated_nodes = non_replicated_request_nodes(http_contexts);
+
+            // Iterate over all outstanding canister http requests
+            for (callback_id, request) in http_contexts.iter() {
                 unique_includable_responses += 1;
                 let candidate_size = callback_id.count_bytes();
                 let size = NumBytes::new((accumulated_size + candidate_size) as u64);
@@ -276,25 +282,47 @@ impl CanisterHttpPayloadBuilderImpl {
 
             let candidates_and_divergences = response_candidates_by_callback_id
                 .into_iter()
-                .filter_map(|(_, grouped_shares)| {
+                .filter_map(|(callback_id, grouped_shares)| {
+                    let non_replicated_node = non_replicated_nodes.get(&callback_id);
                     if let Some((metadata, shares)) = grouped_shares.iter().find(|(_, shares)| {
                         unique_responses_count += 1;
-                        let signers: BTreeSet<_> =
-                            shares.iter().map(|share| share.signature.signer).collect();
-                        // We need at least threshold different signers to include the response
-                        signers.len() >= threshold
+                        match non_replicated_node {
+                            // The share of the assigned node is sufficient
+                            Some(node_id) => shares
+                                .iter()
+                                .any(|share| share.signature.signer == *node_id),
+                            // We need at least threshold different signers to include the response
+                            None => {
+                                let signers: BTreeSet<_> =
+                                    shares.iter().map(|share| share.signature.signer).collect();
+                                signers.len() >= threshold
+                            }
+                        }
                     }) {
                         // A set of grouped shares large enough to meet the
                         // threshold was found, we should produce a result.
+                        // NOTE: The content of a non-replicated response is only
+                        // available in the pool of the node that made the request.
                         pool_access
                             .get_response_content_by_hash(&metadata.content_hash)
                             .map(|content| {
                                 CandidateOrDivergence::Candidate((
                                     metadata.clone(),
-                                    shares.iter().map(|share| share.signature.clone()).collect(),
+                                    shares
+                                        .iter()
+                                        .filter(|share| {
+                                            non_replicated_node.map_or(true, |node_id| {
+                                                share.signature.signer == *node_id
+                                            })
+                                        })
+                                        .map(|share| share.signature.clone())
+                                        .collect(),
                                     content,
                                 ))
                             })
+                    } else if non_replicated_node.is_some() {
+                        // Responses to non-replicated requests can not diverge
+                        None
                     } else {
                         // No set of grouped shares large enough was found
                         // so now we check whether we have divergence.
@@ -435,6 +463,8 @@ impl CanisterHttpPayloadBuilderImpl {
                 CanisterHttpTransientValidationError::ConsensusRegistryVersionUnavailable,
             ))?;
 
+        let non_replicated_nodes = non_replicated_request_nodes(http_contexts);
+
         // Check conditions on individual responses
         for response in &payload.responses {
             // Check that response is consistent
@@ -494,12 +524,29 @@ impl CanisterHttpPayloadBuilderImpl {
                     valid_signers,
                 });
             }
-            if valid_signers.len() < threshold {
-                return permanent_error(CanisterHttpPermanentValidationError::NotEnoughSigners {
-                    committee,
-                    signers: valid_signers,
-                    expected_threshold: threshold,
-                });
+            match non_replicated_nodes.get(&response.content.id) {
+                // A non-replicated response carries the share of the assigned node only
+                Some(node_id) => {
+                    if valid_signers != [*node_id] {
+                        return permanent_error(
+                            CanisterHttpPermanentValidationError::NonReplicatedResponseSignerMismatch {
+                                expected_signer: *node_id,
+                                signers: valid_signers,
+                            },
+                        );
+                    }
+                }
+                None => {
+                    if valid_signers.len() < threshold {
+                        return permanent_error(
+                            CanisterHttpPermanentValidationError::NotEnoughSigners {
+                                committee,
+                                signers: valid_signers,
+                                expected_threshold: threshold,
+                            },
+                        );
+                    }
+                }
             }
             self.crypto
                 .verify_aggregate(&response.proof, consensus_registry_version)
@@ -549,7 +596,14 @@ impl CanisterHttpPayloadBuilderImpl {
                     CanisterHttpPermanentValidationError::DivergenceProofContainsMultipleCallbackIds
                 );
             }
-            for (_, grouped_shares) in grouped_shares {
+            for (callback_id, grouped_shares) in grouped_shares {
+                if non_replicated_nodes.contains_key(&callback_id) {
+                    return permanent_error(
+                        CanisterHttpPermanentValidationError::DivergenceProofForNonReplicatedRequest(
+                            callback_id,
+                        ),
+                    );
+                }
                 if !grouped_shares_meet_divergence_criteria(&grouped_shares, faults_tolerated) {
                     return permanent_error(
                         CanisterHttpPermanentValidationError::DivergenceProofDoesNotMeetDivergenceCriteria
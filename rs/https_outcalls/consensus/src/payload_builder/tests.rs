@@ -36,7 +36,7 @@ use ic_types::{
     canister_http::{
         CanisterHttpMethod, CanisterHttpRequestContext, CanisterHttpResponse,
         CanisterHttpResponseContent, CanisterHttpResponseDivergence, CanisterHttpResponseMetadata,
-        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus,
+        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus, Replication,
         CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
     },
     consensus::get_faults_tolerated,
@@ -328,6 +328,7 @@ fn timeout_priority() {
                     transform: None,
                     // this is the important one
                     time: mock_time(),
+                    replication: Replication::FullyReplicated,
                 };
                 init_state
                     .metadata
@@ -444,6 +445,103 @@ fn divergence_response_inclusion_test() {
     });
 }
 
+/// Check that the response to a non-replicated request is included with only the
+/// share of the assigned node, while a share of any other node is not sufficient
+#[test]
+fn non_replicated_request_test() {
+    let context = default_validation_context();
+    let mut init_state = ic_test_utilities::state::get_initial_state(0, 0);
+    init_state
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .insert(
+            CallbackId::from(0),
+            CanisterHttpRequestContext {
+                request: RequestBuilder::default().build(),
+                url: String::new(),
+                max_response_bytes: None,
+                headers: vec![],
+                body: None,
+                http_method: CanisterHttpMethod::POST,
+                transform: None,
+                time: mock_time(),
+                replication: Replication::NonReplicated(node_test_id(2)),
+            },
+        );
+
+    test_config_with_http_feature(true, 4, |mut payload_builder, canister_http_pool| {
+        let state_manager = Arc::new(RefMockStateManager::default());
+        state_manager
+            .get_mut()
+            .expect_get_state_at()
+            .return_const(Ok(ic_interfaces_state_manager::Labeled::new(
+                Height::new(0),
+                Arc::new(init_state),
+            )));
+        payload_builder.state_reader = state_manager;
+
+        let (response, metadata) = test_response_and_metadata(0);
+
+        // A share of a node other than the assigned one is not sufficient
+        {
+            let mut pool_access = canister_http_pool.write().unwrap();
+            add_own_share_to_pool(
+                pool_access.deref_mut(),
+                &metadata_to_share(1, &metadata),
+                &response,
+            );
+        }
+        let payload = payload_builder.build_payload(
+            Height::new(1),
+            NumBytes::new(4 * 1024 * 1024),
+            &[],
+            &context,
+        );
+        let parsed_payload = bytes_to_payload(&payload).expect("Failed to parse payload");
+        assert!(parsed_payload.responses.is_empty());
+        assert!(parsed_payload.divergence_responses.is_empty());
+
+        // The share of the assigned node alone is sufficient
+        {
+            let mut pool_access = canister_http_pool.write().unwrap();
+            add_own_share_to_pool(
+                pool_access.deref_mut(),
+                &metadata_to_share(2, &metadata),
+                &response,
+            );
+        }
+        let payload = payload_builder.build_payload(
+            Height::new(1),
+            NumBytes::new(4 * 1024 * 1024),
+            &[],
+            &context,
+        );
+        let parsed_payload = bytes_to_payload(&payload).expect("Failed to parse payload");
+        assert_eq!(parsed_payload.responses.len(), 1);
+        assert_eq!(parsed_payload.responses[0].content, response);
+        assert_eq!(
+            parsed_payload.responses[0]
+                .proof
+                .signature
+                .signatures_map
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![node_test_id(2)]
+        );
+
+        assert!(payload_builder
+            .validate_payload(
+                Height::new(1),
+                &test_proposal_context(&context),
+                &payload,
+                &[],
+            )
+            .is_ok());
+    });
+}
+
 /// Submit a very large number of valid responses, then check that the
 /// payload builder does not process all of them but only CANISTER_HTTP_RESPONSES_PER_BLOCK
 #[test]
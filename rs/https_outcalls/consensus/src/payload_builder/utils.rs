@@ -2,7 +2,8 @@ use ic_interfaces::canister_http::CanisterHttpPermanentValidationError;
 use ic_types::{
     batch::ValidationContext,
     canister_http::{
-        CanisterHttpResponseMetadata, CanisterHttpResponseShare, CanisterHttpResponseWithConsensus,
+        CanisterHttpRequestContext, CanisterHttpResponseMetadata, CanisterHttpResponseShare,
+        CanisterHttpResponseWithConsensus, Replication,
     },
     crypto::crypto_hash,
     messages::CallbackId,
@@ -148,3 +149,17 @@ pub(crate) fn group_shares_by_callback_id<
     }
     map
 }
+
+/// Returns the nodes that were assigned to perform the non-replicated requests
+/// among the given request contexts.
+pub(crate) fn non_replicated_request_nodes(
+    contexts: &BTreeMap<CallbackId, CanisterHttpRequestContext>,
+) -> BTreeMap<CallbackId, NodeId> {
+    contexts
+        .iter()
+        .filter_map(|(callback_id, context)| match context.replication {
+            Replication::FullyReplicated => None,
+            Replication::NonReplicated(node_id) => Some((*callback_id, node_id)),
+        })
+        .collect()
+}
@@ -22,7 +22,7 @@ use ic_types::{
 };
 use std::{
     cell::RefCell,
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     convert::TryInto,
     sync::{Arc, Mutex},
     time::Duration,
@@ -183,7 +183,12 @@ impl CanisterHttpPoolManagerImpl {
             .collect();
 
         for (id, context) in http_requests {
-            if !request_ids_already_made.contains(&id) {
+            // Non-replicated requests are only made by the replica they were assigned to.
+            let is_assigned_to_this_replica = match context.replication {
+                Replication::FullyReplicated => true,
+                Replication::NonReplicated(node_id) => node_id == self.replica_config.node_id,
+            };
+            if is_assigned_to_this_replica && !request_ids_already_made.contains(&id) {
                 let timeout = context.time + Duration::from_secs(5 * 60);
                 if let Err(err) = self
                     .http_adapter_shim
@@ -286,6 +291,20 @@ impl CanisterHttpPoolManagerImpl {
             return Vec::new();
         };
 
+        let non_replicated_request_nodes: BTreeMap<_, _> = self
+            .state_reader
+            .get_latest_state()
+            .get_ref()
+            .metadata
+            .subnet_call_context_manager
+            .canister_http_request_contexts
+            .iter()
+            .filter_map(|(id, context)| match context.replication {
+                Replication::FullyReplicated => None,
+                Replication::NonReplicated(node_id) => Some((*id, node_id)),
+            })
+            .collect();
+
         canister_http_pool
             .get_unvalidated_shares()
             .filter_map(|share| {
@@ -312,6 +331,17 @@ impl CanisterHttpPoolManagerImpl {
                             .to_string(),
                     ));
                 }
+                if let Some(node_id) = non_replicated_request_nodes.get(&share.content.id) {
+                    if *node_id != share.signature.signer {
+                        self.metrics.shares_marked_invalid.inc();
+                        return Some(CanisterHttpChangeAction::HandleInvalid(
+                            share.clone(),
+                            "Share of a non-replicated request signed by a node that was not \
+                             assigned to it"
+                                .to_string(),
+                        ));
+                    }
+                }
                 // TODO: more precise error handling
                 if let Err(err) = self.crypto.verify(share, registry_version) {
                     error!(self.log, "Unable to verify signature of share, {}", err);
@@ -398,7 +428,7 @@ pub mod test {
     use ic_logger::replica_logger::no_op_logger;
     use ic_metrics::MetricsRegistry;
     use ic_registry_subnet_type::SubnetType;
-    use ic_test_utilities::types::ids::subnet_test_id;
+    use ic_test_utilities::types::ids::{node_test_id, subnet_test_id};
     use ic_test_utilities_logger::with_test_replica_logger;
     use ic_types::{
         crypto::{CryptoHash, CryptoHashOf},
@@ -472,6 +502,7 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    replication: Replication::FullyReplicated,
                 };
 
                 state_manager
@@ -532,6 +563,73 @@ pub mod test {
         });
     }
 
+    #[test]
+    pub fn test_non_replicated_request_not_made_by_other_nodes() {
+        ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
+            with_test_replica_logger(|log| {
+                let Dependencies {
+                    pool,
+                    replica_config,
+                    crypto,
+                    state_manager,
+                    registry,
+                    membership,
+                    ..
+                } = dependencies(pool_config.clone(), 4);
+                let mut shim_mock = MockNonBlockingChannel::<CanisterHttpRequest>::new();
+                shim_mock
+                    .expect_try_receive()
+                    .return_const(Err(TryReceiveError::Empty));
+
+                let shim: Arc<Mutex<CanisterHttpAdapterClient>> =
+                    Arc::new(Mutex::new(Box::new(shim_mock)));
+
+                // The request is assigned to a node other than this replica.
+                assert_ne!(replica_config.node_id, node_test_id(1000));
+                let request = CanisterHttpRequestContext {
+                    request: ic_test_utilities::types::messages::RequestBuilder::new().build(),
+                    url: "".to_string(),
+                    max_response_bytes: None,
+                    headers: vec![],
+                    body: None,
+                    http_method: CanisterHttpMethod::POST,
+                    transform: None,
+                    time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    replication: Replication::NonReplicated(node_test_id(1000)),
+                };
+
+                state_manager
+                    .get_mut()
+                    .expect_get_latest_state()
+                    .return_const(Labeled::new(
+                        Height::from(1),
+                        Arc::new(state_with_pending_http_calls(BTreeMap::from([(
+                            CallbackId::from(7),
+                            request,
+                        )]))),
+                    ));
+
+                let canister_http_pool =
+                    CanisterHttpPoolImpl::new(MetricsRegistry::new(), no_op_logger());
+                let pool_manager = CanisterHttpPoolManagerImpl::new(
+                    state_manager as Arc<_>,
+                    shim,
+                    crypto,
+                    membership,
+                    pool.get_cache(),
+                    replica_config,
+                    Arc::clone(&registry) as Arc<_>,
+                    MetricsRegistry::new(),
+                    log,
+                );
+
+                // There is no expectation on send, so this fails if the
+                // request is passed to the adapter.
+                pool_manager.generate_change_set(&canister_http_pool);
+            })
+        });
+    }
+
     #[test]
     pub fn test_create_shares() {
         ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
@@ -620,6 +718,7 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    replication: Replication::FullyReplicated,
                 };
 
                 // Expect times to be called exactly once to check that already
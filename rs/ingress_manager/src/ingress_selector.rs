@@ -19,16 +19,37 @@ use ic_interfaces_state_manager::StateManagerError;
 use ic_logger::warn;
 use ic_registry_client_helpers::subnet::IngressMessageSettings;
 use ic_replicated_state::ReplicatedState;
+use ic_tracing::{start_ingress_span, IngressStage};
 use ic_types::{
     artifact::IngressMessageId,
     batch::{IngressPayload, ValidationContext},
     consensus::Payload,
     ingress::{IngressSets, IngressStatus},
     messages::{extract_effective_canister_id, MessageId, SignedIngress},
-    CanisterId, CountBytes, Cycles, Height, NumBytes, Time,
+    CanisterId, CountBytes, Cycles, Height, NumBytes, Time, UserId,
 };
 use ic_validator::RequestValidationError;
-use std::{collections::BTreeMap, collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+};
+
+/// The maximum number of messages from a single sender that are included in a block. This
+/// prevents a single principal from crowding everyone else out of a block, even when it is the
+/// only sender of a canister's messages.
+///
+/// Blocks with more messages from a sender don't pass validation, so all replicas of a subnet
+/// must agree on the limit: changing it is a protocol change that has to ship with a new replica
+/// version. The anonymous principal is exempt, see [`is_bounded_sender`].
+pub(crate) const MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK: usize = 100;
+
+/// Returns whether the messages of the given sender count towards
+/// [`MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK`]. The anonymous principal is shared by all
+/// unauthenticated callers, so bounding it would throttle all of them together. Like any other
+/// sender, it still only gets one turn per round over the senders of a canister.
+fn is_bounded_sender(sender: &UserId) -> bool {
+    !sender.get_ref().is_anonymous()
+}
 
 impl IngressSelector for IngressManager {
     fn get_ingress_payload(
@@ -81,6 +102,8 @@ impl IngressSelector for IngressManager {
         let mut accumulated_size = 0;
         let mut cycles_needed: BTreeMap<CanisterId, Cycles> = BTreeMap::new();
         let mut num_messages = 0;
+        let mut messages_per_sender: BTreeMap<UserId, usize> = BTreeMap::new();
+        let mut deferred_messages = 0;
 
         let ingress_pool = self.ingress_pool.read().unwrap();
 
@@ -91,7 +114,9 @@ impl IngressSelector for IngressManager {
         struct CanisterQueue<'a> {
             /// Number of bytes the canister's queue that was included in ingress
             bytes_included: usize,
-            msgs: Vec<&'a ValidatedIngressArtifact>,
+            /// The messages of each sender. Senders take turns, so that a single sender
+            /// cannot crowd out the others.
+            senders: VecDeque<Vec<&'a ValidatedIngressArtifact>>,
         }
 
         let mut canister_queues =
@@ -101,23 +126,37 @@ impl IngressSelector for IngressManager {
             .validated()
             .get_all_by_expiry_range(expiry_range);
 
+        let mut msgs_by_canister_and_sender = BTreeMap::<_, Vec<_>>::new();
         for artifact in artifacts {
-            let pool_obj = canister_queues
-                .entry(artifact.msg.signed_ingress.canister_id())
-                .or_default();
-            pool_obj.msgs.push(artifact);
+            let ingress = &artifact.msg.signed_ingress;
+            msgs_by_canister_and_sender
+                .entry((ingress.canister_id(), ingress.sender()))
+                .or_default()
+                .push(artifact);
         }
-        let canister_count = canister_queues.len();
 
         // At this point messages are sorted by expiry time. In order to prevent malicious
         // users from putting their messages ahead of others by carefully crafting the expiry
         // times, we sort the ingress messages by the time they were delivered to the pool.
         // NOTE: We sort in reverse order, because messages are pop()-ed from the back.
-        for v in canister_queues.values_mut() {
-            v.msgs.sort_unstable_by_key(|artifact| {
+        for ((canister_id, _), mut msgs) in msgs_by_canister_and_sender {
+            msgs.sort_unstable_by_key(|artifact| {
                 std::cmp::Reverse(artifact.timestamp.as_nanos_since_unix_epoch())
             });
+            canister_queues
+                .entry(canister_id)
+                .or_default()
+                .senders
+                .push_back(msgs);
+        }
+        // The sender whose oldest message was delivered first takes the first turn.
+        for queue in canister_queues.values_mut() {
+            queue.senders.make_contiguous().sort_by_key(|msgs| {
+                msgs.last()
+                    .map(|artifact| artifact.timestamp.as_nanos_since_unix_epoch())
+            });
         }
+        let canister_count = canister_queues.len();
         /* --------------------------------------------------------------------------- */
         // END
         /* --------------------------------------------------------------------------- */
@@ -142,8 +181,21 @@ impl IngressSelector for IngressManager {
                 let canister_id = canisters[i];
                 // For a given canister, add valid ingress messsages until quota is met
                 let queue = &mut canister_queues.get_mut(&canister_id).unwrap();
-                while let Some(msg) = queue.msgs.last() {
+                while let Some(sender_msgs) = queue.senders.front_mut() {
+                    let Some(&msg) = sender_msgs.last() else {
+                        queue.senders.pop_front();
+                        continue;
+                    };
                     let ingress = &msg.msg.signed_ingress;
+                    let sender_count = messages_per_sender.entry(ingress.sender()).or_default();
+                    if is_bounded_sender(&ingress.sender())
+                        && *sender_count >= MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK
+                    {
+                        // The sender's remaining messages wait for a later block.
+                        deferred_messages += sender_msgs.len();
+                        queue.senders.pop_front();
+                        continue;
+                    }
                     let result = self.validate_ingress(
                         IngressMessageId::from(ingress),
                         ingress,
@@ -163,7 +215,7 @@ impl IngressSelector for IngressManager {
                             IngressPermanentError::IngressPayloadTooManyMessages(_, _),
                         )) => break 'outer,
                         _ => {
-                            queue.msgs.pop();
+                            sender_msgs.pop();
                             continue;
                         }
                     };
@@ -182,13 +234,16 @@ impl IngressSelector for IngressManager {
                     }
 
                     num_messages += 1;
+                    *sender_count += 1;
                     accumulated_size += ingress_size;
                     queue.bytes_included += ingress_size;
                     // The quota is not a hard limit. We always include the first message
                     // of each canister. This is why we check the third break criterion
                     // after this line.
                     messages_in_payload.push(ingress.clone());
-                    queue.msgs.pop();
+                    sender_msgs.pop();
+                    // Move on to the next sender of this canister.
+                    queue.senders.rotate_left(1);
                 }
 
                 // If the canister didn't exceed the quota, we know that it has no or
@@ -216,6 +271,9 @@ impl IngressSelector for IngressManager {
         }
         // Relevant ingress was cloned, and no references are held, so we drop the lock.
         drop(ingress_pool);
+        self.metrics
+            .ingress_selector_messages_deferred_for_fairness
+            .inc_by(deferred_messages as u64);
 
         // NOTE: Since the `Vec<SignedIngress>` is deserialized and slightly smaller than the
         // serialized `IngressPayload`, we need to check the size of the latter.
@@ -244,6 +302,14 @@ impl IngressSelector for IngressManager {
         let payload_size = payload.count_bytes();
         debug_assert!(payload_size <= byte_limit.get() as usize);
 
+        if ic_tracing::is_enabled() {
+            for id in payload.message_ids() {
+                start_ingress_span(&id.message_id, IngressStage::Selected)
+                    .with_attribute("ic.certified_height", context.certified_height.get() as i64)
+                    .end();
+            }
+        }
+
         payload
     }
 
@@ -307,11 +373,26 @@ impl IngressSelector for IngressManager {
 
         // Tracks the sum of cycles needed per canister.
         let mut cycles_needed: BTreeMap<CanisterId, Cycles> = BTreeMap::new();
+        let mut messages_per_sender: BTreeMap<UserId, usize> = BTreeMap::new();
         for i in 0..payload.message_count() {
             let (ingress_id, ingress) = payload
                 .get(i)
                 .map_err(IngressPermanentError::IngressPayloadError)?;
 
+            let sender = ingress.sender();
+            let sender_count = messages_per_sender.entry(sender).or_default();
+            *sender_count += 1;
+            if is_bounded_sender(&sender)
+                && *sender_count > MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK
+            {
+                return Err(ValidationError::Permanent(
+                    IngressPermanentError::TooManyMessagesFromSender(
+                        sender,
+                        MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK,
+                    ),
+                ));
+            }
+
             self.validate_ingress(
                 ingress_id.clone(),
                 &ingress,
@@ -610,6 +691,7 @@ mod tests {
     use crate::tests::{access_ingress_pool, setup, setup_registry, setup_with_params};
     use assert_matches::assert_matches;
     use ic_artifact_pool::ingress_pool::IngressPoolImpl;
+    use ic_canister_client_sender::{Ed25519KeyPair, Sender};
     use ic_ic00_types::{CanisterIdRecord, Payload, IC_00};
     use ic_interfaces::{
         execution_environment::IngressHistoryError,
@@ -2085,4 +2167,222 @@ mod tests {
             },
         )
     }
+
+    /// Generates `msg_count` ingress messages to canister 0 from the same new sender.
+    fn generate_ingress_from_one_sender(msg_count: usize) -> Vec<SignedIngress> {
+        let sender = Sender::from_keypair(&Ed25519KeyPair::generate(&mut rand::thread_rng()));
+        (0..msg_count)
+            .map(|i| {
+                SignedIngressBuilder::new()
+                    .canister_id(canister_test_id(0))
+                    .nonce(i as u64)
+                    .expiry_time(mock_time() + MAX_INGRESS_TTL)
+                    .sign_for_sender(&sender)
+                    .build()
+            })
+            .collect()
+    }
+
+    /// Generates `msg_count` anonymous ingress messages to canister 0.
+    fn generate_anonymous_ingress(msg_count: usize) -> Vec<SignedIngress> {
+        (0..msg_count)
+            .map(|i| {
+                SignedIngressBuilder::new()
+                    .canister_id(canister_test_id(0))
+                    .nonce(i as u64)
+                    .expiry_time(mock_time() + MAX_INGRESS_TTL)
+                    .build()
+            })
+            .collect()
+    }
+
+    /// Generates an ingress message to canister 0 from a new sender.
+    fn generate_signed_ingress() -> SignedIngress {
+        SignedIngressBuilder::new()
+            .canister_id(canister_test_id(0))
+            .expiry_time(mock_time() + MAX_INGRESS_TTL)
+            .sign_for_randomly_generated_sender()
+            .build()
+    }
+
+    fn state_with_canister_0() -> ReplicatedState {
+        let (_, canister) = generate_ingress_with_params(
+            canister_test_id(0),
+            /* msg_count = */ 0,
+            /* bytes = */ 0,
+            mock_time(),
+        );
+        ReplicatedStateBuilder::default()
+            .with_canister(canister)
+            .build()
+    }
+
+    fn validation_context() -> ValidationContext {
+        ValidationContext {
+            time: mock_time(),
+            registry_version: RegistryVersion::from(1),
+            certified_height: Height::from(0),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_round_robins_across_senders() {
+        let flooding_msgs = generate_ingress_from_one_sender(5);
+        let flooding_sender = flooding_msgs[0].sender();
+        let other_msgs = vec![generate_signed_ingress(), generate_signed_ingress()];
+        let flooding_size = flooding_msgs[0].count_bytes();
+        let other_size = other_msgs[0].count_bytes();
+        // There is only room for four messages if two of them are from the other senders.
+        let byte_limit =
+            NumBytes::new((2 * flooding_size + 2 * other_size + flooding_size / 2) as u64);
+
+        setup_with_params(
+            None,
+            None,
+            None,
+            Some(state_with_canister_0()),
+            |ingress_manager, ingress_pool| {
+                // The flooding sender's messages were all delivered first.
+                insert_unvalidated_ingress_with_timestamp(
+                    flooding_msgs,
+                    &ingress_pool,
+                    mock_time(),
+                );
+                insert_unvalidated_ingress_with_timestamp(
+                    other_msgs,
+                    &ingress_pool,
+                    mock_time() + Duration::from_secs(1),
+                );
+
+                let payload = ingress_manager.get_ingress_payload(
+                    &HashSet::new(),
+                    &validation_context(),
+                    byte_limit,
+                );
+                let msgs: Vec<SignedIngress> = payload.try_into().unwrap();
+                let is_flooding: Vec<bool> =
+                    msgs.iter().map(|m| m.sender() == flooding_sender).collect();
+                assert_eq!(is_flooding, vec![true, false, false, true]);
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_bounds_messages_per_sender() {
+        let flooding_msgs =
+            generate_ingress_from_one_sender(MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK + 5);
+        let flooding_sender = flooding_msgs[0].sender();
+        let other_msg = generate_signed_ingress();
+
+        setup_with_params(
+            None,
+            None,
+            None,
+            Some(state_with_canister_0()),
+            |ingress_manager, ingress_pool| {
+                insert_unvalidated_ingress_with_timestamp(
+                    flooding_msgs,
+                    &ingress_pool,
+                    mock_time(),
+                );
+                insert_unvalidated_ingress_with_timestamp(
+                    vec![other_msg],
+                    &ingress_pool,
+                    mock_time() + Duration::from_secs(1),
+                );
+
+                let payload = ingress_manager.get_ingress_payload(
+                    &HashSet::new(),
+                    &validation_context(),
+                    NumBytes::new(1024 * 1024),
+                );
+                let msgs: Vec<SignedIngress> = payload.try_into().unwrap();
+                let flooding_count = msgs
+                    .iter()
+                    .filter(|m| m.sender() == flooding_sender)
+                    .count();
+                assert_eq!(flooding_count, MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK);
+                assert_eq!(msgs.len(), MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK + 1);
+                assert_eq!(
+                    ingress_manager
+                        .metrics
+                        .ingress_selector_messages_deferred_for_fairness
+                        .get(),
+                    5
+                );
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_validate_ingress_payload_too_many_messages_from_sender() {
+        setup_with_params(
+            None,
+            None,
+            None,
+            Some(state_with_canister_0()),
+            |ingress_manager, _| {
+                let mut msgs =
+                    generate_ingress_from_one_sender(MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK + 1);
+                let extra_msg = msgs.pop().unwrap();
+                let sender = msgs[0].sender();
+                assert_matches!(
+                    ingress_manager.validate_ingress_payload(
+                        &IngressPayload::from(msgs.clone()),
+                        &HashSet::new(),
+                        &validation_context(),
+                    ),
+                    Ok(())
+                );
+
+                msgs.push(extra_msg);
+                assert_matches!(
+                    ingress_manager.validate_ingress_payload(
+                        &IngressPayload::from(msgs),
+                        &HashSet::new(),
+                        &validation_context(),
+                    ),
+                    Err(ValidationError::Permanent(
+                        IngressPermanentError::TooManyMessagesFromSender(s, limit)
+                    )) if s == sender && limit == MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK
+                );
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_sender_is_not_bounded() {
+        let anonymous_msgs =
+            generate_anonymous_ingress(MAX_INGRESS_MESSAGES_PER_SENDER_PER_BLOCK + 5);
+        let msg_count = anonymous_msgs.len();
+
+        setup_with_params(
+            None,
+            None,
+            None,
+            Some(state_with_canister_0()),
+            |ingress_manager, ingress_pool| {
+                insert_unvalidated_ingress_with_timestamp(
+                    anonymous_msgs,
+                    &ingress_pool,
+                    mock_time(),
+                );
+
+                let payload = ingress_manager.get_ingress_payload(
+                    &HashSet::new(),
+                    &validation_context(),
+                    NumBytes::new(1024 * 1024),
+                );
+                assert_eq!(payload.message_count(), msg_count);
+                assert_matches!(
+                    ingress_manager.validate_ingress_payload(
+                        &payload,
+                        &HashSet::new(),
+                        &validation_context(),
+                    ),
+                    Ok(())
+                );
+            },
+        )
+    }
 }
@@ -33,7 +33,7 @@ use ic_types::{
 use ic_validator::{
     CanisterIdSet, HttpRequestVerifier, HttpRequestVerifierImpl, RequestValidationError,
 };
-use prometheus::{Histogram, IntGauge};
+use prometheus::{Histogram, IntCounter, IntGauge};
 use std::collections::hash_map::{DefaultHasher, RandomState};
 use std::hash::BuildHasher;
 use std::{
@@ -55,6 +55,7 @@ struct IngressManagerMetrics {
     ingress_selector_get_payload_time: Histogram,
     ingress_selector_validate_payload_time: Histogram,
     ingress_payload_cache_size: IntGauge,
+    ingress_selector_messages_deferred_for_fairness: IntCounter,
 }
 
 impl IngressManagerMetrics {
@@ -79,6 +80,11 @@ impl IngressManagerMetrics {
                 "ingress_payload_cache_size",
                 "The number of HashSets in payload builder's ingress payload cache.",
             ),
+            ingress_selector_messages_deferred_for_fairness: metrics_registry.int_counter(
+                "ingress_selector_messages_deferred_for_fairness",
+                "The number of ingress messages left out of a payload because their sender \
+                reached the per-sender message limit.",
+            ),
         }
     }
 }
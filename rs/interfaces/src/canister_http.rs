@@ -64,6 +64,14 @@ pub enum CanisterHttpPermanentValidationError {
         signers: Vec<NodeId>,
         expected_threshold: Threshold,
     },
+    /// The response to a non-replicated request is not signed by exactly the
+    /// node that was assigned to perform the request
+    NonReplicatedResponseSignerMismatch {
+        expected_signer: NodeId,
+        signers: Vec<NodeId>,
+    },
+    /// A divergence proof refers to a non-replicated request, which cannot diverge
+    DivergenceProofForNonReplicatedRequest(CallbackId),
     /// The payload contains a duplicate response
     DuplicateResponse(CallbackId),
     DivergenceProofContainsMultipleCallbackIds,
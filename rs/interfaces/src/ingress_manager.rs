@@ -11,7 +11,7 @@ use ic_types::{
     ingress::IngressSets,
     messages::MessageId,
     time::{Time, UNIX_EPOCH},
-    CanisterId, Height, NumBytes,
+    CanisterId, Height, NumBytes, UserId,
 };
 use std::collections::HashSet;
 
@@ -61,6 +61,7 @@ pub enum IngressPermanentError {
     IngressMessageTooBig(usize, usize),
     IngressPayloadTooBig(usize, usize),
     IngressPayloadTooManyMessages(usize, usize),
+    TooManyMessagesFromSender(UserId, usize),
     DuplicatedIngressMessage(MessageId),
     InsufficientCycles(CanisterOutOfCyclesError),
     CanisterNotFound(CanisterId),
@@ -2,7 +2,10 @@ use crate::{
     routing, scheduling,
     state_machine::{StateMachine, StateMachineImpl},
 };
-use ic_config::execution_environment::{BitcoinConfig, Config as HypervisorConfig};
+use ic_config::{
+    execution_environment::{BitcoinConfig, Config as HypervisorConfig},
+    flag_status::FlagStatus,
+};
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_ic00_types::EcdsaKeyId;
@@ -523,6 +526,10 @@ impl BatchProcessorImpl {
             Arc::clone(&time_in_stream_metrics),
             log.clone(),
         ));
+        let query_stats_cycles_account_manager = match hypervisor_config.query_stats_charging {
+            FlagStatus::Enabled => Some(Arc::clone(&cycles_account_manager)),
+            FlagStatus::Disabled => None,
+        };
         let vsr = Box::new(scheduling::valid_set_rule::ValidSetRuleImpl::new(
             ingress_history_writer,
             cycles_account_manager,
@@ -549,6 +556,7 @@ impl BatchProcessorImpl {
             log.clone(),
             metrics.clone(),
             hypervisor_config.query_stats_epoch_length,
+            query_stats_cycles_account_manager,
         ));
 
         Self {
@@ -21,6 +21,7 @@ use ic_replicated_state::{
     },
     ReplicatedState, StateError,
 };
+use ic_tracing::{start_ingress_span, IngressStage};
 use ic_types::{
     ingress::{IngressState, IngressStatus},
     messages::{
@@ -150,6 +151,8 @@ impl ValidSetRuleImpl {
     ) {
         trace!(self.log, "induct_message");
         let message_id = msg.id();
+        let span = ic_tracing::is_enabled()
+            .then(|| start_ingress_span(&message_id, IngressStage::Inducted));
         let source = msg.sender();
         let receiver = msg.canister_id();
         let payload_bytes = msg.arg().len();
@@ -194,6 +197,9 @@ impl ValidSetRuleImpl {
                 err.to_label_value()
             }
         };
+        if let Some(span) = span {
+            span.with_attribute("ic.induction_status", status).end();
+        }
         self.observe_inducted_ingress_status(status);
         self.observe_unreliable_induct_ingress_message_duration(status, ingress_expiry);
     }
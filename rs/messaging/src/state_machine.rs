@@ -1,5 +1,6 @@
 use crate::message_routing::{MessageRoutingMetrics, NodePublicKeys};
 use crate::routing::{demux::Demux, stream_builder::StreamBuilder};
+use ic_cycles_account_manager::CyclesAccountManager;
 use ic_interfaces::execution_environment::{
     ExecutionRoundType, RegistryExecutionSettings, Scheduler,
 };
@@ -8,7 +9,7 @@ use ic_query_stats::deliver_query_stats;
 use ic_registry_subnet_features::SubnetFeatures;
 use ic_replicated_state::{NetworkTopology, ReplicatedState};
 use ic_types::{batch::Batch, ExecutionRound};
-use std::time::Instant;
+use std::{sync::Arc, time::Instant};
 
 #[cfg(test)]
 mod tests;
@@ -36,6 +37,8 @@ pub(crate) struct StateMachineImpl {
     log: ReplicaLogger,
     metrics: MessageRoutingMetrics,
     query_stats_epoch_length: u64,
+    /// Used to charge canisters for query execution, if enabled.
+    query_stats_cycles_account_manager: Option<Arc<CyclesAccountManager>>,
 }
 
 impl StateMachineImpl {
@@ -46,6 +49,7 @@ impl StateMachineImpl {
         log: ReplicaLogger,
         metrics: MessageRoutingMetrics,
         query_stats_epoch_length: u64,
+        query_stats_cycles_account_manager: Option<Arc<CyclesAccountManager>>,
     ) -> Self {
         Self {
             scheduler,
@@ -54,6 +58,7 @@ impl StateMachineImpl {
             log,
             metrics,
             query_stats_epoch_length,
+            query_stats_cycles_account_manager,
         }
     }
 
@@ -87,6 +92,7 @@ impl StateMachine for StateMachineImpl {
                 batch.batch_number,
                 &self.log,
                 self.query_stats_epoch_length,
+                self.query_stats_cycles_account_manager.as_deref(),
                 &self.metrics.query_stats_metrics,
             );
         }
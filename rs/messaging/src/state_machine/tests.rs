@@ -157,6 +157,7 @@ fn state_machine_populates_network_topology() {
             log,
             fixture.metrics,
             ic_config::execution_environment::QUERY_STATS_EPOCH_LENGTH,
+            None,
         ));
 
         assert_ne!(
@@ -191,6 +192,7 @@ fn test_delivered_batch(provided_batch: Batch) -> ReplicatedState {
             log,
             fixture.metrics,
             ic_config::execution_environment::QUERY_STATS_EPOCH_LENGTH,
+            None,
         ));
 
         state_machine.execute_round(
@@ -253,6 +255,7 @@ fn test_batch_time_regression() {
             log,
             fixture.metrics,
             ic_config::execution_environment::QUERY_STATS_EPOCH_LENGTH,
+            None,
         );
 
         assert_eq!(
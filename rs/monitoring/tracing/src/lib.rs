@@ -0,0 +1,175 @@
+//! Distributed tracing of the lifecycle of ingress messages through the
+//! replica, exported to an OpenTelemetry collector over OTLP.
+//!
+//! Each ingress message gets its own trace. The trace id is the first 16 bytes
+//! of the message id and the id of the root span is the next 8 bytes, so any
+//! component -- on any node -- that knows the message id can contribute spans
+//! to the trace without context being propagated along with the message.
+//! Sampling is a ratio of the trace id, so all components and nodes agree on
+//! which messages are traced.
+use ic_config::tracing::Config;
+use ic_types::{messages::MessageId, NodeId, SubnetId};
+use opentelemetry::{
+    global::{self, BoxedSpan},
+    sdk::{
+        trace::{self as sdktrace, Sampler},
+        Resource,
+    },
+    trace::{
+        Span, SpanBuilder, SpanContext, SpanId, SpanKind, TraceContextExt, TraceError, TraceFlags,
+        TraceId, TraceState, Tracer,
+    },
+    Context, KeyValue, Value,
+};
+use opentelemetry_otlp::WithExportConfig;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(test)]
+mod tests;
+
+/// The name of the tracer spans are created with.
+const TRACER_NAME: &str = "ic-replica";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The stages of the lifecycle of an ingress message.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum IngressStage {
+    /// The message was received and validated by the HTTP endpoint. This is
+    /// the root span of the trace.
+    Received,
+    /// The message was selected by the ingress manager for a block proposal.
+    Selected,
+    /// A block including the message was finalized and delivered.
+    IncludedInBlock,
+    /// The message was inducted into the destination canister's queue.
+    Inducted,
+    /// The message was executed.
+    Executed,
+}
+
+impl IngressStage {
+    fn span_name(&self) -> &'static str {
+        match self {
+            Self::Received => "ingress_received",
+            Self::Selected => "ingress_selected",
+            Self::IncludedInBlock => "ingress_included_in_block",
+            Self::Inducted => "ingress_inducted",
+            Self::Executed => "ingress_executed",
+        }
+    }
+}
+
+/// Flushes and shuts down the exporter when dropped.
+pub struct TracingGuard(());
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        ENABLED.store(false, Ordering::SeqCst);
+        global::shutdown_tracer_provider();
+    }
+}
+
+/// Installs a global OTLP exporter according to `config`, if an endpoint is
+/// configured. Must be called from within a Tokio runtime, on which spans are
+/// exported in batches.
+pub fn init(
+    config: &Config,
+    node_id: NodeId,
+    subnet_id: SubnetId,
+) -> Result<Option<TracingGuard>, TraceError> {
+    let endpoint = match &config.otlp_endpoint {
+        Some(endpoint) => endpoint,
+        None => return Ok(None),
+    };
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            sdktrace::config()
+                .with_sampler(sampler(config.sampling_ratio))
+                .with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", config.service_name.clone()),
+                    KeyValue::new("ic.node_id", node_id.to_string()),
+                    KeyValue::new("ic.subnet_id", subnet_id.to_string()),
+                ])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)?;
+    ENABLED.store(true, Ordering::SeqCst);
+    Ok(Some(TracingGuard(())))
+}
+
+/// Returns whether an exporter is installed. Callers can use this to skip
+/// computing message ids when tracing is disabled.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// The sampler deciding on the trace id only, ignoring the parent, so that
+/// spans of a message are sampled consistently wherever they are created.
+pub(crate) fn sampler(sampling_ratio: f64) -> Sampler {
+    Sampler::TraceIdRatioBased(sampling_ratio)
+}
+
+/// A span covering one stage of the lifecycle of an ingress message. The span
+/// ends when dropped.
+pub struct IngressSpan<S: Span = BoxedSpan>(S);
+
+impl<S: Span> IngressSpan<S> {
+    /// Attaches an attribute to the span.
+    pub fn with_attribute(mut self, key: &'static str, value: impl Into<Value>) -> Self {
+        self.0.set_attribute(KeyValue::new(key, value));
+        self
+    }
+
+    /// Ends the span now rather than when dropped.
+    pub fn end(mut self) {
+        self.0.end();
+    }
+}
+
+/// Starts a span for `stage` of the message with id `message_id`, using the
+/// global tracer.
+pub fn start_ingress_span(message_id: &MessageId, stage: IngressStage) -> IngressSpan {
+    start_ingress_span_with_tracer(&global::tracer(TRACER_NAME), message_id, stage)
+}
+
+pub(crate) fn start_ingress_span_with_tracer<T: Tracer>(
+    tracer: &T,
+    message_id: &MessageId,
+    stage: IngressStage,
+) -> IngressSpan<T::Span> {
+    let (trace_id, root_span_id) = trace_ids(message_id);
+    let mut builder = SpanBuilder::from_name(stage.span_name())
+        .with_kind(SpanKind::Internal)
+        .with_attributes(vec![KeyValue::new("ic.message_id", message_id.to_string())]);
+    let parent = match stage {
+        IngressStage::Received => {
+            builder.trace_id = Some(trace_id);
+            builder.span_id = Some(root_span_id);
+            Context::new()
+        }
+        _ => Context::new().with_remote_span_context(SpanContext::new(
+            trace_id,
+            root_span_id,
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        )),
+    };
+    IngressSpan(tracer.build_with_context(builder, &parent))
+}
+
+/// Returns the trace id and the root span id of the trace of `message_id`.
+pub(crate) fn trace_ids(message_id: &MessageId) -> (TraceId, SpanId) {
+    let bytes = message_id.as_bytes();
+    let mut trace_id = [0; 16];
+    trace_id.copy_from_slice(&bytes[..16]);
+    let mut span_id = [0; 8];
+    span_id.copy_from_slice(&bytes[16..24]);
+    (TraceId::from_bytes(trace_id), SpanId::from_bytes(span_id))
+}
@@ -0,0 +1,74 @@
+use super::*;
+use opentelemetry::sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use opentelemetry::trace::TracerProvider as _;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// A stand-in for the collector, keeping exported spans in memory.
+#[derive(Clone, Debug, Default)]
+struct InMemoryExporter(Arc<Mutex<Vec<SpanData>>>);
+
+impl SpanExporter for InMemoryExporter {
+    fn export(
+        &mut self,
+        batch: Vec<SpanData>,
+    ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        self.0.lock().unwrap().extend(batch);
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+/// Emits a span for each stage of the lifecycle of `message_id` and returns
+/// the exported spans.
+fn trace_lifecycle(message_id: &MessageId, sampling_ratio: f64) -> Vec<SpanData> {
+    let exporter = InMemoryExporter::default();
+    let provider = sdktrace::TracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .with_config(sdktrace::config().with_sampler(sampler(sampling_ratio)))
+        .build();
+    let tracer = provider.tracer("test");
+    for stage in [
+        IngressStage::Received,
+        IngressStage::Selected,
+        IngressStage::IncludedInBlock,
+        IngressStage::Inducted,
+        IngressStage::Executed,
+    ] {
+        start_ingress_span_with_tracer(&tracer, message_id, stage)
+            .with_attribute("stage", stage.span_name())
+            .end();
+    }
+    // Shutting down the provider flushes the exporter.
+    drop(tracer);
+    drop(provider);
+    let spans = exporter.0.lock().unwrap().clone();
+    spans
+}
+
+#[test]
+fn spans_of_a_message_are_correlated_by_message_id() {
+    let message_id = MessageId::from([7; 32]);
+    let (trace_id, root_span_id) = trace_ids(&message_id);
+
+    let spans = trace_lifecycle(&message_id, 1.0);
+    assert_eq!(spans.len(), 5);
+    for span in &spans {
+        assert_eq!(span.span_context.trace_id(), trace_id);
+        if span.name == IngressStage::Received.span_name() {
+            assert_eq!(span.span_context.span_id(), root_span_id);
+            assert_eq!(span.parent_span_id, SpanId::INVALID);
+        } else {
+            assert_eq!(span.parent_span_id, root_span_id);
+        }
+    }
+
+    let other_trace_id = trace_ids(&MessageId::from([8; 32])).0;
+    assert_ne!(trace_id, other_trace_id);
+}
+
+#[test]
+fn sampling_is_all_or_nothing_per_message() {
+    let message_id = MessageId::from([7; 32]);
+    assert!(trace_lifecycle(&message_id, 0.0).is_empty());
+}
@@ -1872,6 +1872,38 @@ pub struct NetworkEconomics {
     /// If unspecified or zero, all proposals are kept.
     #[prost(uint32, tag = "10")]
     pub max_proposals_to_keep_per_topic: u32,
+    /// Parameters of the reduction of node provider rewards for nodes that fail
+    /// to make blocks. If unset, node providers are rewarded regardless of the
+    /// performance of their nodes.
+    #[prost(message, optional, tag = "11")]
+    pub node_rewards_performance_parameters:
+        ::core::option::Option<NodeRewardsPerformanceParameters>,
+}
+/// Determines how much the monthly rewards of a node are reduced, based on the
+/// fraction of blocks it failed to make, as reported by the node metrics history
+/// of the subnet it is assigned to. The reduction is 0 for failure rates at or
+/// below the threshold, `max_reduction_basis_points` for failure rates at or
+/// above `failure_rate_max_basis_points`, and linearly interpolated in between.
+///
+/// When set via a ManageNetworkEconomics proposal, only the specified fields
+/// are updated.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NodeRewardsPerformanceParameters {
+    /// The length of the window, ending at the time rewards are computed, over
+    /// which the failure rate of each node is measured. Defaults to one month.
+    #[prost(uint64, optional, tag = "1")]
+    pub metrics_window_seconds: ::core::option::Option<u64>,
+    /// Defaults to 0.
+    #[prost(uint64, optional, tag = "2")]
+    pub failure_rate_threshold_basis_points: ::core::option::Option<u64>,
+    /// Defaults to 10_000 (i.e. 100%).
+    #[prost(uint64, optional, tag = "3")]
+    pub failure_rate_max_basis_points: ::core::option::Option<u64>,
+    /// Defaults to 0, i.e. rewards are not reduced.
+    #[prost(uint64, optional, tag = "4")]
+    pub max_reduction_basis_points: ::core::option::Option<u64>,
 }
 /// A reward event is an event at which neuron maturity is increased
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -44,12 +44,12 @@ use crate::{
         MostRecentMonthlyNodeProviderRewards, Motion, NetworkEconomics, Neuron, NeuronInfo,
         NeuronState, NeuronsFundAuditInfo, NeuronsFundData,
         NeuronsFundParticipation as NeuronsFundParticipationPb,
-        NeuronsFundSnapshot as NeuronsFundSnapshotPb, NnsFunction, NodeProvider, OpenSnsTokenSwap,
-        Proposal, ProposalData, ProposalInfo, ProposalRewardStatus, ProposalStatus, RewardEvent,
-        RewardNodeProvider, RewardNodeProviders, SetSnsTokenSwapOpenTimeWindow,
-        SettleCommunityFundParticipation, SettleNeuronsFundParticipationRequest,
-        SettleNeuronsFundParticipationResponse, SwapBackgroundInformation, Tally, Topic,
-        UpdateNodeProvider, Vote, WaitForQuietState,
+        NeuronsFundSnapshot as NeuronsFundSnapshotPb, NnsFunction, NodeProvider,
+        NodeRewardsPerformanceParameters, OpenSnsTokenSwap, Proposal, ProposalData, ProposalInfo,
+        ProposalRewardStatus, ProposalStatus, RewardEvent, RewardNodeProvider, RewardNodeProviders,
+        SetSnsTokenSwapOpenTimeWindow, SettleCommunityFundParticipation,
+        SettleNeuronsFundParticipationRequest, SettleNeuronsFundParticipationResponse,
+        SwapBackgroundInformation, Tally, Topic, UpdateNodeProvider, Vote, WaitForQuietState,
     },
     proposals::create_service_nervous_system::ExecutedCreateServiceNervousSystemProposal,
     storage::with_stable_neuron_store,
@@ -63,6 +63,7 @@ use dfn_core::println;
 use dfn_protobuf::ToProto;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_crypto_sha2::Sha256;
+use ic_ic00_types::{NodeMetricsHistoryArgs, NodeMetricsHistoryResponse};
 use ic_nervous_system_common::{
     cmc::CMC, ledger, ledger::IcpLedger, NervousSystemError, SECONDS_PER_DAY,
 };
@@ -77,7 +78,11 @@ use ic_nns_constants::{
     IS_MATCHED_FUNDING_ENABLED, IS_UPDATE_ALLOWED_PRINCIPALS_ENABLED, LIFELINE_CANISTER_ID,
     REGISTRY_CANISTER_ID, ROOT_CANISTER_ID, SNS_WASM_CANISTER_ID,
 };
-use ic_protobuf::registry::dc::v1::AddOrRemoveDataCentersProposalPayload;
+use ic_protobuf::registry::{
+    dc::v1::AddOrRemoveDataCentersProposalPayload, subnet::v1::SubnetListRecord,
+};
+use ic_registry_keys::make_subnet_list_record_key;
+use ic_registry_transport::{deserialize_get_value_response, serialize_get_value_request};
 use ic_sns_init::pb::v1::SnsInitPayload;
 use ic_sns_root::{GetSnsCanistersSummaryRequest, GetSnsCanistersSummaryResponse};
 use ic_sns_swap::pb::v1::{
@@ -94,7 +99,11 @@ use icp_ledger::{
 use itertools::Itertools;
 use mockall::automock;
 use registry_canister::{
-    mutations::do_add_node_operator::AddNodeOperatorPayload, pb::v1::NodeProvidersMonthlyXdrRewards,
+    mutations::do_add_node_operator::AddNodeOperatorPayload,
+    pb::v1::{
+        GetNodeProvidersMonthlyXdrRewardsRequest, GetNodeProvidersMonthlyXdrRewardsResponse,
+        NodeProvidersMonthlyXdrRewards, NodeRewardsReductionCurve,
+    },
 };
 use std::{
     borrow::Cow,
@@ -259,6 +268,79 @@ impl NetworkEconomics {
             minimum_icp_xdr_rate: 100,                                  // 1 XDR
             transaction_fee_e8s: DEFAULT_TRANSFER_FEE.get_e8s(),
             max_proposals_to_keep_per_topic: 100,
+            node_rewards_performance_parameters: None,
+        }
+    }
+}
+
+impl NodeRewardsPerformanceParameters {
+    pub const DEFAULT_METRICS_WINDOW_SECONDS: u64 = ONE_MONTH_SECONDS;
+
+    /// Overwrites the fields of `self` with the fields that are set in `other`.
+    fn merge_from(&mut self, other: NodeRewardsPerformanceParameters) {
+        let NodeRewardsPerformanceParameters {
+            metrics_window_seconds,
+            failure_rate_threshold_basis_points,
+            failure_rate_max_basis_points,
+            max_reduction_basis_points,
+        } = other;
+
+        if metrics_window_seconds.is_some() {
+            self.metrics_window_seconds = metrics_window_seconds;
+        }
+        if failure_rate_threshold_basis_points.is_some() {
+            self.failure_rate_threshold_basis_points = failure_rate_threshold_basis_points;
+        }
+        if failure_rate_max_basis_points.is_some() {
+            self.failure_rate_max_basis_points = failure_rate_max_basis_points;
+        }
+        if max_reduction_basis_points.is_some() {
+            self.max_reduction_basis_points = max_reduction_basis_points;
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.metrics_window_seconds == Some(0) {
+            return Err("metrics_window_seconds must be positive.".to_string());
+        }
+        for (name, value) in [
+            (
+                "failure_rate_threshold_basis_points",
+                self.failure_rate_threshold_basis_points,
+            ),
+            (
+                "failure_rate_max_basis_points",
+                self.failure_rate_max_basis_points,
+            ),
+            (
+                "max_reduction_basis_points",
+                self.max_reduction_basis_points,
+            ),
+        ] {
+            if let Some(value) = value {
+                if value > 10_000 {
+                    return Err(format!(
+                        "{} must be at most 10_000, but is {}.",
+                        name, value
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn metrics_window_seconds(&self) -> u64 {
+        self.metrics_window_seconds
+            .unwrap_or(Self::DEFAULT_METRICS_WINDOW_SECONDS)
+    }
+
+    fn reduction_curve(&self) -> NodeRewardsReductionCurve {
+        NodeRewardsReductionCurve {
+            failure_rate_threshold_basis_points: self
+                .failure_rate_threshold_basis_points
+                .unwrap_or(0),
+            failure_rate_max_basis_points: self.failure_rate_max_basis_points.unwrap_or(10_000),
+            max_reduction_basis_points: self.max_reduction_basis_points.unwrap_or(0),
         }
     }
 }
@@ -4204,6 +4286,12 @@ impl Governance {
                         economics.max_proposals_to_keep_per_topic =
                             ne.max_proposals_to_keep_per_topic
                     }
+                    if let Some(parameters) = ne.node_rewards_performance_parameters {
+                        economics
+                            .node_rewards_performance_parameters
+                            .get_or_insert_with(Default::default)
+                            .merge_from(parameters);
+                    }
                 } else {
                     // If for some reason, we don't have an
                     // 'economics' proto, use the proposed one.
@@ -4969,8 +5057,10 @@ impl Governance {
             Action::ManageNeuron(manage_neuron) => {
                 self.validate_manage_neuron_proposal(manage_neuron)
             }
-            Action::ManageNetworkEconomics(_)
-            | Action::ApproveGenesisKyc(_)
+            Action::ManageNetworkEconomics(network_economics) => {
+                validate_manage_network_economics(network_economics)
+            }
+            Action::ApproveGenesisKyc(_)
             | Action::AddOrRemoveNodeProvider(_)
             | Action::RewardNodeProvider(_)
             | Action::SetDefaultFollowees(_)
@@ -7836,6 +7926,10 @@ impl Governance {
     /// Registry, then fetches the average XDR to ICP conversion rate for
     /// the last 30 days, then applies this conversion rate to convert each
     /// node provider's XDR rewards to ICP.
+    ///
+    /// If `node_rewards_performance_parameters` are set in the network
+    /// economics, the rewards of nodes that failed to make blocks are reduced
+    /// accordingly (see `get_node_providers_monthly_xdr_rewards_with_performance`).
     pub async fn get_monthly_node_provider_rewards(
         &mut self,
     ) -> Result<RewardNodeProviders, GovernanceError> {
@@ -7843,7 +7937,13 @@ impl Governance {
 
         // Maps node providers to their rewards in XDR
         let xdr_permyriad_rewards: NodeProvidersMonthlyXdrRewards =
-            self.get_node_providers_monthly_xdr_rewards().await?;
+            match self.economics().node_rewards_performance_parameters.clone() {
+                Some(parameters) => {
+                    self.get_node_providers_monthly_xdr_rewards_with_performance(parameters)
+                        .await?
+                }
+                None => self.get_node_providers_monthly_xdr_rewards().await?,
+            };
 
         // The average (last 30 days) conversion rate from 10,000ths of an XDR to 1 ICP
         let avg_xdr_permyriad_per_icp = self
@@ -7913,6 +8013,194 @@ impl Governance {
             .map_err(|msg| GovernanceError::new_with_message(ErrorType::External, msg))
     }
 
+    /// Fetches the node metrics history of every subnet over the configured
+    /// metrics window, computes the block-making failure rate of each node,
+    /// and asks the Registry to compute the node providers' rewards taking
+    /// these failure rates into account. If the metrics of a subnet cannot be
+    /// fetched, the failure rates of its nodes are unknown, and the rewards of
+    /// these nodes are not reduced.
+    async fn get_node_providers_monthly_xdr_rewards_with_performance(
+        &mut self,
+        parameters: NodeRewardsPerformanceParameters,
+    ) -> Result<NodeProvidersMonthlyXdrRewards, GovernanceError> {
+        let start_at_timestamp_nanos = self
+            .env
+            .now()
+            .saturating_sub(parameters.metrics_window_seconds())
+            .saturating_mul(1_000_000_000);
+
+        let mut node_failure_rates = HashMap::new();
+        for subnet_id in self.get_subnet_ids().await? {
+            let metrics_history = match self
+                .get_node_metrics_history(subnet_id, start_at_timestamp_nanos)
+                .await
+            {
+                Ok(metrics_history) => metrics_history,
+                Err(err) => {
+                    println!(
+                        "{}ERROR: Cannot fetch the node metrics of subnet {}, the rewards \
+                         of its nodes are not reduced: {}",
+                        LOG_PREFIX, subnet_id, err
+                    );
+                    continue;
+                }
+            };
+            node_failure_rates.extend(
+                node_failure_rates_from_metrics_history(&metrics_history)
+                    .into_iter()
+                    .map(|(node_id, failure_rate)| (node_id.to_string(), failure_rate)),
+            );
+        }
+
+        let request = GetNodeProvidersMonthlyXdrRewardsRequest {
+            node_failure_rates,
+            reduction_curve: Some(parameters.reduction_curve()),
+        };
+        let registry_response: Vec<u8> = self
+            .env
+            .call_canister_method(
+                REGISTRY_CANISTER_ID,
+                "get_node_providers_monthly_xdr_rewards_with_performance",
+                Encode!(&request).unwrap(),
+            )
+            .await
+            .map_err(|(code, msg)| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Error calling 'get_node_providers_monthly_xdr_rewards_with_performance': \
+                         code: {:?}, message: {}",
+                        code, msg
+                    ),
+                )
+            })?;
+
+        let response = Decode!(
+            &registry_response,
+            Result<GetNodeProvidersMonthlyXdrRewardsResponse, String>
+        )
+        .map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!(
+                    "Cannot decode return type from \
+                     get_node_providers_monthly_xdr_rewards_with_performance'. Error: {}",
+                    err,
+                ),
+            )
+        })?
+        .map_err(|msg| GovernanceError::new_with_message(ErrorType::External, msg))?;
+
+        for node_rewards in &response.node_rewards {
+            if node_rewards.reduction_basis_points > 0 {
+                println!(
+                    "{}Rewards of node {} of node provider {} reduced by {} basis points \
+                     (failure rate: {:?}) to {} XDR permyriad.",
+                    LOG_PREFIX,
+                    node_rewards.node_id,
+                    node_rewards.node_provider_id,
+                    node_rewards.reduction_basis_points,
+                    node_rewards.failure_rate,
+                    node_rewards.xdr_permyriad,
+                );
+            }
+        }
+
+        Ok(response.rewards.unwrap_or_default())
+    }
+
+    /// Returns the IDs of all subnets, as listed in the Registry.
+    async fn get_subnet_ids(&mut self) -> Result<Vec<PrincipalId>, GovernanceError> {
+        let request = serialize_get_value_request(make_subnet_list_record_key().into_bytes(), None)
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Cannot serialize the subnet list request: {}", err),
+                )
+            })?;
+        let registry_response = self
+            .env
+            .call_canister_method(REGISTRY_CANISTER_ID, "get_value", request)
+            .await
+            .map_err(|(code, msg)| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Error calling 'get_value' for the subnet list: code: {:?}, message: {}",
+                        code, msg
+                    ),
+                )
+            })?;
+        let (subnet_list_bytes, _version) = deserialize_get_value_response(registry_response)
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Cannot fetch the subnet list from the Registry: {}", err),
+                )
+            })?;
+        let subnet_list = <SubnetListRecord as prost::Message>::decode(
+            subnet_list_bytes.as_slice(),
+        )
+        .map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Cannot decode the subnet list: {}", err),
+            )
+        })?;
+
+        subnet_list
+            .subnets
+            .iter()
+            .map(|subnet_id| {
+                PrincipalId::try_from(subnet_id).map_err(|err| {
+                    GovernanceError::new_with_message(
+                        ErrorType::External,
+                        format!("Invalid subnet ID in the subnet list: {}", err),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// A helper for the management canister's node_metrics_history method
+    async fn get_node_metrics_history(
+        &mut self,
+        subnet_id: PrincipalId,
+        start_at_timestamp_nanos: u64,
+    ) -> Result<Vec<NodeMetricsHistoryResponse>, GovernanceError> {
+        let request = NodeMetricsHistoryArgs {
+            subnet_id,
+            start_at_timestamp_nanos,
+        };
+        let response = self
+            .env
+            .call_canister_method(
+                CanisterId::ic_00(),
+                "node_metrics_history",
+                Encode!(&request).unwrap(),
+            )
+            .await
+            .map_err(|(code, msg)| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Error calling 'node_metrics_history' for subnet {}: code: {:?}, message: {}",
+                        subnet_id, code, msg
+                    ),
+                )
+            })?;
+
+        Decode!(&response, Vec<NodeMetricsHistoryResponse>).map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!(
+                    "Cannot decode return type from node_metrics_history. Error: {}",
+                    err,
+                ),
+            )
+        })
+    }
+
     /// A helper for the CMC's get_average_icp_xdr_conversion_rate method
     async fn get_average_icp_xdr_conversion_rate(
         &mut self,
@@ -8586,6 +8874,21 @@ fn validate_motion(motion: &Motion) -> Result<(), GovernanceError> {
     Ok(())
 }
 
+fn validate_manage_network_economics(
+    network_economics: &NetworkEconomics,
+) -> Result<(), GovernanceError> {
+    if let Some(parameters) = &network_economics.node_rewards_performance_parameters {
+        parameters.validate().map_err(|msg| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                format!("Invalid node_rewards_performance_parameters: {}", msg),
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
 /// Always fails, because this type of proposal is obsolete.
 fn validate_set_sns_token_swap_open_time_window(
     action: &SetSnsTokenSwapOpenTimeWindow,
@@ -8901,6 +9204,47 @@ pub fn get_node_provider_reward(
     }
 }
 
+/// Computes the block-making failure rate of each node that appears in the
+/// given node metrics history of a subnet.
+///
+/// The metrics reported by the management canister are cumulative, so the
+/// failure rate of a node is the number of failures divided by the number of
+/// blocks the node was supposed to make, both counted between the first and
+/// the last time the node appears in the history. Nodes that appear only once,
+/// or that were not supposed to make any blocks in between, are omitted.
+pub fn node_failure_rates_from_metrics_history(
+    metrics_history: &[NodeMetricsHistoryResponse],
+) -> BTreeMap<PrincipalId, f64> {
+    // Maps each node to its (first, last) metrics, ordered by timestamp.
+    let mut first_and_last = BTreeMap::new();
+    for response in metrics_history
+        .iter()
+        .sorted_by_key(|response| response.timestamp_nanos)
+    {
+        for metrics in &response.node_metrics {
+            first_and_last
+                .entry(metrics.node_id)
+                .and_modify(|(_first, last)| *last = metrics)
+                .or_insert((metrics, metrics));
+        }
+    }
+
+    first_and_last
+        .into_iter()
+        .filter_map(|(node_id, (first, last))| {
+            let blocks = last.num_blocks_total.saturating_sub(first.num_blocks_total);
+            let failures = last
+                .num_block_failures_total
+                .saturating_sub(first.num_block_failures_total);
+            let attempts = blocks.saturating_add(failures);
+            if attempts == 0 {
+                return None;
+            }
+            Some((node_id, failures as f64 / attempts as f64))
+        })
+        .collect()
+}
+
 /// TODO[NNS1-2617]: Deprecate settle_community_fund_participation.
 impl settle_community_fund_participation::Committed {
     async fn mint_to_sns_governance(
@@ -2291,3 +2291,116 @@ fn topic_min_max_test() {
         assert!(topic <= Topic::MAX, "Topic::MAX needs to be updated");
     }
 }
+
+mod node_rewards_performance_tests {
+    use super::*;
+    use ic_ic00_types::NodeMetrics;
+
+    fn node_metrics(node_id: u64, blocks: u64, failures: u64) -> NodeMetrics {
+        NodeMetrics {
+            node_id: PrincipalId::new_node_test_id(node_id),
+            num_blocks_total: blocks,
+            num_block_failures_total: failures,
+        }
+    }
+
+    #[test]
+    fn node_failure_rates_are_computed_from_first_and_last_metrics() {
+        let metrics_history = vec![
+            // Deliberately out of order.
+            NodeMetricsHistoryResponse {
+                timestamp_nanos: 300,
+                node_metrics: vec![node_metrics(1, 190, 30), node_metrics(2, 500, 0)],
+            },
+            NodeMetricsHistoryResponse {
+                timestamp_nanos: 100,
+                node_metrics: vec![node_metrics(1, 100, 10), node_metrics(3, 7, 7)],
+            },
+            NodeMetricsHistoryResponse {
+                timestamp_nanos: 200,
+                node_metrics: vec![node_metrics(1, 150, 20), node_metrics(2, 400, 0)],
+            },
+        ];
+
+        assert_eq!(
+            node_failure_rates_from_metrics_history(&metrics_history),
+            btreemap! {
+                // 20 failures out of 110 attempts.
+                PrincipalId::new_node_test_id(1) => 20.0 / 110.0,
+                PrincipalId::new_node_test_id(2) => 0.0,
+                // Node 3 appears only once, so there is nothing to compare with.
+            }
+        );
+    }
+
+    #[test]
+    fn node_rewards_performance_parameters_are_merged_field_by_field() {
+        let mut parameters = NodeRewardsPerformanceParameters {
+            metrics_window_seconds: Some(ONE_DAY_SECONDS),
+            failure_rate_threshold_basis_points: Some(1_000),
+            failure_rate_max_basis_points: Some(6_000),
+            max_reduction_basis_points: Some(8_000),
+        };
+
+        parameters.merge_from(NodeRewardsPerformanceParameters {
+            failure_rate_threshold_basis_points: Some(2_000),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            parameters,
+            NodeRewardsPerformanceParameters {
+                metrics_window_seconds: Some(ONE_DAY_SECONDS),
+                failure_rate_threshold_basis_points: Some(2_000),
+                failure_rate_max_basis_points: Some(6_000),
+                max_reduction_basis_points: Some(8_000),
+            }
+        );
+        assert_eq!(
+            parameters.reduction_curve(),
+            NodeRewardsReductionCurve {
+                failure_rate_threshold_basis_points: 2_000,
+                failure_rate_max_basis_points: 6_000,
+                max_reduction_basis_points: 8_000,
+            }
+        );
+
+        // Unset fields fall back to values that do not reduce rewards.
+        assert_eq!(
+            NodeRewardsPerformanceParameters::default().reduction_curve(),
+            NodeRewardsReductionCurve {
+                failure_rate_threshold_basis_points: 0,
+                failure_rate_max_basis_points: 10_000,
+                max_reduction_basis_points: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn manage_network_economics_rejects_invalid_node_rewards_performance_parameters() {
+        let network_economics = |parameters| NetworkEconomics {
+            node_rewards_performance_parameters: Some(parameters),
+            ..Default::default()
+        };
+
+        assert_is_ok!(validate_manage_network_economics(&network_economics(
+            NodeRewardsPerformanceParameters {
+                metrics_window_seconds: Some(ONE_MONTH_SECONDS),
+                max_reduction_basis_points: Some(10_000),
+                ..Default::default()
+            }
+        )));
+        assert_is_err!(validate_manage_network_economics(&network_economics(
+            NodeRewardsPerformanceParameters {
+                metrics_window_seconds: Some(0),
+                ..Default::default()
+            }
+        )));
+        assert_is_err!(validate_manage_network_economics(&network_economics(
+            NodeRewardsPerformanceParameters {
+                failure_rate_max_basis_points: Some(10_001),
+                ..Default::default()
+            }
+        )));
+    }
+}
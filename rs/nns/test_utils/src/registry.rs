@@ -787,6 +787,7 @@ pub fn prepare_add_node_payload(mutation_id: u8) -> (AddNodePayload, ValidNodePu
         prometheus_metrics_endpoint: "".to_string(),
         chip_id: None,
         public_ipv4_config: None,
+        node_reward_type: None,
     };
 
     (payload, node_public_keys)
@@ -212,6 +212,7 @@ impl NodeRegistration {
             chip_id: get_snp_chip_id().expect("Failed to retrieve chip_id from snp firmware"),
             prometheus_metrics_endpoint: "".to_string(),
             public_ipv4_config: ipv4_config_to_vec(&self.log, &self.node_config.ipv4_config),
+            node_reward_type: self.node_config.registration.node_reward_type.clone(),
         }
     }
 
@@ -53,4 +53,9 @@ pub struct NodeRecord {
     /// If a Node is to be converted into the ApiBoundaryNode, the domain field should be set.
     #[prost(string, optional, tag = "19")]
     pub domain: ::core::option::Option<::prost::alloc::string::String>,
+    /// The type of the node for the purpose of node provider rewards, e.g.
+    /// "type1". This is one of the node types among the rewardable nodes of the
+    /// node's operator.
+    #[prost(string, optional, tag = "20")]
+    pub node_reward_type: ::core::option::Option<::prost::alloc::string::String>,
 }
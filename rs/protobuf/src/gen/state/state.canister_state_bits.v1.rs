@@ -505,6 +505,8 @@ pub struct TotalQueryStats {
     pub ingress_payload_size: ::core::option::Option<Unsigned128>,
     #[prost(message, optional, tag = "4")]
     pub egress_payload_size: ::core::option::Option<Unsigned128>,
+    #[prost(message, optional, tag = "5")]
+    pub cycles_charged: ::core::option::Option<Unsigned128>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -696,6 +698,7 @@ pub enum CyclesUseCase {
     DeletedCanisters = 10,
     NonConsumed = 11,
     BurnedCycles = 12,
+    QueryExecution = 13,
 }
 impl CyclesUseCase {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -719,6 +722,7 @@ impl CyclesUseCase {
             CyclesUseCase::DeletedCanisters => "CYCLES_USE_CASE_DELETED_CANISTERS",
             CyclesUseCase::NonConsumed => "CYCLES_USE_CASE_NON_CONSUMED",
             CyclesUseCase::BurnedCycles => "CYCLES_USE_CASE_BURNED_CYCLES",
+            CyclesUseCase::QueryExecution => "CYCLES_USE_CASE_QUERY_EXECUTION",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -739,6 +743,7 @@ impl CyclesUseCase {
             "CYCLES_USE_CASE_DELETED_CANISTERS" => Some(Self::DeletedCanisters),
             "CYCLES_USE_CASE_NON_CONSUMED" => Some(Self::NonConsumed),
             "CYCLES_USE_CASE_BURNED_CYCLES" => Some(Self::BurnedCycles),
+            "CYCLES_USE_CASE_QUERY_EXECUTION" => Some(Self::QueryExecution),
             _ => None,
         }
     }
@@ -151,6 +151,10 @@ pub struct CanisterHttpRequestContext {
     pub max_response_bytes: ::core::option::Option<u64>,
     #[prost(message, optional, tag = "10")]
     pub transform_context: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// The node that performs a non-replicated request. Unset if the request is
+    /// performed by all replicas.
+    #[prost(message, optional, tag = "11")]
+    pub non_replicated_node_id: ::core::option::Option<super::super::super::types::v1::NodeId>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -1,9 +1,10 @@
+use ic_cycles_account_manager::CyclesAccountManager;
 use ic_logger::{error, info, ReplicaLogger};
 use ic_replicated_state::ReplicatedState;
 use ic_types::{
     batch::{QueryStats, QueryStatsPayload, RawQueryStats},
     consensus::get_faults_tolerated,
-    CanisterId, Height,
+    CanisterId, Cycles, Height,
 };
 use std::collections::BTreeMap;
 
@@ -39,10 +40,14 @@ fn aggregate_query_stats(stats: Vec<&QueryStats>) -> QueryStats {
 }
 
 /// Aggregate given query stats and into each canister's state.
+///
+/// If a [`CyclesAccountManager`] is given, the canister is also charged for the
+/// query calls described by the aggregated stats.
 fn apply_query_stats_to_canister(
     aggregated_stats: &QueryStats,
     canister_id: CanisterId,
     state: &mut ReplicatedState,
+    cycles_account_manager: Option<&CyclesAccountManager>,
     logger: &ReplicaLogger,
 ) {
     // Note that the use of the number of nodes in the subnet like this does not handle the case that
@@ -50,14 +55,32 @@ fn apply_query_stats_to_canister(
     // Given that subnet topology changes are an infrequent event, we tolerate this occasional inaccuracy here.
     let num_nodes_in_subnet = state.system_metadata().node_public_keys.len() as u128;
     if let Some(canister_state) = state.canister_state_mut(&canister_id) {
-        let canister_query_stats = &mut canister_state.scheduler_state.total_query_stats;
-        canister_query_stats.num_calls += aggregated_stats.num_calls as u128 * num_nodes_in_subnet;
-        canister_query_stats.num_instructions +=
-            aggregated_stats.num_instructions as u128 * num_nodes_in_subnet;
-        canister_query_stats.ingress_payload_size +=
+        let num_calls = aggregated_stats.num_calls as u128 * num_nodes_in_subnet;
+        let num_instructions = aggregated_stats.num_instructions as u128 * num_nodes_in_subnet;
+        let ingress_payload_size =
             aggregated_stats.ingress_payload_size as u128 * num_nodes_in_subnet;
-        canister_query_stats.egress_payload_size +=
+        let egress_payload_size =
             aggregated_stats.egress_payload_size as u128 * num_nodes_in_subnet;
+
+        let cycles_charged = match cycles_account_manager {
+            Some(cycles_account_manager) => {
+                let cost = cycles_account_manager.query_execution_cost(
+                    num_calls,
+                    num_instructions,
+                    ingress_payload_size + egress_payload_size,
+                    num_nodes_in_subnet as usize,
+                );
+                cycles_account_manager.charge_for_query_execution(canister_state, cost)
+            }
+            None => Cycles::zero(),
+        };
+
+        let canister_query_stats = &mut canister_state.scheduler_state.total_query_stats;
+        canister_query_stats.num_calls += num_calls;
+        canister_query_stats.num_instructions += num_instructions;
+        canister_query_stats.ingress_payload_size += ingress_payload_size;
+        canister_query_stats.egress_payload_size += egress_payload_size;
+        canister_query_stats.cycles_charged += cycles_charged.get();
     } else {
         info!(
             logger,
@@ -69,12 +92,16 @@ fn apply_query_stats_to_canister(
 /// Add the epoch stats of the current round to the metadata
 /// and on a new epoch aggregate currently stored stats into
 /// the canister query stats.
+///
+/// Canisters are charged for their query calls when the stats are aggregated
+/// if and only if a [`CyclesAccountManager`] is given.
 pub fn deliver_query_stats(
     query_stats: &QueryStatsPayload,
     state: &mut ReplicatedState,
     _height: Height,
     logger: &ReplicaLogger,
     _epoch_length: u64,
+    cycles_account_manager: Option<&CyclesAccountManager>,
     metrics: &QueryStatsAggregatorMetrics,
 ) {
     let epoch = query_stats.epoch;
@@ -111,6 +138,7 @@ pub fn deliver_query_stats(
                             &aggregated_stats,
                             canister_id,
                             state,
+                            cycles_account_manager,
                             logger,
                         );
                     }
@@ -158,7 +186,12 @@ mod tests {
     use super::*;
     use ic_interfaces_state_manager::StateManager;
     use ic_logger::replica_logger::no_op_logger;
-    use ic_test_utilities::state_manager::FakeStateManager;
+    use ic_replicated_state::SubnetTopology;
+    use ic_test_utilities::{
+        cycles_account_manager::CyclesAccountManagerBuilder,
+        state::{CanisterStateBuilder, ReplicatedStateBuilder},
+        state_manager::FakeStateManager,
+    };
     use ic_types::{batch::CanisterQueryStats, NodeId, PrincipalId, QueryStatsEpoch};
     use ic_types_test_utils::ids::canister_test_id;
 
@@ -190,6 +223,7 @@ mod tests {
             Height::new(1),
             &no_op_logger(),
             epoch_length,
+            None,
             &QueryStatsAggregatorMetrics::new(&ic_metrics::MetricsRegistry::new()),
         );
 
@@ -210,4 +244,90 @@ mod tests {
             &test_canister_stats
         );
     }
+
+    /// Tests that canisters are charged for the queries of an epoch once the epoch's stats are
+    /// aggregated, if and only if charging is enabled.
+    #[test]
+    pub fn test_query_stats_charging() {
+        const INITIAL_CYCLES: u128 = 1_000_000_000_000;
+        let canister_id = canister_test_id(1);
+        let nodes: Vec<NodeId> = (1..=4)
+            .map(|i| NodeId::from(PrincipalId::new_node_test_id(i)))
+            .collect();
+        let stats = QueryStats {
+            num_calls: 10,
+            num_instructions: 1_000_000,
+            ingress_payload_size: 100,
+            egress_payload_size: 1_000,
+        };
+        let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+        let metrics = QueryStatsAggregatorMetrics::new(&ic_metrics::MetricsRegistry::new());
+        let epoch_length = ic_config::execution_environment::QUERY_STATS_EPOCH_LENGTH;
+
+        let deliver_epochs = |cycles_account_manager: Option<&CyclesAccountManager>| {
+            let mut state = ReplicatedStateBuilder::new()
+                .with_canister(
+                    CanisterStateBuilder::new()
+                        .with_canister_id(canister_id)
+                        .with_cycles(INITIAL_CYCLES)
+                        .build(),
+                )
+                .build();
+            let own_subnet_id = state.metadata.own_subnet_id;
+            state.metadata.network_topology.subnets.insert(
+                own_subnet_id,
+                SubnetTopology {
+                    nodes: nodes.iter().cloned().collect(),
+                    ..Default::default()
+                },
+            );
+            state.metadata.node_public_keys = nodes.iter().map(|node| (*node, vec![])).collect();
+
+            // All nodes report the stats of epoch 1, which are aggregated once the stats of
+            // epoch 2 start arriving.
+            for (epoch, stats) in [(1, vec![stats.clone()]), (2, vec![])] {
+                for proposer in &nodes {
+                    deliver_query_stats(
+                        &QueryStatsPayload {
+                            epoch: QueryStatsEpoch::from(epoch),
+                            proposer: *proposer,
+                            stats: stats
+                                .iter()
+                                .map(|stats| CanisterQueryStats {
+                                    canister_id,
+                                    stats: stats.clone(),
+                                })
+                                .collect(),
+                        },
+                        &mut state,
+                        Height::new(epoch),
+                        &no_op_logger(),
+                        epoch_length,
+                        cycles_account_manager,
+                        &metrics,
+                    );
+                }
+            }
+            state.canister_state(&canister_id).unwrap().clone()
+        };
+
+        let canister = deliver_epochs(None);
+        assert_eq!(canister.scheduler_state.total_query_stats.num_calls, 40);
+        assert_eq!(canister.scheduler_state.total_query_stats.cycles_charged, 0);
+        assert_eq!(canister.system_state.balance().get(), INITIAL_CYCLES);
+
+        let canister = deliver_epochs(Some(&cycles_account_manager));
+        let expected_cost =
+            cycles_account_manager.query_execution_cost(40, 4_000_000, 4_400, nodes.len());
+        assert!(expected_cost.get() > 0);
+        assert_eq!(canister.scheduler_state.total_query_stats.num_calls, 40);
+        assert_eq!(
+            canister.scheduler_state.total_query_stats.cycles_charged,
+            expected_cost.get()
+        );
+        assert_eq!(
+            canister.system_state.balance().get(),
+            INITIAL_CYCLES - expected_cost.get()
+        );
+    }
 }
@@ -6,14 +6,15 @@ use crate::{
     error::RecoveryError,
     recovery_iterator::RecoveryIterator,
     registry_helper::RegistryPollingStrategy,
-    NeuronArgs, Recovery, RecoveryArgs, RecoveryResult, Step, CUPS_DIR,
+    replay_helper, NeuronArgs, Recovery, RecoveryArgs, RecoveryResult, Step, CUPS_DIR,
+    IC_STATE_DIR,
 };
 use clap::Parser;
 use ic_base_types::{NodeId, SubnetId};
 use ic_types::ReplicaVersion;
 use serde::{Deserialize, Serialize};
 use slog::{info, Logger};
-use std::{iter::Peekable, net::IpAddr};
+use std::{iter::Peekable, net::IpAddr, path::PathBuf};
 use strum::{EnumMessage, IntoEnumIterator};
 use strum_macros::{EnumIter, EnumString};
 use url::Url;
@@ -227,6 +228,30 @@ impl RecoveryIterator<StepType, StepTypeIter> for AppSubnetRecovery {
         }
     }
 
+    fn get_step_target_nodes(&self, step_type: StepType) -> Vec<IpAddr> {
+        match step_type {
+            StepType::DownloadState => self.params.download_node.into_iter().collect(),
+            StepType::UploadState | StepType::WaitForCUP => {
+                self.params.upload_node.into_iter().collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    fn get_step_artifacts(&self, step_type: StepType) -> Vec<PathBuf> {
+        let work_dir = &self.recovery.work_dir;
+        match step_type {
+            StepType::DownloadCertifications => vec![work_dir.join("certifications")],
+            StepType::MergeCertificationPools => vec![work_dir.join("data/ic_consensus_pool")],
+            StepType::DownloadState if self.params.keep_downloaded_state == Some(true) => {
+                vec![work_dir.join(IC_STATE_DIR), self.recovery.data_dir.clone()]
+            }
+            StepType::DownloadState => vec![work_dir.join(IC_STATE_DIR)],
+            StepType::ICReplay => vec![work_dir.join(replay_helper::OUTPUT_FILE_NAME)],
+            _ => vec![],
+        }
+    }
+
     fn get_step_impl(&self, step_type: StepType) -> RecoveryResult<Box<dyn Step>> {
         match step_type {
             StepType::Halt => {
@@ -2,10 +2,13 @@
 use crate::{
     app_subnet_recovery::{AppSubnetRecovery, AppSubnetRecoveryArgs},
     args_merger::merge,
+    cmd::SubCommand,
+    error::{RecoveryError, RecoveryResult},
     get_node_heights_from_metrics,
     nns_recovery_failover_nodes::{NNSRecoveryFailoverNodes, NNSRecoveryFailoverNodesArgs},
     nns_recovery_same_nodes::{NNSRecoverySameNodes, NNSRecoverySameNodesArgs},
     recovery_iterator::RecoveryIterator,
+    recovery_plan::{self, RecoveryPlan},
     recovery_state::{HasRecoveryState, RecoveryState},
     registry_helper::RegistryHelper,
     steps::Step,
@@ -21,6 +24,7 @@ use std::{
     convert::TryFrom,
     fmt::Display,
     io::{stdin, stdout, Write},
+    path::Path,
     str::FromStr,
 };
 use strum::EnumMessage;
@@ -155,6 +159,118 @@ pub fn execute_steps<
     }
 }
 
+/// Writes a plan of the recovery given by `subcommand_args` to `plan_file`, without executing any
+/// of its steps.
+pub fn write_recovery_plan(
+    logger: Logger,
+    args: RecoveryArgs,
+    subcommand_args: SubCommand,
+    plan_file: &Path,
+) -> RecoveryResult<()> {
+    let args = RecoveryArgs {
+        skip_prompts: true,
+        ..args
+    };
+    let steps = match subcommand_args.clone() {
+        SubCommand::AppSubnetRecovery(subnet_recovery_args) => {
+            recovery_plan::make_plan(AppSubnetRecovery::new(
+                logger.clone(),
+                args.clone(),
+                stored_neuron_args(&args)?,
+                subnet_recovery_args,
+            ))
+        }
+        SubCommand::NNSRecoverySameNodes(nns_recovery_args) => recovery_plan::make_plan(
+            NNSRecoverySameNodes::new(logger.clone(), args.clone(), nns_recovery_args),
+        ),
+        SubCommand::NNSRecoveryFailoverNodes(nns_recovery_args) => {
+            recovery_plan::make_plan(NNSRecoveryFailoverNodes::new(
+                logger.clone(),
+                args.clone(),
+                stored_neuron_args(&args)?,
+                nns_recovery_args,
+            ))
+        }
+    };
+
+    let plan = RecoveryPlan {
+        recovery_args: args,
+        subcommand_args,
+        steps,
+        executed_steps: vec![],
+    };
+    plan.write(plan_file)?;
+    info!(
+        logger,
+        "Wrote a plan with {} steps to {:?}",
+        plan.steps.len(),
+        plan_file
+    );
+    Ok(())
+}
+
+/// Executes the recovery plan in `plan_file` without requesting any input, optionally stopping
+/// right before the step named `stop_at`. The executed steps are recorded in `plan_file`.
+pub fn execute_recovery_plan(
+    logger: Logger,
+    plan_file: &Path,
+    stop_at: Option<&str>,
+) -> RecoveryResult<()> {
+    let plan = RecoveryPlan::read(plan_file)?;
+    let args = plan.recovery_args.clone();
+    match plan.subcommand_args.clone() {
+        SubCommand::AppSubnetRecovery(subnet_recovery_args) => recovery_plan::execute_plan(
+            &logger,
+            plan_file,
+            &plan,
+            stop_at,
+            AppSubnetRecovery::new(
+                logger.clone(),
+                args.clone(),
+                stored_neuron_args(&args)?,
+                subnet_recovery_args,
+            ),
+        ),
+        SubCommand::NNSRecoverySameNodes(nns_recovery_args) => recovery_plan::execute_plan(
+            &logger,
+            plan_file,
+            &plan,
+            stop_at,
+            NNSRecoverySameNodes::new(logger.clone(), args, nns_recovery_args),
+        ),
+        SubCommand::NNSRecoveryFailoverNodes(nns_recovery_args) => recovery_plan::execute_plan(
+            &logger,
+            plan_file,
+            &plan,
+            stop_at,
+            NNSRecoveryFailoverNodes::new(
+                logger.clone(),
+                args.clone(),
+                stored_neuron_args(&args)?,
+                nns_recovery_args,
+            ),
+        ),
+    }
+}
+
+/// Neuron arguments can't be entered while planning or executing a plan. Outside of test mode,
+/// they are taken from the recovery state of a previous interactive run in the same directory.
+fn stored_neuron_args(args: &RecoveryArgs) -> RecoveryResult<Option<NeuronArgs>> {
+    if args.test_mode {
+        return Ok(None);
+    }
+    RecoveryState::<SubCommand>::read(&args.dir)?
+        .and_then(|state| state.neuron_args)
+        .map(Some)
+        .ok_or_else(|| {
+            RecoveryError::UnexpectedError(
+                "Neuron arguments are required outside of test mode, but no recovery state \
+                 with neuron arguments was found. Start the recovery interactively first."
+                    .to_string(),
+            )
+        })
+}
+
 fn execute_step_after_consent(logger: &Logger, skip_prompts: bool, step: Box<dyn Step>) {
     info!(logger, "{}", step.descr());
     if !skip_prompts && !consent_given(logger, "Execute now?") {
@@ -54,6 +54,23 @@ pub struct RecoveryToolArgs {
     #[clap(long)]
     pub skip_prompts: bool,
 
+    /// Instead of executing the recovery, write a plan of all its steps as JSON to this file.
+    /// The plan can be reviewed, have steps removed from it, and then be executed with
+    /// --execute-plan.
+    #[clap(long, parse(from_os_str))]
+    pub plan: Option<PathBuf>,
+
+    /// Execute the recovery plan in this file without requesting any input. The status of every
+    /// step is printed to stdout as a line of JSON. The subcommand and its arguments are taken
+    /// from the plan.
+    #[clap(long, parse(from_os_str))]
+    pub execute_plan: Option<PathBuf>,
+
+    /// When executing a plan, stop right before the step with this name. Executing the plan
+    /// again resumes from there, as executed steps are recorded in the plan file.
+    #[clap(long)]
+    pub stop_at: Option<String>,
+
     #[clap(subcommand)]
     pub subcmd: Option<SubCommand>,
 }
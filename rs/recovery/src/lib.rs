@@ -48,6 +48,7 @@ pub mod file_sync_helper;
 pub mod nns_recovery_failover_nodes;
 pub mod nns_recovery_same_nodes;
 pub mod recovery_iterator;
+pub mod recovery_plan;
 pub mod recovery_state;
 pub mod registry_helper;
 pub mod replay_helper;
@@ -30,6 +30,23 @@ fn main() {
         skip_prompts: args.skip_prompts,
     };
 
+    if let Some(plan_file) = args.execute_plan {
+        if let Err(e) = cli::execute_recovery_plan(logger, &plan_file, args.stop_at.as_deref()) {
+            eprintln!("Failed to execute the recovery plan: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(plan_file) = args.plan {
+        let subcmd = args.subcmd.expect("subcommand not provided");
+        if let Err(e) = cli::write_recovery_plan(logger, recovery_args, subcmd, &plan_file) {
+            eprintln!("Failed to write the recovery plan: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let recovery_state = cli::read_and_maybe_update_state(&logger, recovery_args, args.subcmd);
 
     match recovery_state.subcommand_args {
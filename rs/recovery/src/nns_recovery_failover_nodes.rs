@@ -5,7 +5,8 @@ use crate::{
     error::RecoveryError,
     recovery_iterator::RecoveryIterator,
     registry_helper::RegistryPollingStrategy,
-    NeuronArgs, Recovery, RecoveryArgs, RecoveryResult, Step, CUPS_DIR, IC_REGISTRY_LOCAL_STORE,
+    replay_helper, NeuronArgs, Recovery, RecoveryArgs, RecoveryResult, Step, CUPS_DIR,
+    IC_REGISTRY_LOCAL_STORE, IC_STATE_DIR,
 };
 use clap::Parser;
 use ic_base_types::SubnetId;
@@ -163,6 +164,37 @@ impl RecoveryIterator<StepType, StepTypeIter> for NNSRecoveryFailoverNodes {
         self.params.skip.clone().unwrap_or_default()
     }
 
+    fn get_step_target_nodes(&self, step_type: StepType) -> Vec<IpAddr> {
+        match step_type {
+            StepType::StopReplica | StepType::DownloadState => {
+                self.params.download_node.into_iter().collect()
+            }
+            StepType::DownloadParentNNSStore => {
+                self.params.parent_nns_host_ip.into_iter().collect()
+            }
+            StepType::UploadAndHostTar => self.params.aux_ip.into_iter().collect(),
+            StepType::WaitForCUP | StepType::UploadStateToChildNNSHost => {
+                self.params.upload_node.into_iter().collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    fn get_step_artifacts(&self, step_type: StepType) -> Vec<PathBuf> {
+        let work_dir = &self.recovery.work_dir;
+        match step_type {
+            StepType::DownloadCertifications => vec![work_dir.join("certifications")],
+            StepType::MergeCertificationPools => vec![work_dir.join("data/ic_consensus_pool")],
+            StepType::DownloadState => vec![work_dir.join(IC_STATE_DIR)],
+            StepType::DownloadParentNNSStore => vec![self.new_registry_local_store.clone()],
+            StepType::ICReplayWithRegistryContent => {
+                vec![work_dir.join(replay_helper::OUTPUT_FILE_NAME)]
+            }
+            StepType::CreateRegistryTar => vec![self.get_local_store_tar()],
+            _ => vec![],
+        }
+    }
+
     fn read_step_params(&mut self, step_type: StepType) {
         match step_type {
             StepType::StopReplica => {
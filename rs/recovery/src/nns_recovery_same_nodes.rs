@@ -4,7 +4,7 @@ use crate::{
     file_sync_helper::create_dir,
     recovery_iterator::RecoveryIterator,
     registry_helper::RegistryPollingStrategy,
-    RecoveryArgs, RecoveryResult, CUPS_DIR,
+    replay_helper, RecoveryArgs, RecoveryResult, CUPS_DIR, IC_REGISTRY_LOCAL_STORE, IC_STATE_DIR,
 };
 use clap::Parser;
 use ic_base_types::SubnetId;
@@ -159,6 +159,34 @@ impl RecoveryIterator<StepType, StepTypeIter> for NNSRecoverySameNodes {
         }
     }
 
+    fn get_step_target_nodes(&self, step_type: StepType) -> Vec<IpAddr> {
+        match step_type {
+            StepType::StopReplica | StepType::DownloadState => {
+                self.params.download_node.into_iter().collect()
+            }
+            StepType::WaitForCUP | StepType::UploadState => {
+                self.params.upload_node.into_iter().collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    fn get_step_artifacts(&self, step_type: StepType) -> Vec<PathBuf> {
+        let work_dir = &self.recovery.work_dir;
+        match step_type {
+            StepType::DownloadCertifications => vec![work_dir.join("certifications")],
+            StepType::MergeCertificationPools => vec![work_dir.join("data/ic_consensus_pool")],
+            StepType::DownloadState => vec![work_dir.join(IC_STATE_DIR)],
+            StepType::ICReplay => vec![work_dir.join(replay_helper::OUTPUT_FILE_NAME)],
+            StepType::CreateTars => {
+                vec![work_dir.join(format!("{}.tar.gz", IC_REGISTRY_LOCAL_STORE))]
+            }
+            StepType::CopyIcState => vec![self.new_state_dir.clone()],
+            StepType::GetRecoveryCUP => vec![work_dir.join("set_recovery_cup.txt")],
+            _ => vec![],
+        }
+    }
+
     fn get_step_impl(&self, step_type: StepType) -> RecoveryResult<Box<dyn Step>> {
         match step_type {
             StepType::StopReplica => {
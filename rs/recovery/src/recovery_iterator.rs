@@ -7,7 +7,7 @@ use crate::{
 use slog::{info, warn, Logger};
 use strum::EnumMessage;
 
-use std::{fmt::Debug, iter::Peekable};
+use std::{fmt::Debug, iter::Peekable, net::IpAddr, path::PathBuf};
 
 pub trait RecoveryIterator<
     StepType: Copy + Debug + PartialEq + EnumMessage,
@@ -42,6 +42,17 @@ pub trait RecoveryIterator<
         vec![]
     }
 
+    /// Returns the nodes the given step connects to, as far as they are known before the
+    /// recovery starts.
+    fn get_step_target_nodes(&self, _step_type: StepType) -> Vec<IpAddr> {
+        vec![]
+    }
+
+    /// Returns the files and directories the given step is expected to produce.
+    fn get_step_artifacts(&self, _step_type: StepType) -> Vec<PathBuf> {
+        vec![]
+    }
+
     fn next_step(&mut self) -> Option<(StepType, Box<dyn Step>)> {
         let skipped_steps = self.get_skipped_steps();
         let result = if let Some(current_step) = self.get_step_iterator().next() {
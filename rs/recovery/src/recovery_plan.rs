@@ -0,0 +1,554 @@
+//! A recovery plan lists all steps of a recovery together with the commands they are going to
+//! run, the nodes they connect to and the artifacts they produce. A plan is written as JSON, so
+//! that it can be reviewed (and steps removed from it) before it is executed non-interactively.
+//!
+//! During the execution of a plan, the status of every step is printed to stdout as one JSON
+//! object per line, while the usual log output goes to stderr. Executed steps are recorded in the
+//! plan file, so that executing the plan again (e.g. after stopping it) does not repeat them.
+use crate::{
+    cmd::SubCommand,
+    error::{RecoveryError, RecoveryResult},
+    file_sync_helper::{read_file, write_file},
+    recovery_iterator::RecoveryIterator,
+    recovery_state::HasRecoveryState,
+    RecoveryArgs,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use slog::{info, warn, Logger};
+use std::{
+    fmt::Debug,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use strum::EnumMessage;
+
+/// A single step of a [RecoveryPlan].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlannedStep {
+    /// The name of the step, e.g. `DownloadState`.
+    pub step: String,
+    /// The documentation of the step, if there is any.
+    pub explanation: Option<String>,
+    /// The description of the step, i.e. the commands it runs. [None] if the step depends on the
+    /// output of earlier steps, in which case it is only known once these have been executed.
+    pub command: Option<String>,
+    /// Why the command is not known yet, if it isn't.
+    pub pending_reason: Option<String>,
+    /// The nodes the step connects to, as far as they are known upfront.
+    pub target_nodes: Vec<IpAddr>,
+    /// The files and directories the step is expected to produce.
+    pub expected_artifacts: Vec<PathBuf>,
+}
+
+/// A full recovery plan, including the arguments needed to execute it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryPlan {
+    pub recovery_args: RecoveryArgs,
+    pub subcommand_args: SubCommand,
+    pub steps: Vec<PlannedStep>,
+    /// The names of the steps that have already been executed successfully.
+    #[serde(default)]
+    pub executed_steps: Vec<String>,
+}
+
+impl RecoveryPlan {
+    /// Writes the plan as pretty printed JSON to the given file.
+    pub fn write(&self, path: &Path) -> RecoveryResult<()> {
+        serde_json::to_string_pretty(self)
+            .map_err(RecoveryError::serialization_error)
+            .and_then(|json| write_file(path, json))
+    }
+
+    /// Reads a plan from the given file.
+    pub fn read(path: &Path) -> RecoveryResult<Self> {
+        read_file(path).and_then(|content| {
+            serde_json::from_str(&content).map_err(RecoveryError::parsing_error)
+        })
+    }
+}
+
+/// The status of a step during the execution of a [RecoveryPlan].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum StepStatus {
+    Started {
+        command: String,
+    },
+    Succeeded,
+    Skipped {
+        reason: String,
+    },
+    Failed {
+        error: String,
+    },
+    /// Execution stopped before this step, as requested.
+    Stopped,
+}
+
+/// A machine-readable status update of a step, printed as a single line of JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StepReport {
+    pub step: String,
+    #[serde(flatten)]
+    pub status: StepStatus,
+}
+
+/// Walks through all steps of the given recovery without executing them and returns the steps
+/// that would be executed.
+pub fn make_plan<
+    StepType: Copy + Debug + PartialEq + EnumMessage,
+    I: Iterator<Item = StepType>,
+    Steps: RecoveryIterator<StepType, I>,
+>(
+    mut steps: Steps,
+) -> Vec<PlannedStep> {
+    let skipped_steps = steps.get_skipped_steps();
+    let mut planned_steps = vec![];
+    while let Some(step_type) = steps.get_step_iterator().next() {
+        if skipped_steps.contains(&step_type) {
+            continue;
+        }
+        let (command, pending_reason) = match steps.get_step_impl(step_type) {
+            Ok(step) => (Some(step.descr()), None),
+            Err(RecoveryError::StepSkipped) => continue,
+            Err(err) => (None, Some(err.to_string())),
+        };
+        planned_steps.push(PlannedStep {
+            step: format!("{:?}", step_type),
+            explanation: step_type.get_documentation().map(String::from),
+            command,
+            pending_reason,
+            target_nodes: steps.get_step_target_nodes(step_type),
+            expected_artifacts: steps.get_step_artifacts(step_type),
+        });
+    }
+    planned_steps
+}
+
+/// Executes the steps of the plan in `plan_file` without asking for any input. Steps that are not
+/// part of the plan, or that have already been executed, are skipped. A step whose command differs
+/// from the one in the plan is not executed. After each executed step, the plan is written back
+/// to `plan_file` with the step added to its executed steps.
+///
+/// Execution ends at the first failing step. If `stop_at` is given, execution stops right before
+/// the step with that name; executing the plan again resumes from there.
+pub fn execute_plan<
+    StepType: Copy + Debug + PartialEq + EnumMessage + FromStr,
+    SubcommandArgsType: Serialize + DeserializeOwned,
+    I: Iterator<Item = StepType>,
+    Steps: HasRecoveryState<StepType = StepType, SubcommandArgsType = SubcommandArgsType>
+        + RecoveryIterator<StepType, I>,
+>(
+    logger: &Logger,
+    plan_file: &Path,
+    plan: &RecoveryPlan,
+    stop_at: Option<&str>,
+    mut steps: Steps,
+) -> RecoveryResult<()> {
+    let mut plan = plan.clone();
+    for step in plan
+        .steps
+        .iter()
+        .map(|planned_step| planned_step.step.as_str())
+        .chain(plan.executed_steps.iter().map(String::as_str))
+        .chain(stop_at)
+    {
+        if StepType::from_str(step).is_err() {
+            return Err(RecoveryError::UnexpectedError(format!(
+                "Step {} does not exist in this recovery",
+                step
+            )));
+        }
+    }
+
+    let skipped_steps = steps.get_skipped_steps();
+    while let Some(step_type) = steps.get_step_iterator().next() {
+        let step_name = format!("{:?}", step_type);
+        let next_step = steps.get_step_iterator().peek().copied();
+
+        if stop_at == Some(step_name.as_str()) {
+            steps.store_next_step(Some(step_type));
+            save_state(logger, &steps);
+            report(logger, &step_name, StepStatus::Stopped);
+            return Ok(());
+        }
+
+        let Some(planned_step) = plan.steps.iter().find(|step| step.step == step_name) else {
+            report(
+                logger,
+                &step_name,
+                StepStatus::Skipped {
+                    reason: "The step is not part of the plan".to_string(),
+                },
+            );
+            continue;
+        };
+
+        if plan.executed_steps.contains(&step_name) {
+            report(
+                logger,
+                &step_name,
+                StepStatus::Skipped {
+                    reason: "The step has already been executed".to_string(),
+                },
+            );
+            continue;
+        }
+
+        if skipped_steps.contains(&step_type) {
+            report(
+                logger,
+                &step_name,
+                StepStatus::Skipped {
+                    reason: "The step is skipped by the recovery arguments".to_string(),
+                },
+            );
+            continue;
+        }
+
+        let step = match steps.get_step_impl(step_type) {
+            Ok(step) => step,
+            Err(RecoveryError::StepSkipped) => {
+                report(
+                    logger,
+                    &step_name,
+                    StepStatus::Skipped {
+                        reason: "The step is skipped by the recovery arguments".to_string(),
+                    },
+                );
+                continue;
+            }
+            Err(err) => return Err(fail(logger, &step_name, err)),
+        };
+
+        let command = step.descr();
+        if let Some(planned_command) = &planned_step.command {
+            if *planned_command != command {
+                return Err(fail(
+                    logger,
+                    &step_name,
+                    RecoveryError::ValidationFailed(format!(
+                        "The step differs from the plan, which expected `{}`",
+                        planned_command
+                    )),
+                ));
+            }
+        }
+
+        report(logger, &step_name, StepStatus::Started { command });
+        if let Err(err) = step.exec() {
+            return Err(fail(logger, &step_name, err));
+        }
+        report(logger, &step_name, StepStatus::Succeeded);
+
+        plan.executed_steps.push(step_name);
+        plan.write(plan_file)?;
+        steps.store_next_step(next_step);
+        save_state(logger, &steps);
+    }
+
+    Ok(())
+}
+
+fn fail(logger: &Logger, step: &str, err: RecoveryError) -> RecoveryError {
+    report(
+        logger,
+        step,
+        StepStatus::Failed {
+            error: err.to_string(),
+        },
+    );
+    err
+}
+
+fn report(logger: &Logger, step: &str, status: StepStatus) {
+    info!(logger, "Step {}: {:?}", step, status);
+    let report = StepReport {
+        step: step.to_string(),
+        status,
+    };
+    match serde_json::to_string(&report) {
+        Ok(json) => println!("{}", json),
+        Err(err) => warn!(logger, "Failed to serialize the step report: {}", err),
+    }
+}
+
+fn save_state<Steps: HasRecoveryState>(logger: &Logger, steps: &Steps)
+where
+    Steps::SubcommandArgsType: Serialize + DeserializeOwned,
+{
+    if let Err(e) = steps.get_state().and_then(|state| state.save()) {
+        warn!(logger, "Failed to save the recovery state: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app_subnet_recovery::AppSubnetRecoveryArgs, recovery_state::RecoveryState, steps::Step,
+    };
+    use ic_base_types::{PrincipalId, SubnetId};
+    use std::{
+        iter::Peekable,
+        sync::{Arc, Mutex},
+    };
+    use strum_macros::EnumString;
+    use url::Url;
+
+    #[derive(Debug, Copy, Clone, EnumMessage, EnumString, PartialEq)]
+    enum FakeStep {
+        P0,
+        P1,
+        P2,
+        P3,
+    }
+
+    struct FakeStepImpl {
+        step: FakeStep,
+        executed: Arc<Mutex<Vec<FakeStep>>>,
+    }
+
+    impl Step for FakeStepImpl {
+        fn descr(&self) -> String {
+            format!("Execute {:?}", self.step)
+        }
+
+        fn exec(&self) -> RecoveryResult<()> {
+            self.executed.lock().unwrap().push(self.step);
+            Ok(())
+        }
+    }
+
+    /// Fake recovery with the steps P0 to P3, where P1 is always skipped.
+    struct FakeRecovery {
+        step_iterator: Peekable<std::vec::IntoIter<FakeStep>>,
+        logger: Logger,
+        next_step: Option<FakeStep>,
+        executed: Arc<Mutex<Vec<FakeStep>>>,
+    }
+
+    impl FakeRecovery {
+        fn new() -> Self {
+            Self {
+                step_iterator: vec![FakeStep::P0, FakeStep::P1, FakeStep::P2, FakeStep::P3]
+                    .into_iter()
+                    .peekable(),
+                logger: crate::util::make_logger(),
+                next_step: None,
+                executed: Arc::new(Mutex::new(vec![])),
+            }
+        }
+    }
+
+    impl RecoveryIterator<FakeStep, std::vec::IntoIter<FakeStep>> for FakeRecovery {
+        fn get_step_iterator(&mut self) -> &mut Peekable<std::vec::IntoIter<FakeStep>> {
+            &mut self.step_iterator
+        }
+
+        fn store_next_step(&mut self, next_step: Option<FakeStep>) {
+            self.next_step = next_step
+        }
+
+        fn get_logger(&self) -> &Logger {
+            &self.logger
+        }
+
+        fn interactive(&self) -> bool {
+            false
+        }
+
+        fn get_step_impl(&self, step_type: FakeStep) -> RecoveryResult<Box<dyn Step>> {
+            match step_type {
+                FakeStep::P1 => Err(RecoveryError::StepSkipped),
+                step => Ok(Box::new(FakeStepImpl {
+                    step,
+                    executed: self.executed.clone(),
+                })),
+            }
+        }
+
+        fn read_step_params(&mut self, _step_type: FakeStep) {}
+    }
+
+    impl HasRecoveryState for FakeRecovery {
+        type StepType = FakeStep;
+        type SubcommandArgsType = ();
+
+        fn get_next_step(&self) -> Option<Self::StepType> {
+            self.next_step
+        }
+
+        fn get_state(&self) -> RecoveryResult<RecoveryState<Self::SubcommandArgsType>> {
+            Ok(RecoveryState {
+                // The directory doesn't exist, so the state is never written.
+                recovery_args: fake_recovery_args(),
+                subcommand_args: (),
+                neuron_args: None,
+            })
+        }
+    }
+
+    fn fake_recovery_args() -> RecoveryArgs {
+        RecoveryArgs {
+            dir: PathBuf::from("/non_existing_dir"),
+            nns_url: Url::parse("https://fake_nns_url.com/").unwrap(),
+            replica_version: None,
+            key_file: None,
+            test_mode: true,
+            skip_prompts: true,
+        }
+    }
+
+    fn fake_plan(steps: Vec<PlannedStep>) -> RecoveryPlan {
+        RecoveryPlan {
+            recovery_args: fake_recovery_args(),
+            subcommand_args: SubCommand::AppSubnetRecovery(AppSubnetRecoveryArgs {
+                subnet_id: SubnetId::from(PrincipalId::new_subnet_test_id(1)),
+                upgrade_version: None,
+                replacement_nodes: None,
+                pub_key: None,
+                download_node: None,
+                keep_downloaded_state: None,
+                upload_node: None,
+                ecdsa_subnet_id: None,
+                next_step: None,
+                upgrade_image_url: None,
+                upgrade_image_hash: None,
+            }),
+            steps,
+            executed_steps: vec![],
+        }
+    }
+
+    #[test]
+    fn plan_contains_all_steps_that_are_not_skipped() {
+        let plan = make_plan(FakeRecovery::new());
+
+        assert_eq!(
+            plan.iter()
+                .map(|step| step.step.as_str())
+                .collect::<Vec<_>>(),
+            vec!["P0", "P2", "P3"]
+        );
+        assert_eq!(plan[1].command, Some("Execute P2".to_string()));
+    }
+
+    #[test]
+    fn executes_only_planned_steps_and_stops_at_the_given_step() {
+        let mut steps = make_plan(FakeRecovery::new());
+        steps.remove(0);
+        let plan = fake_plan(steps);
+        let recovery = FakeRecovery::new();
+        let executed = recovery.executed.clone();
+        let logger = recovery.logger.clone();
+        let tmp = tempfile::tempdir().unwrap();
+
+        execute_plan(
+            &logger,
+            &tmp.path().join("plan.json"),
+            &plan,
+            Some("P3"),
+            recovery,
+        )
+        .unwrap();
+
+        assert_eq!(*executed.lock().unwrap(), vec![FakeStep::P2]);
+    }
+
+    #[test]
+    fn executing_a_stopped_plan_again_executes_each_step_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let plan_file = tmp.path().join("plan.json");
+        fake_plan(make_plan(FakeRecovery::new()))
+            .write(&plan_file)
+            .unwrap();
+        let executed = Arc::new(Mutex::new(vec![]));
+        let execute = |stop_at| {
+            let recovery = FakeRecovery {
+                executed: executed.clone(),
+                ..FakeRecovery::new()
+            };
+            let logger = recovery.logger.clone();
+            let plan = RecoveryPlan::read(&plan_file).unwrap();
+            execute_plan(&logger, &plan_file, &plan, stop_at, recovery).unwrap();
+        };
+
+        execute(Some("P2"));
+        assert_eq!(*executed.lock().unwrap(), vec![FakeStep::P0]);
+        assert_eq!(
+            RecoveryPlan::read(&plan_file).unwrap().executed_steps,
+            vec!["P0"]
+        );
+
+        execute(None);
+        assert_eq!(
+            *executed.lock().unwrap(),
+            vec![FakeStep::P0, FakeStep::P2, FakeStep::P3]
+        );
+
+        // Executing a finished plan again doesn't execute anything.
+        execute(None);
+        assert_eq!(
+            *executed.lock().unwrap(),
+            vec![FakeStep::P0, FakeStep::P2, FakeStep::P3]
+        );
+    }
+
+    #[test]
+    fn does_not_execute_steps_that_differ_from_the_plan() {
+        let mut steps = make_plan(FakeRecovery::new());
+        steps[0].command = Some("Execute something else".to_string());
+        let plan = fake_plan(steps);
+        let recovery = FakeRecovery::new();
+        let executed = recovery.executed.clone();
+        let logger = recovery.logger.clone();
+        let tmp = tempfile::tempdir().unwrap();
+
+        assert!(execute_plan(
+            &logger,
+            &tmp.path().join("plan.json"),
+            &plan,
+            None,
+            recovery
+        )
+        .is_err());
+        assert!(executed.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_steps() {
+        let plan = fake_plan(make_plan(FakeRecovery::new()));
+        let recovery = FakeRecovery::new();
+        let logger = recovery.logger.clone();
+        let tmp = tempfile::tempdir().unwrap();
+
+        assert!(execute_plan(
+            &logger,
+            &tmp.path().join("plan.json"),
+            &plan,
+            Some("P4"),
+            recovery
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn step_reports_are_flat_json_objects() {
+        let report = StepReport {
+            step: "DownloadState".to_string(),
+            status: StepStatus::Failed {
+                error: "ssh failed".to_string(),
+            },
+        };
+
+        let json = serde_json::to_string(&report).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"step":"DownloadState","status":"failed","error":"ssh failed"}"#
+        );
+        assert_eq!(serde_json::from_str::<StepReport>(&json).unwrap(), report);
+    }
+}
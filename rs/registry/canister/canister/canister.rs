@@ -61,11 +61,13 @@ use registry_canister::{
             do_remove_nodes::RemoveNodesPayload,
             do_update_node_domain_directly::UpdateNodeDomainDirectlyPayload,
             do_update_node_ipv4_config_directly::UpdateNodeIPv4ConfigDirectlyPayload,
+            do_update_node_reward_type_directly::UpdateNodeRewardTypeDirectlyPayload,
         },
         prepare_canister_migration::PrepareCanisterMigrationPayload,
         reroute_canister_ranges::RerouteCanisterRangesPayload,
     },
     pb::v1::{
+        GetNodeProvidersMonthlyXdrRewardsRequest, GetNodeProvidersMonthlyXdrRewardsResponse,
         GetSubnetForCanisterRequest, GetSubnetForCanisterResponse, NodeProvidersMonthlyXdrRewards,
         RegistryCanisterStableStorage,
     },
@@ -828,6 +830,27 @@ fn get_node_providers_monthly_xdr_rewards_() -> Result<NodeProvidersMonthlyXdrRe
     registry().get_node_providers_monthly_xdr_rewards()
 }
 
+#[export_name = "canister_query get_node_providers_monthly_xdr_rewards_with_performance"]
+fn get_node_providers_monthly_xdr_rewards_with_performance() {
+    check_caller_is_governance_and_log("get_node_providers_monthly_xdr_rewards_with_performance");
+    over(
+        candid_one,
+        |request: GetNodeProvidersMonthlyXdrRewardsRequest| -> Result<GetNodeProvidersMonthlyXdrRewardsResponse, String> {
+            get_node_providers_monthly_xdr_rewards_with_performance_(request)
+        },
+    )
+}
+
+#[candid_method(
+    query,
+    rename = "get_node_providers_monthly_xdr_rewards_with_performance"
+)]
+fn get_node_providers_monthly_xdr_rewards_with_performance_(
+    request: GetNodeProvidersMonthlyXdrRewardsRequest,
+) -> Result<GetNodeProvidersMonthlyXdrRewardsResponse, String> {
+    registry().get_node_providers_monthly_xdr_rewards_with_performance(request)
+}
+
 #[export_name = "canister_query get_node_operators_and_dcs_of_node_provider"]
 fn get_node_operators_and_dcs_of_node_provider() {
     over(
@@ -946,6 +969,26 @@ fn update_node_ipv4_config_directly_(
     Ok(())
 }
 
+#[export_name = "canister_update update_node_reward_type_directly"]
+fn update_node_reward_type_directly() {
+    // This method can be called by anyone
+    println!(
+        "{}call: update_node_reward_type_directly from: {}",
+        LOG_PREFIX,
+        dfn_core::api::caller()
+    );
+    over_may_reject(candid_one, update_node_reward_type_directly_);
+}
+
+#[candid_method(update, rename = "update_node_reward_type_directly")]
+fn update_node_reward_type_directly_(
+    payload: UpdateNodeRewardTypeDirectlyPayload,
+) -> Result<(), String> {
+    let result = registry_mut().do_update_node_reward_type_directly(payload);
+    recertify_registry();
+    result
+}
+
 #[export_name = "canister_update remove_node_directly"]
 fn remove_node_directly() {
     // This method can be called by anyone
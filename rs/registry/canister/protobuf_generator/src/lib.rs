@@ -28,6 +28,22 @@ pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
         "ic_registry_canister.pb.v1.NodeProvidersMonthlyXdrRewards",
         "#[derive(candid::CandidType, candid::Deserialize)]",
     );
+    config.type_attribute(
+        "ic_registry_canister.pb.v1.NodeRewardsReductionCurve",
+        "#[derive(candid::CandidType, candid::Deserialize)]",
+    );
+    config.type_attribute(
+        "ic_registry_canister.pb.v1.GetNodeProvidersMonthlyXdrRewardsRequest",
+        "#[derive(candid::CandidType, candid::Deserialize)]",
+    );
+    config.type_attribute(
+        "ic_registry_canister.pb.v1.NodeMonthlyXdrRewards",
+        "#[derive(candid::CandidType, candid::Deserialize)]",
+    );
+    config.type_attribute(
+        "ic_registry_canister.pb.v1.GetNodeProvidersMonthlyXdrRewardsResponse",
+        "#[derive(candid::CandidType, candid::Deserialize)]",
+    );
     config.type_attribute(
         "ic_registry_canister.pb.v1.GetSubnetForCanisterRequest",
         "#[derive(candid::CandidType, candid::Deserialize)]",
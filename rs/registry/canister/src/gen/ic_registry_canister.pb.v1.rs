@@ -118,6 +118,68 @@ pub struct NodeProvidersMonthlyXdrRewards {
     #[prost(map = "string, uint64", tag = "1")]
     pub rewards: ::std::collections::HashMap<::prost::alloc::string::String, u64>,
 }
+/// A piecewise linear curve mapping the block-making failure rate of a node to
+/// a reduction of its monthly rewards. Nodes with a failure rate at or below
+/// `failure_rate_threshold_basis_points` are not penalized, nodes with a failure
+/// rate at or above `failure_rate_max_basis_points` have their rewards reduced by
+/// `max_reduction_basis_points`, and the reduction is linearly interpolated in
+/// between.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NodeRewardsReductionCurve {
+    #[prost(uint64, tag = "1")]
+    pub failure_rate_threshold_basis_points: u64,
+    #[prost(uint64, tag = "2")]
+    pub failure_rate_max_basis_points: u64,
+    #[prost(uint64, tag = "3")]
+    pub max_reduction_basis_points: u64,
+}
+/// Request to compute the monthly rewards of node providers, taking into
+/// account the performance of their nodes.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNodeProvidersMonthlyXdrRewardsRequest {
+    /// Maps Node IDs to the fraction (between 0.0 and 1.0) of blocks the node
+    /// failed to make over the rewarding period. Nodes not present in this map
+    /// are rewarded in full.
+    #[prost(map = "string, double", tag = "1")]
+    pub node_failure_rates: ::std::collections::HashMap<::prost::alloc::string::String, f64>,
+    #[prost(message, optional, tag = "2")]
+    pub reduction_curve: ::core::option::Option<NodeRewardsReductionCurve>,
+}
+/// The rewards (in 10,000ths of an SDR) of a single rewardable node.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NodeMonthlyXdrRewards {
+    /// Empty if the Node Operator has fewer nodes in the registry than
+    /// rewardable nodes of this type.
+    #[prost(string, tag = "1")]
+    pub node_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub node_provider_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub node_operator_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub node_type: ::prost::alloc::string::String,
+    /// The rewards of the node before any performance based reduction.
+    #[prost(uint64, tag = "5")]
+    pub base_xdr_permyriad: u64,
+    #[prost(double, optional, tag = "6")]
+    pub failure_rate: ::core::option::Option<f64>,
+    #[prost(uint64, tag = "7")]
+    pub reduction_basis_points: u64,
+    /// The rewards of the node after the performance based reduction.
+    #[prost(uint64, tag = "8")]
+    pub xdr_permyriad: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNodeProvidersMonthlyXdrRewardsResponse {
+    #[prost(message, optional, tag = "1")]
+    pub rewards: ::core::option::Option<NodeProvidersMonthlyXdrRewards>,
+    #[prost(message, repeated, tag = "2")]
+    pub node_rewards: ::prost::alloc::vec::Vec<NodeMonthlyXdrRewards>,
+}
 /// Maps the supplied PrincipalId (of a canister) to the subnet to which the canister is assigned to.
 /// There is no guarantee that the canister exists, even if a subnet ID is returned.
 #[derive(candid::CandidType, candid::Deserialize)]
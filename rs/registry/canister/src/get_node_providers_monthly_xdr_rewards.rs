@@ -1,17 +1,25 @@
-use crate::{pb::v1::NodeProvidersMonthlyXdrRewards, registry::Registry};
+use crate::{
+    pb::v1::{
+        GetNodeProvidersMonthlyXdrRewardsRequest, GetNodeProvidersMonthlyXdrRewardsResponse,
+        NodeMonthlyXdrRewards, NodeProvidersMonthlyXdrRewards, NodeRewardsReductionCurve,
+    },
+    registry::Registry,
+};
 #[cfg(target_arch = "wasm32")]
 use dfn_core::println;
 use ic_nns_common::registry::decode_or_panic;
 use ic_protobuf::registry::dc::v1::DataCenterRecord;
+use ic_protobuf::registry::node::v1::NodeRecord;
 use ic_protobuf::registry::node_operator::v1::NodeOperatorRecord;
 use ic_protobuf::registry::node_rewards::v2::{NodeRewardRate, NodeRewardsTable};
 use ic_registry_keys::{
-    make_data_center_record_key, NODE_OPERATOR_RECORD_KEY_PREFIX, NODE_REWARDS_TABLE_KEY,
+    make_data_center_record_key, NODE_OPERATOR_RECORD_KEY_PREFIX, NODE_RECORD_KEY_PREFIX,
+    NODE_REWARDS_TABLE_KEY,
 };
 use ic_types::PrincipalId;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
-use std::str::from_utf8;
+use std::str::{from_utf8, FromStr};
 
 impl Registry {
     /// Return a map from Node Provider IDs to the amount (in 10,000ths of an
@@ -20,7 +28,36 @@ impl Registry {
     pub fn get_node_providers_monthly_xdr_rewards(
         &self,
     ) -> Result<NodeProvidersMonthlyXdrRewards, String> {
+        self.get_node_providers_monthly_xdr_rewards_with_performance(
+            GetNodeProvidersMonthlyXdrRewardsRequest::default(),
+        )
+        .map(|response| response.rewards.unwrap_or_default())
+    }
+
+    /// Like `get_node_providers_monthly_xdr_rewards`, but reduces the rewards
+    /// of each node according to its block-making failure rate, as given in
+    /// `request`, and the reduction curve. The rewards of each rewardable node
+    /// are reported individually in the response.
+    ///
+    /// The rewardable nodes of each node type of a Node Operator are matched
+    /// against the nodes of that Node Operator in the registry that have this
+    /// node type, in the order of node ID. The node type of a node is the one
+    /// in its record or, if the record does not specify one, the only node
+    /// type among the rewardable nodes of its Node Operator. Rewardable nodes
+    /// that cannot be matched to a node in the registry are rewarded in full.
+    pub fn get_node_providers_monthly_xdr_rewards_with_performance(
+        &self,
+        request: GetNodeProvidersMonthlyXdrRewardsRequest,
+    ) -> Result<GetNodeProvidersMonthlyXdrRewardsResponse, String> {
+        let GetNodeProvidersMonthlyXdrRewardsRequest {
+            node_failure_rates,
+            reduction_curve,
+        } = request;
+        let reduction_curve = reduction_curve.unwrap_or_default();
+
         let mut rewards = NodeProvidersMonthlyXdrRewards::default();
+        let mut node_rewards = vec![];
+        let mut nodes_by_operator = self.get_nodes_by_node_operator();
 
         let rewards_table_bytes = self
             .get(NODE_REWARDS_TABLE_KEY.as_bytes(), self.latest_version())
@@ -85,7 +122,18 @@ impl Registry {
                     .rewards
                     .entry(node_provider_id.to_string())
                     .or_default();
+                let mut node_ids_by_type = node_ids_by_reward_type(
+                    nodes_by_operator
+                        .remove(&node_operator_id)
+                        .unwrap_or_default(),
+                    &node_operator.rewardable_nodes,
+                    node_operator_id,
+                );
                 for (node_type, node_count) in node_operator.rewardable_nodes {
+                    let mut node_ids = node_ids_by_type
+                        .remove(&node_type)
+                        .unwrap_or_default()
+                        .into_iter();
                     let rate = match rewards_table.get_rate(region, &node_type) {
                         Some(rate) => rate,
                         None => {
@@ -102,7 +150,7 @@ impl Registry {
                         }
                     };
 
-                    let dc_reward = match &node_type {
+                    let base_node_rewards: Vec<u64> = match &node_type {
                         t if t.starts_with("type3") => {
                             // For type3 nodes, the rewards are progressively reduced for each additional node owned by a NP.
                             // This helps to improve network decentralization. The first node gets the full reward.
@@ -152,7 +200,7 @@ impl Registry {
                             let dc_reward_coefficient_percent =
                                 rate.reward_coefficient_percent.unwrap_or(80) as f64 / 100.0;
 
-                            let mut dc_reward = 0;
+                            let mut base_node_rewards = vec![];
                             for i in 0..node_count {
                                 let node_reward = (reward_base * np_coeff) as u64;
                                 println!(
@@ -164,15 +212,55 @@ impl Registry {
                                     node_operator.dc_id,
                                     node_reward,
                                 );
-                                dc_reward += node_reward;
+                                base_node_rewards.push(node_reward);
                                 np_coeff *= dc_reward_coefficient_percent;
                             }
                             np_coefficients.insert(np_coefficients_key, np_coeff);
-                            dc_reward
+                            base_node_rewards
                         }
-                        _ => node_count as u64 * rate.xdr_permyriad_per_node_per_month,
+                        _ => vec![rate.xdr_permyriad_per_node_per_month; node_count as usize],
                     };
 
+                    let mut dc_reward = 0;
+                    for base_xdr_permyriad in base_node_rewards {
+                        let node_id = node_ids.next().map(|node_id| node_id.to_string());
+                        let failure_rate = node_id
+                            .as_ref()
+                            .and_then(|node_id| node_failure_rates.get(node_id))
+                            .copied();
+                        let reduction_basis_points = failure_rate
+                            .map(|failure_rate| {
+                                reward_reduction_basis_points(&reduction_curve, failure_rate)
+                            })
+                            .unwrap_or(0);
+                        let xdr_permyriad = (base_xdr_permyriad as u128
+                            * (10_000 - reduction_basis_points) as u128
+                            / 10_000) as u64;
+                        if reduction_basis_points > 0 {
+                            println!(
+                                "Node {:?} of NodeProvider {} with failure rate {:?}: \
+                                 reward reduced by {} basis points from {} to {}",
+                                node_id,
+                                node_provider_id,
+                                failure_rate,
+                                reduction_basis_points,
+                                base_xdr_permyriad,
+                                xdr_permyriad,
+                            );
+                        }
+                        dc_reward += xdr_permyriad;
+                        node_rewards.push(NodeMonthlyXdrRewards {
+                            node_id: node_id.unwrap_or_default(),
+                            node_provider_id: node_provider_id.to_string(),
+                            node_operator_id: node_operator_id.to_string(),
+                            node_type: node_type.clone(),
+                            base_xdr_permyriad,
+                            failure_rate,
+                            reduction_basis_points,
+                            xdr_permyriad,
+                        });
+                    }
+
                     println!(
                         "NodeProvider {} reward for all {} {} nodes in {} DC: reward {}",
                         node_provider_id,
@@ -186,14 +274,106 @@ impl Registry {
             }
         }
 
-        Ok(rewards)
+        Ok(GetNodeProvidersMonthlyXdrRewardsResponse {
+            rewards: Some(rewards),
+            node_rewards,
+        })
+    }
+
+    /// Return the IDs and node reward types of the nodes in the registry,
+    /// grouped by their Node Operator and sorted by node ID.
+    fn get_nodes_by_node_operator(
+        &self,
+    ) -> BTreeMap<PrincipalId, Vec<(PrincipalId, Option<String>)>> {
+        let mut nodes_by_operator: BTreeMap<PrincipalId, Vec<(PrincipalId, Option<String>)>> =
+            BTreeMap::new();
+        for (key, values) in self.store.iter() {
+            if !key.starts_with(NODE_RECORD_KEY_PREFIX.as_bytes()) {
+                continue;
+            }
+            let value = values.back().unwrap();
+            if value.deletion_marker {
+                continue;
+            }
+            let node_id = match from_utf8(&key[NODE_RECORD_KEY_PREFIX.len()..])
+                .ok()
+                .and_then(|node_id| PrincipalId::from_str(node_id).ok())
+            {
+                Some(node_id) => node_id,
+                None => continue,
+            };
+            let node_record = decode_or_panic::<NodeRecord>(value.value.clone());
+            if let Ok(node_operator_id) = PrincipalId::try_from(&node_record.node_operator_id) {
+                nodes_by_operator
+                    .entry(node_operator_id)
+                    .or_default()
+                    .push((node_id, node_record.node_reward_type));
+            }
+        }
+        for nodes in nodes_by_operator.values_mut() {
+            nodes.sort();
+        }
+        nodes_by_operator
+    }
+}
+
+/// Group the given nodes of a Node Operator by their node reward type,
+/// preserving their order. Nodes whose record does not specify a node reward
+/// type are attributed to the only node type among the rewardable nodes of
+/// the Node Operator. If there is no such type, these nodes cannot be matched
+/// to a reward and are left out.
+fn node_ids_by_reward_type(
+    nodes: Vec<(PrincipalId, Option<String>)>,
+    rewardable_nodes: &BTreeMap<String, u32>,
+    node_operator_id: PrincipalId,
+) -> BTreeMap<String, Vec<PrincipalId>> {
+    let only_node_type = match rewardable_nodes.keys().collect::<Vec<_>>()[..] {
+        [node_type] => Some(node_type.clone()),
+        _ => None,
+    };
+    let mut node_ids_by_type: BTreeMap<String, Vec<PrincipalId>> = BTreeMap::new();
+    for (node_id, node_reward_type) in nodes {
+        match node_reward_type.or_else(|| only_node_type.clone()) {
+            Some(node_type) => node_ids_by_type.entry(node_type).or_default().push(node_id),
+            None => println!(
+                "Node {} of Node Operator {} has no node reward type, and the Node Operator \
+                 has several rewardable node types: its rewards are not reduced",
+                node_id, node_operator_id
+            ),
+        }
+    }
+    node_ids_by_type
+}
+
+/// Return the reduction (in basis points) of the rewards of a node with the
+/// given block-making failure rate, according to `curve`.
+fn reward_reduction_basis_points(curve: &NodeRewardsReductionCurve, failure_rate: f64) -> u64 {
+    let &NodeRewardsReductionCurve {
+        failure_rate_threshold_basis_points,
+        failure_rate_max_basis_points,
+        max_reduction_basis_points,
+    } = curve;
+    let max_reduction_basis_points = max_reduction_basis_points.min(10_000);
+    // A NaN failure rate is mapped to 0 by the cast.
+    let failure_rate_basis_points = (failure_rate.clamp(0.0, 1.0) * 10_000.0) as u64;
+
+    if failure_rate_basis_points <= failure_rate_threshold_basis_points {
+        0
+    } else if failure_rate_basis_points >= failure_rate_max_basis_points {
+        max_reduction_basis_points
+    } else {
+        max_reduction_basis_points
+            * (failure_rate_basis_points - failure_rate_threshold_basis_points)
+            / (failure_rate_max_basis_points - failure_rate_threshold_basis_points)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::test_helpers::prepare_registry_with_nodes;
     use crate::mutations::do_add_node_operator::AddNodeOperatorPayload;
+    use crate::mutations::node_management::do_update_node_reward_type_directly::UpdateNodeRewardTypeDirectlyPayload;
     use ic_nervous_system_common_test_keys::{
         TEST_USER1_PRINCIPAL, TEST_USER2_PRINCIPAL, TEST_USER3_PRINCIPAL, TEST_USER4_PRINCIPAL,
     };
@@ -204,6 +384,7 @@ mod tests {
     };
     use ic_registry_keys::make_node_operator_record_key;
     use ic_registry_transport::pb::v1::{registry_mutation, RegistryMutation};
+    use ic_types::NodeId;
     use maplit::btreemap;
 
     use std::collections::BTreeMap;
@@ -576,4 +757,266 @@ mod tests {
             np2_expected_reward_ch + np2_expected_reward_de
         );
     }
+
+    #[test]
+    fn test_reward_reduction_basis_points() {
+        let curve = NodeRewardsReductionCurve {
+            failure_rate_threshold_basis_points: 1_000,
+            failure_rate_max_basis_points: 6_000,
+            max_reduction_basis_points: 8_000,
+        };
+
+        assert_eq!(reward_reduction_basis_points(&curve, 0.0), 0);
+        assert_eq!(reward_reduction_basis_points(&curve, 0.1), 0);
+        assert_eq!(reward_reduction_basis_points(&curve, 0.35), 4_000);
+        assert_eq!(reward_reduction_basis_points(&curve, 0.6), 8_000);
+        assert_eq!(reward_reduction_basis_points(&curve, 1.0), 8_000);
+        assert_eq!(reward_reduction_basis_points(&curve, 2.0), 8_000);
+        assert_eq!(reward_reduction_basis_points(&curve, f64::NAN), 0);
+
+        // The default curve never reduces rewards.
+        let curve = NodeRewardsReductionCurve::default();
+        assert_eq!(reward_reduction_basis_points(&curve, 1.0), 0);
+
+        // Reductions are capped at 100%.
+        let curve = NodeRewardsReductionCurve {
+            max_reduction_basis_points: 20_000,
+            ..Default::default()
+        };
+        assert_eq!(reward_reduction_basis_points(&curve, 1.0), 10_000);
+    }
+
+    #[test]
+    fn test_node_ids_by_reward_type() {
+        let no = PrincipalId::new_user_test_id(999);
+        let node = PrincipalId::new_node_test_id;
+        let nodes = vec![
+            (node(1), Some("type3".to_string())),
+            (node(2), None),
+            (node(3), Some("type1".to_string())),
+            (node(4), Some("type3".to_string())),
+        ];
+
+        // Nodes are matched on the node type in their record, and nodes
+        // without one cannot be attributed to any of several node types.
+        let rewardable_nodes = btreemap! {
+            "type1".to_string() => 2,
+            "type3".to_string() => 2,
+        };
+        assert_eq!(
+            node_ids_by_reward_type(nodes.clone(), &rewardable_nodes, no),
+            btreemap! {
+                "type1".to_string() => vec![node(3)],
+                "type3".to_string() => vec![node(1), node(4)],
+            }
+        );
+
+        // With a single rewardable node type, nodes without a node type in
+        // their record are attributed to it.
+        let rewardable_nodes = btreemap! { "type3".to_string() => 4 };
+        assert_eq!(
+            node_ids_by_reward_type(nodes, &rewardable_nodes, no),
+            btreemap! {
+                "type1".to_string() => vec![node(3)],
+                "type3".to_string() => vec![node(1), node(2), node(4)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_node_providers_monthly_xdr_rewards_with_performance() {
+        let registry = registry_init_empty();
+
+        // `prepare_registry_with_nodes` assigns all nodes to this Node Operator.
+        let np = *TEST_USER1_PRINCIPAL;
+        let no = PrincipalId::new_user_test_id(999);
+        let mut registry = registry_add_node_operator(
+            registry,
+            np,
+            no,
+            "ZH1".to_string(),
+            "Europe,CH,Zurich".into(),
+            3,
+            btreemap! { "type1".to_string() => 3 },
+        );
+
+        let json = r#"{
+            "Europe":                      { "type1": [1000, null] }
+        }"#;
+        let map: BTreeMap<String, BTreeMap<String, NodeRewardRate>> =
+            serde_json::from_str(json).unwrap();
+        registry.do_update_node_rewards_table(UpdateNodeRewardsTableProposalPayload::from(map));
+
+        // Only two of the three rewardable nodes are actually in the registry.
+        let (mutate_request, node_ids) = prepare_registry_with_nodes(1, 2);
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+        let mut node_ids: Vec<String> = node_ids
+            .into_iter()
+            .map(|node_id| node_id.get().to_string())
+            .collect();
+        node_ids.sort_by_key(|node_id| PrincipalId::from_str(node_id).unwrap());
+
+        let request = GetNodeProvidersMonthlyXdrRewardsRequest {
+            node_failure_rates: btreemap! {
+                node_ids[0].clone() => 0.5,
+                node_ids[1].clone() => 0.01,
+            }
+            .into_iter()
+            .collect(),
+            reduction_curve: Some(NodeRewardsReductionCurve {
+                failure_rate_threshold_basis_points: 1_000,
+                failure_rate_max_basis_points: 6_000,
+                max_reduction_basis_points: 8_000,
+            }),
+        };
+        let response = registry
+            .get_node_providers_monthly_xdr_rewards_with_performance(request)
+            .unwrap();
+
+        // 50% failure rate: (5000 - 1000) / (6000 - 1000) * 80% = 64% reduction.
+        assert_eq!(
+            response.node_rewards,
+            vec![
+                NodeMonthlyXdrRewards {
+                    node_id: node_ids[0].clone(),
+                    node_provider_id: np.to_string(),
+                    node_operator_id: no.to_string(),
+                    node_type: "type1".to_string(),
+                    base_xdr_permyriad: 1000,
+                    failure_rate: Some(0.5),
+                    reduction_basis_points: 6_400,
+                    xdr_permyriad: 360,
+                },
+                NodeMonthlyXdrRewards {
+                    node_id: node_ids[1].clone(),
+                    node_provider_id: np.to_string(),
+                    node_operator_id: no.to_string(),
+                    node_type: "type1".to_string(),
+                    base_xdr_permyriad: 1000,
+                    failure_rate: Some(0.01),
+                    reduction_basis_points: 0,
+                    xdr_permyriad: 1000,
+                },
+                NodeMonthlyXdrRewards {
+                    node_id: "".to_string(),
+                    node_provider_id: np.to_string(),
+                    node_operator_id: no.to_string(),
+                    node_type: "type1".to_string(),
+                    base_xdr_permyriad: 1000,
+                    failure_rate: None,
+                    reduction_basis_points: 0,
+                    xdr_permyriad: 1000,
+                },
+            ]
+        );
+        assert_eq!(
+            response.rewards.unwrap().rewards,
+            btreemap! { np.to_string() => 360 + 1000 + 1000 }
+                .into_iter()
+                .collect()
+        );
+
+        // Without failure rates, the rewards are the same as before.
+        assert_eq!(
+            *registry
+                .get_node_providers_monthly_xdr_rewards()
+                .unwrap()
+                .rewards
+                .get(&np.to_string())
+                .unwrap(),
+            3 * 1000
+        );
+    }
+
+    #[test]
+    fn test_get_node_providers_monthly_xdr_rewards_with_performance_multiple_node_types() {
+        let registry = registry_init_empty();
+
+        // `prepare_registry_with_nodes` assigns all nodes to this Node Operator.
+        let np = *TEST_USER1_PRINCIPAL;
+        let no = PrincipalId::new_user_test_id(999);
+        let mut registry = registry_add_node_operator(
+            registry,
+            np,
+            no,
+            "ZH1".to_string(),
+            "Europe,CH,Zurich".into(),
+            2,
+            btreemap! { "type1".to_string() => 1, "type3".to_string() => 1 },
+        );
+
+        let json = r#"{
+            "Europe":                      { "type1": [1000, null], "type3": [2000, 70] }
+        }"#;
+        let map: BTreeMap<String, BTreeMap<String, NodeRewardRate>> =
+            serde_json::from_str(json).unwrap();
+        registry.do_update_node_rewards_table(UpdateNodeRewardsTableProposalPayload::from(map));
+
+        let (mutate_request, node_ids) = prepare_registry_with_nodes(1, 2);
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+        let mut node_ids: Vec<_> = node_ids.into_iter().map(|node_id| node_id.get()).collect();
+        node_ids.sort();
+
+        let request = GetNodeProvidersMonthlyXdrRewardsRequest {
+            node_failure_rates: btreemap! {
+                node_ids[0].to_string() => 0.5,
+                node_ids[1].to_string() => 1.0,
+            }
+            .into_iter()
+            .collect(),
+            reduction_curve: Some(NodeRewardsReductionCurve {
+                failure_rate_threshold_basis_points: 1_000,
+                failure_rate_max_basis_points: 6_000,
+                max_reduction_basis_points: 8_000,
+            }),
+        };
+        let node_rewards = |registry: &Registry| {
+            registry
+                .get_node_providers_monthly_xdr_rewards_with_performance(request.clone())
+                .unwrap()
+                .node_rewards
+                .into_iter()
+                .map(|rewards| {
+                    (
+                        rewards.node_id,
+                        rewards.node_type,
+                        rewards.reduction_basis_points,
+                        rewards.xdr_permyriad,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // The node records do not specify a node reward type, so none of the
+        // nodes can be matched to one of the several rewardable node types.
+        assert_eq!(
+            node_rewards(&registry),
+            vec![
+                ("".to_string(), "type1".to_string(), 0, 1000),
+                ("".to_string(), "type3".to_string(), 0, 2000),
+            ]
+        );
+
+        // Once the Node Operator has set the node reward types, the rewards
+        // of both nodes are reduced.
+        for (node_id, node_reward_type) in [(node_ids[0], "type3"), (node_ids[1], "type1")] {
+            registry
+                .do_update_node_reward_type(
+                    UpdateNodeRewardTypeDirectlyPayload {
+                        node_id: NodeId::from(node_id),
+                        node_reward_type: Some(node_reward_type.to_string()),
+                    },
+                    no,
+                )
+                .unwrap();
+        }
+        // 100% failure rate: 80% reduction. 50% failure rate: 64% reduction.
+        assert_eq!(
+            node_rewards(&registry),
+            vec![
+                (node_ids[1].to_string(), "type1".to_string(), 8_000, 200),
+                (node_ids[0].to_string(), "type3".to_string(), 6_400, 720),
+            ]
+        );
+    }
 }
@@ -3,7 +3,7 @@ use crate::{
     invariants::{
         api_boundary_node::check_api_boundary_node_invariants,
         assignment::check_node_assignment_invariants,
-        common::RegistrySnapshot,
+        common::{InvariantCheckError, RegistrySnapshot},
         crypto::check_node_crypto_keys_invariants,
         endpoint::check_endpoint_invariants,
         firewall::check_firewall_invariants,
@@ -65,49 +65,7 @@ impl Registry {
 
         let snapshot = self.take_latest_snapshot_with_mutations(mutations);
 
-        // Node invariants
-        // TODO(NNS1-202): re-enable this check when cd hourly test issues are sorted
-        // out.
-        // Note that for now, once a node record has been added, it MUST not be
-        // modified, as P2P and Transport rely on this data to stay the same
-
-        // Node Operator invariants
-        let mut result = check_node_operator_invariants(&snapshot, false);
-
-        // Crypto invariants
-        result = result.and(check_node_crypto_keys_invariants(&snapshot));
-
-        // Node assignment invariants
-        result = result.and(check_node_assignment_invariants(&snapshot));
-
-        // Routing Table invariants
-        result = result.and(check_routing_table_invariants(&snapshot));
-
-        // Canister migrations invariants
-        result = result.and(check_canister_migrations_invariants(&snapshot));
-
-        // Subnet invariants
-        result = result.and(check_subnet_invariants(&snapshot));
-
-        // Replica version invariants
-        result = result.and(check_replica_version_invariants(&snapshot));
-
-        // API Boundary Node invariant
-        result = result.and(check_api_boundary_node_invariants(&snapshot));
-
-        // HostOS version invariants
-        result = result.and(check_hostos_version_invariants(&snapshot));
-
-        // Endpoint invariants
-        result = result.and(check_endpoint_invariants(&snapshot, false));
-
-        // Firewall invariants
-        result = result.and(check_firewall_invariants(&snapshot));
-
-        // Unassigned node invariants
-        result = result.and(check_unassigned_nodes_config_invariants(&snapshot));
-
-        if let Err(e) = result {
+        if let Some(e) = check_invariants(&snapshot).into_iter().next() {
             panic!(
                 "{} invariant check failed with message: {}",
                 LOG_PREFIX, e.msg
@@ -147,6 +105,50 @@ impl Registry {
     }
 }
 
+/// Checks all global state invariants on the given snapshot of the registry
+/// and returns the violations, in the order in which the checks are run.
+///
+/// This is what `check_global_state_invariants` runs before any mutation is
+/// applied, exposed so that mutations can be validated offline against a copy
+/// of the registry.
+pub fn check_invariants(snapshot: &RegistrySnapshot) -> Vec<InvariantCheckError> {
+    // Node invariants
+    // TODO(NNS1-202): re-enable this check when cd hourly test issues are sorted
+    // out.
+    // Note that for now, once a node record has been added, it MUST not be
+    // modified, as P2P and Transport rely on this data to stay the same
+
+    [
+        // Node Operator invariants
+        check_node_operator_invariants(snapshot, false),
+        // Crypto invariants
+        check_node_crypto_keys_invariants(snapshot),
+        // Node assignment invariants
+        check_node_assignment_invariants(snapshot),
+        // Routing Table invariants
+        check_routing_table_invariants(snapshot),
+        // Canister migrations invariants
+        check_canister_migrations_invariants(snapshot),
+        // Subnet invariants
+        check_subnet_invariants(snapshot),
+        // Replica version invariants
+        check_replica_version_invariants(snapshot),
+        // API Boundary Node invariant
+        check_api_boundary_node_invariants(snapshot),
+        // HostOS version invariants
+        check_hostos_version_invariants(snapshot),
+        // Endpoint invariants
+        check_endpoint_invariants(snapshot, false),
+        // Firewall invariants
+        check_firewall_invariants(snapshot),
+        // Unassigned node invariants
+        check_unassigned_nodes_config_invariants(snapshot),
+    ]
+    .into_iter()
+    .filter_map(Result::err)
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::registry::EncodedVersion;
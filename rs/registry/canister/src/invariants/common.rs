@@ -18,13 +18,20 @@ use ic_registry_keys::{
     make_subnet_list_record_key, ECDSA_SIGNING_SUBNET_LIST_KEY_PREFIX, HOSTOS_VERSION_KEY_PREFIX,
 };
 
+// The invariant checks also run outside of the canister, e.g. in ic-regedit,
+// whose output goes to stdout: there, their diagnostics go to stderr instead.
+#[cfg(target_arch = "wasm32")]
+pub(crate) use dfn_core::println;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::eprintln as println;
+
 /// A representation of the data held by the registry.
 /// It is kept in-memory only, for global consistency checks before mutations
 /// are finalized.
-pub(crate) type RegistrySnapshot = BTreeMap<Vec<u8>, Vec<u8>>;
+pub type RegistrySnapshot = BTreeMap<Vec<u8>, Vec<u8>>;
 
 #[derive(Debug)]
-pub(crate) struct InvariantCheckError {
+pub struct InvariantCheckError {
     pub msg: String,
     pub source: Option<Box<dyn error::Error + 'static>>,
 }
@@ -22,8 +22,7 @@ use ic_types::crypto::KeyPurpose;
 use prost::Message;
 use std::collections::{BTreeMap, HashMap, HashSet};
 
-#[cfg(target_arch = "wasm32")]
-use dfn_core::println;
+use crate::invariants::common::println;
 use ic_crypto_utils_basic_sig::conversions::derive_node_id;
 
 #[cfg(test)]
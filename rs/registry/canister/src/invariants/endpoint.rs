@@ -12,8 +12,7 @@ use prost::alloc::collections::BTreeSet;
 
 use ic_protobuf::registry::node::v1::ConnectionEndpoint;
 
-#[cfg(target_arch = "wasm32")]
-use dfn_core::println;
+use crate::invariants::common::println;
 
 /// Node records are valid with connection endpoints containing
 /// syntactically correct data ("ip_addr" field parses as an IP address,
@@ -403,6 +402,7 @@ mod tests {
                 chip_id: None,
                 public_ipv4_config: None,
                 domain: None,
+                node_reward_type: None,
             }),
         );
 
@@ -427,6 +427,7 @@ mod tests {
                 chip_id: None,
                 public_ipv4_config: None,
                 domain: None,
+                node_reward_type: None,
             }),
         );
 
@@ -454,6 +455,7 @@ mod tests {
                 chip_id: None,
                 public_ipv4_config: None,
                 domain: None,
+                node_reward_type: None,
             }),
         );
         check_endpoint_invariants(&snapshot, true).unwrap();
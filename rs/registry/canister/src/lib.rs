@@ -3,7 +3,7 @@ pub mod common;
 pub mod get_node_operators_and_dcs_of_node_provider;
 pub mod get_node_providers_monthly_xdr_rewards;
 pub mod init;
-mod invariants;
+pub mod invariants;
 pub mod mutations;
 pub mod pb;
 pub mod proto_on_wire;
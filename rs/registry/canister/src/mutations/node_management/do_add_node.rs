@@ -74,6 +74,19 @@ impl Registry {
             return Err("Node allowance for this Node Operator is exhausted".to_string());
         }
 
+        if let Some(node_reward_type) = &payload.node_reward_type {
+            if !node_operator_record
+                .rewardable_nodes
+                .contains_key(node_reward_type)
+            {
+                return Err(format!(
+                    "{}do_add_node: Node reward type '{}' is not among the rewardable nodes \
+                     of the Node Operator",
+                    LOG_PREFIX, node_reward_type
+                ));
+            }
+        }
+
         // 4. Validate keys and get the node id
         let (node_id, valid_pks) = valid_keys_from_payload(&payload)?;
 
@@ -93,6 +106,7 @@ impl Registry {
                 .clone()
                 .map(make_valid_node_ivp4_config_or_panic),
             domain: None,
+            node_reward_type: payload.node_reward_type.clone(),
         };
 
         // 6. Insert node, public keys, and crypto keys
@@ -136,6 +150,11 @@ pub struct AddNodePayload {
     pub prometheus_metrics_endpoint: String,
 
     pub public_ipv4_config: Option<Vec<String>>,
+
+    /// The type of the node for the purpose of node provider rewards. If
+    /// specified, it must be one of the node types among the rewardable nodes
+    /// of the Node Operator.
+    pub node_reward_type: Option<String>,
 }
 
 /// Parses the ConnectionEndpoint string
@@ -320,6 +339,7 @@ mod tests {
             prometheus_metrics_endpoint: "".to_string(),
             chip_id: None,
             public_ipv4_config: None,
+            node_reward_type: None,
         };
     }
 
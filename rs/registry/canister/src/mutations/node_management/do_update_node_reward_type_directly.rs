@@ -0,0 +1,189 @@
+use crate::mutations::node_management::common::{
+    get_node_operator_id_for_node, get_node_operator_record,
+};
+use crate::{common::LOG_PREFIX, mutations::common::encode_or_panic, registry::Registry};
+
+use candid::{CandidType, Deserialize};
+use ic_registry_keys::make_node_record_key;
+use ic_registry_transport::update;
+use serde::Serialize;
+
+#[cfg(target_arch = "wasm32")]
+use dfn_core::println;
+
+use ic_base_types::{NodeId, PrincipalId};
+
+// Payload of the request to update the node reward type of an existing node
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UpdateNodeRewardTypeDirectlyPayload {
+    pub node_id: NodeId,
+    pub node_reward_type: Option<String>,
+}
+
+impl Registry {
+    /// Updates the node reward type of a node, which is used to match the
+    /// node against the rewardable nodes of its Node Operator.
+    /// This method is called directly by the node operator
+    pub fn do_update_node_reward_type_directly(
+        &mut self,
+        payload: UpdateNodeRewardTypeDirectlyPayload,
+    ) -> Result<(), String> {
+        let caller_id = dfn_core::api::caller();
+        println!(
+            "{}do_update_node_reward_type_directly started: {:?} caller: {:?}",
+            LOG_PREFIX, payload, caller_id
+        );
+        self.do_update_node_reward_type(payload, caller_id)
+    }
+
+    pub(crate) fn do_update_node_reward_type(
+        &mut self,
+        payload: UpdateNodeRewardTypeDirectlyPayload,
+        caller_id: PrincipalId,
+    ) -> Result<(), String> {
+        let UpdateNodeRewardTypeDirectlyPayload {
+            node_id,
+            node_reward_type,
+        } = payload;
+
+        // Get existing node record and apply the changes
+        let mut node_record = self.get_node_or_panic(node_id);
+
+        // Ensure caller is an actual node operator of the node
+        let node_operator_id = get_node_operator_id_for_node(self, node_id)
+            .map_err(|e| format!("Failed to obtain the node operator ID: {}", e))?;
+        if node_operator_id != caller_id {
+            return Err("The caller does not match this node's node operator id.".to_string());
+        }
+
+        // Ensure the node reward type is one the Node Operator is rewarded for,
+        // as in `do_add_node`
+        if let Some(ref node_reward_type) = node_reward_type {
+            let node_operator_record = get_node_operator_record(self, node_operator_id)?;
+            if !node_operator_record
+                .rewardable_nodes
+                .contains_key(node_reward_type)
+            {
+                return Err(format!(
+                    "{}do_update_node_reward_type_directly: Node reward type '{}' is not among \
+                     the rewardable nodes of the Node Operator",
+                    LOG_PREFIX, node_reward_type
+                ));
+            }
+        }
+
+        node_record.node_reward_type = node_reward_type;
+
+        // Create the mutation
+        let update_node_record = update(
+            make_node_record_key(node_id).as_bytes(),
+            encode_or_panic(&node_record),
+        );
+        let mutations = vec![update_node_record];
+
+        // Check invariants before applying the mutation
+        self.maybe_apply_mutation_internal(mutations);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::test_helpers::{invariant_compliant_registry, prepare_registry_with_nodes},
+        mutations::do_add_node_operator::AddNodeOperatorPayload,
+    };
+    use maplit::btreemap;
+
+    fn registry_with_node() -> (Registry, NodeId, PrincipalId) {
+        let mut registry = invariant_compliant_registry(0);
+        // `prepare_registry_with_nodes` assigns all nodes to this Node Operator.
+        let node_operator_id = PrincipalId::new_user_test_id(999);
+        registry.do_add_node_operator(AddNodeOperatorPayload {
+            node_operator_principal_id: Some(node_operator_id),
+            node_allowance: 0,
+            node_provider_principal_id: Some(PrincipalId::new_user_test_id(998)),
+            dc_id: "dc1".into(),
+            rewardable_nodes: btreemap! {
+                "type1".to_string() => 1,
+                "type3".to_string() => 1,
+            },
+            ipv6: None,
+        });
+        // Add node to registry
+        let (mutate_request, node_ids) = prepare_registry_with_nodes(
+            1, // mutation id
+            1, // node count
+        );
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+        let node_id = node_ids.first().expect("no node ids found").to_owned();
+        (registry, node_id, node_operator_id)
+    }
+
+    #[test]
+    fn should_fail_if_caller_is_not_node_operator() {
+        let (mut registry, node_id, _) = registry_with_node();
+        let payload = UpdateNodeRewardTypeDirectlyPayload {
+            node_id,
+            node_reward_type: Some("type1".to_string()),
+        };
+
+        assert_eq!(
+            registry.do_update_node_reward_type(payload, PrincipalId::new_user_test_id(101)),
+            Err("The caller does not match this node's node operator id.".to_string())
+        );
+        assert_eq!(registry.get_node_or_panic(node_id).node_reward_type, None);
+    }
+
+    #[test]
+    fn should_fail_if_node_reward_type_is_not_rewardable() {
+        let (mut registry, node_id, node_operator_id) = registry_with_node();
+        let payload = UpdateNodeRewardTypeDirectlyPayload {
+            node_id,
+            node_reward_type: Some("type2".to_string()),
+        };
+
+        let err = registry
+            .do_update_node_reward_type(payload, node_operator_id)
+            .unwrap_err();
+        assert!(
+            err.contains("Node reward type 'type2' is not among the rewardable nodes"),
+            "{}",
+            err
+        );
+        assert_eq!(registry.get_node_or_panic(node_id).node_reward_type, None);
+    }
+
+    #[test]
+    fn should_succeed_if_request_is_valid() {
+        let (mut registry, node_id, node_operator_id) = registry_with_node();
+
+        // Assert setting the node reward type to Some() works
+        let node_reward_type = Some("type3".to_string());
+        registry
+            .do_update_node_reward_type(
+                UpdateNodeRewardTypeDirectlyPayload {
+                    node_id,
+                    node_reward_type: node_reward_type.clone(),
+                },
+                node_operator_id,
+            )
+            .unwrap();
+        let node_record = registry.get_node_or_panic(node_id);
+        assert_eq!(node_record.node_reward_type, node_reward_type);
+
+        // Assert setting the node reward type to None also works
+        registry
+            .do_update_node_reward_type(
+                UpdateNodeRewardTypeDirectlyPayload {
+                    node_id,
+                    node_reward_type: None,
+                },
+                node_operator_id,
+            )
+            .unwrap();
+        let node_record = registry.get_node_or_panic(node_id);
+        assert_eq!(node_record.node_reward_type, None);
+    }
+}
@@ -4,3 +4,4 @@ pub mod do_remove_node_directly;
 pub mod do_remove_nodes;
 pub mod do_update_node_domain_directly;
 pub mod do_update_node_ipv4_config_directly;
+pub mod do_update_node_reward_type_directly;
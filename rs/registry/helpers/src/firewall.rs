@@ -152,6 +152,7 @@ mod tests {
                         chip_id: None,
                         public_ipv4_config: None,
                         domain: None,
+                        node_reward_type: None,
                     },
                 )
             })
@@ -2,7 +2,9 @@ use anyhow::{bail, Context, Result};
 use clap::Parser;
 use ic_crypto_utils_threshold_sig_der::threshold_sig_public_key_from_der;
 use ic_registry_client::client::RegistryVersion;
+use ic_registry_transport::pb::v1::RegistryAtomicMutateRequest;
 use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
+use prost::Message;
 use serde_json::Value;
 use std::fmt;
 use std::{collections::HashSet, fs::File, io::BufReader, path::PathBuf};
@@ -138,6 +140,61 @@ pub enum CommandArg {
         #[clap(parse(from_os_str))]
         snapshot_file: PathBuf,
     },
+    CheckInvariants {
+        /// The registry version of the snapshot. (default: latest available
+        /// version.)
+        #[clap(short, long, allow_hyphen_values = true)]
+        version: Option<i64>,
+
+        /// Path to a file with mutations to apply to the snapshot before
+        /// checking the invariants, in the format printed by show-diff. If
+        /// neither this nor --mutate-request-file is provided, the snapshot
+        /// itself is checked.
+        #[clap(short, long, parse(from_os_str))]
+        mutations_file: Option<PathBuf>,
+
+        /// Path to a file with a protobuf-encoded RegistryAtomicMutateRequest
+        /// to apply to the snapshot before checking the invariants (may not be
+        /// specified together with --mutations-file). Requests with
+        /// preconditions are rejected.
+        #[clap(long, parse(from_os_str))]
+        mutate_request_file: Option<PathBuf>,
+
+        /// Path to the local store (may not be specified together with --url).
+        #[clap(parse(from_os_str))]
+        local_store_path: PathBuf,
+    },
+    CanisterCheckInvariants {
+        /// Url to a node hosting the registry canister (may not be specified
+        /// together with --local-store).
+        #[clap(long, parse(try_from_str = url::Url::parse))]
+        url: Url,
+
+        /// Optional path to the threshold public key of the root subnet
+        /// (a.k.a. NNS public key). One way to get this key is via
+        /// "ic-admin --nns-url https://nns.ic0.app  get-subnet-public-key"
+        #[clap(long, parse(from_os_str))]
+        nns_public_key: Option<PathBuf>,
+
+        /// The registry version of the snapshot. (default: latest available
+        /// version.)
+        #[clap(short, long, allow_hyphen_values = true)]
+        version: Option<i64>,
+
+        /// Path to a file with mutations to apply to the snapshot before
+        /// checking the invariants, in the format printed by show-diff. If
+        /// neither this nor --mutate-request-file is provided, the snapshot
+        /// itself is checked.
+        #[clap(short, long, parse(from_os_str))]
+        mutations_file: Option<PathBuf>,
+
+        /// Path to a file with a protobuf-encoded RegistryAtomicMutateRequest
+        /// to apply to the snapshot before checking the invariants (may not be
+        /// specified together with --mutations-file). Requests with
+        /// preconditions are rejected.
+        #[clap(long, parse(from_os_str))]
+        mutate_request_file: Option<PathBuf>,
+    },
 }
 
 impl CliArgs {
@@ -232,6 +289,38 @@ impl CliArgs {
                     snapshot,
                 }
             }
+            CommandArg::CheckInvariants {
+                version,
+                mutations_file,
+                mutate_request_file,
+                local_store_path,
+            } => {
+                let version: VersionSpec = version.into();
+                let source = SourceSpec::LocalStore(Self::is_dir(local_store_path)?);
+                let mutations = Self::read_mutations(mutations_file, mutate_request_file)?;
+
+                Command::CheckInvariants {
+                    registry_spec: RegistrySpec { version, source },
+                    mutations,
+                }
+            }
+            CommandArg::CanisterCheckInvariants {
+                url,
+                nns_public_key,
+                version,
+                mutations_file,
+                mutate_request_file,
+            } => {
+                let version: VersionSpec = version.into();
+                let nns_key_material = get_key_material(nns_public_key)?;
+                let source = SourceSpec::Canister(url, nns_key_material);
+                let mutations = Self::read_mutations(mutations_file, mutate_request_file)?;
+
+                Command::CheckInvariants {
+                    registry_spec: RegistrySpec { version, source },
+                    mutations,
+                }
+            }
         };
         Ok(res)
     }
@@ -277,6 +366,30 @@ impl CliArgs {
         let value: Value = serde_json::from_reader(rdr).map_err(json_error)?;
         Ok(value)
     }
+
+    fn read_mutate_request(p: PathBuf) -> Result<RegistryAtomicMutateRequest> {
+        use ArgError::*;
+        let bytes = std::fs::read(&p).map_err(IoError)?;
+        let request = RegistryAtomicMutateRequest::decode(bytes.as_slice())
+            .map_err(|e| ProtobufError(p.clone(), e))?;
+        if !request.preconditions.is_empty() {
+            bail!(UnsupportedPreconditions(p));
+        }
+        Ok(request)
+    }
+
+    fn read_mutations(
+        mutations_file: Option<PathBuf>,
+        mutate_request_file: Option<PathBuf>,
+    ) -> Result<Option<Mutations>> {
+        let res = match (mutations_file, mutate_request_file) {
+            (Some(_), Some(_)) => bail!(ArgError::ConflictingMutations),
+            (Some(p), None) => Some(Mutations::Diff(Self::read_json_value(p)?)),
+            (None, Some(p)) => Some(Mutations::Request(Self::read_mutate_request(p)?)),
+            (None, None) => None,
+        };
+        Ok(res)
+    }
 }
 
 pub fn universal_projection() -> Vec<String> {
@@ -293,6 +406,18 @@ pub enum ArgError {
 
     #[error("JsonError when reading file `{0:?}`: {1:?}")]
     JsonError(PathBuf, serde_json::Error),
+
+    #[error("ProtobufError when reading file `{0:?}`: {1:?}")]
+    ProtobufError(PathBuf, prost::DecodeError),
+
+    #[error("--mutations-file and --mutate-request-file may not be specified together.")]
+    ConflictingMutations,
+
+    #[error(
+        "The request in `{0:?}` has preconditions, which cannot be checked: snapshots do not \
+         record the version at which each key was last mutated."
+    )]
+    UnsupportedPreconditions(PathBuf),
 }
 
 #[derive(Debug, Clone)]
@@ -328,6 +453,20 @@ pub enum Command {
         snapshot: Value,
         amend: bool,
     },
+    CheckInvariants {
+        registry_spec: RegistrySpec,
+        mutations: Option<Mutations>,
+    },
+}
+
+/// Mutations to check the invariants with.
+#[derive(Debug, Clone)]
+pub enum Mutations {
+    /// Keys mapped to their new values, in the format printed by show-diff.
+    Diff(Value),
+    /// A request as submitted to the `atomic_mutate` method of the registry
+    /// canister.
+    Request(RegistryAtomicMutateRequest),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
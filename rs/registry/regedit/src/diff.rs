@@ -104,7 +104,7 @@ pub fn snapshot_to_version(obj: &Value) -> Result<u64> {
         })
 }
 
-fn is_deleted_marker(value: &Value) -> bool {
+pub(crate) fn is_deleted_marker(value: &Value) -> bool {
     value.as_str().map(|s| s == DELETED_MARKER).unwrap_or(false)
 }
 
@@ -0,0 +1,297 @@
+//! Applies mutations to a snapshot and checks the registry invariants on the
+//! result, without touching the registry itself.
+use crate::{
+    args::Mutations,
+    diff::{self, is_deleted_marker, DiffErr},
+    json,
+    normalization::{self, NormalizedSnapshot},
+    protobuf,
+    snapshot::{Snapshot, SPECIAL_FIELD_PREFIX, VERSION_FIELD},
+};
+use anyhow::{anyhow, Result};
+use ic_registry_transport::{
+    delete,
+    pb::v1::{registry_mutation::Type, RegistryDelta, RegistryMutation, RegistryValue},
+    upsert,
+};
+use registry_canister::{
+    invariants::{check_invariants, RegistrySnapshot},
+    pb::v1::{registry_stable_storage::Version as ReprVersion, RegistryStableStorage},
+    registry::Registry,
+};
+use serde_json::Value;
+use std::{any::Any, collections::BTreeMap, panic};
+
+pub const DIFF_FIELD: &str = "diff";
+pub const VIOLATIONS_FIELD: &str = "violations";
+pub const REJECTION_FIELD: &str = "rejection";
+
+/// Applies the given mutations, if any, to the base snapshot and checks the
+/// invariants that the registry canister enforces on the result.
+///
+/// The mutations are checked the way the registry canister checks them: the
+/// mutation types have to match the keys of the base snapshot, and the result
+/// has to satisfy all invariants. Mutations in the format printed by
+/// `show-diff` are upserts, except for keys marked with `"(deleted)"`, which
+/// are deleted.
+///
+/// Returns an object containing the (normalized) diff between the base
+/// snapshot and the result, all violated invariants of the result, and the
+/// message the registry canister would reject the mutations with, if any.
+pub fn dry_run(base_snapshot: Snapshot, mutations: Option<Mutations>) -> Result<Value> {
+    let base_version = diff::snapshot_to_version(&base_snapshot.0)?;
+    let base_raw = snapshot_to_raw(&base_snapshot)?;
+    let mutations = match mutations {
+        Some(Mutations::Diff(mutations)) => diff_to_mutations(&base_snapshot, mutations)?,
+        Some(Mutations::Request(request)) => request.mutations,
+        None => vec![],
+    };
+
+    let mutation_type_errors = mutation_type_errors(&base_raw, base_version, &mutations);
+    let new_raw = apply_mutations(base_raw, &mutations);
+    let violations = violated_invariants(&new_raw);
+    let rejection = rejection(&mutation_type_errors, &violations);
+    let new_version = if mutations.is_empty() {
+        base_version
+    } else {
+        base_version + 1
+    };
+    let new_snapshot = raw_to_snapshot(&new_raw, new_version);
+    let diff = diff::make_diff(base_snapshot, new_snapshot)?;
+    let (normalized_diff, _) = normalization::normalize(diff.0);
+
+    let mut res = BTreeMap::default();
+    res.insert(DIFF_FIELD.to_string(), normalized_diff.0);
+    res.insert(
+        VIOLATIONS_FIELD.to_string(),
+        json::assert_to_value(violations),
+    );
+    res.insert(
+        REJECTION_FIELD.to_string(),
+        json::assert_to_value(rejection),
+    );
+    Ok(json::assert_to_value(res))
+}
+
+/// Returns the protobuf-encoded values of the snapshot by key.
+fn snapshot_to_raw(snapshot: &Snapshot) -> Result<RegistrySnapshot> {
+    let obj = snapshot
+        .0
+        .as_object()
+        .ok_or_else(|| anyhow!(DiffErr::InvalidJsonValue("Expected an object.".into())))?;
+    Ok(obj
+        .iter()
+        .filter(|(k, _)| !k.starts_with(SPECIAL_FIELD_PREFIX))
+        .map(|(k, v)| {
+            (
+                k.as_bytes().to_vec(),
+                protobuf::value_to_raw_data(k, v.clone()),
+            )
+        })
+        .collect())
+}
+
+fn raw_to_snapshot(raw: &RegistrySnapshot, version: u64) -> Snapshot {
+    let mut res: BTreeMap<String, Value> = raw
+        .iter()
+        .map(|(k, v)| {
+            let k = String::from_utf8_lossy(k).to_string();
+            let v = protobuf::raw_data_to_value(&k, v);
+            (k, v)
+        })
+        .collect();
+    res.insert(VERSION_FIELD.to_string(), json::assert_to_value(version));
+    Snapshot(json::assert_to_value(res))
+}
+
+/// Translates mutations in the format printed by `show-diff` into registry
+/// mutations.
+fn diff_to_mutations(base_snapshot: &Snapshot, mutations: Value) -> Result<Vec<RegistryMutation>> {
+    let (_, inv_map) = normalization::normalize(base_snapshot.0.clone());
+    let mutations = normalization::expand(&inv_map, NormalizedSnapshot(mutations));
+    let mutations = mutations
+        .0
+        .as_object()
+        .ok_or_else(|| DiffErr::InvalidJsonValue("Mutations are not an Object.".into()))?;
+
+    Ok(mutations
+        .iter()
+        .filter(|(k, _)| !k.starts_with(SPECIAL_FIELD_PREFIX))
+        .map(|(k, v)| {
+            if is_deleted_marker(v) {
+                delete(k)
+            } else {
+                upsert(k, protobuf::value_to_raw_data(k, v.clone()))
+            }
+        })
+        .collect())
+}
+
+/// Returns the snapshot that results from applying `mutations`, regardless
+/// of whether the registry canister would accept them.
+fn apply_mutations(
+    mut snapshot: RegistrySnapshot,
+    mutations: &[RegistryMutation],
+) -> RegistrySnapshot {
+    for m in mutations {
+        if m.mutation_type == Type::Delete as i32 {
+            snapshot.remove(&m.key);
+        } else {
+            snapshot.insert(m.key.clone(), m.value.clone());
+        }
+    }
+    snapshot
+}
+
+/// Returns the errors of the registry canister's check that the type of each
+/// mutation matches the base snapshot, e.g. that updated keys exist.
+fn mutation_type_errors(
+    base_snapshot: &RegistrySnapshot,
+    base_version: u64,
+    mutations: &[RegistryMutation],
+) -> Vec<String> {
+    let mut registry = Registry::new();
+    registry.from_serializable_form(RegistryStableStorage {
+        version: ReprVersion::Unspecified as i32,
+        deltas: base_snapshot
+            .iter()
+            .map(|(key, value)| RegistryDelta {
+                key: key.clone(),
+                values: vec![RegistryValue {
+                    value: value.clone(),
+                    version: base_version,
+                    deletion_marker: false,
+                }],
+            })
+            .collect(),
+        changelog: vec![],
+    });
+    registry
+        .verify_mutation_type(mutations)
+        .iter()
+        .map(|e| e.to_string())
+        .collect()
+}
+
+/// Returns the message the registry canister would reject the mutations
+/// with, if any. The registry canister rejects mutations at the first
+/// problem, so only that one is reported.
+fn rejection(mutation_type_errors: &[String], violations: &[String]) -> Option<String> {
+    if !mutation_type_errors.is_empty() {
+        return Some(format!(
+            "Verification of the mutation type failed with the following errors: [{}].",
+            mutation_type_errors.join(", ")
+        ));
+    }
+    violations
+        .first()
+        .map(|violation| format!("Invariant check failed with message: {}", violation))
+}
+
+/// Runs the invariant checks of the registry canister on the given snapshot
+/// and returns the error messages of the violated invariants.
+///
+/// Some checks panic instead of returning an error, which the registry
+/// canister treats as a violation as well. The panic message is reported in
+/// that case, and the remaining checks are skipped.
+fn violated_invariants(snapshot: &RegistrySnapshot) -> Vec<String> {
+    match panic::catch_unwind(|| check_invariants(snapshot)) {
+        Ok(errors) => errors.into_iter().map(|e| e.to_string()).collect(),
+        Err(payload) => vec![format!(
+            "Invariant check panicked: {}",
+            panic_message(payload)
+        )],
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dry_run, DIFF_FIELD, REJECTION_FIELD, VIOLATIONS_FIELD};
+    use crate::{
+        args::{Mutations, SourceSpec, VersionSpec},
+        diff::DELETED_MARKER,
+        json,
+        snapshot::{self, Snapshot},
+        source,
+        tests::run_ic_prep,
+    };
+    use ic_registry_keys::make_subnet_list_record_key;
+    use ic_registry_transport::{delete, pb::v1::RegistryAtomicMutateRequest, update};
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    fn latest_snapshot() -> (TempDir, Snapshot) {
+        let (guard, ic_prep_dir) = run_ic_prep();
+        let cl = source::get_changelog(SourceSpec::LocalStore(
+            ic_prep_dir.registry_local_store_path(),
+        ))
+        .unwrap();
+        let snapshot =
+            snapshot::changelog_to_snapshot(cl, VersionSpec::RelativeToLatest(0)).unwrap();
+        (guard, snapshot)
+    }
+
+    #[test]
+    fn deleting_the_subnet_list_is_reported_as_violation() {
+        let (_guard, base_snapshot) = latest_snapshot();
+        let base_out = dry_run(base_snapshot.clone(), None).unwrap();
+
+        let mut mutations = BTreeMap::new();
+        mutations.insert(
+            make_subnet_list_record_key(),
+            json::assert_to_value(DELETED_MARKER),
+        );
+        let out = dry_run(
+            base_snapshot,
+            Some(Mutations::Diff(json::assert_to_value(mutations))),
+        )
+        .unwrap();
+
+        assert_eq!(
+            out[DIFF_FIELD][make_subnet_list_record_key()],
+            json::assert_to_value(DELETED_MARKER)
+        );
+        let base_violations = base_out[VIOLATIONS_FIELD].as_array().unwrap();
+        let violations = out[VIOLATIONS_FIELD].as_array().unwrap();
+        assert!(
+            violations.iter().any(|v| !base_violations.contains(v)),
+            "{}",
+            out
+        );
+        assert!(out[REJECTION_FIELD].is_string(), "{}", out);
+    }
+
+    #[test]
+    fn mutate_requests_are_checked_like_the_registry_canister_does() {
+        let (_guard, base_snapshot) = latest_snapshot();
+
+        let request = RegistryAtomicMutateRequest {
+            mutations: vec![delete(make_subnet_list_record_key())],
+            preconditions: vec![],
+        };
+        let out = dry_run(base_snapshot.clone(), Some(Mutations::Request(request))).unwrap();
+        assert_eq!(
+            out[DIFF_FIELD][make_subnet_list_record_key()],
+            json::assert_to_value(DELETED_MARKER)
+        );
+        assert!(out[REJECTION_FIELD].is_string(), "{}", out);
+
+        // Updating a key that doesn't exist violates no invariant, but the
+        // precondition of the mutation type.
+        let request = RegistryAtomicMutateRequest {
+            mutations: vec![update("unknown_key", "value")],
+            preconditions: vec![],
+        };
+        let out = dry_run(base_snapshot, Some(Mutations::Request(request))).unwrap();
+        let rejection = out[REJECTION_FIELD].as_str().unwrap();
+        assert!(rejection.contains("Verification of the mutation type failed"));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod args;
 mod diff;
+mod dry_run;
 mod json;
 mod normalization;
 mod projection;
@@ -132,6 +133,13 @@ pub fn execute_command(cmd: Command) -> Result<Value> {
             local_store.store(v, changelog_entry)?;
             diff.0
         }
+        Command::CheckInvariants {
+            registry_spec,
+            mutations,
+        } => {
+            let base_snapshot = registry_spec_to_snapshot(registry_spec)?;
+            dry_run::dry_run(base_snapshot, mutations)?
+        }
     };
     Ok(res)
 }
@@ -0,0 +1,146 @@
+use ic_prep_lib::{
+    internet_computer::{IcConfig, TopologyConfig},
+    node::{NodeConfiguration, NodeIndex},
+    prep_state_directory::IcPrepStateDir,
+    subnet_configuration::{SubnetConfig, SubnetRunningState},
+};
+use ic_registry_provisional_whitelist::ProvisionalWhitelist;
+use ic_registry_subnet_type::SubnetType;
+use ic_registry_transport::{insert, pb::v1::RegistryAtomicMutateRequest, precondition};
+use ic_types::ReplicaVersion;
+use prost::Message;
+use serde_json::Value;
+use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf, process::Command, str::FromStr};
+use tempfile::TempDir;
+
+const NODE_INDEX: NodeIndex = 100;
+const SUBNET_ID: u64 = 0;
+
+/// Bazel passes the path of the binary in `IC_REGEDIT_BIN`, Cargo sets
+/// `CARGO_BIN_EXE_ic-regedit` when building the test.
+fn regedit_bin() -> PathBuf {
+    std::env::var_os("IC_REGEDIT_BIN")
+        .or_else(|| option_env!("CARGO_BIN_EXE_ic-regedit").map(Into::into))
+        .map(PathBuf::from)
+        .expect("missing ic-regedit binary")
+}
+
+fn run_ic_prep() -> (TempDir, IcPrepStateDir) {
+    let mut subnet_nodes: BTreeMap<NodeIndex, NodeConfiguration> = BTreeMap::new();
+    subnet_nodes.insert(
+        NODE_INDEX,
+        NodeConfiguration {
+            xnet_api: SocketAddr::from_str("0.0.0.0:0").unwrap(),
+            public_api: SocketAddr::from_str("0.0.0.0:8080").unwrap(),
+            node_operator_principal_id: None,
+            secret_key_store: None,
+            chip_id: None,
+        },
+    );
+
+    let mut topology_config = TopologyConfig::default();
+    topology_config.insert_subnet(
+        SUBNET_ID,
+        SubnetConfig::new(
+            SUBNET_ID,
+            subnet_nodes,
+            ReplicaVersion::default(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            SubnetType::System,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            vec![],
+            vec![],
+            SubnetRunningState::Active,
+        ),
+    );
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let ic_config = IcConfig::new(
+        /* target_dir= */ temp_dir.path(),
+        topology_config,
+        ReplicaVersion::default(),
+        /* generate_subnet_records= */ true,
+        /* nns_subnet_index= */ Some(0),
+        /* release_package_url= */ None,
+        /* release_package_sha256_hex */ None,
+        Some(ProvisionalWhitelist::All),
+        None,
+        None,
+        /* ssh_readonly_access_to_unassigned_nodes */ vec![],
+        /* guest_launch_measurement_sha256_hex */ None,
+    );
+    ic_config.initialize().unwrap();
+    let path: PathBuf = temp_dir.path().into();
+    (temp_dir, IcPrepStateDir::new(path))
+}
+
+#[test]
+fn check_invariants_prints_only_the_result_to_stdout() {
+    let (guard, ic_prep_dir) = run_ic_prep();
+    let request_file = guard.path().join("request.pb");
+    let request = RegistryAtomicMutateRequest {
+        mutations: vec![insert("a_key_that_does_not_exist", "value")],
+        preconditions: vec![],
+    };
+    std::fs::write(&request_file, request.encode_to_vec()).unwrap();
+
+    let output = Command::new(regedit_bin())
+        .arg("check-invariants")
+        .arg("--mutate-request-file")
+        .arg(&request_file)
+        .arg(ic_prep_dir.registry_local_store_path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{:?}", output);
+    let out: Value = serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+        panic!(
+            "stdout is not JSON ({}): {}",
+            e,
+            String::from_utf8_lossy(&output.stdout)
+        )
+    });
+    assert!(
+        out["diff"]["a_key_that_does_not_exist"].is_string(),
+        "{}",
+        out
+    );
+    assert_eq!(out["rejection"], Value::Null, "{}", out);
+}
+
+#[test]
+fn check_invariants_rejects_requests_with_preconditions() {
+    let dir = tempfile::tempdir().unwrap();
+    let request_file = dir.path().join("request.pb");
+    let request = RegistryAtomicMutateRequest {
+        mutations: vec![insert("a_key_that_does_not_exist", "value")],
+        preconditions: vec![precondition("a_key_that_does_not_exist", 1)],
+    };
+    std::fs::write(&request_file, request.encode_to_vec()).unwrap();
+
+    let output = Command::new(regedit_bin())
+        .arg("check-invariants")
+        .arg("--mutate-request-file")
+        .arg(&request_file)
+        .arg(dir.path())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success(), "{:?}", output);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("has preconditions"),
+        "{:?}",
+        output
+    );
+}
@@ -56,6 +56,10 @@ pub enum SubCommand {
     /// Create a recovery CUP and write it to a file.
     GetRecoveryCup(GetRecoveryCupCmd),
 
+    /// Replay up to the given height and execute read-only queries against
+    /// the resulting state, without creating a checkpoint at that height.
+    QueryAtHeight(QueryAtHeightCmd),
+
     /// Restore from the backup. Deprecated.
     RestoreFromBackup(RestoreFromBackupCmd),
 
@@ -88,6 +92,19 @@ pub struct GetRecoveryCupCmd {
     pub registry_store_sha256: Option<String>,
 }
 
+#[derive(Clone, Parser)]
+pub struct QueryAtHeightCmd {
+    /// Height up to which the blocks are replayed.
+    pub height: u64,
+    /// Path to a JSON file with the list of queries, e.g.
+    /// `[{"canister_id": "ryjl3-tyaaa-aaaaa-aaaba-cai", "method": "symbol", "args": "()"}]`.
+    /// The arguments are in the textual Candid format.
+    pub queries_file: PathBuf,
+    /// The sender of the queries; anonymous if not specified.
+    #[clap(long)]
+    pub sender: Option<PrincipalId>,
+}
+
 #[derive(Clone, Parser)]
 pub struct AddAndBlessReplicaVersionCmd {
     /// The Replica version ID.
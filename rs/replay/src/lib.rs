@@ -17,6 +17,7 @@
 use crate::cmd::{ReplayToolArgs, SubCommand};
 use crate::ingress::*;
 use crate::player::{Player, ReplayResult};
+use crate::query::cmd_query_at_height;
 
 use ic_canister_client::{Agent, Sender};
 use ic_config::{Config, ConfigSource};
@@ -34,6 +35,7 @@ pub mod cmd;
 pub mod ingress;
 mod mocks;
 pub mod player;
+pub mod query;
 mod validator;
 
 /// Replays the past blocks and creates a checkpoint of the latest state.
@@ -126,6 +128,18 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
             return;
         }
 
+        if let Some(SubCommand::QueryAtHeight(cmd)) = subcmd {
+            if target_height.is_some() {
+                panic!("Target height cannot be used with the query-at-height sub-command.");
+            }
+            let _enter_guard = rt.enter();
+            let player = Player::new(cfg, subnet_id)
+                .with_replay_target_height(Some(cmd.height))
+                .without_checkpoint_at_target_height();
+            *res_clone.borrow_mut() = cmd_query_at_height(&player, cmd);
+            return;
+        }
+
         {
             let _enter_guard = rt.enter();
             let player = match (subcmd.as_ref(), target_height) {
@@ -58,8 +58,8 @@ use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::UserQuery,
     time::current_time,
-    CryptoHashOfState, Height, PrincipalId, Randomness, RegistryVersion, ReplicaVersion, SubnetId,
-    Time, UserId,
+    CanisterId, CryptoHashOfState, Height, PrincipalId, Randomness, RegistryVersion,
+    ReplicaVersion, SubnetId, Time, UserId,
 };
 use ic_types::{
     consensus::CatchUpContentProtobufBytes,
@@ -129,6 +129,8 @@ pub struct Player {
     // The target height until which the state will be replayed.
     // None means finalized height.
     replay_target_height: Option<u64>,
+    // Whether a checkpoint is created at the target height.
+    persist_target_height: bool,
 }
 
 impl Player {
@@ -367,6 +369,7 @@ impl Player {
             _async_log_guard,
             tmp_dir: None,
             replay_target_height: None,
+            persist_target_height: true,
         }
     }
 
@@ -376,6 +379,13 @@ impl Player {
         self
     }
 
+    /// Keep the state at the replay target height in memory only, instead of creating a
+    /// checkpoint for it.
+    pub fn without_checkpoint_at_target_height(mut self) -> Self {
+        self.persist_target_height = false;
+        self
+    }
+
     /// In case a consensus pool was supplied, replay past finalized but
     /// un-executed blocks by delivering ingress messages for execution,
     /// and make a full checkpoint of the latest state when they all finish.
@@ -688,6 +698,7 @@ impl Player {
                 self.replica_version.clone(),
                 &self.log,
                 replay_target_height,
+                self.persist_target_height,
                 None,
             ) {
                 Ok(h) => break h,
@@ -845,6 +856,33 @@ impl Player {
         }
     }
 
+    /// Execute a read-only query against the latest state.
+    pub fn query(
+        &self,
+        source: PrincipalId,
+        receiver: CanisterId,
+        method_name: String,
+        method_payload: Vec<u8>,
+        ingress_expiry: Time,
+    ) -> Result<WasmResult, String> {
+        let query = UserQuery {
+            source: UserId::from(source),
+            receiver,
+            method_name,
+            method_payload,
+            ingress_expiry: ingress_expiry.as_nanos_since_unix_epoch(),
+            nonce: None,
+        };
+        self.http_query_handler
+            .query(query, self.state_manager.get_latest_state(), Vec::new())
+            .map_err(|err| format!("Failed run query: {:?}", err))
+    }
+
+    /// Return the height of the latest state known to the state manager.
+    pub fn latest_state_height(&self) -> Height {
+        self.state_manager.latest_state_height()
+    }
+
     /// Return the highest CatchUpPackage
     pub fn get_highest_catch_up_package(&self) -> CatchUpPackage {
         PoolReader::new(self.consensus_pool.as_ref().unwrap()).get_highest_catch_up_package()
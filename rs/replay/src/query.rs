@@ -0,0 +1,168 @@
+//! Read-only queries executed against a replayed state.
+
+use crate::cmd::QueryAtHeightCmd;
+use crate::player::{Player, ReplayResult};
+use candid::IDLArgs;
+use ic_types::{ingress::WasmResult, time::current_time, CanisterId, Height, PrincipalId};
+use serde::Deserialize;
+use std::{path::Path, str::FromStr, time::Duration};
+
+/// A query as specified in the queries file.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+struct QuerySpec {
+    canister_id: String,
+    method: String,
+    #[serde(default = "empty_args")]
+    args: String,
+}
+
+fn empty_args() -> String {
+    "()".to_string()
+}
+
+/// A query with Candid-encoded arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Query {
+    pub canister_id: CanisterId,
+    pub method_name: String,
+    pub method_payload: Vec<u8>,
+}
+
+impl TryFrom<QuerySpec> for Query {
+    type Error = String;
+
+    fn try_from(spec: QuerySpec) -> Result<Self, Self::Error> {
+        let canister_id = CanisterId::from_str(&spec.canister_id)
+            .map_err(|err| format!("Invalid canister id {}: {:?}", spec.canister_id, err))?;
+        let method_payload = candid_parser::parse_idl_args(&spec.args)
+            .map_err(|err| format!("Invalid arguments of {}: {}", spec.method, err))?
+            .to_bytes()
+            .map_err(|err| format!("Couldn't encode arguments of {}: {}", spec.method, err))?;
+        Ok(Query {
+            canister_id,
+            method_name: spec.method,
+            method_payload,
+        })
+    }
+}
+
+fn parse_queries(json: &str) -> Result<Vec<Query>, String> {
+    serde_json::from_str::<Vec<QuerySpec>>(json)
+        .map_err(|err| format!("Invalid queries: {}", err))?
+        .into_iter()
+        .map(Query::try_from)
+        .collect()
+}
+
+/// Read and encode the queries from the given JSON file.
+pub fn load_queries(path: &Path) -> Result<Vec<Query>, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|err| format!("Couldn't read {:?}: {}", path, err))?;
+    parse_queries(&json)
+}
+
+/// Return the textual representation of a query result, decoding Candid replies.
+fn format_result(result: &Result<WasmResult, String>) -> String {
+    match result {
+        Ok(WasmResult::Reply(bytes)) => match IDLArgs::from_bytes(bytes) {
+            Ok(args) => args.to_string(),
+            Err(_) => format!("(non-Candid reply) {}", hex::encode(bytes)),
+        },
+        Ok(WasmResult::Reject(msg)) => format!("Rejected: {}", msg),
+        Err(err) => format!("Error: {}", err),
+    }
+}
+
+/// Replay up to the height given in the command, without creating a checkpoint at that height,
+/// and print the results of the queries against the state at that height.
+pub fn cmd_query_at_height(player: &Player, cmd: &QueryAtHeightCmd) -> ReplayResult {
+    let queries = load_queries(&cmd.queries_file).unwrap_or_else(|err| {
+        println!("{}", err);
+        std::process::exit(1);
+    });
+    let height = Height::from(cmd.height);
+    if player.latest_state_height() > height {
+        println!(
+            "The local state is already at height {}, which is above the target height {}. \
+            Use a data root with an older checkpoint.",
+            player.latest_state_height(),
+            height
+        );
+        std::process::exit(1);
+    }
+
+    let state_params = player.replay(|_, _| Vec::new())?;
+    if player.latest_state_height() != height {
+        println!(
+            "Couldn't replay up to height {}, the latest state is at height {}",
+            height,
+            player.latest_state_height()
+        );
+        std::process::exit(1);
+    }
+
+    let sender = cmd.sender.unwrap_or_else(PrincipalId::new_anonymous);
+    let ingress_expiry = current_time() + Duration::from_secs(60);
+    for query in queries {
+        let result = player.query(
+            sender,
+            query.canister_id,
+            query.method_name.clone(),
+            query.method_payload,
+            ingress_expiry,
+        );
+        println!(
+            "{}.{} at height {}: {}",
+            query.canister_id,
+            query.method_name,
+            height,
+            format_result(&result)
+        );
+    }
+    Ok(state_params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Encode;
+
+    #[test]
+    fn parse_queries_test() {
+        let queries = parse_queries(
+            r#"[
+                {"canister_id": "ryjl3-tyaaa-aaaaa-aaaba-cai", "method": "symbol"},
+                {"canister_id": "rrkah-fqaaa-aaaaa-aaaaq-cai", "method": "get_neuron_info", "args": "(42 : nat64)"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(queries.len(), 2);
+        assert_eq!(
+            queries[0].canister_id,
+            CanisterId::from_str("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap()
+        );
+        assert_eq!(queries[0].method_name, "symbol");
+        assert_eq!(queries[0].method_payload, Encode!().unwrap());
+        assert_eq!(queries[1].method_payload, Encode!(&42u64).unwrap());
+    }
+
+    #[test]
+    fn invalid_queries_are_rejected_test() {
+        assert!(parse_queries(r#"[{"canister_id": "abc", "method": "symbol"}]"#).is_err());
+        assert!(parse_queries(
+            r#"[{"canister_id": "ryjl3-tyaaa-aaaaa-aaaba-cai", "method": "symbol", "args": "(42"}]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn format_result_test() {
+        let reply = Ok(WasmResult::Reply(Encode!(&"ICP".to_string()).unwrap()));
+        assert_eq!(format_result(&reply), "(\"ICP\")");
+        let reject = Ok(WasmResult::Reject("no such method".to_string()));
+        assert_eq!(format_result(&reject), "Rejected: no such method");
+        let raw = Ok(WasmResult::Reply(vec![1, 2]));
+        assert_eq!(format_result(&raw), "(non-Candid reply) 0102");
+    }
+}
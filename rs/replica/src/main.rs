@@ -5,7 +5,7 @@ use ic_config::Config;
 use ic_crypto_sha2::Sha256;
 use ic_crypto_tls_interfaces::TlsHandshake;
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
-use ic_logger::{info, new_replica_logger_from_config};
+use ic_logger::{info, new_replica_logger_from_config, warn};
 use ic_metrics::MetricsRegistry;
 use ic_replica::setup;
 use ic_sys::PAGE_SIZE;
@@ -236,6 +236,15 @@ fn main() -> io::Result<()> {
         let _ = REPLICA_BINARY_HASH.set(hash);
     }
 
+    // The OTLP exporter exports spans in batches from the main runtime.
+    let _tracing_guard = {
+        let _enter = rt_main.enter();
+        ic_tracing::init(&config.tracing, node_id, subnet_id).unwrap_or_else(|err| {
+            warn!(logger, "Failed to set up tracing: {}", err);
+            None
+        })
+    };
+
     let crypto = Arc::new(crypto);
     let _metrics_endpoint = MetricsHttpEndpoint::new(
         rt_http.handle().clone(),
@@ -173,13 +173,18 @@ pub fn construct_ic_stack(
         subnet_config.cycles_account_manager_config,
     ));
 
+    // Keep compiled canister modules across restarts next to the state.
+    let mut hypervisor_config = config.hypervisor.clone();
+    hypervisor_config
+        .compilation_cache_dir
+        .get_or_insert_with(|| config.state_manager.state_root().join("compilation_cache"));
     let execution_services = ExecutionServices::setup_execution(
         log.clone(),
         metrics_registry,
         subnet_id,
         subnet_type,
         subnet_config.scheduler_config,
-        config.hypervisor.clone(),
+        hypervisor_config,
         cycles_account_manager.clone(),
         state_manager.clone(),
         state_manager.get_fd_factory(),
@@ -241,6 +246,8 @@ pub fn construct_ic_stack(
     let BitcoinAdapterClients {
         btc_testnet_client,
         btc_mainnet_client,
+        doge_testnet_client,
+        doge_mainnet_client,
     } = setup_bitcoin_adapter_clients(
         log.clone(),
         metrics_registry,
@@ -252,6 +259,8 @@ pub fn construct_ic_stack(
         metrics_registry,
         btc_mainnet_client,
         btc_testnet_client,
+        doge_mainnet_client,
+        doge_testnet_client,
         subnet_id,
         registry.clone(),
         config.bitcoin_payload_builder_config,
@@ -211,7 +211,7 @@ fn bitcoin_get_successors() {
             let response = call_get_successors(
                 &canister,
                 ic00::BitcoinGetSuccessorsArgs::Initial(ic00::BitcoinGetSuccessorsRequestInitial {
-                    network: ic_btc_interface::Network::Regtest,
+                    network: ic_btc_types_internal::Network::Regtest,
                     anchor: vec![],
                     processed_block_hashes: vec![],
                 }),
@@ -249,7 +249,7 @@ fn bitcoin_get_successors_pagination() {
             let response = call_get_successors(
                 &canister,
                 ic00::BitcoinGetSuccessorsArgs::Initial(ic00::BitcoinGetSuccessorsRequestInitial {
-                    network: ic_btc_interface::Network::Regtest,
+                    network: ic_btc_types_internal::Network::Regtest,
                     anchor: vec![],
                     processed_block_hashes: vec![],
                 }),
@@ -295,7 +295,7 @@ fn bitcoin_get_successors_pagination_invalid_adapter_request() {
             let response = call_get_successors(
                 &canister,
                 ic00::BitcoinGetSuccessorsArgs::Initial(ic00::BitcoinGetSuccessorsRequestInitial {
-                    network: ic_btc_interface::Network::Regtest,
+                    network: ic_btc_types_internal::Network::Regtest,
                     anchor: vec![],
                     processed_block_hashes: vec![],
                 }),
@@ -329,7 +329,7 @@ fn bitcoin_get_successors_reject() {
             let response = call_get_successors(
                 &canister,
                 ic00::BitcoinGetSuccessorsArgs::Initial(ic00::BitcoinGetSuccessorsRequestInitial {
-                    network: ic_btc_interface::Network::Regtest,
+                    network: ic_btc_types_internal::Network::Regtest,
                     anchor: vec![],
                     processed_block_hashes: vec![],
                 }),
@@ -361,7 +361,7 @@ fn bitcoin_send_transaction_internal_valid_request() {
             let response = call_send_transaction_internal(
                 &canister,
                 ic00::BitcoinSendTransactionInternalArgs {
-                    network: ic_btc_interface::Network::Regtest,
+                    network: ic_btc_types_internal::Network::Regtest,
                     transaction: vec![1, 2, 3],
                 },
             );
@@ -390,7 +390,7 @@ fn bitcoin_send_transaction_internal_reject() {
             let response = call_send_transaction_internal(
                 &canister,
                 ic00::BitcoinSendTransactionInternalArgs {
-                    network: ic_btc_interface::Network::Regtest,
+                    network: ic_btc_types_internal::Network::Regtest,
                     transaction: vec![1, 2, 3],
                 },
             );
@@ -443,7 +443,7 @@ fn bitcoin_send_transaction_internal_no_permissions() {
             let response = call_send_transaction_internal(
                 &canister,
                 ic00::BitcoinSendTransactionInternalArgs {
-                    network: ic_btc_interface::Network::Regtest,
+                    network: ic_btc_types_internal::Network::Regtest,
                     transaction: vec![1, 2, 3],
                 },
             );
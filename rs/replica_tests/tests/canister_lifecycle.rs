@@ -6,7 +6,7 @@ use ic_error_types::{ErrorCode, RejectCode};
 use ic_ic00_types::{
     self as ic00, CanisterChange, CanisterIdRecord, CanisterInstallMode,
     CanisterSettingsArgsBuilder, CanisterStatusResultV2, CanisterStatusType, EmptyBlob,
-    InstallCodeArgs, Method, Payload, UpdateSettingsArgs, IC_00,
+    InstallCodeArgs, MemoryMetric, MemoryMetrics, Method, Payload, UpdateSettingsArgs, IC_00,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_replica_tests as utils;
@@ -718,6 +718,14 @@ fn can_get_canister_information() {
                 0u128,
                 0u128,
                 0u128,
+                0u128,
+                MemoryMetrics {
+                    canister_history: MemoryMetric::new(
+                        NumBytes::from((2 * size_of::<CanisterChange>() + 2 * size_of::<PrincipalId>()) as u64),
+                        0u128,
+                    ),
+                    ..MemoryMetrics::default()
+                },
             )
         );
 
@@ -775,6 +783,8 @@ fn can_get_canister_information() {
                     0u128,
                     0u128,
                     0u128,
+                    0u128,
+                    MemoryMetrics::default(),
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
                 2 * BALANCE_EPSILON,
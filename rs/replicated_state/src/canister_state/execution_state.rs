@@ -529,16 +529,34 @@ impl ExecutionState {
 
     /// Returns the memory currently used by the `ExecutionState`.
     pub fn memory_usage(&self) -> NumBytes {
-        // We use 8 bytes per global.
-        let globals_size_bytes = 8 * self.exported_globals.len() as u64;
-        let wasm_binary_size_bytes = self.wasm_binary.binary.len() as u64;
+        self.wasm_memory_usage()
+            + self.stable_memory_usage()
+            + self.globals_memory_usage()
+            + self.wasm_binary_memory_usage()
+            + self.metadata.memory_usage()
+    }
+
+    /// Returns the memory currently used by the Wasm (heap) memory.
+    pub fn wasm_memory_usage(&self) -> NumBytes {
         num_bytes_try_from(self.wasm_memory.size)
             .expect("could not convert from wasm memory number of pages to bytes")
-            + num_bytes_try_from(self.stable_memory.size)
-                .expect("could not convert from stable memory number of pages to bytes")
-            + NumBytes::from(globals_size_bytes)
-            + NumBytes::from(wasm_binary_size_bytes)
-            + self.metadata.memory_usage()
+    }
+
+    /// Returns the memory currently used by the stable memory.
+    pub fn stable_memory_usage(&self) -> NumBytes {
+        num_bytes_try_from(self.stable_memory.size)
+            .expect("could not convert from stable memory number of pages to bytes")
+    }
+
+    /// Returns the memory currently used by the exported globals.
+    pub fn globals_memory_usage(&self) -> NumBytes {
+        // We use 8 bytes per global.
+        NumBytes::from(8 * self.exported_globals.len() as u64)
+    }
+
+    /// Returns the memory currently used by the Wasm binary.
+    pub fn wasm_binary_memory_usage(&self) -> NumBytes {
+        NumBytes::from(self.wasm_binary.binary.len() as u64)
     }
 
     /// Returns the number of global variables in the Wasm module.
@@ -62,6 +62,7 @@ pub enum CyclesUseCase {
     DeletedCanisters,
     NonConsumed,
     BurnedCycles,
+    QueryExecution,
 }
 
 impl CyclesUseCase {
@@ -81,6 +82,7 @@ impl CyclesUseCase {
             Self::DeletedCanisters => "DeletedCanisters",
             Self::NonConsumed => "NonConsumed",
             Self::BurnedCycles => "BurnedCycles",
+            Self::QueryExecution => "QueryExecution",
         }
     }
 }
@@ -100,6 +102,7 @@ impl From<CyclesUseCase> for i32 {
             CyclesUseCase::DeletedCanisters => 10,
             CyclesUseCase::NonConsumed => 11,
             CyclesUseCase::BurnedCycles => 12,
+            CyclesUseCase::QueryExecution => 13,
         }
     }
 }
@@ -119,6 +122,7 @@ impl From<i32> for CyclesUseCase {
             10 => Self::DeletedCanisters,
             11 => Self::NonConsumed,
             12 => Self::BurnedCycles,
+            13 => Self::QueryExecution,
             _ => panic!("Unsupported value"),
         }
     }
@@ -1341,7 +1345,8 @@ impl SystemState {
             | CyclesUseCase::HTTPOutcalls
             | CyclesUseCase::DeletedCanisters
             | CyclesUseCase::NonConsumed
-            | CyclesUseCase::BurnedCycles => requested_amount,
+            | CyclesUseCase::BurnedCycles
+            | CyclesUseCase::QueryExecution => requested_amount,
         };
         self.cycles_balance -= remaining_amount;
         self.observe_consumed_cycles_with_use_case(
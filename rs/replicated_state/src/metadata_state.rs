@@ -446,7 +446,8 @@ impl SubnetMetrics {
                 | CyclesUseCase::RequestAndResponseTransmission
                 | CyclesUseCase::Uninstall
                 | CyclesUseCase::CanisterCreation
-                | CyclesUseCase::BurnedCycles => total += *cycles,
+                | CyclesUseCase::BurnedCycles
+                | CyclesUseCase::QueryExecution => total += *cycles,
             }
         }
 
@@ -20,7 +20,7 @@ use ic_test_utilities::{
 };
 use ic_types::{
     batch::BlockmakerMetrics,
-    canister_http::{CanisterHttpMethod, CanisterHttpRequestContext},
+    canister_http::{CanisterHttpMethod, CanisterHttpRequestContext, Replication},
     ingress::WasmResult,
     messages::{CallbackId, CanisterCall, Payload},
     ExecutionRound,
@@ -696,6 +696,7 @@ fn subnet_call_contexts_deserialization() {
         http_method: CanisterHttpMethod::GET,
         transform: Some(transform.clone()),
         time: mock_time(),
+        replication: Replication::FullyReplicated,
     };
     subnet_call_context_manager.push_context(SubnetCallContext::CanisterHttpRequest(
         canister_http_request,
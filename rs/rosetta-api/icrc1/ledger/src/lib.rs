@@ -1,9 +1,11 @@
 pub mod cdk_runtime;
+pub mod stable_memory;
 
 #[cfg(test)]
 mod tests;
 
 use crate::cdk_runtime::CdkRuntime;
+use crate::stable_memory::{StableAllowancesData, StableBalances, StableBlockData};
 use candid::{
     types::number::{Int, Nat},
     CandidType, Principal,
@@ -14,13 +16,13 @@ use ic_icrc1::{Block, LedgerBalances, Transaction};
 pub use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_canister_core::{
     archive::ArchiveCanisterWasm,
-    blockchain::Blockchain,
+    blockchain::{BlockData, Blockchain},
     ledger::{apply_transaction, block_locations, LedgerContext, LedgerData, TransactionInfo},
     range_utils,
 };
 use ic_ledger_core::{
-    approvals::AllowanceTable,
-    balances::Balances,
+    approvals::{AllowanceTable, AllowancesData, HeapAllowancesData},
+    balances::{Balances, BalancesStore},
     block::{BlockIndex, BlockType, EncodedBlock, FeeCollector},
     timestamp::TimeStamp,
     tokens::TokensType,
@@ -270,13 +272,32 @@ pub enum LedgerArgument {
     Upgrade(Option<UpgradeArgs>),
 }
 
+type LegacyAllowanceTable<Tokens> =
+    AllowanceTable<HeapAllowancesData<ApprovalKey, Account, Tokens>>;
+
+/// The ledger state.
+///
+/// Balances, allowances and unarchived blocks are kept in stable memory (see
+/// [stable_memory]), the remaining fields are encoded on upgrade.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(bound = "")]
 pub struct Ledger<Tokens: TokensType> {
-    balances: LedgerBalances<Tokens>,
-    #[serde(default)]
-    approvals: AllowanceTable<ApprovalKey, Account, Tokens>,
-    blockchain: Blockchain<CdkRuntime, Icrc1ArchiveWasm>,
+    // The heap data structures of the ledger versions that did not use stable
+    // structures. They are only populated when decoding a legacy state and are
+    // moved to stable memory by [Ledger::migrate_to_stable_structures].
+    #[serde(rename = "balances", default, skip_serializing)]
+    legacy_balances: Option<LedgerBalances<Tokens>>,
+    #[serde(rename = "approvals", default, skip_serializing)]
+    legacy_approvals: Option<LegacyAllowanceTable<Tokens>>,
+    #[serde(rename = "blockchain", default, skip_serializing)]
+    legacy_blockchain: Option<Blockchain<CdkRuntime, Icrc1ArchiveWasm>>,
+
+    #[serde(rename = "stable_balances", default)]
+    balances: Balances<StableBalances<Tokens>>,
+    #[serde(rename = "stable_approvals", default)]
+    approvals: AllowanceTable<StableAllowancesData<Tokens>>,
+    #[serde(rename = "stable_blockchain", default)]
+    blockchain: Blockchain<CdkRuntime, Icrc1ArchiveWasm, StableBlockData>,
 
     minting_account: Account,
     fee_collector: Option<FeeCollector<Account>>,
@@ -356,7 +377,10 @@ impl<Tokens: TokensType> Ledger<Tokens> {
         now: TimeStamp,
     ) -> Self {
         let mut ledger = Self {
-            balances: LedgerBalances::default(),
+            legacy_balances: None,
+            legacy_approvals: None,
+            legacy_blockchain: None,
+            balances: Balances::default(),
             approvals: Default::default(),
             blockchain: Blockchain::new_with_archive(archive_options),
             transactions_by_hash: BTreeMap::new(),
@@ -406,6 +430,44 @@ impl<Tokens: TokensType> Ledger<Tokens> {
 
         ledger
     }
+
+    /// Moves the balances, allowances and unarchived blocks of a legacy state
+    /// into stable memory.
+    pub fn migrate_to_stable_structures(&mut self) {
+        if let Some(legacy_balances) = self.legacy_balances.take() {
+            for (account, amount) in legacy_balances.store {
+                self.balances
+                    .store
+                    .update(account, |_| {
+                        Ok::<_, std::convert::Infallible>(amount.clone())
+                    })
+                    .unwrap();
+            }
+            self.balances.token_pool = legacy_balances.token_pool;
+        }
+
+        if let Some(legacy_approvals) = self.legacy_approvals.take() {
+            let data = self.approvals.allowances_data_mut();
+            for (key, allowance) in legacy_approvals.allowances_data().iter() {
+                let (account, spender): (Account, Account) = key.clone().into();
+                if let Some(expires_at) = allowance.expires_at {
+                    data.insert_expiry(expires_at, &account, &spender);
+                }
+                data.insert_arrival(allowance.arrived_at, &account, &spender);
+                data.set_allowance(&account, &spender, allowance.clone());
+            }
+        }
+
+        if let Some(legacy_blockchain) = self.legacy_blockchain.take() {
+            for block in legacy_blockchain.blocks {
+                self.blockchain.blocks.add_block(block);
+            }
+            self.blockchain.last_hash = legacy_blockchain.last_hash;
+            self.blockchain.last_timestamp = legacy_blockchain.last_timestamp;
+            self.blockchain.archive = legacy_blockchain.archive;
+            self.blockchain.num_archived_blocks = legacy_blockchain.num_archived_blocks;
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
@@ -425,8 +487,8 @@ impl From<ApprovalKey> for (Account, Account) {
 
 impl<Tokens: TokensType> LedgerContext for Ledger<Tokens> {
     type AccountId = Account;
-    type Approvals = AllowanceTable<ApprovalKey, Account, Tokens>;
-    type BalancesStore = BTreeMap<Self::AccountId, Tokens>;
+    type Approvals = AllowanceTable<StableAllowancesData<Tokens>>;
+    type BalancesStore = StableBalances<Tokens>;
     type Tokens = Tokens;
 
     fn balances(&self) -> &Balances<Self::BalancesStore> {
@@ -453,6 +515,7 @@ impl<Tokens: TokensType> LedgerContext for Ledger<Tokens> {
 impl<Tokens: TokensType> LedgerData for Ledger<Tokens> {
     type Runtime = CdkRuntime;
     type ArchiveWasm = Icrc1ArchiveWasm;
+    type BlockData = StableBlockData;
     type Transaction = Transaction<Tokens>;
     type Block = Block<Tokens>;
 
@@ -484,11 +547,13 @@ impl<Tokens: TokensType> LedgerData for Ledger<Tokens> {
         &self.token_symbol
     }
 
-    fn blockchain(&self) -> &Blockchain<Self::Runtime, Self::ArchiveWasm> {
+    fn blockchain(&self) -> &Blockchain<Self::Runtime, Self::ArchiveWasm, Self::BlockData> {
         &self.blockchain
     }
 
-    fn blockchain_mut(&mut self) -> &mut Blockchain<Self::Runtime, Self::ArchiveWasm> {
+    fn blockchain_mut(
+        &mut self,
+    ) -> &mut Blockchain<Self::Runtime, Self::ArchiveWasm, Self::BlockData> {
         &mut self.blockchain
     }
 
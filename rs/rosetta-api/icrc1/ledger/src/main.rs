@@ -2,17 +2,18 @@ use candid::candid_method;
 use candid::types::number::Nat;
 use ic_canister_log::{declare_log_buffer, export};
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
-use ic_cdk::api::stable::{StableReader, StableWriter};
+use ic_cdk::api::stable::StableReader;
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use ic_icrc1::{
     endpoints::{convert_transfer_error, StandardRecord},
     Operation, Transaction,
 };
-use ic_icrc1_ledger::{Ledger, LedgerArgument};
+use ic_icrc1_ledger::{stable_memory, Ledger, LedgerArgument};
 use ic_ledger_canister_core::ledger::{
     apply_transaction, archive_blocks, LedgerAccess, LedgerContext, LedgerData,
     TransferError as CoreTransferError,
 };
+use ic_ledger_core::balances::InspectableBalancesStore;
 use ic_ledger_core::tokens::Zero;
 use ic_ledger_core::{approvals::Approvals, timestamp::TimeStamp};
 use icrc_ledger_types::icrc1::transfer::Memo;
@@ -94,18 +95,35 @@ fn init(args: LedgerArgument) {
 
 #[pre_upgrade]
 fn pre_upgrade() {
-    Access::with_ledger(|ledger| ciborium::ser::into_writer(ledger, StableWriter::default()))
+    Access::with_ledger(stable_memory::write_upgrades_memory)
         .expect("failed to encode ledger state");
 }
 
+/// Returns true if the stable memory contains the ledger state encoded by
+/// ledger versions that kept all the data on the heap.
+fn is_legacy_stable_memory_layout() -> bool {
+    if ic_cdk::api::stable::stable64_size() == 0 {
+        return false;
+    }
+    let mut magic = [0u8; 3];
+    ic_cdk::api::stable::stable64_read(0, &mut magic);
+    &magic != b"MGR"
+}
+
 #[post_upgrade]
 fn post_upgrade(args: Option<LedgerArgument>) {
-    LEDGER.with(|cell| {
-        *cell.borrow_mut() = Some(
-            ciborium::de::from_reader(StableReader::default())
-                .expect("failed to decode ledger state"),
-        );
-    });
+    // The legacy state must be decoded before the memory manager is
+    // initialized because the memory manager overwrites the beginning of the
+    // stable memory.
+    let ledger = if is_legacy_stable_memory_layout() {
+        let mut ledger: Ledger<Tokens> = ciborium::de::from_reader(StableReader::default())
+            .expect("failed to decode legacy ledger state");
+        ledger.migrate_to_stable_structures();
+        ledger
+    } else {
+        stable_memory::read_upgrades_memory().expect("failed to decode ledger state")
+    };
+    LEDGER.with(|cell| *cell.borrow_mut() = Some(ledger));
 
     if let Some(args) = args {
         match args {
@@ -153,8 +171,8 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
         )?;
         w.encode_gauge(
             "ledger_transactions",
-            ledger.blockchain().blocks.len() as f64,
-            "Total number of transactions stored in the main memory.",
+            ledger.blockchain().num_unarchived_blocks() as f64,
+            "Total number of transactions stored in the ledger memory.",
         )?;
         w.encode_gauge(
             "ledger_archived_transactions",
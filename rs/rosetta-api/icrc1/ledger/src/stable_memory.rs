@@ -0,0 +1,532 @@
+//! Ledger data structures stored in stable memory.
+//!
+//! Balances, allowances and the blocks that have not been archived yet live in
+//! stable structures, so the ledger does not need to serialize them on upgrade.
+//! The rest of the ledger state is small and is encoded into the upgrades memory
+//! in `pre_upgrade`.
+//!
+//! NOTE: the structures are thread-local, so there can be only one
+//! [crate::Ledger] instance per thread.
+
+use candid::Nat;
+use ic_ledger_canister_core::blockchain::BlockData;
+use ic_ledger_core::approvals::{Allowance, AllowancesData};
+use ic_ledger_core::balances::{BalancesStore, InspectableBalancesStore};
+use ic_ledger_core::block::EncodedBlock;
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_ledger_core::tokens::{TokensType, Zero};
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::storable::Blob;
+use ic_stable_structures::{
+    BoundedStorable, DefaultMemoryImpl, Memory, StableBTreeMap, StableLog, Storable,
+};
+use icrc_ledger_types::icrc1::account::Account;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::ops::Bound::{Excluded, Unbounded};
+use std::ops::Range;
+
+const UPGRADES_MEMORY_ID: MemoryId = MemoryId::new(0);
+const BALANCES_MEMORY_ID: MemoryId = MemoryId::new(1);
+const ALLOWANCES_MEMORY_ID: MemoryId = MemoryId::new(2);
+const ALLOWANCES_EXPIRATIONS_MEMORY_ID: MemoryId = MemoryId::new(3);
+const ALLOWANCES_ARRIVALS_MEMORY_ID: MemoryId = MemoryId::new(4);
+const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(5);
+const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(6);
+
+/// The maximum length of the LEB128 encoding of a 256-bit token amount.
+const MAX_TOKENS_BYTES: usize = 37;
+
+const WASM_PAGE_SIZE: u64 = 65536;
+
+/// The size of the buffers used to encode and decode the upgrades memory.
+const UPGRADES_BUFFER_SIZE: usize = 1024 * 1024;
+
+type VM = VirtualMemory<DefaultMemoryImpl>;
+
+// An account represented as principal of type Blob<29> and the effective subaccount.
+type AccountKey = (Blob<29>, [u8; 32]);
+type AccountSpenderKey = (AccountKey, AccountKey);
+// Token amounts are stored as LEB128-encoded naturals so that the same
+// structures can hold both 64-bit and 256-bit tokens.
+type StoredTokens = Blob<MAX_TOKENS_BYTES>;
+
+type BalancesMap = StableBTreeMap<AccountKey, StoredTokens, VM>;
+type AllowancesMap = StableBTreeMap<AccountSpenderKey, StoredAllowance, VM>;
+type AllowancesQueue = StableBTreeMap<(u64, AccountSpenderKey), (), VM>;
+type BlockLog = StableLog<Vec<u8>, VM, VM>;
+
+thread_local! {
+    /// Static memory manager to manage the memory available for stable structures.
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+
+    /// Account balances; accounts with zero balance are not stored.
+    static BALANCES: RefCell<BalancesMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(BalancesMap::init(memory_manager.get(BALANCES_MEMORY_ID)))
+    });
+
+    /// Allowances indexed by (account, spender).
+    static ALLOWANCES: RefCell<AllowancesMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AllowancesMap::init(memory_manager.get(ALLOWANCES_MEMORY_ID)))
+    });
+
+    /// Allowances with an expiration date ordered by the expiration date.
+    static ALLOWANCES_EXPIRATIONS: RefCell<AllowancesQueue> = with_memory_manager(|memory_manager| {
+        RefCell::new(AllowancesQueue::init(memory_manager.get(ALLOWANCES_EXPIRATIONS_MEMORY_ID)))
+    });
+
+    /// Allowances ordered by the time they were last set.
+    static ALLOWANCES_ARRIVALS: RefCell<AllowancesQueue> = with_memory_manager(|memory_manager| {
+        RefCell::new(AllowancesQueue::init(memory_manager.get(ALLOWANCES_ARRIVALS_MEMORY_ID)))
+    });
+
+    /// Encoded blocks that have not been archived yet.
+    static BLOCKS: RefCell<BlockLog> = with_memory_manager(|memory_manager| {
+        RefCell::new(BlockLog::init(memory_manager.get(BLOCK_LOG_INDEX_MEMORY_ID), memory_manager.get(BLOCK_LOG_DATA_MEMORY_ID))
+            .expect("failed to initialize stable log"))
+    });
+}
+
+/// A helper function to access the memory manager.
+fn with_memory_manager<R>(f: impl FnOnce(&MemoryManager<DefaultMemoryImpl>) -> R) -> R {
+    MEMORY_MANAGER.with(|cell| f(&cell.borrow()))
+}
+
+fn account_key(account: &Account) -> AccountKey {
+    let owner = Blob::try_from(account.owner.as_slice()).expect("bug: principal too long");
+    (owner, *account.effective_subaccount())
+}
+
+fn account_from_key((owner, subaccount): &AccountKey) -> Account {
+    Account {
+        owner: candid::Principal::from_slice(owner.as_slice()),
+        subaccount: if subaccount == &[0; 32] {
+            None
+        } else {
+            Some(*subaccount)
+        },
+    }
+}
+
+fn encode_tokens<Tokens: TokensType>(tokens: &Tokens) -> Vec<u8> {
+    let mut buf = vec![];
+    let nat: Nat = tokens.clone().into();
+    nat.encode(&mut buf).expect("bug: failed to encode tokens");
+    buf
+}
+
+fn decode_tokens<Tokens: TokensType>(bytes: &[u8]) -> Tokens {
+    let nat = Nat::decode(&mut &bytes[..])
+        .unwrap_or_else(|e| panic!("bug: invalid tokens encoding {:?}: {}", bytes, e));
+    Tokens::try_from(nat).unwrap_or_else(|e| panic!("bug: invalid token amount: {}", e))
+}
+
+fn stored_tokens<Tokens: TokensType>(tokens: &Tokens) -> StoredTokens {
+    StoredTokens::try_from(&encode_tokens(tokens)[..]).expect("bug: token amount too large")
+}
+
+/// The balances store backed by a stable B-tree map.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(bound = "")]
+pub struct StableBalances<Tokens> {
+    #[serde(skip)]
+    _marker: PhantomData<Tokens>,
+}
+
+impl<Tokens> Default for StableBalances<Tokens> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Tokens: TokensType> BalancesStore for StableBalances<Tokens> {
+    type AccountId = Account;
+    type Tokens = Tokens;
+
+    fn get_balance(&self, k: &Account) -> Option<Tokens> {
+        BALANCES
+            .with(|cell| cell.borrow().get(&account_key(k)))
+            .map(|amount| decode_tokens(amount.as_slice()))
+    }
+
+    fn update<F, E>(&mut self, k: Account, mut f: F) -> Result<Tokens, E>
+    where
+        F: FnMut(Option<&Tokens>) -> Result<Tokens, E>,
+    {
+        let prev = self.get_balance(&k);
+        let new_v = f(prev.as_ref())?;
+        let key = account_key(&k);
+        BALANCES.with(|cell| {
+            let mut balances = cell.borrow_mut();
+            if new_v.is_zero() {
+                balances.remove(&key);
+            } else {
+                balances.insert(key, stored_tokens(&new_v));
+            }
+        });
+        Ok(new_v)
+    }
+}
+
+impl<Tokens: TokensType> InspectableBalancesStore for StableBalances<Tokens> {
+    fn iter(&self) -> Box<dyn Iterator<Item = (Account, Tokens)> + '_> {
+        // The map cannot be borrowed for the lifetime of the iterator, so every
+        // step looks up the entry following the previously returned key.
+        let mut last_key: Option<AccountKey> = None;
+        Box::new(std::iter::from_fn(move || {
+            let (key, amount) = BALANCES.with(|cell| {
+                let balances = cell.borrow();
+                let mut range = match &last_key {
+                    Some(key) => balances.range((Excluded(key.clone()), Unbounded)),
+                    None => balances.range(..),
+                };
+                range.next()
+            })?;
+            let entry = (account_from_key(&key), decode_tokens(amount.as_slice()));
+            last_key = Some(key);
+            Some(entry)
+        }))
+    }
+
+    fn len(&self) -> usize {
+        BALANCES.with(|cell| cell.borrow().len()) as usize
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct StoredAllowance {
+    amount: Vec<u8>,
+    expires_at: Option<u64>,
+    arrived_at: u64,
+}
+
+impl Storable for StoredAllowance {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut buf = Vec::with_capacity(17 + self.amount.len());
+        buf.extend_from_slice(&self.arrived_at.to_le_bytes());
+        match self.expires_at {
+            Some(expires_at) => {
+                buf.push(1);
+                buf.extend_from_slice(&expires_at.to_le_bytes());
+            }
+            None => {
+                buf.push(0);
+                buf.extend_from_slice(&[0; 8]);
+            }
+        }
+        buf.extend_from_slice(&self.amount);
+        Cow::Owned(buf)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let arrived_at = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let expires_at = match bytes[8] {
+            0 => None,
+            _ => Some(u64::from_le_bytes(bytes[9..17].try_into().unwrap())),
+        };
+        Self {
+            amount: bytes[17..].to_vec(),
+            expires_at,
+            arrived_at,
+        }
+    }
+}
+
+impl BoundedStorable for StoredAllowance {
+    const MAX_SIZE: u32 = 17 + MAX_TOKENS_BYTES as u32;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+/// The allowances data backed by stable B-tree maps.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "")]
+pub struct StableAllowancesData<Tokens> {
+    #[serde(skip)]
+    _marker: PhantomData<Tokens>,
+}
+
+impl<Tokens> Default for StableAllowancesData<Tokens> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+fn account_spender_key(account: &Account, spender: &Account) -> AccountSpenderKey {
+    (account_key(account), account_key(spender))
+}
+
+impl<Tokens: TokensType> AllowancesData for StableAllowancesData<Tokens> {
+    type AccountId = Account;
+    type Tokens = Tokens;
+
+    fn get_allowance(&self, account: &Account, spender: &Account) -> Option<Allowance<Tokens>> {
+        let allowance =
+            ALLOWANCES.with(|cell| cell.borrow().get(&account_spender_key(account, spender)))?;
+        Some(Allowance {
+            amount: decode_tokens(&allowance.amount),
+            expires_at: allowance
+                .expires_at
+                .map(TimeStamp::from_nanos_since_unix_epoch),
+            arrived_at: TimeStamp::from_nanos_since_unix_epoch(allowance.arrived_at),
+        })
+    }
+
+    fn set_allowance(
+        &mut self,
+        account: &Account,
+        spender: &Account,
+        allowance: Allowance<Tokens>,
+    ) {
+        let stored = StoredAllowance {
+            amount: encode_tokens(&allowance.amount),
+            expires_at: allowance
+                .expires_at
+                .map(|ts| ts.as_nanos_since_unix_epoch()),
+            arrived_at: allowance.arrived_at.as_nanos_since_unix_epoch(),
+        };
+        ALLOWANCES.with(|cell| {
+            cell.borrow_mut()
+                .insert(account_spender_key(account, spender), stored)
+        });
+    }
+
+    fn remove_allowance(&mut self, account: &Account, spender: &Account) {
+        ALLOWANCES.with(|cell| {
+            cell.borrow_mut()
+                .remove(&account_spender_key(account, spender))
+        });
+    }
+
+    fn insert_expiry(&mut self, timestamp: TimeStamp, account: &Account, spender: &Account) {
+        let key = (
+            timestamp.as_nanos_since_unix_epoch(),
+            account_spender_key(account, spender),
+        );
+        ALLOWANCES_EXPIRATIONS.with(|cell| cell.borrow_mut().insert(key, ()));
+    }
+
+    fn remove_expiry(&mut self, timestamp: TimeStamp, account: &Account, spender: &Account) {
+        let key = (
+            timestamp.as_nanos_since_unix_epoch(),
+            account_spender_key(account, spender),
+        );
+        ALLOWANCES_EXPIRATIONS.with(|cell| cell.borrow_mut().remove(&key));
+    }
+
+    fn insert_arrival(&mut self, timestamp: TimeStamp, account: &Account, spender: &Account) {
+        let key = (
+            timestamp.as_nanos_since_unix_epoch(),
+            account_spender_key(account, spender),
+        );
+        ALLOWANCES_ARRIVALS.with(|cell| cell.borrow_mut().insert(key, ()));
+    }
+
+    fn remove_arrival(&mut self, timestamp: TimeStamp, account: &Account, spender: &Account) {
+        let key = (
+            timestamp.as_nanos_since_unix_epoch(),
+            account_spender_key(account, spender),
+        );
+        ALLOWANCES_ARRIVALS.with(|cell| cell.borrow_mut().remove(&key));
+    }
+
+    fn first_expiry(&self) -> Option<(TimeStamp, Account, Account)> {
+        let (timestamp, (account, spender)) = ALLOWANCES_EXPIRATIONS
+            .with(|cell| cell.borrow().first_key_value())?
+            .0;
+        Some((
+            TimeStamp::from_nanos_since_unix_epoch(timestamp),
+            account_from_key(&account),
+            account_from_key(&spender),
+        ))
+    }
+
+    fn oldest_arrivals(&self, n: usize) -> Vec<(Account, Account)> {
+        ALLOWANCES_ARRIVALS.with(|cell| {
+            cell.borrow()
+                .iter()
+                .take(n)
+                .map(|((_arrival, (account, spender)), ())| {
+                    (account_from_key(&account), account_from_key(&spender))
+                })
+                .collect()
+        })
+    }
+
+    fn len_allowances(&self) -> usize {
+        ALLOWANCES.with(|cell| cell.borrow().len()) as usize
+    }
+
+    fn len_expirations(&self) -> usize {
+        ALLOWANCES_EXPIRATIONS.with(|cell| cell.borrow().len()) as usize
+    }
+
+    fn len_arrivals(&self) -> usize {
+        ALLOWANCES_ARRIVALS.with(|cell| cell.borrow().len()) as usize
+    }
+}
+
+/// The unarchived blocks stored in a stable log.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct StableBlockData {}
+
+impl BlockData for StableBlockData {
+    fn add_block(&mut self, block: EncodedBlock) {
+        BLOCKS
+            .with(|cell| cell.borrow_mut().append(&block.into_vec()))
+            .expect("failed to append a block to the stable log");
+    }
+
+    fn get_blocks(&self, range: Range<u64>) -> Vec<EncodedBlock> {
+        BLOCKS.with(|cell| {
+            let blocks = cell.borrow();
+            range
+                .map(|index| {
+                    blocks
+                        .get(index)
+                        .map(EncodedBlock::from)
+                        .unwrap_or_else(|| panic!("bug: block {} is not in the stable log", index))
+                })
+                .collect()
+        })
+    }
+
+    fn get_block(&self, index: u64) -> Option<EncodedBlock> {
+        BLOCKS.with(|cell| cell.borrow().get(index).map(EncodedBlock::from))
+    }
+
+    /// The stable log is append-only, so removing a prefix rewrites the log
+    /// with the remaining blocks. The number of unarchived blocks is bounded by
+    /// the archiving trigger threshold, and archiving is rare compared to
+    /// appending, so the copy is cheap.
+    fn remove_blocks(&mut self, num_blocks: u64) {
+        let len = self.len();
+        if num_blocks > len {
+            panic!(
+                "Asked to remove more blocks than present. Present: {}, to remove: {}",
+                len, num_blocks
+            );
+        }
+        let remaining = self.get_blocks(num_blocks..len);
+        BLOCKS.with(|cell| {
+            let mut blocks = cell.borrow_mut();
+            *blocks = with_memory_manager(|memory_manager| {
+                BlockLog::new(
+                    memory_manager.get(BLOCK_LOG_INDEX_MEMORY_ID),
+                    memory_manager.get(BLOCK_LOG_DATA_MEMORY_ID),
+                )
+            });
+            for block in remaining {
+                blocks
+                    .append(&block.into_vec())
+                    .expect("failed to append a block to the stable log");
+            }
+        });
+    }
+
+    fn len(&self) -> u64 {
+        BLOCKS.with(|cell| cell.borrow().len())
+    }
+
+    fn is_empty(&self) -> bool {
+        BLOCKS.with(|cell| cell.borrow().is_empty())
+    }
+
+    fn last_block(&self) -> Option<EncodedBlock> {
+        match self.len() {
+            0 => None,
+            len => self.get_block(len - 1),
+        }
+    }
+}
+
+/// Writes to the upgrades memory, growing it as necessary.
+struct UpgradesWriter {
+    memory: VM,
+    offset: u64,
+}
+
+impl Write for UpgradesWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let required_pages = (self.offset + buf.len() as u64 + WASM_PAGE_SIZE - 1) / WASM_PAGE_SIZE;
+        let current_pages = self.memory.size();
+        if required_pages > current_pages && self.memory.grow(required_pages - current_pages) == -1
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::OutOfMemory,
+                "failed to grow the upgrades memory",
+            ));
+        }
+        self.memory.write(self.offset, buf);
+        self.offset += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads from the upgrades memory up to the specified offset.
+struct UpgradesReader {
+    memory: VM,
+    offset: u64,
+    end: u64,
+}
+
+impl Read for UpgradesReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = (buf.len() as u64).min(self.end - self.offset) as usize;
+        self.memory.read(self.offset, &mut buf[..n]);
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+/// Encodes the state into the upgrades memory.
+///
+/// The memory starts with the length of the encoded state as a little-endian
+/// u64 followed by the CBOR encoding of the state.
+pub fn write_upgrades_memory<T: Serialize>(state: &T) -> Result<(), String> {
+    let memory = with_memory_manager(|memory_manager| memory_manager.get(UPGRADES_MEMORY_ID));
+    let mut writer = std::io::BufWriter::with_capacity(
+        UPGRADES_BUFFER_SIZE,
+        UpgradesWriter {
+            memory: memory.clone(),
+            offset: 8,
+        },
+    );
+    ciborium::ser::into_writer(state, &mut writer)
+        .map_err(|err| format!("failed to encode the state: {}", err))?;
+    let writer = writer
+        .into_inner()
+        .map_err(|err| format!("failed to write the state: {}", err))?;
+    memory.write(0, &(writer.offset - 8).to_le_bytes());
+    Ok(())
+}
+
+/// Decodes the state written by [write_upgrades_memory].
+pub fn read_upgrades_memory<T: DeserializeOwned>() -> Result<T, String> {
+    let memory = with_memory_manager(|memory_manager| memory_manager.get(UPGRADES_MEMORY_ID));
+    if memory.size() == 0 {
+        return Err("the upgrades memory is empty".to_string());
+    }
+    let mut len_bytes = [0; 8];
+    memory.read(0, &mut len_bytes);
+    let reader = std::io::BufReader::with_capacity(
+        UPGRADES_BUFFER_SIZE,
+        UpgradesReader {
+            memory,
+            offset: 8,
+            end: 8 + u64::from_le_bytes(len_bytes),
+        },
+    );
+    ciborium::de::from_reader(reader).map_err(|err| format!("failed to decode the state: {}", err))
+}
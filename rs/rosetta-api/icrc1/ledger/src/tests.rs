@@ -2,8 +2,10 @@ use crate::{InitArgs, Ledger};
 use ic_base_types::PrincipalId;
 use ic_icrc1::{Operation, Transaction};
 use ic_ledger_canister_core::archive::ArchiveOptions;
-use ic_ledger_canister_core::ledger::{LedgerContext, LedgerTransaction, TxApplyError};
-use ic_ledger_core::approvals::{Allowance, Approvals};
+use ic_ledger_canister_core::ledger::{
+    apply_transaction, LedgerContext, LedgerData, LedgerTransaction, TxApplyError,
+};
+use ic_ledger_core::approvals::{Allowance, Approvals, PrunableApprovals};
 use ic_ledger_core::timestamp::TimeStamp;
 use ic_ledger_core::Tokens;
 use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue as Value;
@@ -606,3 +608,110 @@ fn test_approval_burn_from() {
     assert_eq!(ctx.balances().account_balance(&spender), Tokens::ZERO);
     assert_eq!(ctx.balances().total_supply().get_e8s(), 90_000);
 }
+
+#[test]
+fn test_migrate_legacy_state_to_stable_structures() {
+    use crate::{cdk_runtime::CdkRuntime, Icrc1ArchiveWasm, LegacyAllowanceTable};
+    use ic_icrc1::{Block, LedgerBalances};
+    use ic_ledger_canister_core::blockchain::Blockchain;
+    use ic_ledger_core::block::BlockType;
+
+    let now = ts(1);
+    let from = test_account_id(1);
+    let spender = test_account_id(2);
+    let expiration = now + Duration::from_secs(300);
+
+    let mut legacy_balances = LedgerBalances::<Tokens>::default();
+    legacy_balances.mint(&from, tokens(100_000)).unwrap();
+
+    let mut legacy_approvals = LegacyAllowanceTable::<Tokens>::default();
+    legacy_approvals
+        .approve(&from, &spender, tokens(50_000), Some(expiration), now, None)
+        .unwrap();
+
+    let mut legacy_blockchain = Blockchain::<CdkRuntime, Icrc1ArchiveWasm>::default();
+    let mint = Block::from_transaction(
+        None,
+        Transaction::mint(from, tokens(100_000), Some(now), None),
+        now,
+        Tokens::ZERO,
+        None,
+    );
+    legacy_blockchain.add_block(mint.clone()).unwrap();
+
+    let mut ctx = Ledger::from_init_args(default_init_args(), now);
+    ctx.legacy_balances = Some(legacy_balances);
+    ctx.legacy_approvals = Some(legacy_approvals);
+    ctx.legacy_blockchain = Some(legacy_blockchain);
+
+    ctx.migrate_to_stable_structures();
+
+    assert!(ctx.legacy_balances.is_none());
+    assert!(ctx.legacy_approvals.is_none());
+    assert!(ctx.legacy_blockchain.is_none());
+
+    assert_eq!(ctx.balances().account_balance(&from), tokens(100_000));
+    assert_eq!(ctx.balances().total_supply(), tokens(100_000));
+    assert_eq!(
+        ctx.approvals().allowance(&from, &spender, now),
+        Allowance {
+            amount: tokens(50_000),
+            expires_at: Some(expiration),
+            arrived_at: now,
+        },
+    );
+    assert_eq!(ctx.blockchain().chain_length(), 1);
+    assert_eq!(ctx.blockchain().get(0), Some(mint.encode()));
+
+    // Expired allowances migrated from the heap are pruned as usual.
+    let later = expiration + Duration::from_secs(1);
+    assert_eq!(ctx.approvals_mut().prune(later, 10), 1);
+    assert_eq!(
+        ctx.approvals().allowance(&from, &spender, now),
+        Allowance::default()
+    );
+}
+
+#[test]
+fn test_stable_block_data_remove_archived_blocks() {
+    let now = ts(1);
+    let mut ctx = Ledger::from_init_args(default_init_args(), now);
+
+    for i in 1..=5 {
+        let mint = Transaction::mint(test_account_id(i), tokens(i), Some(now), None);
+        apply_transaction(&mut ctx, mint, now, Tokens::ZERO).unwrap();
+    }
+    let blocks = ctx.blockchain().block_slice(0..5);
+
+    ctx.blockchain_mut().remove_archived_blocks(3);
+
+    assert_eq!(ctx.blockchain().num_archived_blocks(), 3);
+    assert_eq!(ctx.blockchain().num_unarchived_blocks(), 2);
+    assert_eq!(ctx.blockchain().get(2), None);
+    assert_eq!(ctx.blockchain().block_slice(3..5), blocks[3..5].to_vec());
+    assert_eq!(ctx.blockchain().last(), Some(blocks[4].clone()));
+
+    let mint = Transaction::mint(test_account_id(6), tokens(6), Some(now), None);
+    assert_eq!(
+        apply_transaction(&mut ctx, mint, now, Tokens::ZERO)
+            .unwrap()
+            .0,
+        5
+    );
+    assert_eq!(ctx.blockchain().num_unarchived_blocks(), 3);
+}
+
+#[test]
+fn test_upgrades_memory_roundtrip() {
+    let now = ts(1);
+    let from = test_account_id(1);
+    let mut ctx = Ledger::from_init_args(default_init_args(), now);
+    ctx.balances_mut().mint(&from, tokens(100_000)).unwrap();
+
+    crate::stable_memory::write_upgrades_memory(&ctx).unwrap();
+    let decoded: Ledger<Tokens> = crate::stable_memory::read_upgrades_memory().unwrap();
+
+    assert_eq!(decoded.token_name(), TOKEN_NAME);
+    assert_eq!(decoded.balances().total_supply(), tokens(100_000));
+    assert_eq!(decoded.balances().account_balance(&from), tokens(100_000));
+}
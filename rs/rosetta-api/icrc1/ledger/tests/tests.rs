@@ -401,11 +401,18 @@ fn test_upgrade_from_first_version() {
     assert_eq!(balance_2, balance_of(&env, ledger_id, account(2)));
     assert_eq!(balance_3, balance_of(&env, ledger_id, account(3)));
 
-    // check that transfer works
-    transfer(&env, ledger_id, MINTER, account(1), 1_000_000);
+    // check that transfer works and that the blocks were migrated
+    assert_eq!(4, transfer(&env, ledger_id, MINTER, account(1), 1_000_000));
     transfer(&env, ledger_id, MINTER, account(1), 2_000_000);
     transfer(&env, ledger_id, MINTER, account(2), 3_000_000);
     transfer(&env, ledger_id, account(1), account(3), 1_000_000);
+
+    // check that the migrated state survives another upgrade
+    let balance_1 = balance_of(&env, ledger_id, account(1));
+    let upgrade_args = Encode!(&LedgerArgument::Upgrade(None)).unwrap();
+    env.upgrade_canister(ledger_id, ledger_wasm(), upgrade_args)
+        .expect("Unable to upgrade the ledger canister");
+    assert_eq!(balance_1, balance_of(&env, ledger_id, account(1)));
 }
 
 mod verify_written_blocks {
@@ -11,11 +11,70 @@ use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock};
 use ic_ledger_core::timestamp::TimeStamp;
 use ic_ledger_hash_of::HashOf;
 
+/// Stores the blocks that have not been archived yet.
+pub trait BlockData {
+    fn add_block(&mut self, block: EncodedBlock);
+
+    /// Returns the blocks with local indices in the specified range.
+    fn get_blocks(&self, range: std::ops::Range<u64>) -> Vec<EncodedBlock>;
+
+    fn get_block(&self, index: u64) -> Option<EncodedBlock>;
+
+    /// Removes the `num_blocks` oldest blocks.
+    fn remove_blocks(&mut self, num_blocks: u64);
+
+    fn len(&self) -> u64;
+
+    fn is_empty(&self) -> bool;
+
+    fn last_block(&self) -> Option<EncodedBlock>;
+}
+
+impl BlockData for Vec<EncodedBlock> {
+    fn add_block(&mut self, block: EncodedBlock) {
+        self.push(block);
+    }
+
+    fn get_blocks(&self, range: std::ops::Range<u64>) -> Vec<EncodedBlock> {
+        let range = usize::try_from(range.start).unwrap()..usize::try_from(range.end).unwrap();
+        self[range].to_vec()
+    }
+
+    fn get_block(&self, index: u64) -> Option<EncodedBlock> {
+        self.get(usize::try_from(index).unwrap()).cloned()
+    }
+
+    fn remove_blocks(&mut self, num_blocks: u64) {
+        // redundant since split_off would panic, but here we can give a more
+        // descriptive message
+        if num_blocks > self.len() as u64 {
+            panic!(
+                "Asked to remove more blocks than present. Present: {}, to remove: {}",
+                self.len(),
+                num_blocks
+            );
+        }
+        *self = self.split_off(num_blocks as usize);
+    }
+
+    fn len(&self) -> u64 {
+        Vec::len(self) as u64
+    }
+
+    fn is_empty(&self) -> bool {
+        Vec::is_empty(self)
+    }
+
+    fn last_block(&self) -> Option<EncodedBlock> {
+        self.last().cloned()
+    }
+}
+
 /// Stores a chain of transactions with their metadata
 #[derive(Serialize, Deserialize, Debug)]
-#[serde(bound = "")]
-pub struct Blockchain<Rt: Runtime, Wasm: ArchiveCanisterWasm> {
-    pub blocks: Vec<EncodedBlock>,
+#[serde(bound(serialize = "BD: Serialize", deserialize = "BD: Deserialize<'de>"))]
+pub struct Blockchain<Rt: Runtime, Wasm: ArchiveCanisterWasm, BD = Vec<EncodedBlock>> {
+    pub blocks: BD,
     pub last_hash: Option<HashOf<EncodedBlock>>,
 
     /// The timestamp of the most recent block. Must be monotonically
@@ -32,10 +91,10 @@ pub struct Blockchain<Rt: Runtime, Wasm: ArchiveCanisterWasm> {
     pub num_archived_blocks: u64,
 }
 
-impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Default for Blockchain<Rt, Wasm> {
+impl<Rt: Runtime, Wasm: ArchiveCanisterWasm, BD: Default> Default for Blockchain<Rt, Wasm, BD> {
     fn default() -> Self {
         Self {
-            blocks: vec![],
+            blocks: BD::default(),
             last_hash: None,
             last_timestamp: TimeStamp::from_nanos_since_unix_epoch(0),
             archive: Arc::new(RwLock::new(None)),
@@ -44,8 +103,11 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Default for Blockchain<Rt, Wasm> {
     }
 }
 
-impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Blockchain<Rt, Wasm> {
-    pub fn new_with_archive(archive_options: ArchiveOptions) -> Self {
+impl<Rt: Runtime, Wasm: ArchiveCanisterWasm, BD: BlockData> Blockchain<Rt, Wasm, BD> {
+    pub fn new_with_archive(archive_options: ArchiveOptions) -> Self
+    where
+        BD: Default,
+    {
         Self {
             archive: Arc::new(RwLock::new(Some(Archive::new(archive_options)))),
             ..Self::default()
@@ -68,21 +130,20 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Blockchain<Rt, Wasm> {
         self.last_timestamp = block.timestamp();
         let encoded_block = block.encode();
         self.last_hash = Some(B::block_hash(&encoded_block));
-        self.blocks.push(encoded_block);
+        self.blocks.add_block(encoded_block);
         Ok(self.chain_length().checked_sub(1).unwrap())
     }
 
-    pub fn get(&self, height: BlockIndex) -> Option<&EncodedBlock> {
+    pub fn get(&self, height: BlockIndex) -> Option<EncodedBlock> {
         if height < self.num_archived_blocks() {
             None
         } else {
-            self.blocks
-                .get(usize::try_from(height - self.num_archived_blocks()).unwrap())
+            self.blocks.get_block(height - self.num_archived_blocks())
         }
     }
 
-    pub fn last(&self) -> Option<&EncodedBlock> {
-        self.blocks.last()
+    pub fn last(&self) -> Option<EncodedBlock> {
+        self.blocks.last_block()
     }
 
     pub fn num_archived_blocks(&self) -> u64 {
@@ -90,20 +151,20 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Blockchain<Rt, Wasm> {
     }
 
     pub fn num_unarchived_blocks(&self) -> u64 {
-        self.blocks.len() as u64
+        self.blocks.len()
     }
 
     /// The range of block indices that are not archived yet.
     pub fn local_block_range(&self) -> std::ops::Range<u64> {
-        self.num_archived_blocks..self.num_archived_blocks + self.blocks.len() as u64
+        self.num_archived_blocks..self.num_archived_blocks + self.blocks.len()
     }
 
-    /// Returns the slice of blocks stored locally.
+    /// Returns the blocks stored locally in the specified range.
     ///
     /// # Panic
     ///
     /// This function panics if the specified range is not a subset of locally available blocks.
-    pub fn block_slice(&self, local_blocks: std::ops::Range<u64>) -> &[EncodedBlock] {
+    pub fn block_slice(&self, local_blocks: std::ops::Range<u64>) -> Vec<EncodedBlock> {
         use crate::range_utils::{is_subrange, offset};
 
         assert!(
@@ -113,7 +174,8 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Blockchain<Rt, Wasm> {
             self.local_block_range()
         );
 
-        &self.blocks[offset(&local_blocks, self.num_archived_blocks)]
+        let range = offset(&local_blocks, self.num_archived_blocks);
+        self.blocks.get_blocks(range.start as u64..range.end as u64)
     }
 
     pub fn chain_length(&self) -> BlockIndex {
@@ -121,16 +183,7 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Blockchain<Rt, Wasm> {
     }
 
     pub fn remove_archived_blocks(&mut self, len: usize) {
-        // redundant since split_off would panic, but here we can give a more
-        // descriptive message
-        if len > self.blocks.len() {
-            panic!(
-                "Asked to remove more blocks than present. Present: {}, to remove: {}",
-                self.blocks.len(),
-                len
-            );
-        }
-        self.blocks = self.blocks.split_off(len);
+        self.blocks.remove_blocks(len as u64);
         self.num_archived_blocks += len as u64;
     }
 
@@ -150,8 +203,10 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Blockchain<Rt, Wasm> {
             return VecDeque::new();
         }
 
-        let blocks_to_archive: VecDeque<EncodedBlock> =
-            VecDeque::from(self.blocks[0..num_blocks_to_archive.min(num_blocks_before)].to_vec());
+        let blocks_to_archive: VecDeque<EncodedBlock> = VecDeque::from(
+            self.blocks
+                .get_blocks(0..num_blocks_to_archive.min(num_blocks_before) as u64),
+        );
 
         println!(
             "get_blocks_for_archiving(): trigger_threshold: {}, num_blocks: {}, blocks before archiving: {}, blocks to archive: {}",
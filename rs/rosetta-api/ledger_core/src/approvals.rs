@@ -1,7 +1,6 @@
 use crate::timestamp::TimeStamp;
 use crate::tokens::{TokensType, Zero};
 use serde::{Deserialize, Serialize};
-use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, BTreeSet};
 use std::marker::PhantomData;
 
@@ -82,8 +81,74 @@ impl<Tokens: Zero> Default for Allowance<Tokens> {
     }
 }
 
+/// The storage backing an [AllowanceTable].
+///
+/// The table keeps three indices: the allowances themselves, the allowances
+/// ordered by expiration time (used for pruning) and the allowances ordered by
+/// arrival time (used for trimming).
+pub trait AllowancesData {
+    type AccountId;
+    type Tokens;
+
+    fn get_allowance(
+        &self,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    ) -> Option<Allowance<Self::Tokens>>;
+
+    fn set_allowance(
+        &mut self,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+        allowance: Allowance<Self::Tokens>,
+    );
+
+    fn remove_allowance(&mut self, account: &Self::AccountId, spender: &Self::AccountId);
+
+    fn insert_expiry(
+        &mut self,
+        timestamp: TimeStamp,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    );
+
+    fn remove_expiry(
+        &mut self,
+        timestamp: TimeStamp,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    );
+
+    fn insert_arrival(
+        &mut self,
+        timestamp: TimeStamp,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    );
+
+    fn remove_arrival(
+        &mut self,
+        timestamp: TimeStamp,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    );
+
+    /// Returns the entry of the expiration queue with the earliest timestamp.
+    fn first_expiry(&self) -> Option<(TimeStamp, Self::AccountId, Self::AccountId)>;
+
+    /// Returns up to `n` (account, spender) pairs in the order of arrival.
+    fn oldest_arrivals(&self, n: usize) -> Vec<(Self::AccountId, Self::AccountId)>;
+
+    fn len_allowances(&self) -> usize;
+
+    fn len_expirations(&self) -> usize;
+
+    fn len_arrivals(&self) -> usize;
+}
+
+/// [AllowancesData] stored in heap-allocated maps.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct AllowanceTable<K, AccountId, Tokens>
+pub struct HeapAllowancesData<K, AccountId, Tokens>
 where
     K: Ord,
 {
@@ -96,37 +161,147 @@ where
     _marker: PhantomData<fn(&AccountId, &AccountId) -> K>,
 }
 
-impl<K: Ord, AccountId, Tokens> Default for AllowanceTable<K, AccountId, Tokens> {
+impl<K: Ord, AccountId, Tokens> Default for HeapAllowancesData<K, AccountId, Tokens> {
     fn default() -> Self {
-        Self::new()
+        Self {
+            allowances: BTreeMap::new(),
+            expiration_queue: BTreeSet::new(),
+            arrival_queue: BTreeSet::new(),
+            _marker: PhantomData,
+        }
     }
 }
 
-impl<K, AccountId, Tokens> AllowanceTable<K, AccountId, Tokens>
+impl<K: Ord, AccountId, Tokens> HeapAllowancesData<K, AccountId, Tokens> {
+    /// Iterates over all allowances, including the expired ones that have not
+    /// been pruned yet.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &Allowance<Tokens>)> {
+        self.allowances.iter()
+    }
+}
+
+impl<K, AccountId, Tokens> AllowancesData for HeapAllowancesData<K, AccountId, Tokens>
 where
-    K: Ord,
+    K: Ord + for<'a> From<(&'a AccountId, &'a AccountId)> + Clone,
+    K: Into<(AccountId, AccountId)>,
+    Tokens: Clone,
 {
-    pub fn new() -> Self {
+    type AccountId = AccountId;
+    type Tokens = Tokens;
+
+    fn get_allowance(&self, account: &AccountId, spender: &AccountId) -> Option<Allowance<Tokens>> {
+        self.allowances.get(&K::from((account, spender))).cloned()
+    }
+
+    fn set_allowance(
+        &mut self,
+        account: &AccountId,
+        spender: &AccountId,
+        allowance: Allowance<Tokens>,
+    ) {
+        self.allowances
+            .insert(K::from((account, spender)), allowance);
+    }
+
+    fn remove_allowance(&mut self, account: &AccountId, spender: &AccountId) {
+        self.allowances.remove(&K::from((account, spender)));
+    }
+
+    fn insert_expiry(&mut self, timestamp: TimeStamp, account: &AccountId, spender: &AccountId) {
+        self.expiration_queue
+            .insert((timestamp, K::from((account, spender))));
+    }
+
+    fn remove_expiry(&mut self, timestamp: TimeStamp, account: &AccountId, spender: &AccountId) {
+        self.expiration_queue
+            .remove(&(timestamp, K::from((account, spender))));
+    }
+
+    fn insert_arrival(&mut self, timestamp: TimeStamp, account: &AccountId, spender: &AccountId) {
+        self.arrival_queue
+            .insert((timestamp, K::from((account, spender))));
+    }
+
+    fn remove_arrival(&mut self, timestamp: TimeStamp, account: &AccountId, spender: &AccountId) {
+        self.arrival_queue
+            .remove(&(timestamp, K::from((account, spender))));
+    }
+
+    fn first_expiry(&self) -> Option<(TimeStamp, AccountId, AccountId)> {
+        self.expiration_queue.first().map(|(timestamp, key)| {
+            let (account, spender) = key.clone().into();
+            (*timestamp, account, spender)
+        })
+    }
+
+    fn oldest_arrivals(&self, n: usize) -> Vec<(AccountId, AccountId)> {
+        self.arrival_queue
+            .iter()
+            .take(n)
+            .map(|(_arrival, key)| key.clone().into())
+            .collect()
+    }
+
+    fn len_allowances(&self) -> usize {
+        self.allowances.len()
+    }
+
+    fn len_expirations(&self) -> usize {
+        self.expiration_queue.len()
+    }
+
+    fn len_arrivals(&self) -> usize {
+        self.arrival_queue.len()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(transparent)]
+pub struct AllowanceTable<AD> {
+    allowances_data: AD,
+}
+
+impl<AD: Default> Default for AllowanceTable<AD> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<AD> AllowanceTable<AD> {
+    pub fn new() -> Self
+    where
+        AD: Default,
+    {
         Self {
-            allowances: BTreeMap::new(),
-            expiration_queue: BTreeSet::new(),
-            arrival_queue: BTreeSet::new(),
-            _marker: PhantomData,
+            allowances_data: AD::default(),
         }
     }
 
+    pub fn allowances_data(&self) -> &AD {
+        &self.allowances_data
+    }
+
+    pub fn allowances_data_mut(&mut self) -> &mut AD {
+        &mut self.allowances_data
+    }
+}
+
+impl<AD> AllowanceTable<AD>
+where
+    AD: AllowancesData,
+{
     fn check_postconditions(&self) {
         debug_assert!(
-            self.expiration_queue.len() <= self.allowances.len(),
+            self.allowances_data.len_expirations() <= self.allowances_data.len_allowances(),
             "expiration queue length ({}) larger than allowances length ({})",
-            self.expiration_queue.len(),
-            self.allowances.len()
+            self.allowances_data.len_expirations(),
+            self.allowances_data.len_allowances()
         );
         debug_assert!(
-            self.arrival_queue.len() == self.allowances.len(),
+            self.allowances_data.len_arrivals() == self.allowances_data.len_allowances(),
             "arrival_queue length ({}) should be equal to allowances length ({})",
-            self.arrival_queue.len(),
-            self.allowances.len()
+            self.allowances_data.len_arrivals(),
+            self.allowances_data.len_allowances()
         );
     }
 
@@ -137,26 +312,24 @@ where
     }
 }
 
-impl<K, AccountId, Tokens> Approvals for AllowanceTable<K, AccountId, Tokens>
+impl<AD> Approvals for AllowanceTable<AD>
 where
-    K: Ord + for<'a> From<(&'a AccountId, &'a AccountId)> + Clone,
-    K: Into<(AccountId, AccountId)>,
-    AccountId: std::cmp::PartialEq,
-    Tokens: TokensType,
+    AD: AllowancesData,
+    AD::AccountId: std::cmp::PartialEq,
+    AD::Tokens: TokensType,
 {
-    type AccountId = AccountId;
-    type Tokens = Tokens;
+    type AccountId = AD::AccountId;
+    type Tokens = AD::Tokens;
 
     fn allowance(
         &self,
-        account: &AccountId,
-        spender: &AccountId,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
         now: TimeStamp,
-    ) -> Allowance<Tokens> {
-        let key = K::from((account, spender));
-        match self.allowances.get(&key) {
+    ) -> Allowance<Self::Tokens> {
+        match self.allowances_data.get_allowance(account, spender) {
             Some(allowance) if allowance.expires_at.unwrap_or_else(remote_future) > now => {
-                allowance.clone()
+                allowance
             }
             _ => Allowance::default(),
         }
@@ -164,13 +337,13 @@ where
 
     fn approve(
         &mut self,
-        account: &AccountId,
-        spender: &AccountId,
-        amount: Tokens,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+        amount: Self::Tokens,
         expires_at: Option<TimeStamp>,
         now: TimeStamp,
-        expected_allowance: Option<Tokens>,
-    ) -> Result<Tokens, ApproveError<Tokens>> {
+        expected_allowance: Option<Self::Tokens>,
+    ) -> Result<Self::Tokens, ApproveError<Self::Tokens>> {
         self.with_postconditions_check(|table| {
             if account == spender {
                 return Err(ApproveError::SelfApproval);
@@ -180,66 +353,71 @@ where
                 return Err(ApproveError::ExpiredApproval { now });
             }
 
-            let key = K::from((account, spender));
+            let data = &mut table.allowances_data;
 
-            match table.allowances.entry(key.clone()) {
-                Entry::Vacant(e) => {
-                    if amount == Tokens::zero() {
+            match data.get_allowance(account, spender) {
+                None => {
+                    if amount == Self::Tokens::zero() {
                         return Ok(amount);
                     }
                     if let Some(expected_allowance) = expected_allowance {
                         if !expected_allowance.is_zero() {
                             return Err(ApproveError::AllowanceChanged {
-                                current_allowance: Tokens::zero(),
+                                current_allowance: Self::Tokens::zero(),
                             });
                         }
                     }
                     if let Some(expires_at) = expires_at {
-                        table.expiration_queue.insert((expires_at, key.clone()));
+                        data.insert_expiry(expires_at, account, spender);
                     }
-                    table.arrival_queue.insert((now, key));
-                    e.insert(Allowance {
-                        amount: amount.clone(),
-                        expires_at,
-                        arrived_at: now,
-                    });
+                    data.insert_arrival(now, account, spender);
+                    data.set_allowance(
+                        account,
+                        spender,
+                        Allowance {
+                            amount: amount.clone(),
+                            expires_at,
+                            arrived_at: now,
+                        },
+                    );
                     Ok(amount)
                 }
-                Entry::Occupied(mut e) => {
-                    let allowance = e.get_mut();
+                Some(allowance) => {
                     if let Some(expected_allowance) = expected_allowance {
                         if expected_allowance != allowance.amount {
                             return Err(ApproveError::AllowanceChanged {
-                                current_allowance: allowance.amount.clone(),
+                                current_allowance: allowance.amount,
                             });
                         }
                     }
-                    table
-                        .arrival_queue
-                        .remove(&(allowance.arrived_at, key.clone()));
-                    if amount == Tokens::zero() {
-                        if let Some(expires_at) = e.get().expires_at {
-                            table.expiration_queue.remove(&(expires_at, key.clone()));
+                    data.remove_arrival(allowance.arrived_at, account, spender);
+                    if amount == Self::Tokens::zero() {
+                        if let Some(expires_at) = allowance.expires_at {
+                            data.remove_expiry(expires_at, account, spender);
                         }
-                        e.remove();
+                        data.remove_allowance(account, spender);
                         return Ok(amount);
                     }
-                    table.arrival_queue.insert((now, key.clone()));
-                    allowance.amount = amount;
-                    allowance.arrived_at = now;
-                    let old_expiration = std::mem::replace(&mut allowance.expires_at, expires_at);
-
-                    if expires_at != old_expiration {
-                        if let Some(old_expiration) = old_expiration {
-                            table
-                                .expiration_queue
-                                .remove(&(old_expiration, key.clone()));
+                    data.insert_arrival(now, account, spender);
+
+                    if expires_at != allowance.expires_at {
+                        if let Some(old_expiration) = allowance.expires_at {
+                            data.remove_expiry(old_expiration, account, spender);
                         }
                         if let Some(expires_at) = expires_at {
-                            table.expiration_queue.insert((expires_at, key));
+                            data.insert_expiry(expires_at, account, spender);
                         }
                     }
-                    Ok(e.get().amount.clone())
+                    data.set_allowance(
+                        account,
+                        spender,
+                        Allowance {
+                            amount: amount.clone(),
+                            expires_at,
+                            arrived_at: now,
+                        },
+                    );
+                    Ok(amount)
                 }
             }
         })
@@ -247,82 +425,79 @@ where
 
     fn use_allowance(
         &mut self,
-        account: &AccountId,
-        spender: &AccountId,
-        amount: Tokens,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+        amount: Self::Tokens,
         now: TimeStamp,
-    ) -> Result<Tokens, InsufficientAllowance<Tokens>> {
+    ) -> Result<Self::Tokens, InsufficientAllowance<Self::Tokens>> {
         self.with_postconditions_check(|table| {
-            let key = K::from((account, spender));
+            let data = &mut table.allowances_data;
 
-            match table.allowances.entry(key.clone()) {
-                Entry::Vacant(_) => Err(InsufficientAllowance(Tokens::zero())),
-                Entry::Occupied(mut e) => {
-                    if e.get().expires_at.unwrap_or_else(remote_future) <= now {
-                        Err(InsufficientAllowance(Tokens::zero()))
-                    } else {
-                        let allowance = e.get_mut();
-                        if allowance.amount < amount {
-                            return Err(InsufficientAllowance(allowance.amount.clone()));
-                        }
-                        allowance.amount = allowance
-                            .amount
-                            .checked_sub(&amount)
-                            .expect("Underflow when using allowance");
-                        let rest = allowance.amount.clone();
-                        if rest.is_zero() {
-                            if let Some(expires_at) = e.get().expires_at {
-                                table.expiration_queue.remove(&(expires_at, key.clone()));
-                            }
-                            table.arrival_queue.remove(&(e.get().arrived_at, key));
-                            e.remove();
+            match data.get_allowance(account, spender) {
+                None => Err(InsufficientAllowance(Self::Tokens::zero())),
+                Some(allowance) => {
+                    if allowance.expires_at.unwrap_or_else(remote_future) <= now {
+                        return Err(InsufficientAllowance(Self::Tokens::zero()));
+                    }
+                    if allowance.amount < amount {
+                        return Err(InsufficientAllowance(allowance.amount));
+                    }
+                    let rest = allowance
+                        .amount
+                        .checked_sub(&amount)
+                        .expect("Underflow when using allowance");
+                    if rest.is_zero() {
+                        if let Some(expires_at) = allowance.expires_at {
+                            data.remove_expiry(expires_at, account, spender);
                         }
-                        Ok(rest)
+                        data.remove_arrival(allowance.arrived_at, account, spender);
+                        data.remove_allowance(account, spender);
+                    } else {
+                        data.set_allowance(
+                            account,
+                            spender,
+                            Allowance {
+                                amount: rest.clone(),
+                                expires_at: allowance.expires_at,
+                                arrived_at: allowance.arrived_at,
+                            },
+                        );
                     }
+                    Ok(rest)
                 }
             }
         })
     }
 
     fn select_approvals_to_trim(&self, n: usize) -> Vec<(Self::AccountId, Self::AccountId)> {
-        let mut result = vec![];
-        for (_expiration, key) in &self.arrival_queue {
-            if result.len() >= n {
-                break;
-            }
-            result.push(key.clone().into());
-        }
-        result
+        self.allowances_data.oldest_arrivals(n)
     }
 }
 
-impl<K, AccountId, Tokens> PrunableApprovals for AllowanceTable<K, AccountId, Tokens>
+impl<AD> PrunableApprovals for AllowanceTable<AD>
 where
-    K: Ord + Clone,
+    AD: AllowancesData,
 {
     fn prune(&mut self, now: TimeStamp, limit: usize) -> usize {
         self.with_postconditions_check(|table| {
+            let data = &mut table.allowances_data;
             let mut pruned = 0;
             for _ in 0..limit {
-                match table.expiration_queue.first() {
-                    Some((ts, _key)) => {
-                        if *ts > now {
-                            return pruned;
-                        }
-                    }
+                let (expires_at, account, spender) = match data.first_expiry() {
+                    Some(expiry) => expiry,
                     None => {
                         return pruned;
                     }
+                };
+                if expires_at > now {
+                    return pruned;
                 }
-                if let Some((_, key)) = table.expiration_queue.pop_first() {
-                    if let Some(allowance) = table.allowances.get(&key) {
-                        if allowance.expires_at.unwrap_or_else(remote_future) <= now {
-                            table
-                                .arrival_queue
-                                .remove(&(allowance.arrived_at, key.clone()));
-                            table.allowances.remove(&key);
-                            pruned += 1;
-                        }
+                data.remove_expiry(expires_at, &account, &spender);
+                if let Some(allowance) = data.get_allowance(&account, &spender) {
+                    if allowance.expires_at.unwrap_or_else(remote_future) <= now {
+                        data.remove_arrival(allowance.arrived_at, &account, &spender);
+                        data.remove_allowance(&account, &spender);
+                        pruned += 1;
                     }
                 }
             }
@@ -331,7 +506,7 @@ where
     }
 
     fn len(&self) -> usize {
-        self.allowances.len()
+        self.allowances_data.len_allowances()
     }
 }
 
@@ -9,7 +9,10 @@ pub trait BalancesStore {
     type Tokens;
 
     /// Returns the balance on the specified account.
-    fn get_balance(&self, k: &Self::AccountId) -> Option<&Self::Tokens>;
+    ///
+    /// The balance is returned by value so that stores that keep balances
+    /// outside of the heap (e.g., in stable memory) can implement this trait.
+    fn get_balance(&self, k: &Self::AccountId) -> Option<Self::Tokens>;
 
     /// Update balance for an account using function f.
     /// Its arg is previous balance or None if not found and
@@ -21,7 +24,7 @@ pub trait BalancesStore {
 
 #[allow(clippy::len_without_is_empty)]
 pub trait InspectableBalancesStore: BalancesStore {
-    fn iter(&self) -> Box<dyn Iterator<Item = (&Self::AccountId, &Self::Tokens)> + '_>;
+    fn iter(&self) -> Box<dyn Iterator<Item = (Self::AccountId, Self::Tokens)> + '_>;
 
     fn len(&self) -> usize;
 }
@@ -34,8 +37,8 @@ where
     type AccountId = AccountId;
     type Tokens = Tokens;
 
-    fn get_balance(&self, k: &Self::AccountId) -> Option<&Self::Tokens> {
-        self.get(k)
+    fn get_balance(&self, k: &Self::AccountId) -> Option<Self::Tokens> {
+        self.get(k).cloned()
     }
 
     fn update<F, E>(&mut self, k: AccountId, mut f: F) -> Result<Self::Tokens, E>
@@ -72,8 +75,8 @@ where
         self.len()
     }
 
-    fn iter(&self) -> Box<dyn Iterator<Item = (&Self::AccountId, &Self::Tokens)> + '_> {
-        Box::new(self.iter())
+    fn iter(&self) -> Box<dyn Iterator<Item = (Self::AccountId, Self::Tokens)> + '_> {
+        Box::new(self.iter().map(|(k, v)| (k.clone(), v.clone())))
     }
 }
 
@@ -225,7 +228,6 @@ where
     pub fn account_balance(&self, account: &S::AccountId) -> S::Tokens {
         self.store
             .get_balance(account)
-            .cloned()
             .unwrap_or_else(S::Tokens::zero)
     }
 
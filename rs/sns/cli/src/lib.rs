@@ -1,6 +1,7 @@
 use crate::{
     deploy::{DirectSnsDeployerForTests, SnsWasmSnsDeployer},
     init_config_file::{InitConfigFileArgs, SnsCliInitConfig, SnsInitialTokenDistributionConfig},
+    neuron::NeuronArgs,
     prepare_canisters::PrepareCanistersArgs,
     propose::ProposeArgs,
 };
@@ -34,6 +35,7 @@ use tempfile::NamedTempFile;
 
 pub mod deploy;
 pub mod init_config_file;
+pub mod neuron;
 pub mod prepare_canisters;
 pub mod propose;
 pub mod unit_helpers;
@@ -79,6 +81,8 @@ pub enum SubCommand {
     PrepareCanisters(PrepareCanistersArgs),
     /// Submit an NNS proposal to create new SNS.
     Propose(ProposeArgs),
+    /// Stake, configure, vote with and disburse the maturity of SNS neurons.
+    Neuron(NeuronArgs),
 }
 
 /// The arguments used to configure a SNS deployment
@@ -618,6 +622,7 @@ trait Request {
 struct Canister {
     network: String,
     name: String,
+    identity: Option<String>,
 }
 
 #[derive(Debug)]
@@ -636,7 +641,19 @@ impl Canister {
         let network = network.to_string();
         let name = name.to_string();
 
-        Self { network, name }
+        Self {
+            network,
+            name,
+            identity: None,
+        }
+    }
+
+    /// Makes calls using the given dfx identity instead of the one currently in
+    /// use. Like with the `--identity` flag of dfx, this can be any identity
+    /// known to dfx, e.g. one backed by a PEM file or by an HSM.
+    pub fn with_identity(mut self, identity: Option<&str>) -> Self {
+        self.identity = identity.map(|identity| identity.to_string());
+        self
     }
 
     pub fn call<Req>(&self, request: &Req) -> Result<Req::Response, CanisterCallError>
@@ -675,7 +692,7 @@ impl Canister {
         })?;
 
         // Step 2: The real work of making the call takes place here.
-        let command = [
+        let mut command = vec![
             "dfx",
             "canister",
             "--network",
@@ -687,6 +704,9 @@ impl Canister {
             argument_file,
             "--output=raw",
         ];
+        if let Some(identity) = &self.identity {
+            command.extend(["--identity", identity.as_str()]);
+        }
         let result = run_command(&command);
 
         // Step 3: Handle errors.
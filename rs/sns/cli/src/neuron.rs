@@ -0,0 +1,403 @@
+use crate::{run_command, Canister, Request};
+use candid::Nat;
+use clap::{ArgEnum, Parser};
+use ic_base_types::PrincipalId;
+use ic_nervous_system_common::ledger::compute_neuron_staking_subaccount_bytes;
+use ic_sns_governance::pb::v1::{
+    manage_neuron::{
+        claim_or_refresh::{By, MemoAndController},
+        configure::Operation,
+        AddNeuronPermissions, ClaimOrRefresh, Command, Configure, DisburseMaturity, Follow,
+        IncreaseDissolveDelay, RegisterVote,
+    },
+    manage_neuron_response, Account, ManageNeuron, ManageNeuronResponse, NeuronId,
+    NeuronPermissionList, NeuronPermissionType, ProposalId, Vote,
+};
+use icrc_ledger_types::icrc1::{
+    account::Account as Icrc1Account,
+    transfer::{Memo, TransferArg, TransferError},
+};
+use std::str::FromStr;
+
+#[cfg(test)]
+mod neuron_tests;
+
+/// The permissions granted by `add-hotkey`, unless specified otherwise. These
+/// allow the hotkey to vote and to make proposals on behalf of the neuron, but
+/// not to disburse or otherwise change its stake.
+const DEFAULT_HOTKEY_PERMISSIONS: &[NeuronPermissionType] = &[
+    NeuronPermissionType::Vote,
+    NeuronPermissionType::SubmitProposal,
+];
+
+#[derive(Debug, Parser)]
+pub struct NeuronArgs {
+    /// The network the SNS is deployed to. This can be "local", "ic", or the
+    /// URL of an IC network.
+    #[clap(default_value = "local", long)]
+    network: String,
+
+    /// The dfx identity with which to send the requests, e.g. one backed by a
+    /// PEM file or by an HSM. If not specified, the current dfx identity is
+    /// used.
+    #[clap(long)]
+    identity: Option<String>,
+
+    /// The canister ID or name (via dfx.json) of the SNS governance canister.
+    #[clap(default_value = "sns_governance", long)]
+    sns_governance_canister: String,
+
+    #[clap(subcommand)]
+    sub_command: NeuronSubCommand,
+}
+
+#[derive(Debug, Parser)]
+pub enum NeuronSubCommand {
+    /// Transfer tokens to the staking subaccount of a neuron and claim (or
+    /// refresh) the neuron. The neuron is controlled by the sender.
+    Stake(StakeArgs),
+    /// Increase the dissolve delay of a neuron.
+    IncreaseDissolveDelay(IncreaseDissolveDelayArgs),
+    /// Grant a principal permission to vote and make proposals with a neuron.
+    AddHotkey(AddHotkeyArgs),
+    /// Set the neurons that a neuron follows on proposals of a given function.
+    Follow(FollowArgs),
+    /// Vote on a proposal with a neuron.
+    Vote(VoteArgs),
+    /// Disburse (a percentage of) the maturity of a neuron to a ledger account.
+    DisburseMaturity(DisburseMaturityArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct StakeArgs {
+    /// The amount of tokens to stake, in e8s. This is in addition to the
+    /// ledger transfer fee. If not specified, no tokens are transferred and
+    /// the neuron is only claimed or refreshed, e.g. after tokens were
+    /// transferred to its staking subaccount by other means.
+    #[clap(long)]
+    amount_e8s: Option<u64>,
+
+    /// The memo (nonce) that, together with the sender's principal, determines
+    /// the neuron's subaccount. Use different memos to stake several neurons.
+    #[clap(default_value = "0", long)]
+    memo: u64,
+
+    /// The canister ID or name (via dfx.json) of the SNS ledger canister.
+    #[clap(default_value = "sns_ledger", long)]
+    sns_ledger_canister: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct IncreaseDissolveDelayArgs {
+    /// The ID (hex-encoded subaccount) of the neuron.
+    #[clap(long, parse(try_from_str = parse_neuron_id))]
+    neuron_id: NeuronId,
+
+    /// The number of seconds to add to the neuron's current dissolve delay.
+    #[clap(long)]
+    additional_dissolve_delay_seconds: u32,
+}
+
+#[derive(Debug, Parser)]
+pub struct AddHotkeyArgs {
+    /// The ID (hex-encoded subaccount) of the neuron.
+    #[clap(long, parse(try_from_str = parse_neuron_id))]
+    neuron_id: NeuronId,
+
+    /// The principal to grant the permissions to.
+    #[clap(long)]
+    principal: PrincipalId,
+
+    /// The permissions to grant. If not specified, the principal is allowed to
+    /// vote and to submit proposals.
+    #[clap(arg_enum, long, multiple_values = true)]
+    permissions: Vec<NeuronPermissionType>,
+}
+
+#[derive(Debug, Parser)]
+pub struct FollowArgs {
+    /// The ID (hex-encoded subaccount) of the neuron.
+    #[clap(long, parse(try_from_str = parse_neuron_id))]
+    neuron_id: NeuronId,
+
+    /// The ID of the proposal function (i.e. of a native or generic proposal
+    /// type) for which the followees are set. 0 sets the followees for all
+    /// functions without a specific rule.
+    #[clap(long)]
+    function_id: u64,
+
+    /// The IDs (hex-encoded subaccounts) of the neurons to follow. If none
+    /// are specified, the neuron stops following on this function.
+    #[clap(long, parse(try_from_str = parse_neuron_id), multiple_values = true)]
+    followees: Vec<NeuronId>,
+}
+
+#[derive(Debug, Parser)]
+pub struct VoteArgs {
+    /// The ID (hex-encoded subaccount) of the neuron.
+    #[clap(long, parse(try_from_str = parse_neuron_id))]
+    neuron_id: NeuronId,
+
+    /// The ID of the proposal to vote on.
+    #[clap(long)]
+    proposal_id: u64,
+
+    /// Whether to vote to adopt or to reject the proposal.
+    #[clap(arg_enum, long)]
+    vote: VoteChoice,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoteChoice {
+    Yes,
+    No,
+}
+
+#[derive(Debug, Parser)]
+pub struct DisburseMaturityArgs {
+    /// The ID (hex-encoded subaccount) of the neuron.
+    #[clap(long, parse(try_from_str = parse_neuron_id))]
+    neuron_id: NeuronId,
+
+    /// The percentage of the neuron's maturity to disburse, from 1 to 100.
+    #[clap(default_value = "100", long)]
+    percentage_to_disburse: u32,
+
+    /// The principal whose (default) account receives the disbursed tokens. If
+    /// not specified, the sender's account is used.
+    #[clap(long)]
+    to_principal: Option<PrincipalId>,
+}
+
+fn parse_neuron_id(neuron_id: &str) -> Result<NeuronId, String> {
+    NeuronId::from_str(neuron_id).map_err(|err| err.to_string())
+}
+
+impl Request for ManageNeuron {
+    type Response = ManageNeuronResponse;
+    const METHOD_NAME: &'static str = "manage_neuron";
+}
+
+impl Request for TransferArg {
+    type Response = Result<Nat, TransferError>;
+    const METHOD_NAME: &'static str = "icrc1_transfer";
+}
+
+pub fn exec(args: NeuronArgs) {
+    let NeuronArgs {
+        network,
+        identity,
+        sns_governance_canister,
+        sub_command,
+    } = args;
+    let identity = identity.as_deref();
+    let governance = Canister::new(&network, &sns_governance_canister).with_identity(identity);
+
+    let manage_neuron = match sub_command {
+        NeuronSubCommand::Stake(args) => {
+            let controller = get_principal_or_exit(&network, identity);
+            if let Some(amount_e8s) = args.amount_e8s {
+                let governance_canister_id =
+                    resolve_canister_id_or_exit(&network, &sns_governance_canister);
+                let ledger =
+                    Canister::new(&network, &args.sns_ledger_canister).with_identity(identity);
+                let transfer =
+                    new_stake_transfer(governance_canister_id, controller, args.memo, amount_e8s);
+                eprintln!(
+                    "Transferring {} e8s to the staking subaccount of the neuron...",
+                    amount_e8s,
+                );
+                match ledger.call(&transfer) {
+                    Ok(Ok(block_index)) => eprintln!("Transferred in block {}.", block_index),
+                    Ok(Err(err)) => exit_with_error("The transfer was rejected", err),
+                    Err(err) => exit_with_error("Unable to transfer the stake", err),
+                }
+            }
+            new_claim_or_refresh(controller, args.memo)
+        }
+        NeuronSubCommand::IncreaseDissolveDelay(args) => args.into(),
+        NeuronSubCommand::AddHotkey(args) => args.into(),
+        NeuronSubCommand::Follow(args) => args.into(),
+        NeuronSubCommand::Vote(args) => args.into(),
+        NeuronSubCommand::DisburseMaturity(args) => args.into(),
+    };
+
+    match governance.call(&manage_neuron) {
+        Ok(ManageNeuronResponse {
+            command: Some(manage_neuron_response::Command::Error(err)),
+        }) => exit_with_error("The SNS governance canister returned an error", err),
+        Ok(ManageNeuronResponse {
+            command: Some(manage_neuron_response::Command::ClaimOrRefresh(response)),
+        }) => {
+            println!("Success!");
+            if let Some(neuron_id) = response.refreshed_neuron_id {
+                println!("Neuron ID: {}", neuron_id);
+            }
+        }
+        Ok(ManageNeuronResponse {
+            command: Some(response),
+        }) => {
+            println!("Success!");
+            println!("{:#?}", response);
+        }
+        Ok(response) => exit_with_error("Invalid response", response),
+        Err(err) => exit_with_error("Unable to call the SNS governance canister", err),
+    }
+}
+
+/// Returns the ICRC-1 transfer of `amount_e8s` to the subaccount of the SNS
+/// governance canister that backs the neuron of `controller` with `memo`.
+fn new_stake_transfer(
+    governance_canister_id: PrincipalId,
+    controller: PrincipalId,
+    memo: u64,
+    amount_e8s: u64,
+) -> TransferArg {
+    TransferArg {
+        from_subaccount: None,
+        to: Icrc1Account {
+            owner: governance_canister_id.0,
+            subaccount: Some(compute_neuron_staking_subaccount_bytes(controller, memo)),
+        },
+        fee: None,
+        created_at_time: None,
+        memo: Some(Memo::from(memo)),
+        amount: Nat::from(amount_e8s),
+    }
+}
+
+fn new_claim_or_refresh(controller: PrincipalId, memo: u64) -> ManageNeuron {
+    ManageNeuron {
+        subaccount: compute_neuron_staking_subaccount_bytes(controller, memo).to_vec(),
+        command: Some(Command::ClaimOrRefresh(ClaimOrRefresh {
+            by: Some(By::MemoAndController(MemoAndController {
+                memo,
+                controller: Some(controller),
+            })),
+        })),
+    }
+}
+
+impl From<IncreaseDissolveDelayArgs> for ManageNeuron {
+    fn from(args: IncreaseDissolveDelayArgs) -> Self {
+        ManageNeuron {
+            subaccount: args.neuron_id.id,
+            command: Some(Command::Configure(Configure {
+                operation: Some(Operation::IncreaseDissolveDelay(IncreaseDissolveDelay {
+                    additional_dissolve_delay_seconds: args.additional_dissolve_delay_seconds,
+                })),
+            })),
+        }
+    }
+}
+
+impl From<AddHotkeyArgs> for ManageNeuron {
+    fn from(args: AddHotkeyArgs) -> Self {
+        let permissions: &[NeuronPermissionType] = if args.permissions.is_empty() {
+            DEFAULT_HOTKEY_PERMISSIONS
+        } else {
+            &args.permissions
+        };
+        ManageNeuron {
+            subaccount: args.neuron_id.id,
+            command: Some(Command::AddNeuronPermissions(AddNeuronPermissions {
+                principal_id: Some(args.principal),
+                permissions_to_add: Some(NeuronPermissionList {
+                    permissions: permissions.iter().map(|p| *p as i32).collect(),
+                }),
+            })),
+        }
+    }
+}
+
+impl From<FollowArgs> for ManageNeuron {
+    fn from(args: FollowArgs) -> Self {
+        ManageNeuron {
+            subaccount: args.neuron_id.id,
+            command: Some(Command::Follow(Follow {
+                function_id: args.function_id,
+                followees: args.followees,
+            })),
+        }
+    }
+}
+
+impl From<VoteArgs> for ManageNeuron {
+    fn from(args: VoteArgs) -> Self {
+        let vote = match args.vote {
+            VoteChoice::Yes => Vote::Yes,
+            VoteChoice::No => Vote::No,
+        };
+        ManageNeuron {
+            subaccount: args.neuron_id.id,
+            command: Some(Command::RegisterVote(RegisterVote {
+                proposal: Some(ProposalId {
+                    id: args.proposal_id,
+                }),
+                vote: vote as i32,
+            })),
+        }
+    }
+}
+
+impl From<DisburseMaturityArgs> for ManageNeuron {
+    fn from(args: DisburseMaturityArgs) -> Self {
+        ManageNeuron {
+            subaccount: args.neuron_id.id,
+            command: Some(Command::DisburseMaturity(DisburseMaturity {
+                percentage_to_disburse: args.percentage_to_disburse,
+                to_account: args.to_principal.map(|owner| Account {
+                    owner: Some(owner),
+                    subaccount: None,
+                }),
+            })),
+        }
+    }
+}
+
+/// Returns the principal of the given dfx identity, or of the current one if
+/// `identity` is `None`.
+fn get_principal_or_exit(network: &str, identity: Option<&str>) -> PrincipalId {
+    let mut command = vec!["dfx", "identity", "--network", network, "get-principal"];
+    if let Some(identity) = identity {
+        command.extend(["--identity", identity]);
+    }
+    let (stdout, _stderr) = run_command(&command).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    PrincipalId::from_str(stdout.trim()).unwrap_or_else(|err| {
+        exit_with_error(
+            &format!("Unable to parse {:?} as a principal", stdout.trim()),
+            err,
+        )
+    })
+}
+
+/// Returns `canister` if it is a canister ID, or else looks up the ID of the
+/// canister with that name (via dfx.json).
+fn resolve_canister_id_or_exit(network: &str, canister: &str) -> PrincipalId {
+    if let Ok(canister_id) = PrincipalId::from_str(canister) {
+        return canister_id;
+    }
+    let command = ["dfx", "canister", "--network", network, "id", canister];
+    let (stdout, _stderr) = run_command(&command).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    PrincipalId::from_str(stdout.trim()).unwrap_or_else(|err| {
+        exit_with_error(
+            &format!(
+                "Unable to parse {:?} as the ID of {}",
+                stdout.trim(),
+                canister
+            ),
+            err,
+        )
+    })
+}
+
+fn exit_with_error(context: &str, err: impl std::fmt::Debug) -> ! {
+    eprintln!("{}: {:?}", context, err);
+    std::process::exit(1);
+}
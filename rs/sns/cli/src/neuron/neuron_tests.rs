@@ -0,0 +1,153 @@
+use crate::{
+    neuron::{new_claim_or_refresh, new_stake_transfer, NeuronArgs, NeuronSubCommand},
+    CliArgs, SubCommand,
+};
+use candid::Nat;
+use clap::Parser;
+use ic_base_types::PrincipalId;
+use ic_nervous_system_common::ledger::compute_neuron_staking_subaccount_bytes;
+use ic_sns_governance::pb::v1::{
+    manage_neuron::{
+        claim_or_refresh::{By, MemoAndController},
+        AddNeuronPermissions, ClaimOrRefresh, Command, Follow, RegisterVote,
+    },
+    ManageNeuron, NeuronId, NeuronPermissionList, NeuronPermissionType, ProposalId, Vote,
+};
+
+const NEURON_ID: &str = "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20";
+
+fn parse_neuron_args(args: &[&str]) -> NeuronArgs {
+    let args = ["sns", "neuron"].iter().chain(args);
+    match CliArgs::try_parse_from(args).unwrap().sub_command {
+        SubCommand::Neuron(args) => args,
+        sub_command => panic!("Unexpected sub command: {:?}", sub_command),
+    }
+}
+
+fn to_manage_neuron(sub_command: NeuronSubCommand) -> ManageNeuron {
+    match sub_command {
+        NeuronSubCommand::IncreaseDissolveDelay(args) => args.into(),
+        NeuronSubCommand::AddHotkey(args) => args.into(),
+        NeuronSubCommand::Follow(args) => args.into(),
+        NeuronSubCommand::Vote(args) => args.into(),
+        NeuronSubCommand::DisburseMaturity(args) => args.into(),
+        NeuronSubCommand::Stake(args) => panic!("Stake is not a single command: {:?}", args),
+    }
+}
+
+fn neuron_id() -> NeuronId {
+    NeuronId {
+        id: hex::decode(NEURON_ID).unwrap(),
+    }
+}
+
+#[test]
+fn test_stake_transfers_to_the_staking_subaccount_and_claims_it() {
+    let governance = PrincipalId::new_user_test_id(1);
+    let controller = PrincipalId::new_user_test_id(2);
+    let subaccount = compute_neuron_staking_subaccount_bytes(controller, 42);
+
+    let transfer = new_stake_transfer(governance, controller, 42, 100_000_000);
+    assert_eq!(transfer.to.owner, governance.0);
+    assert_eq!(transfer.to.subaccount, Some(subaccount));
+    assert_eq!(transfer.amount, Nat::from(100_000_000_u64));
+
+    assert_eq!(
+        new_claim_or_refresh(controller, 42),
+        ManageNeuron {
+            subaccount: subaccount.to_vec(),
+            command: Some(Command::ClaimOrRefresh(ClaimOrRefresh {
+                by: Some(By::MemoAndController(MemoAndController {
+                    memo: 42,
+                    controller: Some(controller),
+                })),
+            })),
+        }
+    );
+}
+
+#[test]
+fn test_add_hotkey_grants_voting_permissions_by_default() {
+    let principal = PrincipalId::new_user_test_id(3);
+    let args = parse_neuron_args(&[
+        "add-hotkey",
+        "--neuron-id",
+        NEURON_ID,
+        "--principal",
+        &principal.to_string(),
+    ]);
+
+    assert_eq!(
+        to_manage_neuron(args.sub_command),
+        ManageNeuron {
+            subaccount: neuron_id().id,
+            command: Some(Command::AddNeuronPermissions(AddNeuronPermissions {
+                principal_id: Some(principal),
+                permissions_to_add: Some(NeuronPermissionList {
+                    permissions: vec![
+                        NeuronPermissionType::Vote as i32,
+                        NeuronPermissionType::SubmitProposal as i32,
+                    ],
+                }),
+            })),
+        }
+    );
+}
+
+#[test]
+fn test_follow_sets_followees_for_function() {
+    let args = parse_neuron_args(&[
+        "--network",
+        "ic",
+        "follow",
+        "--neuron-id",
+        NEURON_ID,
+        "--function-id",
+        "1000",
+        "--followees",
+        "aa",
+        "bb",
+    ]);
+    assert_eq!(args.network, "ic");
+
+    assert_eq!(
+        to_manage_neuron(args.sub_command),
+        ManageNeuron {
+            subaccount: neuron_id().id,
+            command: Some(Command::Follow(Follow {
+                function_id: 1000,
+                followees: vec![NeuronId { id: vec![0xaa] }, NeuronId { id: vec![0xbb] }],
+            })),
+        }
+    );
+}
+
+#[test]
+fn test_vote() {
+    let args = parse_neuron_args(&[
+        "vote",
+        "--neuron-id",
+        NEURON_ID,
+        "--proposal-id",
+        "7",
+        "--vote",
+        "no",
+    ]);
+
+    assert_eq!(
+        to_manage_neuron(args.sub_command),
+        ManageNeuron {
+            subaccount: neuron_id().id,
+            command: Some(Command::RegisterVote(RegisterVote {
+                proposal: Some(ProposalId { id: 7 }),
+                vote: Vote::No as i32,
+            })),
+        }
+    );
+}
+
+#[test]
+fn test_invalid_neuron_id_is_rejected() {
+    let args = ["sns", "neuron", "vote", "--neuron-id", "not hex"];
+    assert!(CliArgs::try_parse_from(args).is_err());
+}
@@ -48,8 +48,10 @@ use ic_sns_governance::{
         GetRunningSnsVersionRequest, GetRunningSnsVersionResponse,
         GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
         Governance as GovernanceProto, ListNervousSystemFunctionsResponse, ListNeurons,
-        ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
-        ManageNeuronResponse, NervousSystemParameters, RewardEvent, SetMode, SetModeResponse,
+        ListNeuronsResponse, ListProposals, ListProposalsResponse,
+        ListTreasuryDisbursementStreamsRequest, ListTreasuryDisbursementStreamsResponse,
+        ManageNeuron, ManageNeuronResponse, NervousSystemParameters, RewardEvent, SetMode,
+        SetModeResponse,
     },
     types::{Environment, HeapGrowthPotential},
 };
@@ -625,6 +627,22 @@ fn get_maturity_modulation_(
     )
 }
 
+/// Returns the streams of treasury disbursements scheduled by proposals,
+/// including how far each of them has progressed.
+#[export_name = "canister_query list_treasury_disbursement_streams"]
+fn list_treasury_disbursement_streams() {
+    log!(INFO, "list_treasury_disbursement_streams");
+    over(candid_one, list_treasury_disbursement_streams_)
+}
+
+/// Internal method for calling list_treasury_disbursement_streams.
+#[candid_method(query, rename = "list_treasury_disbursement_streams")]
+fn list_treasury_disbursement_streams_(
+    request: ListTreasuryDisbursementStreamsRequest,
+) -> ListTreasuryDisbursementStreamsResponse {
+    governance().list_treasury_disbursement_streams(request)
+}
+
 /// The canister's heartbeat.
 #[export_name = "canister_heartbeat"]
 fn canister_heartbeat() {
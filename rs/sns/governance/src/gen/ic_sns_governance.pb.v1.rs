@@ -396,6 +396,94 @@ pub mod transfer_sns_treasury_funds {
         }
     }
 }
+/// A proposal to schedule a stream of recurring transfers of SNS treasury funds
+/// to (optionally a Subaccount of) the target principal. Instead of a single
+/// immediate transfer, the governance canister makes the transfers itself, as
+/// part of its periodic tasks, as they become due.
+///
+/// The i-th disbursement (counting from 0) becomes due at
+/// `start_timestamp_seconds + i * period_seconds`, except that nothing is
+/// disbursed before `start_timestamp_seconds + cliff_duration_seconds`. Once
+/// the cliff has passed, any disbursements that became due before it are made.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScheduleTreasuryDisbursementStream {
+    /// Whether to make the transfers from the NNS ledger (in ICP) or
+    /// from the SNS ledger (in SNS tokens).
+    #[prost(enumeration = "transfer_sns_treasury_funds::TransferFrom", tag = "1")]
+    pub from_treasury: i32,
+    /// The amount to transfer in each disbursement, in e8s.
+    #[prost(uint64, tag = "2")]
+    pub amount_e8s_per_disbursement: u64,
+    /// An optional memo to use for each of the transfers.
+    #[prost(uint64, optional, tag = "3")]
+    pub memo: ::core::option::Option<u64>,
+    /// The principal to transfer the funds to.
+    #[prost(message, optional, tag = "4")]
+    pub to_principal: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// An (optional) Subaccount of the principal to transfer the funds to.
+    #[prost(message, optional, tag = "5")]
+    pub to_subaccount: ::core::option::Option<Subaccount>,
+    /// When the stream starts (seconds since UNIX epoch).
+    #[prost(uint64, tag = "6")]
+    pub start_timestamp_seconds: u64,
+    /// How long after the start no disbursements are made at all.
+    #[prost(uint64, tag = "7")]
+    pub cliff_duration_seconds: u64,
+    /// The time between two consecutive disbursements.
+    #[prost(uint64, tag = "8")]
+    pub period_seconds: u64,
+    /// The total number of disbursements to make.
+    #[prost(uint64, tag = "9")]
+    pub disbursement_count: u64,
+}
+/// A proposal to cancel a stream of treasury disbursements that was previously
+/// scheduled by a ScheduleTreasuryDisbursementStream proposal. Disbursements
+/// that have already been made are not affected.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelTreasuryDisbursementStream {
+    /// The id of the stream to cancel, which is the id of the proposal that
+    /// scheduled it.
+    #[prost(uint64, tag = "1")]
+    pub stream_id: u64,
+}
+/// The progress of a stream of treasury disbursements.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TreasuryDisbursementStream {
+    /// The id of the stream, which is the id of the proposal that scheduled it.
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    /// The schedule of the stream, as adopted.
+    #[prost(message, optional, tag = "2")]
+    pub schedule: ::core::option::Option<ScheduleTreasuryDisbursementStream>,
+    /// The number of disbursements that have been made so far.
+    #[prost(uint64, tag = "3")]
+    pub disbursements_completed: u64,
+    /// The total amount disbursed so far, in e8s.
+    #[prost(uint64, tag = "4")]
+    pub disbursed_e8s: u64,
+    /// When the last disbursement was made (seconds since UNIX epoch).
+    #[prost(uint64, optional, tag = "5")]
+    pub last_disbursement_timestamp_seconds: ::core::option::Option<u64>,
+    /// If the stream was cancelled, when this happened (seconds since UNIX
+    /// epoch) and which proposal did it.
+    #[prost(uint64, optional, tag = "6")]
+    pub cancelled_timestamp_seconds: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "7")]
+    pub cancelled_by_proposal_id: ::core::option::Option<u64>,
+    /// The reason why the most recent attempt to make a disbursement failed, and
+    /// when that was (seconds since UNIX epoch), if it failed. Cleared on the
+    /// next successful disbursement.
+    #[prost(string, optional, tag = "8")]
+    pub last_failure_reason: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(uint64, optional, tag = "9")]
+    pub last_failure_timestamp_seconds: ::core::option::Option<u64>,
+}
 /// A proposal function that changes the ledger's parameters.
 /// Fields with None values will remain unchanged.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -504,7 +592,7 @@ pub struct Proposal {
     /// of this mapping.
     #[prost(
         oneof = "proposal::Action",
-        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17"
+        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19"
     )]
     pub action: ::core::option::Option<proposal::Action>,
 }
@@ -605,6 +693,16 @@ pub mod proposal {
         /// Id = 13
         #[prost(message, tag = "17")]
         ManageLedgerParameters(super::ManageLedgerParameters),
+        /// Schedule a stream of recurring SNS treasury transfers to an account.
+        ///
+        /// Id = 14.
+        #[prost(message, tag = "18")]
+        ScheduleTreasuryDisbursementStream(super::ScheduleTreasuryDisbursementStream),
+        /// Cancel a previously scheduled stream of SNS treasury transfers.
+        ///
+        /// Id = 15.
+        #[prost(message, tag = "19")]
+        CancelTreasuryDisbursementStream(super::CancelTreasuryDisbursementStream),
     }
 }
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -1312,6 +1410,16 @@ pub struct Governance {
     pub is_finalizing_disburse_maturity: ::core::option::Option<bool>,
     #[prost(message, optional, tag = "26")]
     pub maturity_modulation: ::core::option::Option<governance::MaturityModulation>,
+    /// Streams of treasury disbursements scheduled by proposals, keyed by stream
+    /// id (the id of the proposal that scheduled the stream). Streams that have
+    /// completed or have been cancelled are kept as a record of what was paid.
+    #[prost(btree_map = "uint64, message", tag = "27")]
+    pub treasury_disbursement_streams:
+        ::prost::alloc::collections::BTreeMap<u64, TreasuryDisbursementStream>,
+    /// True if the heartbeat function is currently making treasury disbursements,
+    /// meaning that it should finish before being called again.
+    #[prost(bool, optional, tag = "28")]
+    pub is_processing_treasury_disbursement_streams: ::core::option::Option<bool>,
 }
 /// Nested message and enum types in `Governance`.
 pub mod governance {
@@ -2453,6 +2561,19 @@ pub struct GetMaturityModulationResponse {
     #[prost(message, optional, tag = "1")]
     pub maturity_modulation: ::core::option::Option<governance::MaturityModulation>,
 }
+/// Request message for 'list_treasury_disbursement_streams'.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTreasuryDisbursementStreamsRequest {}
+/// Response message for 'list_treasury_disbursement_streams'.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTreasuryDisbursementStreamsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub streams: ::prost::alloc::vec::Vec<TreasuryDisbursementStream>,
+}
 /// A request to add maturity to a neuron. The associated endpoint is only
 /// available when governance is compiled with the `test` feature enabled.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -39,22 +39,25 @@ use crate::{
             neuron::{DissolveState, Followees},
             proposal::Action,
             transfer_sns_treasury_funds::TransferFrom,
-            Account as AccountProto, Ballot, ClaimSwapNeuronsError, ClaimSwapNeuronsRequest,
-            ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus, DefaultFollowees,
-            DeregisterDappCanisters, DisburseMaturityInProgress, Empty,
-            ExecuteGenericNervousSystemFunction, FailStuckUpgradeInProgressRequest,
-            FailStuckUpgradeInProgressResponse, GetMaturityModulationRequest,
-            GetMaturityModulationResponse, GetMetadataRequest, GetMetadataResponse, GetMode,
-            GetModeResponse, GetNeuron, GetNeuronResponse, GetProposal, GetProposalResponse,
-            GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
-            Governance as GovernanceProto, GovernanceError, ListNervousSystemFunctionsResponse,
-            ListNeurons, ListNeuronsResponse, ListProposals, ListProposalsResponse,
-            ManageLedgerParameters, ManageNeuron, ManageNeuronResponse, ManageSnsMetadata,
-            MintSnsTokens, NervousSystemFunction, NervousSystemParameters, Neuron, NeuronId,
-            NeuronPermission, NeuronPermissionList, NeuronPermissionType, Proposal, ProposalData,
-            ProposalDecisionStatus, ProposalId, ProposalRewardStatus, RegisterDappCanisters,
-            RewardEvent, Tally, TransferSnsTreasuryFunds, UpgradeSnsControlledCanister,
-            UpgradeSnsToNextVersion, Vote, VotingRewardsParameters, WaitForQuietState,
+            Account as AccountProto, Ballot, CancelTreasuryDisbursementStream,
+            ClaimSwapNeuronsError, ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse,
+            ClaimedSwapNeuronStatus, DefaultFollowees, DeregisterDappCanisters,
+            DisburseMaturityInProgress, Empty, ExecuteGenericNervousSystemFunction,
+            FailStuckUpgradeInProgressRequest, FailStuckUpgradeInProgressResponse,
+            GetMaturityModulationRequest, GetMaturityModulationResponse, GetMetadataRequest,
+            GetMetadataResponse, GetMode, GetModeResponse, GetNeuron, GetNeuronResponse,
+            GetProposal, GetProposalResponse, GetSnsInitializationParametersRequest,
+            GetSnsInitializationParametersResponse, Governance as GovernanceProto, GovernanceError,
+            ListNervousSystemFunctionsResponse, ListNeurons, ListNeuronsResponse, ListProposals,
+            ListProposalsResponse, ListTreasuryDisbursementStreamsRequest,
+            ListTreasuryDisbursementStreamsResponse, ManageLedgerParameters, ManageNeuron,
+            ManageNeuronResponse, ManageSnsMetadata, MintSnsTokens, NervousSystemFunction,
+            NervousSystemParameters, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
+            NeuronPermissionType, Proposal, ProposalData, ProposalDecisionStatus, ProposalId,
+            ProposalRewardStatus, RegisterDappCanisters, RewardEvent,
+            ScheduleTreasuryDisbursementStream, Tally, TransferSnsTreasuryFunds,
+            TreasuryDisbursementStream, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion,
+            Vote, VotingRewardsParameters, WaitForQuietState,
         },
     },
     proposal::{
@@ -148,6 +151,10 @@ pub fn log_prefix() -> String {
 /// The static MEMO used when calculating the SNS Treasury subaccount.
 pub const TREASURY_SUBACCOUNT_NONCE: u64 = 0;
 
+/// How long to wait before retrying a treasury disbursement that failed (e.g.
+/// because the treasury did not hold enough funds at the time).
+pub const TREASURY_DISBURSEMENT_RETRY_INTERVAL_SECONDS: u64 = 60 * 60;
+
 /// Converts bytes to a subaccountpub fn bytes_to_subaccount(bytes: &[u8]) -> Result<icrc_ledger_types::icrc1::account::Subaccount, GovernanceError> {
 pub fn bytes_to_subaccount(
     bytes: &[u8],
@@ -157,6 +164,27 @@ pub fn bytes_to_subaccount(
     })
 }
 
+/// Returns the account that the disbursements of a treasury disbursement
+/// stream are made to.
+fn schedule_to_account(
+    schedule: &ScheduleTreasuryDisbursementStream,
+) -> Result<Account, GovernanceError> {
+    let owner = schedule.to_principal.ok_or_else(|| {
+        GovernanceError::new_with_message(
+            ErrorType::InvalidProposal,
+            "Expected treasury disbursement stream to have a target principal",
+        )
+    })?;
+    Ok(Account {
+        owner: owner.0,
+        subaccount: schedule
+            .to_subaccount
+            .as_ref()
+            .map(|s| bytes_to_subaccount(&s.subaccount[..]))
+            .transpose()?,
+    })
+}
+
 impl NeuronPermissionType {
     /// Returns all the different types of neuron permissions as a vector.
     pub fn all() -> Vec<i32> {
@@ -2066,6 +2094,12 @@ impl Governance {
                 self.perform_manage_ledger_parameters(proposal_id, manage_ledger_parameters)
                     .await
             }
+            Action::ScheduleTreasuryDisbursementStream(schedule) => {
+                self.perform_schedule_treasury_disbursement_stream(proposal_id, schedule)
+            }
+            Action::CancelTreasuryDisbursementStream(cancel) => {
+                self.perform_cancel_treasury_disbursement_stream(proposal_id, cancel)
+            }
             // This should not be possible, because Proposal validation is performed when
             // a proposal is first made.
             Action::Unspecified(_) => Err(GovernanceError::new_with_message(
@@ -2574,15 +2608,32 @@ impl Governance {
                     .expect("Couldn't transform transfer.subaccount to Subaccount")
             }),
         };
-        match transfer.from_treasury() {
+        self.transfer_from_treasury(
+            transfer.from_treasury(),
+            transfer.amount_e8s,
+            to,
+            transfer.memo.unwrap_or(0),
+        )
+        .await
+    }
+
+    /// Transfers `amount_e8s` from the given treasury to `to`.
+    async fn transfer_from_treasury(
+        &self,
+        from_treasury: TransferFrom,
+        amount_e8s: u64,
+        to: Account,
+        memo: u64,
+    ) -> Result<(), GovernanceError> {
+        match from_treasury {
             TransferFrom::IcpTreasury => self
                 .nns_ledger
                 .transfer_funds(
-                    transfer.amount_e8s,
+                    amount_e8s,
                     NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
                     None,
                     to,
-                    transfer.memo.unwrap_or(0),
+                    memo,
                 )
                 .await
                 .map(|_| ())
@@ -2601,11 +2652,11 @@ impl Governance {
                 );
                 self.ledger
                     .transfer_funds(
-                        transfer.amount_e8s,
+                        amount_e8s,
                         transaction_fee_e8s,
                         Some(treasury_subaccount),
                         to,
-                        transfer.memo.unwrap_or(0),
+                        memo,
                     )
                     .await
                     .map(|_| ())
@@ -2623,6 +2674,72 @@ impl Governance {
         }
     }
 
+    /// Records a new stream of treasury disbursements. The disbursements
+    /// themselves are made by `maybe_make_treasury_disbursements`.
+    fn perform_schedule_treasury_disbursement_stream(
+        &mut self,
+        proposal_id: u64,
+        schedule: ScheduleTreasuryDisbursementStream,
+    ) -> Result<(), GovernanceError> {
+        // Make sure that the stream can actually be paid out before recording it.
+        schedule_to_account(&schedule)?;
+
+        match self.proto.treasury_disbursement_streams.entry(proposal_id) {
+            Entry::Occupied(_) => Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "A treasury disbursement stream with id {} already exists.",
+                    proposal_id
+                ),
+            )),
+            Entry::Vacant(entry) => {
+                entry.insert(TreasuryDisbursementStream {
+                    id: proposal_id,
+                    schedule: Some(schedule),
+                    ..Default::default()
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Stops a stream of treasury disbursements. Disbursements that have
+    /// already been made are kept on record.
+    fn perform_cancel_treasury_disbursement_stream(
+        &mut self,
+        proposal_id: u64,
+        cancel: CancelTreasuryDisbursementStream,
+    ) -> Result<(), GovernanceError> {
+        let now = self.env.now();
+        let stream = self
+            .proto
+            .treasury_disbursement_streams
+            .get_mut(&cancel.stream_id)
+            .ok_or_else(|| {
+                GovernanceError::new_with_message(
+                    ErrorType::NotFound,
+                    format!(
+                        "There is no treasury disbursement stream with id {}.",
+                        cancel.stream_id
+                    ),
+                )
+            })?;
+
+        if !stream.is_active() {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "Treasury disbursement stream {} is already cancelled or completed.",
+                    cancel.stream_id
+                ),
+            ));
+        }
+
+        stream.cancelled_timestamp_seconds = Some(now);
+        stream.cancelled_by_proposal_id = Some(proposal_id);
+        Ok(())
+    }
+
     async fn perform_mint_sns_tokens(
         &mut self,
         mint: MintSnsTokens,
@@ -4401,6 +4518,106 @@ impl Governance {
         self.proto.is_finalizing_disburse_maturity = None;
     }
 
+    /// Makes the next disbursement of every treasury disbursement stream that
+    /// has one due, unless this is already happening.
+    ///
+    /// At most one disbursement per stream is made per call, so a stream that
+    /// fell behind (e.g. at its cliff) catches up over consecutive heartbeats.
+    async fn maybe_make_treasury_disbursements(&mut self) {
+        if self
+            .proto
+            .is_processing_treasury_disbursement_streams
+            .unwrap_or(false)
+        {
+            return;
+        }
+
+        let now_seconds = self.env.now();
+        let due_streams: Vec<TreasuryDisbursementStream> = self
+            .proto
+            .treasury_disbursement_streams
+            .values()
+            .filter(|stream| stream.is_disbursement_due(now_seconds))
+            .filter(|stream| match stream.last_failure_timestamp_seconds {
+                Some(last_failure_timestamp_seconds) => {
+                    now_seconds
+                        >= last_failure_timestamp_seconds
+                            .saturating_add(TREASURY_DISBURSEMENT_RETRY_INTERVAL_SECONDS)
+                }
+                None => true,
+            })
+            .cloned()
+            .collect();
+        if due_streams.is_empty() {
+            return;
+        }
+
+        self.proto.is_processing_treasury_disbursement_streams = Some(true);
+        for stream in due_streams {
+            let schedule = match stream.schedule.as_ref() {
+                Some(schedule) => schedule,
+                None => continue,
+            };
+
+            let transfer_result = match schedule_to_account(schedule) {
+                Ok(to) => {
+                    self.transfer_from_treasury(
+                        schedule.from_treasury(),
+                        schedule.amount_e8s_per_disbursement,
+                        to,
+                        schedule.memo.unwrap_or(0),
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+
+            // The stream is looked up again, as it may have been cancelled while
+            // the transfer was in flight. A disbursement that was made is recorded
+            // regardless.
+            let stream_mut = match self.proto.treasury_disbursement_streams.get_mut(&stream.id) {
+                Some(stream_mut) => stream_mut,
+                None => {
+                    log!(
+                        ERROR,
+                        "Treasury disbursement stream {} disappeared while making a disbursement.",
+                        stream.id
+                    );
+                    continue;
+                }
+            };
+            match transfer_result {
+                Ok(()) => {
+                    stream_mut.disbursements_completed += 1;
+                    stream_mut.disbursed_e8s = stream_mut
+                        .disbursed_e8s
+                        .saturating_add(schedule.amount_e8s_per_disbursement);
+                    stream_mut.last_disbursement_timestamp_seconds = Some(now_seconds);
+                    stream_mut.last_failure_reason = None;
+                    stream_mut.last_failure_timestamp_seconds = None;
+                    log!(
+                        INFO,
+                        "Made disbursement {} of {} of treasury disbursement stream {}.",
+                        stream_mut.disbursements_completed,
+                        schedule.disbursement_count,
+                        stream.id
+                    );
+                }
+                Err(e) => {
+                    log!(
+                        ERROR,
+                        "Failed making a disbursement of treasury disbursement stream {}: {}",
+                        stream.id,
+                        e
+                    );
+                    stream_mut.last_failure_reason = Some(e.to_string());
+                    stream_mut.last_failure_timestamp_seconds = Some(now_seconds);
+                }
+            }
+        }
+        self.proto.is_processing_treasury_disbursement_streams = None;
+    }
+
     /// When a neuron is finally dissolved, if there is any staked maturity it is moved to regular maturity
     /// which can be spawned.
     pub(crate) fn maybe_move_staked_maturity(&mut self) {
@@ -4531,6 +4748,8 @@ impl Governance {
 
         self.maybe_finalize_disburse_maturity().await;
 
+        self.maybe_make_treasury_disbursements().await;
+
         self.maybe_move_staked_maturity();
 
         self.maybe_gc();
@@ -5310,6 +5529,20 @@ impl Governance {
         }
     }
 
+    pub fn list_treasury_disbursement_streams(
+        &self,
+        _: ListTreasuryDisbursementStreamsRequest,
+    ) -> ListTreasuryDisbursementStreamsResponse {
+        ListTreasuryDisbursementStreamsResponse {
+            streams: self
+                .proto
+                .treasury_disbursement_streams
+                .values()
+                .cloned()
+                .collect(),
+        }
+    }
+
     #[cfg(feature = "test")]
     pub fn add_maturity(
         &mut self,
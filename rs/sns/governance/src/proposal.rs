@@ -8,10 +8,11 @@ use crate::{
         proposal,
         proposal::Action,
         transfer_sns_treasury_funds::TransferFrom,
-        DeregisterDappCanisters, ExecuteGenericNervousSystemFunction, Governance,
-        ManageLedgerParameters, ManageSnsMetadata, MintSnsTokens, Motion, NervousSystemFunction,
-        NervousSystemParameters, Proposal, ProposalData, ProposalDecisionStatus,
-        ProposalRewardStatus, RegisterDappCanisters, Tally, TransferSnsTreasuryFunds,
+        CancelTreasuryDisbursementStream, DeregisterDappCanisters,
+        ExecuteGenericNervousSystemFunction, Governance, ManageLedgerParameters, ManageSnsMetadata,
+        MintSnsTokens, Motion, NervousSystemFunction, NervousSystemParameters, Proposal,
+        ProposalData, ProposalDecisionStatus, ProposalRewardStatus, RegisterDappCanisters,
+        ScheduleTreasuryDisbursementStream, Tally, TransferSnsTreasuryFunds,
         UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
     },
 };
@@ -54,6 +55,10 @@ pub const MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS: usize = 700;
 /// The maximum number of GenericNervousSystemFunctions the system allows.
 pub const MAX_NUMBER_OF_GENERIC_NERVOUS_SYSTEM_FUNCTIONS: usize = 200_000;
 
+/// The maximum number of disbursements that a single
+/// ScheduleTreasuryDisbursementStream proposal can schedule.
+pub const MAX_TREASURY_DISBURSEMENT_STREAM_COUNT: u64 = 1_000;
+
 /// The maximum number of dapps that can be registered in a single
 /// RegisterDappCanisters proposal.
 pub const MAX_NUMBER_OF_DAPPS_TO_REGISTER_PER_PROPOSAL: usize = 1_000;
@@ -249,6 +254,20 @@ pub async fn validate_and_render_action(
         proposal::Action::ManageLedgerParameters(manage_ledger_parameters) => {
             validate_and_render_manage_ledger_parameters(manage_ledger_parameters)
         }
+        proposal::Action::ScheduleTreasuryDisbursementStream(schedule) => {
+            let sns_transfer_fee_e8s = governance_proto
+                .parameters
+                .as_ref()
+                .and_then(|params| params.transaction_fee_e8s)
+                .unwrap_or(DEFAULT_TRANSFER_FEE.get_e8s());
+            validate_and_render_schedule_treasury_disbursement_stream(
+                schedule,
+                sns_transfer_fee_e8s,
+            )
+        }
+        proposal::Action::CancelTreasuryDisbursementStream(cancel) => {
+            validate_and_render_cancel_treasury_disbursement_stream(cancel, governance_proto)
+        }
     }
 }
 
@@ -373,6 +392,183 @@ fn validate_and_render_transfer_sns_treasury_funds(
     ))
 }
 
+/// Validates and render ScheduleTreasuryDisbursementStream proposal
+fn validate_and_render_schedule_treasury_disbursement_stream(
+    schedule: &ScheduleTreasuryDisbursementStream,
+    sns_transfer_fee_e8s: u64,
+) -> Result<String, String> {
+    let mut defects: Vec<String> = vec![];
+    let (from, unit) = match schedule.from_treasury() {
+        TransferFrom::IcpTreasury => ("ICP Treasury (ICP Ledger)", "ICP"),
+        TransferFrom::SnsTokenTreasury => ("SNS Token Treasury (SNS Ledger)", "SNS Tokens"),
+        TransferFrom::Unspecified => {
+            defects.push(
+                "Must specify a treasury from which to transfer the funds (ICP/SNS Token)."
+                    .to_string(),
+            );
+            ("", "")
+        }
+    };
+
+    // Each disbursement is a regular treasury transfer, so it is subject to the
+    // same minimum as a TransferSnsTreasuryFunds proposal.
+    let minimum_transaction = match schedule.from_treasury() {
+        TransferFrom::IcpTreasury => NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
+        TransferFrom::SnsTokenTreasury => sns_transfer_fee_e8s,
+        TransferFrom::Unspecified => 0,
+    };
+
+    if schedule.amount_e8s_per_disbursement < minimum_transaction {
+        defects.push(format!(
+            "For transactions from {}, the fee and minimum transaction is {} e8s",
+            from, minimum_transaction
+        ))
+    }
+
+    if schedule.disbursement_count == 0 {
+        defects.push("disbursement_count must be at least 1.".to_string());
+    } else if schedule.disbursement_count > MAX_TREASURY_DISBURSEMENT_STREAM_COUNT {
+        defects.push(format!(
+            "disbursement_count must be at most {}.",
+            MAX_TREASURY_DISBURSEMENT_STREAM_COUNT
+        ));
+    }
+
+    if schedule.disbursement_count > 1 && schedule.period_seconds == 0 {
+        defects.push(
+            "period_seconds must be positive when more than one disbursement is scheduled."
+                .to_string(),
+        );
+    }
+
+    let total_amount_e8s = schedule
+        .amount_e8s_per_disbursement
+        .checked_mul(schedule.disbursement_count);
+    if total_amount_e8s.is_none() {
+        defects.push("The total amount of the stream overflows u64.".to_string());
+    }
+
+    let last_disbursement_timestamp_seconds = schedule
+        .period_seconds
+        .checked_mul(schedule.disbursement_count.saturating_sub(1))
+        .and_then(|offset| schedule.start_timestamp_seconds.checked_add(offset))
+        .and_then(|last| {
+            schedule
+                .start_timestamp_seconds
+                .checked_add(schedule.cliff_duration_seconds)
+                .map(|cliff| last.max(cliff))
+        });
+    if last_disbursement_timestamp_seconds.is_none() {
+        defects.push("The schedule of the stream overflows u64 timestamps.".to_string());
+    }
+
+    let to_principal = if let Some(to_principal) = schedule.to_principal {
+        if to_principal == PrincipalId::new_anonymous() {
+            defects.push("to_principal must not be anonymous.".to_string());
+        }
+        to_principal
+    } else {
+        defects.push("Must specify a principal to make the transfers to.".to_string());
+        PrincipalId::new_anonymous()
+    };
+
+    let to_account = match &schedule.to_subaccount {
+        None => Account {
+            owner: to_principal.0,
+            subaccount: None,
+        }
+        .to_string(),
+        Some(s) => match bytes_to_subaccount(&s.subaccount[..]) {
+            Ok(s) => Account {
+                owner: to_principal.0,
+                subaccount: Some(s),
+            }
+            .to_string(),
+            Err(e) => {
+                defects.push(e.error_message);
+                "".to_string()
+            }
+        },
+    };
+
+    // Generate final report.
+    if !defects.is_empty() {
+        return Err(format!(
+            "ScheduleTreasuryDisbursementStream proposal was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    let display_amount_tokens = i2d(schedule.amount_e8s_per_disbursement) / i2d(E8);
+    let total_amount_e8s = total_amount_e8s.unwrap_or_default();
+    let display_total_amount_tokens = i2d(total_amount_e8s) / i2d(E8);
+
+    Ok(format!(
+        r"# Proposal to schedule a stream of SNS Treasury disbursements:
+## Source treasury: {from}
+## Amount per disbursement: {display_amount_tokens:.8} {unit}
+## Amount per disbursement (e8s): {amount_e8s}
+## Number of disbursements: {count}
+## Total amount: {display_total_amount_tokens:.8} {unit}
+## Total amount (e8s): {total_amount_e8s}
+## Start (seconds since UNIX epoch): {start}
+## Cliff duration (seconds): {cliff}
+## Period (seconds): {period}
+## Last disbursement due (seconds since UNIX epoch): {last}
+## Target principal: {to_principal}
+## Target account: {to_account}
+## Memo: {memo}",
+        amount_e8s = schedule.amount_e8s_per_disbursement,
+        count = schedule.disbursement_count,
+        start = schedule.start_timestamp_seconds,
+        cliff = schedule.cliff_duration_seconds,
+        period = schedule.period_seconds,
+        last = last_disbursement_timestamp_seconds.unwrap_or_default(),
+        memo = schedule.memo.unwrap_or(0)
+    ))
+}
+
+/// Validates and render CancelTreasuryDisbursementStream proposal
+fn validate_and_render_cancel_treasury_disbursement_stream(
+    cancel: &CancelTreasuryDisbursementStream,
+    governance_proto: &Governance,
+) -> Result<String, String> {
+    let stream = governance_proto
+        .treasury_disbursement_streams
+        .get(&cancel.stream_id)
+        .ok_or_else(|| {
+            format!(
+                "CancelTreasuryDisbursementStream proposal was invalid: \
+                 there is no treasury disbursement stream with id {}.",
+                cancel.stream_id
+            )
+        })?;
+
+    if !stream.is_active() {
+        return Err(format!(
+            "CancelTreasuryDisbursementStream proposal was invalid: \
+             treasury disbursement stream {} is already cancelled or completed.",
+            cancel.stream_id
+        ));
+    }
+
+    let disbursement_count = stream
+        .schedule
+        .as_ref()
+        .map(|schedule| schedule.disbursement_count)
+        .unwrap_or_default();
+
+    Ok(format!(
+        r"# Proposal to cancel a stream of SNS Treasury disbursements:
+## Stream id: {stream_id}
+## Disbursements completed: {completed} of {disbursement_count}
+## Amount disbursed so far (e8s): {disbursed_e8s}",
+        stream_id = cancel.stream_id,
+        completed = stream.disbursements_completed,
+        disbursed_e8s = stream.disbursed_e8s,
+    ))
+}
+
 /// Validates and render MintSnsTokens proposal
 fn validate_and_render_mint_sns_tokens(
     mint: &MintSnsTokens,
@@ -1482,6 +1678,8 @@ mod tests {
             sns_initialization_parameters: "".to_string(),
             is_finalizing_disburse_maturity: None,
             maturity_modulation: None,
+            treasury_disbursement_streams: Default::default(),
+            is_processing_treasury_disbursement_streams: None,
         }
     }
 
@@ -2644,6 +2842,97 @@ Version {
         );
     }
 
+    #[test]
+    fn validate_and_render_schedule_treasury_disbursement_stream_invalid_schedule() {
+        assert_eq!(
+            validate_and_render_schedule_treasury_disbursement_stream(
+                &ScheduleTreasuryDisbursementStream {
+                    from_treasury: TransferFrom::IcpTreasury.into(),
+                    amount_e8s_per_disbursement: 1000000,
+                    memo: None,
+                    to_principal: Some(basic_principal_id()),
+                    to_subaccount: None,
+                    start_timestamp_seconds: 0,
+                    cliff_duration_seconds: 0,
+                    period_seconds: 0,
+                    disbursement_count: 0,
+                },
+                0
+            )
+            .unwrap_err(),
+            "ScheduleTreasuryDisbursementStream proposal was invalid for the following reason(s):\ndisbursement_count must be at least 1."
+        );
+        assert_eq!(
+            validate_and_render_schedule_treasury_disbursement_stream(
+                &ScheduleTreasuryDisbursementStream {
+                    from_treasury: TransferFrom::IcpTreasury.into(),
+                    amount_e8s_per_disbursement: 1000,
+                    memo: None,
+                    to_principal: Some(basic_principal_id()),
+                    to_subaccount: None,
+                    start_timestamp_seconds: 0,
+                    cliff_duration_seconds: 0,
+                    period_seconds: 0,
+                    disbursement_count: 2,
+                },
+                0
+            )
+            .unwrap_err(),
+            "ScheduleTreasuryDisbursementStream proposal was invalid for the following reason(s):\n\
+             For transactions from ICP Treasury (ICP Ledger), the fee and minimum transaction is 10000 e8s\n\
+             period_seconds must be positive when more than one disbursement is scheduled."
+        );
+    }
+
+    #[test]
+    fn validate_and_render_schedule_treasury_disbursement_stream_renders_for_valid_inputs() {
+        assert_eq!(
+            validate_and_render_schedule_treasury_disbursement_stream(
+                &ScheduleTreasuryDisbursementStream {
+                    from_treasury: TransferFrom::SnsTokenTreasury.into(),
+                    amount_e8s_per_disbursement: 1000000,
+                    memo: Some(1000),
+                    to_principal: Some(basic_principal_id()),
+                    to_subaccount: None,
+                    start_timestamp_seconds: 1000,
+                    cliff_duration_seconds: 500,
+                    period_seconds: 100,
+                    disbursement_count: 12,
+                },
+                0
+            )
+            .unwrap(),
+            r"# Proposal to schedule a stream of SNS Treasury disbursements:
+## Source treasury: SNS Token Treasury (SNS Ledger)
+## Amount per disbursement: 0.01000000 SNS Tokens
+## Amount per disbursement (e8s): 1000000
+## Number of disbursements: 12
+## Total amount: 0.12000000 SNS Tokens
+## Total amount (e8s): 12000000
+## Start (seconds since UNIX epoch): 1000
+## Cliff duration (seconds): 500
+## Period (seconds): 100
+## Last disbursement due (seconds since UNIX epoch): 2100
+## Target principal: bg4sm-wzk
+## Target account: bg4sm-wzk
+## Memo: 1000"
+        );
+    }
+
+    #[test]
+    fn validate_and_render_cancel_treasury_disbursement_stream_unknown_stream() {
+        let governance_proto = governance_proto_for_proposal_tests(None);
+        assert_eq!(
+            validate_and_render_cancel_treasury_disbursement_stream(
+                &CancelTreasuryDisbursementStream { stream_id: 42 },
+                &governance_proto,
+            )
+            .unwrap_err(),
+            "CancelTreasuryDisbursementStream proposal was invalid: \
+             there is no treasury disbursement stream with id 42."
+        );
+    }
+
     #[test]
     fn validate_and_render_mint_sns_tokens_renders_for_valid_inputs() {
         // Valid case
@@ -21,13 +21,14 @@ use crate::{
             nervous_system_function::FunctionType,
             neuron::Followees,
             proposal::Action,
-            ClaimSwapNeuronsError, ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus,
-            DefaultFollowees, DeregisterDappCanisters, Empty, ExecuteGenericNervousSystemFunction,
-            GovernanceError, ManageNeuronResponse, MintSnsTokens, Motion, NervousSystemFunction,
-            NervousSystemParameters, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
-            NeuronPermissionType, ProposalId, RegisterDappCanisters, RewardEvent,
-            TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
-            VotingRewardsParameters,
+            CancelTreasuryDisbursementStream, ClaimSwapNeuronsError, ClaimSwapNeuronsResponse,
+            ClaimedSwapNeuronStatus, DefaultFollowees, DeregisterDappCanisters, Empty,
+            ExecuteGenericNervousSystemFunction, GovernanceError, ManageNeuronResponse,
+            MintSnsTokens, Motion, NervousSystemFunction, NervousSystemParameters, Neuron,
+            NeuronId, NeuronPermission, NeuronPermissionList, NeuronPermissionType, ProposalId,
+            RegisterDappCanisters, RewardEvent, ScheduleTreasuryDisbursementStream,
+            TransferSnsTreasuryFunds, TreasuryDisbursementStream, UpgradeSnsControlledCanister,
+            UpgradeSnsToNextVersion, Vote, VotingRewardsParameters,
         },
     },
     proposal::ValidGenericNervousSystemFunction,
@@ -110,6 +111,12 @@ pub mod native_action_ids {
 
     /// ManageLedgerParameters Action.
     pub const MANAGE_LEDGER_PARAMETERS: u64 = 13;
+
+    /// ScheduleTreasuryDisbursementStream Action.
+    pub const SCHEDULE_TREASURY_DISBURSEMENT_STREAM: u64 = 14;
+
+    /// CancelTreasuryDisbursementStream Action.
+    pub const CANCEL_TREASURY_DISBURSEMENT_STREAM: u64 = 15;
 }
 
 impl governance::Mode {
@@ -235,6 +242,15 @@ impl governance::Mode {
                 )
             )),
 
+            Action::ScheduleTreasuryDisbursementStream(_) => Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "ScheduleTreasuryDisbursementStream proposals are not allowed while \
+                        governance is in PreInitializationSwap mode: {:#?}",
+                    action
+                )
+            )),
+
             _ => Ok(()),
         }
     }
@@ -1097,6 +1113,26 @@ impl From<Action> for NervousSystemFunction {
                 ),
                 function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
             },
+            Action::ScheduleTreasuryDisbursementStream(_) => NervousSystemFunction {
+                id: native_action_ids::SCHEDULE_TREASURY_DISBURSEMENT_STREAM,
+                name: "Schedule treasury disbursement stream".to_string(),
+                description: Some(
+                    "Proposal to schedule recurring transfers of funds from an SNS Governance \
+                     controlled treasury account"
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            Action::CancelTreasuryDisbursementStream(_) => NervousSystemFunction {
+                id: native_action_ids::CANCEL_TREASURY_DISBURSEMENT_STREAM,
+                name: "Cancel treasury disbursement stream".to_string(),
+                description: Some(
+                    "Proposal to cancel the remaining transfers of a previously scheduled \
+                     treasury disbursement stream."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
         }
     }
 }
@@ -1521,9 +1557,10 @@ impl Action {
     fn proposal_criticality(&self) -> ProposalCriticality {
         use Action::*;
         match self {
-            DeregisterDappCanisters(_) | TransferSnsTreasuryFunds(_) | MintSnsTokens(_) => {
-                ProposalCriticality::Critical
-            }
+            DeregisterDappCanisters(_)
+            | TransferSnsTreasuryFunds(_)
+            | MintSnsTokens(_)
+            | ScheduleTreasuryDisbursementStream(_) => ProposalCriticality::Critical,
 
             Unspecified(_)
             | ManageNervousSystemParameters(_)
@@ -1535,7 +1572,8 @@ impl Action {
             | UpgradeSnsToNextVersion(_)
             | ManageSnsMetadata(_)
             | ManageLedgerParameters(_)
-            | RegisterDappCanisters(_) => ProposalCriticality::Normal,
+            | RegisterDappCanisters(_)
+            | CancelTreasuryDisbursementStream(_) => ProposalCriticality::Normal,
         }
     }
 }
@@ -1672,6 +1710,12 @@ impl From<&Action> for u64 {
             Action::TransferSnsTreasuryFunds(_) => native_action_ids::TRANSFER_SNS_TREASURY_FUNDS,
             Action::MintSnsTokens(_) => native_action_ids::MINT_SNS_TOKENS,
             Action::ManageLedgerParameters(_) => native_action_ids::MANAGE_LEDGER_PARAMETERS,
+            Action::ScheduleTreasuryDisbursementStream(_) => {
+                native_action_ids::SCHEDULE_TREASURY_DISBURSEMENT_STREAM
+            }
+            Action::CancelTreasuryDisbursementStream(_) => {
+                native_action_ids::CANCEL_TREASURY_DISBURSEMENT_STREAM
+            }
         }
     }
 }
@@ -2135,6 +2179,62 @@ impl From<MintSnsTokens> for Action {
     }
 }
 
+impl From<ScheduleTreasuryDisbursementStream> for Action {
+    fn from(schedule_treasury_disbursement_stream: ScheduleTreasuryDisbursementStream) -> Action {
+        Action::ScheduleTreasuryDisbursementStream(schedule_treasury_disbursement_stream)
+    }
+}
+
+impl From<CancelTreasuryDisbursementStream> for Action {
+    fn from(cancel_treasury_disbursement_stream: CancelTreasuryDisbursementStream) -> Action {
+        Action::CancelTreasuryDisbursementStream(cancel_treasury_disbursement_stream)
+    }
+}
+
+impl ScheduleTreasuryDisbursementStream {
+    /// Returns the time (in seconds since the UNIX epoch) at which the
+    /// disbursement with the given (0-based) index becomes due, taking the
+    /// cliff into account, or None if that time is not representable.
+    pub fn disbursement_due_timestamp_seconds(&self, index: u64) -> Option<u64> {
+        let scheduled = self
+            .period_seconds
+            .checked_mul(index)
+            .and_then(|offset| self.start_timestamp_seconds.checked_add(offset))?;
+        let cliff = self
+            .start_timestamp_seconds
+            .checked_add(self.cliff_duration_seconds)?;
+        Some(scheduled.max(cliff))
+    }
+}
+
+impl TreasuryDisbursementStream {
+    /// Returns true if the stream has been neither cancelled nor completed.
+    pub fn is_active(&self) -> bool {
+        let disbursement_count = self
+            .schedule
+            .as_ref()
+            .map(|schedule| schedule.disbursement_count)
+            .unwrap_or_default();
+        self.cancelled_timestamp_seconds.is_none()
+            && self.disbursements_completed < disbursement_count
+    }
+
+    /// Returns true if the next disbursement of this stream should be made at
+    /// `now_seconds`.
+    pub fn is_disbursement_due(&self, now_seconds: u64) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+        self.schedule
+            .as_ref()
+            .and_then(|schedule| {
+                schedule.disbursement_due_timestamp_seconds(self.disbursements_completed)
+            })
+            .map(|due_timestamp_seconds| due_timestamp_seconds <= now_seconds)
+            .unwrap_or(false)
+    }
+}
+
 pub mod test_helpers {
     use super::*;
     use ic_crypto_sha2::Sha256;
@@ -34,13 +34,14 @@ use ic_sns_governance::{
             neuron::{DissolveState, Followees},
             proposal::Action,
             transfer_sns_treasury_funds::TransferFrom,
-            Account as AccountProto, AddMaturityRequest, Ballot, ClaimSwapNeuronsError,
-            ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus,
-            DeregisterDappCanisters, Empty, GovernanceError, ManageNeuronResponse, MintSnsTokens,
+            Account as AccountProto, AddMaturityRequest, Ballot, CancelTreasuryDisbursementStream,
+            ClaimSwapNeuronsError, ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse,
+            ClaimedSwapNeuronStatus, DeregisterDappCanisters, Empty, GovernanceError,
+            ListTreasuryDisbursementStreamsRequest, ManageNeuronResponse, MintSnsTokens,
             MintTokensRequest, MintTokensResponse, Motion, NervousSystemParameters, Neuron,
             NeuronId, NeuronPermission, NeuronPermissionList, NeuronPermissionType, Proposal,
-            ProposalData, ProposalId, RegisterDappCanisters, TransferSnsTreasuryFunds, Vote,
-            WaitForQuietState,
+            ProposalData, ProposalId, RegisterDappCanisters, ScheduleTreasuryDisbursementStream,
+            TransferSnsTreasuryFunds, Vote, WaitForQuietState,
         },
     },
     types::{native_action_ids, ONE_DAY_SECONDS, ONE_MONTH_SECONDS},
@@ -3156,6 +3157,90 @@ fn test_transfer_treasury_funds_has_higher_voting_thresholds() {
     );
 }
 
+#[test]
+fn test_treasury_disbursement_stream_is_disbursed_and_can_be_cancelled() {
+    // Set up the test environment with a single neuron, so that proposals
+    // immediately pass and execute.
+    let (mut canister_fixture, user_principal, neuron_id) =
+        GovernanceCanisterFixtureBuilder::new().create_with_test_neuron();
+
+    let recipient = icrc_ledger_types::icrc1::account::Account {
+        owner: PrincipalId::new_user_test_id(42).0,
+        subaccount: None,
+    };
+    let start_timestamp_seconds = canister_fixture.get_state().now;
+
+    // Schedule 4 monthly disbursements, with a cliff after the first two months.
+    let schedule = ScheduleTreasuryDisbursementStream {
+        from_treasury: TransferFrom::IcpTreasury as i32,
+        amount_e8s_per_disbursement: 10 * E8,
+        memo: None,
+        to_principal: Some(PrincipalId::new_user_test_id(42)),
+        to_subaccount: None,
+        start_timestamp_seconds,
+        cliff_duration_seconds: 2 * ONE_MONTH_SECONDS,
+        period_seconds: ONE_MONTH_SECONDS,
+        disbursement_count: 4,
+    };
+    let (stream_id, proposal_data) = canister_fixture
+        .make_default_proposal(&neuron_id, schedule, user_principal)
+        .unwrap();
+    assert_eq!(proposal_data.failed_timestamp_seconds, 0);
+    assert!(proposal_data.executed_timestamp_seconds > 0);
+
+    // Nothing is disbursed before the cliff.
+    canister_fixture
+        .advance_time_by(ONE_MONTH_SECONDS)
+        .heartbeat();
+    assert_eq!(
+        canister_fixture.get_account_balance(&recipient, TargetLedger::Icp),
+        0
+    );
+
+    // At the cliff, the disbursements that became due so far are made, one per heartbeat.
+    canister_fixture
+        .advance_time_by(ONE_MONTH_SECONDS)
+        .heartbeat()
+        .heartbeat()
+        .heartbeat();
+    assert_eq!(
+        canister_fixture.get_account_balance(&recipient, TargetLedger::Icp),
+        3 * 10 * E8
+    );
+
+    // Cancel the stream before the last disbursement.
+    let (_, proposal_data) = canister_fixture
+        .make_default_proposal(
+            &neuron_id,
+            CancelTreasuryDisbursementStream {
+                stream_id: stream_id.id,
+            },
+            user_principal,
+        )
+        .unwrap();
+    assert_eq!(proposal_data.failed_timestamp_seconds, 0);
+    assert!(proposal_data.executed_timestamp_seconds > 0);
+
+    canister_fixture
+        .advance_time_by(ONE_MONTH_SECONDS)
+        .heartbeat();
+    assert_eq!(
+        canister_fixture.get_account_balance(&recipient, TargetLedger::Icp),
+        3 * 10 * E8
+    );
+
+    let streams = canister_fixture
+        .governance
+        .list_treasury_disbursement_streams(ListTreasuryDisbursementStreamsRequest {})
+        .streams;
+    assert_eq!(streams.len(), 1);
+    assert_eq!(streams[0].id, stream_id.id);
+    assert_eq!(streams[0].disbursements_completed, 3);
+    assert_eq!(streams[0].disbursed_e8s, 3 * 10 * E8);
+    assert!(streams[0].cancelled_timestamp_seconds.is_some());
+    assert!(!streams[0].is_active());
+}
+
 #[test]
 fn test_mint_sns_tokens_has_higher_voting_thresholds() {
     let user_principal = PrincipalId::new_user_test_id(1000);
@@ -18,8 +18,19 @@
 //!   - uses `/some/dir` to store state
 //!   - sets the log level to info
 //!   - serves metrics at localhost:18080 instead of dumping them at stdout
-
-use anyhow::Result;
+//!
+//! Several subnets, each with one or more replicas, can be started with
+//! `--topology`:
+//!     cargo run --bin ic-starter -- --topology system:1,application:1,application:2
+//! That:
+//!   - starts an NNS system subnet with one replica and two application
+//!     subnets with one and two replicas, respectively
+//!   - gives every replica its own loopback address 127.0.<subnet>.<replica>
+//!     (both 1-based), on which it serves the public API (port 8080 unless
+//!     --http-port is given), XNet and P2P
+//!   - keeps running until one of the replicas exits, then stops the others
+
+use anyhow::{bail, Result};
 use clap::Parser;
 use ic_config::{
     adapters::AdaptersConfig,
@@ -57,11 +68,26 @@ use std::{
     net::{IpAddr, Ipv4Addr, SocketAddrV4},
     time::Duration,
 };
-use std::{io, os::unix::process::CommandExt, path::PathBuf, process::Command, str::FromStr};
+use std::{
+    io,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
 use tempfile::TempDir;
 
 const NODE_INDEX: NodeIndex = 100;
-const SUBNET_ID: u64 = 0;
+const NNS_SUBNET_INDEX: u64 = 0;
+/// The port of the public API if neither --http-port nor --http-port-file is
+/// given.
+const DEFAULT_HTTP_PORT: u16 = 8080;
+/// The XNet port of every replica in a multi-subnet topology.
+const TOPOLOGY_XNET_PORT: u16 = 2497;
+/// The P2P port of every replica in a multi-subnet topology. The peer manager
+/// connects to all peers on this port, so replicas can only be told apart by
+/// their IP address.
+const TOPOLOGY_TRANSPORT_PORT: u16 = 4100;
 
 fn main() -> Result<()> {
     let config = CliArgs::parse().validate()?;
@@ -72,33 +98,25 @@ fn main() -> Result<()> {
     let (log, _async_log_guard) = new_replica_logger_from_config(&logger_config);
 
     info!(log, "ic-starter. Configuration: {:?}", config);
-    let config_path = config.state_dir.join("ic.json5");
 
-    info!(log, "Initialize replica configuration {:?}", config_path);
+    for node in &config.nodes {
+        info!(
+            log,
+            "Initialize replica configuration {:?}", node.config_path
+        );
 
-    let replica_config = config.build_replica_config();
+        let replica_config = config.build_replica_config(node);
 
-    // assemble config
-    let config_json = serde_json::to_string(&replica_config).unwrap();
-    std::fs::write(config_path.clone(), config_json.into_bytes()).unwrap();
+        // assemble config
+        let config_json = serde_json::to_string(&replica_config).unwrap();
+        std::fs::write(&node.config_path, config_json.into_bytes()).unwrap();
+    }
 
     if !config.registry_local_store_path.exists() {
         // assemble registry.json
         // At the moment, this always regenerates the node key.
         // TODO: Only regenerate if necessary, depends on CRP-359
 
-        let mut subnet_nodes: BTreeMap<NodeIndex, NodeConfiguration> = BTreeMap::new();
-        subnet_nodes.insert(
-            NODE_INDEX,
-            NodeConfiguration {
-                xnet_api: SocketAddr::from_str("0.0.0.0:0").unwrap(),
-                public_api: config.http_listen_addr,
-                node_operator_principal_id: None,
-                secret_key_store: None,
-                chip_id: None,
-            },
-        );
-
         let ecdsa_config = config.ecdsa_keyid.clone().map(|key_id| EcdsaConfig {
             quadruples_to_create_in_advance: 1,
             key_ids: vec![(&key_id).into()],
@@ -108,31 +126,54 @@ fn main() -> Result<()> {
         });
 
         let mut topology_config = TopologyConfig::default();
-        topology_config.insert_subnet(
-            SUBNET_ID,
-            SubnetConfig::new(
-                SUBNET_ID,
-                subnet_nodes,
-                config.replica_version.clone(),
-                None,
-                None,
-                None,
-                config.unit_delay,
-                config.initial_notary_delay,
-                config.dkg_interval_length,
-                None,
-                config.subnet_type,
-                None,
-                None,
-                None,
-                Some(config.subnet_features),
-                ecdsa_config,
-                None,
-                vec![],
-                vec![],
-                SubnetRunningState::default(),
-            ),
-        );
+        for (subnet_index, subnet_type) in (0..).zip(config.subnet_types.iter()) {
+            let subnet_nodes: BTreeMap<NodeIndex, NodeConfiguration> = config
+                .nodes
+                .iter()
+                .filter(|node| node.subnet_index == subnet_index)
+                .map(|node| {
+                    (
+                        node.node_index,
+                        NodeConfiguration {
+                            xnet_api: node.xnet_addr,
+                            public_api: node.http_listen_addr,
+                            node_operator_principal_id: None,
+                            secret_key_store: None,
+                            chip_id: None,
+                        },
+                    )
+                })
+                .collect();
+
+            topology_config.insert_subnet(
+                subnet_index,
+                SubnetConfig::new(
+                    subnet_index,
+                    subnet_nodes,
+                    config.replica_version.clone(),
+                    None,
+                    None,
+                    None,
+                    config.unit_delay,
+                    config.initial_notary_delay,
+                    config.dkg_interval_length,
+                    None,
+                    *subnet_type,
+                    None,
+                    None,
+                    None,
+                    Some(config.subnet_features),
+                    // The ECDSA key is only held by the NNS subnet.
+                    ecdsa_config
+                        .clone()
+                        .filter(|_| subnet_index == NNS_SUBNET_INDEX),
+                    None,
+                    vec![],
+                    vec![],
+                    SubnetRunningState::default(),
+                ),
+            );
+        }
 
         // N.B. it is safe to generate subnet records here, we only skip this
         // step for a specific deployment case in ic-prep: when we want to deploy
@@ -143,10 +184,10 @@ fn main() -> Result<()> {
             topology_config,
             config.replica_version.clone(),
             /* generate_subnet_records= */ true, // see note above
-            /* nns_subnet_index= */ Some(0),
+            /* nns_subnet_index= */ Some(NNS_SUBNET_INDEX),
             /* release_package_url= */ None,
             /* release_package_sha256_hex */ None,
-            config.provisional_whitelist,
+            config.provisional_whitelist.clone(),
             None,
             None,
             /* ssh_readonly_access_to_unassigned_nodes */ vec![],
@@ -155,40 +196,52 @@ fn main() -> Result<()> {
 
         ic_config.set_use_specified_ids_allocation_range(config.use_specified_ids_allocation_range);
 
-        ic_config.initialize()?;
+        let initialized_ic = ic_config.initialize()?;
+        for (subnet_index, subnet) in initialized_ic.initialized_topology.iter() {
+            info!(log, "Subnet {} has id {}", subnet_index, subnet.subnet_id);
+        }
     }
 
-    let (mut base_cmd, use_cargo) = match config.replica_path {
-        Some(f) => (Command::new(f), false),
-        None => (Command::new(config.cargo_bin), true),
-    };
+    if let [node] = config.nodes.as_slice() {
+        let mut cmd = config.replica_command(&node.config_path);
+        info!(log, "Executing {:?}", cmd);
+        cmd.exec();
+        return Ok(());
+    }
 
-    // If ic-starter is built in release mode then, by default, start replica
-    // in release mode as well.
-    let mut cargo_opts = config.cargo_opts;
-    if !cfg!(debug_assertions) && !cargo_opts.contains("--release") {
-        cargo_opts.push_str("--release")
-    };
+    let mut replicas = Vec::with_capacity(config.nodes.len());
+    for node in &config.nodes {
+        info!(
+            log,
+            "Replica {} of subnet {}: public API at {}, XNet at {}",
+            node.node_index,
+            node.subnet_index,
+            node.http_listen_addr,
+            node.xnet_addr
+        );
+        let mut cmd = config.replica_command(&node.config_path);
+        info!(log, "Executing {:?}", cmd);
+        replicas.push(cmd.spawn()?);
+    }
 
-    let cmd = if use_cargo {
-        base_cmd
-            .arg("run")
-            .arg("--bin")
-            .arg("replica")
-            .args(cargo_opts.split_whitespace())
-            .arg("--")
-    } else {
-        &mut base_cmd
-    };
-    let cmd = cmd
-        .arg("--replica-version")
-        .arg(config.replica_version.to_string())
-        .arg("--config-file")
-        .args([config_path.to_str().unwrap()]);
-    info!(log, "Executing {:?}", cmd);
-    cmd.exec();
-
-    Ok(())
+    // Keep the topology running until one of the replicas exits, then stop
+    // the others.
+    loop {
+        let mut exit_status = None;
+        for replica in replicas.iter_mut() {
+            exit_status = replica.try_wait()?;
+            if exit_status.is_some() {
+                break;
+            }
+        }
+        if let Some(status) = exit_status {
+            for replica in replicas.iter_mut() {
+                let _ = replica.kill();
+            }
+            bail!("A replica exited with {}, stopped all replicas", status);
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Parser)]
@@ -322,10 +375,25 @@ struct CliArgs {
     ecdsa_keyid: Option<String>,
 
     /// Subnet type
+    ///
+    /// This argument is incompatible with --topology.
     #[clap(long = "subnet-type",
                 possible_values = &["application", "verified_application", "system"])]
     subnet_type: Option<String>,
 
+    /// Start several subnets instead of a single replica. The value is a comma
+    /// separated list of `<subnet type>:<number of replicas>`, e.g.
+    /// `system:1,application:1,application:2`. The first subnet is the NNS.
+    ///
+    /// Every replica listens on its own loopback address
+    /// 127.0.<subnet>.<replica> (both 1-based). Outside of Linux, these
+    /// addresses have to be configured before starting the topology.
+    ///
+    /// This argument is incompatible with --http-listen-addr, --http-port-file,
+    /// --subnet-type and --use-specified-ids-allocation-range.
+    #[clap(long = "topology")]
+    topology: Option<String>,
+
     /// Unix Domain Socket for Bitcoin testnet
     #[clap(long = "bitcoin-testnet-uds-path")]
     bitcoin_testnet_uds_path: Option<PathBuf>,
@@ -383,9 +451,6 @@ impl CliArgs {
             ));
         }
 
-        // XXX: Must be kept in sync with SubnetConfiguration implementation
-        let node_dir = state_dir.join(format!("node-{}", NODE_INDEX));
-
         // check whether state_dir is writeable
         if state_dir.metadata()?.permissions().readonly() {
             return Err(io::Error::new(
@@ -394,49 +459,6 @@ impl CliArgs {
             ));
         }
 
-        let (http_listen_addr, http_port_file) =
-            match (self.http_port, self.http_listen_addr, self.http_port_file) {
-                (None, None, None) => Ok((
-                    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080),
-                    None,
-                )),
-                (None, None, Some(path)) => Ok((
-                    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0),
-                    Some(path),
-                )),
-                (None, Some(listen_addr), None) => Ok((listen_addr, None)),
-                (None, Some(listen_addr), Some(path)) => Ok((listen_addr, Some(path))),
-                (Some(port), None, None) => Ok((
-                    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port),
-                    None,
-                )),
-                (Some(_), None, Some(_)) => Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "Arguments --http-port and --http-port-file are incompatible.",
-                )),
-                (Some(_), Some(_), _) => Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "Arguments --http-port and --http-listen-addr are incompatible",
-                )),
-            }?;
-
-        // check whether parent directory of port_file exists
-        if let Some(http_port_file) = &http_port_file {
-            if http_port_file
-                .parent()
-                .and_then(|p| if !p.is_dir() { None } else { Some(p) })
-                .is_none()
-            {
-                return Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    format!(
-                        "Parent directory of http_port_file not found at: {:?}",
-                        replica_path
-                    ),
-                ));
-            }
-        }
-
         let metrics_port = self.metrics_port;
         let mut metrics_addr = self.metrics_addr;
 
@@ -456,6 +478,114 @@ impl CliArgs {
                 Some(SocketAddrV4::new("0.0.0.0".parse().expect("can't fail"), port).into());
         }
 
+        let (subnet_types, nodes) = match self.topology.as_deref() {
+            None => {
+                let subnet_type =
+                    parse_subnet_type(self.subnet_type.as_deref().unwrap_or("system"))?;
+                let (http_listen_addr, http_port_file) =
+                    match (self.http_port, self.http_listen_addr, self.http_port_file) {
+                        (None, None, None) => Ok((
+                            SocketAddr::new(
+                                IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+                                DEFAULT_HTTP_PORT,
+                            ),
+                            None,
+                        )),
+                        (None, None, Some(path)) => Ok((
+                            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0),
+                            Some(path),
+                        )),
+                        (None, Some(listen_addr), None) => Ok((listen_addr, None)),
+                        (None, Some(listen_addr), Some(path)) => Ok((listen_addr, Some(path))),
+                        (Some(port), None, None) => Ok((
+                            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port),
+                            None,
+                        )),
+                        (Some(_), None, Some(_)) => Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "Arguments --http-port and --http-port-file are incompatible.",
+                        )),
+                        (Some(_), Some(_), _) => Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "Arguments --http-port and --http-listen-addr are incompatible",
+                        )),
+                    }?;
+
+                // check whether parent directory of port_file exists
+                if let Some(http_port_file) = &http_port_file {
+                    if http_port_file
+                        .parent()
+                        .and_then(|p| if !p.is_dir() { None } else { Some(p) })
+                        .is_none()
+                    {
+                        return Err(io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!(
+                                "Parent directory of http_port_file not found at: {:?}",
+                                replica_path
+                            ),
+                        ));
+                    }
+                }
+                let node = ReplicaNode {
+                    node_index: NODE_INDEX,
+                    subnet_index: NNS_SUBNET_INDEX,
+                    config_path: state_dir.join("ic.json5"),
+                    http_listen_addr,
+                    http_port_file,
+                    xnet_addr: SocketAddr::from_str("0.0.0.0:0").unwrap(),
+                    transport_addr: SocketAddr::from_str("0.0.0.0:0").unwrap(),
+                    metrics_addr,
+                };
+                (vec![subnet_type], vec![node])
+            }
+            Some(topology) => {
+                for (is_set, arg) in [
+                    (self.http_listen_addr.is_some(), "--http-listen-addr"),
+                    (self.http_port_file.is_some(), "--http-port-file"),
+                    (self.subnet_type.is_some(), "--subnet-type"),
+                    (
+                        self.use_specified_ids_allocation_range,
+                        "--use-specified-ids-allocation-range",
+                    ),
+                ] {
+                    if is_set {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Arguments --topology and {} are incompatible.", arg),
+                        ));
+                    }
+                }
+                let subnets = parse_topology(topology)?;
+                let http_port = self.http_port.unwrap_or(DEFAULT_HTTP_PORT);
+
+                let mut nodes = vec![];
+                for (subnet_index, (_, replicas)) in (0..).zip(subnets.iter()) {
+                    for replica in 0..*replicas {
+                        let node_index = NODE_INDEX + nodes.len() as NodeIndex;
+                        let ip =
+                            IpAddr::V4(Ipv4Addr::new(127, 0, subnet_index as u8 + 1, replica + 1));
+                        nodes.push(ReplicaNode {
+                            node_index,
+                            subnet_index,
+                            config_path: state_dir.join(format!("ic-{}.json5", node_index)),
+                            http_listen_addr: SocketAddr::new(ip, http_port),
+                            http_port_file: None,
+                            xnet_addr: SocketAddr::new(ip, TOPOLOGY_XNET_PORT),
+                            transport_addr: SocketAddr::new(ip, TOPOLOGY_TRANSPORT_PORT),
+                            metrics_addr: metrics_addr
+                                .map(|metrics_addr| SocketAddr::new(ip, metrics_addr.port())),
+                        });
+                    }
+                }
+                let subnet_types = subnets
+                    .into_iter()
+                    .map(|(subnet_type, _)| subnet_type)
+                    .collect();
+                (subnet_types, nodes)
+            }
+        };
+
         let log_level = match self.log_level {
             Some(log_level) => match log_level.to_lowercase().as_str() {
                 // According to the principle of least surprise, accept also a
@@ -476,9 +606,6 @@ impl CliArgs {
             None => slog::Level::Warning,
         };
 
-        let artifact_pool_dir = node_dir.join("ic_consensus_pool");
-        let crypto_root = node_dir.join("crypto");
-        let state_manager_root = node_dir.join("state");
         let registry_local_store_path = state_dir.join("ic_registry_local_store");
 
         let provisional_whitelist = match self.provisional_whitelist.unwrap_or_default().as_str() {
@@ -495,18 +622,6 @@ impl CliArgs {
         let unit_delay = self.unit_delay_millis.map(Duration::from_millis);
         let initial_notary_delay = self.initial_notary_delay_millis.map(Duration::from_millis);
 
-        let subnet_type = match self.subnet_type.as_deref() {
-            Some("application") => SubnetType::Application,
-            Some("verified_application") => SubnetType::VerifiedApplication,
-            Some("system") | None => SubnetType::System,
-            Some(s) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("Invalid subnet_type: {}", s),
-                ))
-            }
-        };
-
         let ecdsa_keyid = self
             .ecdsa_keyid
             .as_ref()
@@ -527,13 +642,7 @@ impl CliArgs {
             cargo_bin,
             cargo_opts,
             state_dir,
-            http_listen_addr,
-            http_port_file,
-            metrics_addr,
             provisional_whitelist,
-            artifact_pool_dir,
-            crypto_root,
-            state_manager_root,
             registry_local_store_path,
             _state_dir_holder,
             unit_delay,
@@ -542,7 +651,8 @@ impl CliArgs {
             consensus_pool_backend: self.consensus_pool_backend,
             subnet_features: to_subnet_features(&self.subnet_features),
             ecdsa_keyid,
-            subnet_type,
+            subnet_types,
+            nodes,
             bitcoin_testnet_uds_path: self.bitcoin_testnet_uds_path,
             https_outcalls_uds_path: self.canister_http_uds_path,
             use_specified_ids_allocation_range: self.use_specified_ids_allocation_range,
@@ -551,6 +661,51 @@ impl CliArgs {
     }
 }
 
+fn parse_subnet_type(subnet_type: &str) -> io::Result<SubnetType> {
+    match subnet_type {
+        "application" => Ok(SubnetType::Application),
+        "verified_application" => Ok(SubnetType::VerifiedApplication),
+        "system" => Ok(SubnetType::System),
+        s => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid subnet_type: {}", s),
+        )),
+    }
+}
+
+/// Parses a topology of the form `system:1,application:2` into the type and
+/// the number of replicas of each subnet.
+fn parse_topology(topology: &str) -> io::Result<Vec<(SubnetType, u8)>> {
+    let subnets = topology
+        .split(',')
+        .map(|subnet| {
+            let invalid_subnet = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid subnet in topology, expected <subnet type>:<1-254 replicas>: {}",
+                        subnet
+                    ),
+                )
+            };
+            let (subnet_type, replicas) = subnet.split_once(':').ok_or_else(invalid_subnet)?;
+            let replicas = replicas
+                .parse::<u8>()
+                .ok()
+                .filter(|replicas| (1..=254).contains(replicas))
+                .ok_or_else(invalid_subnet)?;
+            Ok((parse_subnet_type(subnet_type)?, replicas))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    if subnets.len() > 255 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "A topology can have at most 255 subnets",
+        ));
+    }
+    Ok(subnets)
+}
+
 fn to_subnet_features(features: &[String]) -> SubnetFeatures {
     let canister_sandboxing = features.iter().any(|s| s.as_str() == "canister_sandboxing");
     let http_requests = features.iter().any(|s| s.as_str() == "http_requests");
@@ -571,13 +726,7 @@ struct ValidatedConfig {
     cargo_bin: String,
     cargo_opts: String,
     state_dir: PathBuf,
-    http_listen_addr: SocketAddr,
-    http_port_file: Option<PathBuf>,
-    metrics_addr: Option<SocketAddr>,
     provisional_whitelist: Option<ProvisionalWhitelist>,
-    artifact_pool_dir: PathBuf,
-    crypto_root: PathBuf,
-    state_manager_root: PathBuf,
     registry_local_store_path: PathBuf,
     unit_delay: Option<Duration>,
     initial_notary_delay: Option<Duration>,
@@ -585,7 +734,9 @@ struct ValidatedConfig {
     consensus_pool_backend: Option<String>,
     subnet_features: SubnetFeatures,
     ecdsa_keyid: Option<EcdsaKeyId>,
-    subnet_type: SubnetType,
+    /// The type of every subnet, indexed by subnet index.
+    subnet_types: Vec<SubnetType>,
+    nodes: Vec<ReplicaNode>,
     bitcoin_testnet_uds_path: Option<PathBuf>,
     https_outcalls_uds_path: Option<PathBuf>,
     use_specified_ids_allocation_range: bool,
@@ -595,31 +746,49 @@ struct ValidatedConfig {
     _state_dir_holder: Option<TempDir>,
 }
 
+/// A replica started by ic-starter and the addresses it listens on.
+#[derive(Debug)]
+struct ReplicaNode {
+    node_index: NodeIndex,
+    subnet_index: u64,
+    config_path: PathBuf,
+    http_listen_addr: SocketAddr,
+    http_port_file: Option<PathBuf>,
+    xnet_addr: SocketAddr,
+    transport_addr: SocketAddr,
+    metrics_addr: Option<SocketAddr>,
+}
+
 impl ValidatedConfig {
-    fn build_replica_config(self: &ValidatedConfig) -> ReplicaConfig {
-        let state_manager = Some(StateManagerConfig::new(self.state_manager_root.clone()));
+    fn build_replica_config(self: &ValidatedConfig, node: &ReplicaNode) -> ReplicaConfig {
+        // XXX: Must be kept in sync with SubnetConfiguration implementation
+        let node_dir = self.state_dir.join(format!("node-{}", node.node_index));
+        let artifact_pool_dir = node_dir.join("ic_consensus_pool");
+        let crypto_root = node_dir.join("crypto");
+        let state_manager_root = node_dir.join("state");
+
+        let state_manager = Some(StateManagerConfig::new(state_manager_root));
         let http_handler = Some(HttpHandlerConfig {
-            listen_addr: self.http_listen_addr,
-            port_file_path: self.http_port_file.clone(),
+            listen_addr: node.http_listen_addr,
+            port_file_path: node.http_port_file.clone(),
             ..Default::default()
         });
-        let metrics = self.metrics_addr.map(|metrics_addr| MetricsConfig {
+        let metrics = node.metrics_addr.map(|metrics_addr| MetricsConfig {
             exporter: Exporter::Http(metrics_addr),
             ..Default::default()
         });
 
-        let mut artifact_pool_cfg =
-            ArtifactPoolTomlConfig::new(self.artifact_pool_dir.clone(), None);
+        let mut artifact_pool_cfg = ArtifactPoolTomlConfig::new(artifact_pool_dir, None);
         // artifact_pool.rs picks "lmdb" if None here
         artifact_pool_cfg.consensus_pool_backend = self.consensus_pool_backend.clone();
         let artifact_pool = Some(artifact_pool_cfg);
 
-        let crypto = Some(CryptoConfig::new(self.crypto_root.clone()));
+        let crypto = Some(CryptoConfig::new(crypto_root));
         let registry_client = Some(RegistryClientConfig {
             local_store: self.registry_local_store_path.clone(),
         });
         let logger_config = LoggerConfig {
-            node_id: NODE_INDEX,
+            node_id: node.node_index,
             level: self.log_level,
             debug_overrides: self.debug_overrides.clone(),
             ..LoggerConfig::default()
@@ -627,8 +796,8 @@ impl ValidatedConfig {
         let logger = Some(logger_config);
 
         let transport = Some(TransportConfig {
-            node_ip: "0.0.0.0".to_string(),
-            listening_port: 0,
+            node_ip: node.transport_addr.ip().to_string(),
+            listening_port: node.transport_addr.port(),
             send_queue_size: 1024,
             ..Default::default()
         });
@@ -680,4 +849,32 @@ impl ValidatedConfig {
             ..ReplicaConfig::default()
         }
     }
+
+    /// Returns the command that starts the replica with the given config file.
+    fn replica_command(&self, config_path: &Path) -> Command {
+        let mut cmd = match &self.replica_path {
+            Some(f) => Command::new(f),
+            None => {
+                // If ic-starter is built in release mode then, by default, start
+                // replica in release mode as well.
+                let mut cargo_opts = self.cargo_opts.clone();
+                if !cfg!(debug_assertions) && !cargo_opts.contains("--release") {
+                    cargo_opts.push_str(" --release")
+                };
+
+                let mut cmd = Command::new(&self.cargo_bin);
+                cmd.arg("run")
+                    .arg("--bin")
+                    .arg("replica")
+                    .args(cargo_opts.split_whitespace())
+                    .arg("--");
+                cmd
+            }
+        };
+        cmd.arg("--replica-version")
+            .arg(self.replica_version.to_string())
+            .arg("--config-file")
+            .arg(config_path);
+        cmd
+    }
 }
@@ -5521,6 +5521,7 @@ fn query_stats_are_collected() {
                 num_instructions: INITIAL_VALUES,
                 ingress_payload_size: INITIAL_VALUES,
                 egress_payload_size: INITIAL_VALUES,
+                cycles_charged: 0,
             },
         );
     }
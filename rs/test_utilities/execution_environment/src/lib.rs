@@ -391,6 +391,18 @@ impl ExecutionTest {
         )
     }
 
+    pub fn non_replicated_http_request_fee(
+        &self,
+        request_size: NumBytes,
+        response_size_limit: Option<NumBytes>,
+    ) -> Cycles {
+        self.cycles_account_manager.non_replicated_http_request_fee(
+            request_size,
+            response_size_limit,
+            self.subnet_size(),
+        )
+    }
+
     pub fn reduced_wasm_compilation_fee(&self, wasm: &[u8]) -> Cycles {
         let cost = wasm_compilation_cost(wasm);
         self.cycles_account_manager()
@@ -33,7 +33,9 @@ use ic_ic00_types::{
 };
 use ic_test_utilities::cycles_account_manager::CyclesAccountManagerBuilder;
 use ic_test_utilities::{mock_time, types::messages::RequestBuilder};
-use ic_types::canister_http::{CanisterHttpRequestContext, MAX_CANISTER_HTTP_REQUEST_BYTES};
+use ic_types::canister_http::{
+    CanisterHttpRequestContext, Replication, MAX_CANISTER_HTTP_REQUEST_BYTES,
+};
 use proxy_canister::{RemoteHttpRequest, RemoteHttpResponse};
 use slog::{info, Logger};
 use std::convert::TryFrom;
@@ -57,6 +59,7 @@ fn expected_cycle_cost(
             .sender(proxy_canister)
             .build(),
         request,
+        Replication::FullyReplicated,
     ))
     .unwrap();
     let req_size = dummy_context.variable_parts_size();
@@ -97,6 +100,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -124,6 +128,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -154,6 +159,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
                     },
                     cycles: 0,
                 },
@@ -176,6 +182,7 @@ pub fn test(env: TestEnv) {
                 context: vec![0, 1, 2],
             }),
             max_response_bytes: None,
+            is_replicated: None,
         };
         test_results.push(
             test_canister_http_property(
@@ -226,6 +233,7 @@ pub fn test(env: TestEnv) {
                 context: vec![0, 1, 2],
             }),
             max_response_bytes: Some(16384),
+            is_replicated: None,
         };
         test_results.push(
             test_canister_http_property(
@@ -281,6 +289,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: Some(4 * 1024 * 1024),
+                        is_replicated: None,
                     },
                     cycles: 0,
                 },
@@ -308,6 +317,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -335,6 +345,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -365,6 +376,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -392,6 +404,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: Some(8 * 1024),
+                        is_replicated: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -420,6 +433,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -447,6 +461,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -474,6 +489,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
                     },
                     cycles: 500_000_000_000,
                 },
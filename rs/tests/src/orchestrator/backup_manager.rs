@@ -45,7 +45,7 @@ use crate::{
 };
 use ic_backup::{
     backup_helper::{last_checkpoint, ls_path},
-    config::{ColdStorage, Config, SubnetConfig},
+    config::{ColdStorage, Config, NotificationSinkConfig, SubnetConfig},
     util::sleep_secs,
 };
 use ic_base_types::SubnetId;
@@ -195,6 +195,7 @@ pub fn test(env: TestEnv) {
     let cold_storage = Some(ColdStorage {
         cold_storage_dir: cold_storage_dir.clone(),
         versions_hot: 1,
+        backend: Default::default(),
     });
     let config = Config {
         push_metrics: false,
@@ -209,6 +210,7 @@ pub fn test(env: TestEnv) {
         hot_disk_resource_threshold_percentage: 75,
         cold_disk_resource_threshold_percentage: 95,
         slack_token: "NO_TOKEN_IN_TESTING".to_string(),
+        notification_sinks: vec![NotificationSinkConfig::File { path: None }],
         cold_storage,
         blacklisted_nodes: None,
         subnets: vec![subnet],
@@ -70,6 +70,7 @@ pub type BoundedHttpHeaders = BoundedVec<
 //       function : func (record {response : http_response; context : blob}) -> (http_response) query;
 //       context : blob;
 //     };
+//     is_replicated : opt bool;
 //   })`
 #[derive(CandidType, Deserialize, Debug, Clone, PartialEq)]
 pub struct CanisterHttpRequestArgs {
@@ -79,6 +80,9 @@ pub struct CanisterHttpRequestArgs {
     pub body: Option<Vec<u8>>,
     pub method: HttpMethod,
     pub transform: Option<TransformContext>,
+    /// If set to `Some(false)`, the request is performed by a single,
+    /// randomly chosen replica instead of by all replicas of the subnet.
+    pub is_replicated: Option<bool>,
 }
 
 impl Payload<'_> for CanisterHttpRequestArgs {}
@@ -91,6 +95,12 @@ impl CanisterHttpRequestArgs {
             .as_ref()
             .map(|transform_context| PrincipalId::from(transform_context.function.0.principal))
     }
+
+    /// Returns true if the request should be performed by all replicas,
+    /// which is the default.
+    pub fn is_replicated(&self) -> bool {
+        self.is_replicated.unwrap_or(true)
+    }
 }
 
 #[test]
@@ -113,6 +123,7 @@ fn test_http_headers_max_number() {
             body: None,
             method: HttpMethod::GET,
             transform: None,
+            is_replicated: None,
         };
 
         // Act.
@@ -166,6 +177,7 @@ fn test_http_headers_max_total_size() {
             body: None,
             method: HttpMethod::GET,
             transform: None,
+            is_replicated: None,
         };
 
         // Act.
@@ -213,6 +225,7 @@ fn test_http_headers_max_element_size() {
             body: None,
             method: HttpMethod::GET,
             transform: None,
+            is_replicated: None,
         };
 
         // Act.
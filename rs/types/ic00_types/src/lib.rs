@@ -773,6 +773,71 @@ pub struct QueryStats {
     num_instructions_total: candid::Nat,
     request_payload_bytes_total: candid::Nat,
     response_payload_bytes_total: candid::Nat,
+    cycles_charged_total: candid::Nat,
+}
+
+/// The size of a component of a canister's memory and the cycles burned per
+/// day for storing it.
+///
+/// The cycles are the ones charged for the component's size alone. A canister
+/// with a memory allocation is charged for the allocation rather than for the
+/// memory it uses, except for its message memory.
+#[derive(Clone, CandidType, Debug, Deserialize, Eq, PartialEq)]
+pub struct MemoryMetric {
+    size: candid::Nat,
+    idle_cycles_burned_per_day: candid::Nat,
+}
+
+impl MemoryMetric {
+    pub fn new(size: NumBytes, idle_cycles_burned_per_day: u128) -> Self {
+        Self {
+            size: candid::Nat::from(size.get()),
+            idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
+        }
+    }
+
+    pub fn size(&self) -> NumBytes {
+        NumBytes::from(self.size.0.to_u64().unwrap())
+    }
+
+    pub fn idle_cycles_burned_per_day(&self) -> u128 {
+        self.idle_cycles_burned_per_day.0.to_u128().unwrap()
+    }
+}
+
+impl Default for MemoryMetric {
+    fn default() -> Self {
+        Self::new(NumBytes::from(0), 0)
+    }
+}
+
+/// Struct used for encoding/decoding the breakdown of a canister's memory
+/// `(record {
+///     wasm_memory: memory_metric;
+///     stable_memory: memory_metric;
+///     global_memory: memory_metric;
+///     wasm_binary: memory_metric;
+///     custom_sections: memory_metric;
+///     canister_history: memory_metric;
+///     wasm_chunk_store: memory_metric;
+///     message_memory: memory_metric;
+/// })`
+/// where `memory_metric` is
+/// `record { size: nat; idle_cycles_burned_per_day: nat }`.
+#[derive(Clone, CandidType, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct MemoryMetrics {
+    /// The Wasm (heap) memory.
+    pub wasm_memory: MemoryMetric,
+    pub stable_memory: MemoryMetric,
+    /// The exported globals of the Wasm module.
+    pub global_memory: MemoryMetric,
+    pub wasm_binary: MemoryMetric,
+    /// The custom sections of the Wasm module.
+    pub custom_sections: MemoryMetric,
+    pub canister_history: MemoryMetric,
+    pub wasm_chunk_store: MemoryMetric,
+    /// The memory used by the canister's queued messages.
+    pub message_memory: MemoryMetric,
 }
 
 /// Struct used for encoding/decoding
@@ -791,7 +856,9 @@ pub struct QueryStats {
 ///         num_instructions: nat;
 ///         ingress_payload_size: nat;
 ///         egress_payload_size: nat;
-///     }
+///         cycles_charged_total: nat;
+///     };
+///     memory_metrics: memory_metrics;
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -807,6 +874,7 @@ pub struct CanisterStatusResultV2 {
     idle_cycles_burned_per_day: candid::Nat,
     reserved_cycles: candid::Nat,
     query_stats: QueryStats,
+    memory_metrics: MemoryMetrics,
 }
 
 impl CanisterStatusResultV2 {
@@ -828,6 +896,8 @@ impl CanisterStatusResultV2 {
         query_num_instructions: u128,
         query_ingress_payload_size: u128,
         query_egress_payload_size: u128,
+        query_cycles_charged: u128,
+        memory_metrics: MemoryMetrics,
     ) -> Self {
         Self {
             status,
@@ -854,7 +924,9 @@ impl CanisterStatusResultV2 {
                 num_instructions_total: candid::Nat::from(query_num_instructions),
                 request_payload_bytes_total: candid::Nat::from(query_ingress_payload_size),
                 response_payload_bytes_total: candid::Nat::from(query_egress_payload_size),
+                cycles_charged_total: candid::Nat::from(query_cycles_charged),
             },
+            memory_metrics,
         }
     }
 
@@ -905,6 +977,10 @@ impl CanisterStatusResultV2 {
     pub fn settings(&self) -> DefiniteCanisterSettingsArgs {
         self.settings.clone()
     }
+
+    pub fn memory_metrics(&self) -> MemoryMetrics {
+        self.memory_metrics.clone()
+    }
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -57,6 +57,8 @@ pub struct TotalQueryStats {
     pub num_instructions: u128,
     pub ingress_payload_size: u128,
     pub egress_payload_size: u128,
+    /// Cycles charged for query execution, if query charging is enabled.
+    pub cycles_charged: u128,
 }
 
 fn get_u128_from_protobuf(proto: Option<Unsigned128>) -> Result<u128, ProxyDecodeError> {
@@ -87,6 +89,11 @@ impl TryFrom<TotalQueryStatsProto> for TotalQueryStats {
             num_instructions: get_u128_from_protobuf(value.num_instructions)?,
             ingress_payload_size: get_u128_from_protobuf(value.ingress_payload_size)?,
             egress_payload_size: get_u128_from_protobuf(value.egress_payload_size)?,
+            // Checkpoints written before query charging was introduced don't contain this field.
+            cycles_charged: match value.cycles_charged {
+                Some(cycles_charged) => get_u128_from_protobuf(Some(cycles_charged))?,
+                None => 0,
+            },
         })
     }
 }
@@ -98,6 +105,7 @@ impl From<&TotalQueryStats> for TotalQueryStatsProto {
             num_instructions: Some(get_protobuf_for_u128(value.num_instructions)),
             ingress_payload_size: Some(get_protobuf_for_u128(value.ingress_payload_size)),
             egress_payload_size: Some(get_protobuf_for_u128(value.egress_payload_size)),
+            cycles_charged: Some(get_protobuf_for_u128(value.cycles_charged)),
         }
     }
 }
@@ -41,11 +41,17 @@
 //! The blockmaker indicates, which requests have timed out, i.e. the blocktime of the latest finalized block is higher than
 //! the timestamp of a request plus the timeout interval. This condition is verifiable by the other nodes in the network.
 //! Once a timeout has made it into a finalized block, the request is answered with an error message.
+//!
+//! 5. A canister may opt out of replication by setting `is_replicated` to `false`. Execution then assigns the
+//! request to a single, randomly chosen replica (see [`Replication`]), which is the only one to make the request.
+//! The share of that replica alone is sufficient to include the response into a block. Since the response content
+//! is not gossiped, the response is included once the assigned replica is the block maker.
 use crate::{
     crypto::{CryptoHashOf, Signed},
     messages::{CallbackId, RejectContext, Request},
+    node_id_into_protobuf, node_id_try_from_option,
     signature::*,
-    CanisterId, CountBytes, RegistryVersion, Time,
+    CanisterId, CountBytes, NodeId, RegistryVersion, Time,
 };
 use ic_base_types::{NumBytes, PrincipalId};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
@@ -110,6 +116,18 @@ impl From<TransformContext> for Transform {
     }
 }
 
+/// Which replicas perform a canister http request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Replication {
+    /// Every replica of the subnet performs the request and the response
+    /// requires a threshold of matching signature shares.
+    #[default]
+    FullyReplicated,
+    /// Only the given replica performs the request. Its signature share alone
+    /// is sufficient to include the response into a block.
+    NonReplicated(NodeId),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CanisterHttpRequestContext {
     pub request: Request,
@@ -121,6 +139,8 @@ pub struct CanisterHttpRequestContext {
     pub http_method: CanisterHttpMethod,
     pub transform: Option<Transform>,
     pub time: Time,
+    #[serde(default)]
+    pub replication: Replication,
 }
 
 impl From<&CanisterHttpRequestContext> for pb_metadata::CanisterHttpRequestContext {
@@ -151,6 +171,10 @@ impl From<&CanisterHttpRequestContext> for pb_metadata::CanisterHttpRequestConte
                 .map(|transform| transform.context.clone()),
             http_method: pb_metadata::HttpMethod::from(&context.http_method).into(),
             time: context.time.as_nanos_since_unix_epoch(),
+            non_replicated_node_id: match context.replication {
+                Replication::FullyReplicated => None,
+                Replication::NonReplicated(node_id) => Some(node_id_into_protobuf(node_id)),
+            },
         }
     }
 }
@@ -209,6 +233,12 @@ impl TryFrom<pb_metadata::CanisterHttpRequestContext> for CanisterHttpRequestCon
                 .try_into()?,
             transform,
             time: Time::from_nanos_since_unix_epoch(context.time),
+            replication: match context.non_replicated_node_id {
+                None => Replication::FullyReplicated,
+                Some(node_id) => {
+                    Replication::NonReplicated(node_id_try_from_option(Some(node_id))?)
+                }
+            },
         })
     }
 }
@@ -265,11 +295,19 @@ pub fn validate_http_headers_and_body(
     Ok(())
 }
 
-impl TryFrom<(Time, &Request, CanisterHttpRequestArgs)> for CanisterHttpRequestContext {
+/// Converts the arguments of a `http_request` call into a [`CanisterHttpRequestContext`].
+///
+/// The [`Replication`] is chosen by the caller, which is responsible for selecting the
+/// replica that performs a non-replicated request.
+impl TryFrom<(Time, &Request, CanisterHttpRequestArgs, Replication)>
+    for CanisterHttpRequestContext
+{
     type Error = CanisterHttpRequestContextError;
 
-    fn try_from(input: (Time, &Request, CanisterHttpRequestArgs)) -> Result<Self, Self::Error> {
-        let (time, request, args) = input;
+    fn try_from(
+        input: (Time, &Request, CanisterHttpRequestArgs, Replication),
+    ) -> Result<Self, Self::Error> {
+        let (time, request, args, replication) = input;
         if let Some(transform_principal_id) = args.transform_principal() {
             if request.sender.get() != transform_principal_id {
                 return Err(CanisterHttpRequestContextError::TransformPrincipalId(
@@ -331,6 +369,7 @@ impl TryFrom<(Time, &Request, CanisterHttpRequestArgs)> for CanisterHttpRequestC
             },
             transform: args.transform.map(From::from),
             time,
+            replication,
         })
     }
 }
@@ -369,7 +408,7 @@ pub struct InvalidTransformPrincipalId {
     actual_principal_id: PrincipalId,
 }
 
-/// Errors that can occur when converting from (time, request, [`CanisterHttpRequestArgs`]) to
+/// Errors that can occur when converting from (time, request, [`CanisterHttpRequestArgs`], [`Replication`]) to
 /// an [`CanisterHttpRequestContext`].
 #[derive(Debug)]
 pub enum CanisterHttpRequestContextError {
@@ -654,6 +693,7 @@ mod tests {
                 metadata: None,
             },
             time: UNIX_EPOCH,
+            replication: Replication::FullyReplicated,
         };
 
         let expected_size = context.url.len()
@@ -695,6 +735,7 @@ mod tests {
                 metadata: None,
             },
             time: UNIX_EPOCH,
+            replication: Replication::FullyReplicated,
         };
 
         let expected_size = context.url.len()
@@ -4,6 +4,7 @@ use crate::{
     message::Message,
     metrics::{FUTURE_STARTED, REQUEST_STARTING},
     plan::{EngineCall, Plan},
+    scenario::CallMix,
     stats::Fact,
     RequestType,
 };
@@ -195,7 +196,7 @@ impl Engine {
             request_type,
             canister_method_name,
         );
-        let (collector, rec_handle) = collector::start::<Fact>(plan.clone(), periodic_output);
+        let (collector, rec_handle) = collector::start::<Fact>(plan.requests, periodic_output);
 
         let (tx, rx) = channel(requests);
         let time_origin = Instant::now();
@@ -239,6 +240,69 @@ impl Engine {
         rec_handle.join().unwrap()
     }
 
+    /// Execute the requests of a scenario phase
+    ///
+    /// - `schedule` - Offsets from the start of the phase at which to issue the
+    ///   requests, in ascending order
+    /// - `duration` - The duration of the phase, only used to report the average
+    ///   requested rate
+    /// - `mix` - Generates the canister and call for each request
+    /// - `nonce` - Nonce to use for update calls
+    pub async fn execute_phase(
+        &self,
+        schedule: &[Duration],
+        duration: Duration,
+        mix: &CallMix,
+        nonce: String,
+        periodic_output: bool,
+    ) -> Vec<Fact> {
+        let requests = schedule.len();
+        if requests == 0 {
+            debug!("Not executing any requests");
+            return vec![];
+        }
+        let rpms = (requests as f64 * 1000. / duration.as_secs_f64().max(1.)) as usize;
+        debug!("⏱️  Executing {} requests in {:?}", requests, duration);
+
+        let (collector, rec_handle) = collector::start::<Fact>(requests, periodic_output);
+
+        let (tx, rx) = channel(requests);
+        let time_origin = Instant::now();
+
+        let rx_handle = tokio::task::spawn(Engine::evaluate_requests(
+            rx,
+            collector,
+            Some(rpms),
+            time_origin,
+        ));
+
+        let mut tx_handles = vec![];
+        for (n, offset) in schedule.iter().enumerate() {
+            let target_instant = time_origin + START_OFFSET + *offset;
+            sleep_until(tokio::time::Instant::from_std(target_instant)).await;
+            let tx = tx.clone();
+            let nonce = nonce.clone();
+            let (canister_id, call) = mix.generate_call();
+            let agent = self.agents[n % self.agents.len()].clone();
+            FUTURE_STARTED.inc();
+            tx_handles.push(tokio::task::spawn(async move {
+                REQUEST_STARTING.inc();
+                Engine::execute_call(&agent, tx, time_origin, &canister_id, &nonce, call, n).await;
+            }));
+        }
+        for tx_handle in tx_handles {
+            tx_handle.await.unwrap_or_else(|_| {
+                panic!("Await the tx failed.");
+            });
+        }
+        std::mem::drop(tx);
+        rx_handle.await.unwrap_or_else(|_| {
+            panic!("Await the rx failed.");
+        });
+
+        rec_handle.join().unwrap()
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn execute_request(
         agent: Agent,
@@ -248,14 +312,36 @@ impl Engine {
         n: usize,
         random_query_payload: bool,
     ) -> bool {
-        match plan.generate_call(n, random_query_payload) {
+        Engine::execute_call(
+            &agent,
+            tx,
+            time_origin,
+            &plan.canister_id,
+            &plan.nonce,
+            plan.generate_call(n, random_query_payload),
+            n,
+        )
+        .await
+    }
+
+    async fn execute_call(
+        agent: &Agent,
+        tx: Sender<CallResult>,
+        time_origin: Instant,
+        canister_id: &CanisterId,
+        nonce: &str,
+        call: EngineCall,
+        n: usize,
+    ) -> bool {
+        match call {
             EngineCall::Read { method, arg } => {
-                Engine::execute_query(&agent, tx, time_origin, plan, method, arg, n)
+                Engine::execute_query(agent, tx, time_origin, canister_id, method, arg, n)
                     .await
                     .is_some()
             }
             EngineCall::Write { method, arg } => {
-                Engine::execute_update(&agent, tx, time_origin, plan, method, arg, n).await
+                Engine::execute_update(agent, tx, time_origin, canister_id, nonce, method, arg, n)
+                    .await
             }
         }
     }
@@ -265,13 +351,13 @@ impl Engine {
         agent: &Agent,
         tx: Sender<CallResult>,
         _time_origin: Instant,
-        plan: &Plan,
+        canister_id: &CanisterId,
         method: String,
         arg: Vec<u8>,
         n: usize,
     ) -> Option<u32> {
         let time_query_start = Instant::now();
-        let response = agent.execute_query(&plan.canister_id, &method, arg).await;
+        let response = agent.execute_query(canister_id, &method, arg).await;
         let time_query_end = Instant::now();
         debug!("Sent query ({}). Response was: {:?}", n, response);
 
@@ -336,17 +422,17 @@ impl Engine {
         agent: &Agent,
         tx: Sender<CallResult>,
         time_origin: Instant,
-        plan: &Plan,
+        canister_id: &CanisterId,
+        nonce: &str,
         method: String,
         arg: Vec<u8>,
         n: usize,
     ) -> bool {
-        let nonce =
-            ic_crypto_sha2::Sha256::hash(&format!("inc {} {}", plan.nonce.clone(), n).into_bytes());
+        let nonce = ic_crypto_sha2::Sha256::hash(&format!("inc {} {}", nonce, n).into_bytes());
         let deadline = Instant::now() + agent.ingress_timeout;
         let (content, request_id) = prepare_update(
             &agent.sender,
-            &plan.canister_id,
+            canister_id,
             method,
             arg,
             nonce.to_vec(),
@@ -357,7 +443,7 @@ impl Engine {
 
         debug!("Sending signed update. request id: {}.", request_id);
 
-        let path = update_path(plan.canister_id);
+        let path = update_path(*canister_id);
         let time_start = std::time::Instant::now();
         debug!(
             "Sending update() call ({}) after {}ms since origin",
@@ -467,7 +553,7 @@ impl Engine {
                     let wait = Engine::wait_ingress_for_counter_canister(
                         agent,
                         request_id.clone(),
-                        &plan.canister_id,
+                        canister_id,
                         deadline,
                     )
                     .await;
@@ -27,6 +27,7 @@ mod engine;
 mod message;
 mod metrics;
 mod plan;
+mod scenario;
 mod stats;
 
 use ic_canister_client::{HttpClient, HttpClientConfig, Sender as AgentSender};
@@ -34,6 +35,8 @@ use ic_canister_client_sender::ed25519_public_key_to_der;
 use ic_config::metrics::{Config as MetricsConfig, Exporter};
 use ic_test_identity::{get_pair, TEST_IDENTITY_KEYPAIR, TEST_IDENTITY_KEYPAIR_HARD_CODED};
 use ic_types::{messages::Blob, CanisterId, PrincipalId, UserId};
+use scenario::Scenario;
+use serde::Serialize;
 use stats::Summary;
 
 #[cfg(build = "debug")]
@@ -59,7 +62,7 @@ fn get_logger() -> slog::Logger {
     )
 }
 
-fn write_output_json(filename: &str, summaries: &[Summary]) -> io::Result<()> {
+fn write_output_json<T: Serialize>(filename: &str, summaries: &[T]) -> io::Result<()> {
     use std::fs::File;
 
     let path = PathBuf::from(filename);
@@ -98,7 +101,7 @@ pub enum ChartSize {
 async fn main() {
     let matches = clap::Command::new("IC workload generator")
         .author("DFINITY team <team@dfinity.org>")
-        .about("The workload generator generate calls at a given rps (-r), for a period of time (-n)")
+        .about("The workload generator generate calls at a given rps (-r), for a period of time (-n), or as described by a scenario file (--scenario)")
         .arg(
             Arg::new("URL")
                 .required(true)
@@ -120,7 +123,7 @@ async fn main() {
         .arg(
             Arg::new("rps")
                 .short('r')
-                .required(true)
+                .required_unless_present("scenario")
                 .takes_value(true)
                 .help("Requests per second to generate. Accepts fractional values, e.g. 1.5 rps."),
         )
@@ -133,6 +136,14 @@ async fn main() {
                        down starting from --rps, and tries to locate\
                        the actual max rps currently possible.")
         )
+        .arg(
+            Arg::new("scenario")
+                .long("scenario")
+                .value_name("FILE")
+                .takes_value(true)
+                .conflicts_with_all(&["rps", "duration", "evaluate-max-rps", "canister", "canister-id", "method", "updates", "call-method", "payload", "payload-size"])
+                .help("Path to a YAML (or JSON, with a .json extension) scenario file describing the canisters to call, and phases with weighted mixes of update and query calls, rate profiles and success criteria. The summary of each phase is printed, and the exit code is non-zero if a phase does not meet its success criteria. See the README for the format.")
+        )
         .arg(
             Arg::new("canister-id")
                 .long("canister-id")
@@ -317,7 +328,9 @@ async fn main() {
         .unwrap()
         .parse::<usize>()
         .unwrap();
-    let rps = matches.value_of("rps").unwrap().parse::<f64>().unwrap();
+    let rps = matches
+        .value_of("rps")
+        .map_or(0f64, |rps| rps.parse::<f64>().unwrap());
     let rpms = (rps * 1000f64).floor() as usize;
 
     let principal_id = matches
@@ -333,6 +346,12 @@ async fn main() {
         }
     };
 
+    let scenario = matches.value_of("scenario").map(|path| {
+        Scenario::load(Path::new(path)).unwrap_or_else(|err| {
+            panic!("Failed to load scenario: {}", err);
+        })
+    });
+
     let log = get_logger();
     let _guard = slog_scope::set_global_logger(log);
 
@@ -473,6 +492,39 @@ async fn main() {
                 eng.wait_for_all_agents_to_be_healthy().await;
             }
 
+            // case insensitive
+            let chart_size = ChartSize::from_str(
+                matches
+                    .value_of("chart-size")
+                    .expect("chart-size option not specified"),
+                true,
+            )
+            .expect("Failed to parse chart-size option.");
+
+            if let Some(scenario) = scenario {
+                let reports =
+                    scenario::run(&eng, &scenario, &nonce, periodic_output, chart_size).await;
+                std::mem::drop(eng);
+                if reports.iter().any(|report| !report.violations.is_empty()) {
+                    exit_code_success = false;
+                }
+
+                if let Some(metrics) = metrics_runtime.take() {
+                    std::mem::drop(metrics);
+                }
+
+                if let Some(filename) = matches.value_of("summary-file") {
+                    if let Err(e) = write_output_json(filename, &reports) {
+                        println!(
+                            "Error while writing the phase reports to file {}: {}",
+                            filename, e
+                        );
+                        exit_code_success = false;
+                    }
+                }
+                return;
+            }
+
             // use id of install canister if no id specified
             let canister_id = if let Some(s) = matches.value_of("canister-id") {
                 let canister_id =
@@ -517,15 +569,6 @@ async fn main() {
                     })
             };
 
-            // case insensitive
-            let chart_size = ChartSize::from_str(
-                matches
-                    .value_of("chart-size")
-                    .expect("chart-size option not specified"),
-                true,
-            )
-            .expect("Failed to parse chart-size option.");
-
             // Hold all summaries so we can serialize them later if needed
             let mut summaries: Vec<Summary> = Vec::new();
 
@@ -0,0 +1,545 @@
+//! Scenarios describe a workload as a sequence of phases. Each phase sends a
+//! weighted mix of update and query calls to one or more canisters at a rate
+//! that follows a rate profile, and may define criteria that the phase's
+//! statistics have to meet for the run to succeed.
+//!
+//! Scenarios are read from YAML files, or from JSON files if the file name
+//! ends in `.json`:
+//!
+//! ```yaml
+//! canisters:
+//!   counter: rwlgt-iiaaa-aaaaa-aaaaa-cai
+//! phases:
+//!   - name: warmup
+//!     duration_secs: 60
+//!     rate: { profile: ramp, from_rps: 1, to_rps: 100 }
+//!     mix:
+//!       - { canister: counter, kind: query, method: read, weight: 9 }
+//!       - canister: counter
+//!         kind: update
+//!         method: write
+//!         payload: { type: random, size: 1KiB }
+//!     success: { min_success_rate: 0.99, max_p99_latency_ms: 2000 }
+//! ```
+use crate::{
+    engine::Engine,
+    plan::EngineCall,
+    stats::{Fact, Summary},
+    ChartSize,
+};
+use byte_unit::Byte;
+use ic_types::{CanisterId, PrincipalId};
+use rand::{distributions::WeightedIndex, prelude::Distribution, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, convert::TryFrom, path::Path, str::FromStr, time::Duration};
+
+// The granularity at which rate profiles are turned into schedules.
+const SCHEDULE_RESOLUTION: Duration = Duration::from_millis(1);
+
+/// How the request rate of a phase changes over its duration.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "profile", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RateProfile {
+    /// A fixed rate.
+    Constant { rps: f64 },
+    /// A rate that changes linearly from `from_rps` to `to_rps`.
+    Ramp { from_rps: f64, to_rps: f64 },
+    /// A rate that starts at `start_rps` and increases by `step_rps` every
+    /// `step_secs`.
+    Step {
+        start_rps: f64,
+        step_rps: f64,
+        step_secs: f64,
+    },
+    /// A rate of `base_rps`, except for `spike_secs` starting at `at_secs`,
+    /// during which it is `spike_rps`.
+    Spike {
+        base_rps: f64,
+        spike_rps: f64,
+        at_secs: f64,
+        spike_secs: f64,
+    },
+}
+
+impl RateProfile {
+    /// Returns the requested rate `t` seconds into a phase lasting
+    /// `duration_secs`.
+    pub fn rps_at(&self, t: f64, duration_secs: f64) -> f64 {
+        let rps = match *self {
+            Self::Constant { rps } => rps,
+            Self::Ramp { from_rps, to_rps } => {
+                from_rps + (to_rps - from_rps) * (t / duration_secs).min(1.)
+            }
+            Self::Step {
+                start_rps,
+                step_rps,
+                step_secs,
+            } => start_rps + step_rps * (t / step_secs).floor(),
+            Self::Spike {
+                base_rps,
+                spike_rps,
+                at_secs,
+                spike_secs,
+            } => {
+                if t >= at_secs && t < at_secs + spike_secs {
+                    spike_rps
+                } else {
+                    base_rps
+                }
+            }
+        };
+        rps.max(0.)
+    }
+
+    /// Returns the offsets from the start of a phase lasting `duration_secs` at
+    /// which requests are issued, so that the requests follow the profile.
+    ///
+    /// The `n`-th request is issued once the rate integrated over the phase so
+    /// far exceeds `n`.
+    pub fn schedule(&self, duration_secs: u64) -> Vec<Duration> {
+        let steps = Duration::from_secs(duration_secs).as_nanos() / SCHEDULE_RESOLUTION.as_nanos();
+        let mut schedule = vec![];
+        let mut requested = 0.;
+        for step in 0..steps as u32 {
+            let t = SCHEDULE_RESOLUTION * step;
+            while requested > schedule.len() as f64 {
+                schedule.push(t);
+            }
+            requested += self.rps_at(t.as_secs_f64(), duration_secs as f64)
+                * SCHEDULE_RESOLUTION.as_secs_f64();
+        }
+        schedule
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let (rates, periods) = match *self {
+            Self::Constant { rps } => (vec![rps], vec![]),
+            Self::Ramp { from_rps, to_rps } => (vec![from_rps, to_rps], vec![]),
+            Self::Step {
+                start_rps,
+                step_secs,
+                ..
+            } => (vec![start_rps], vec![step_secs]),
+            Self::Spike {
+                base_rps,
+                spike_rps,
+                spike_secs,
+                ..
+            } => (vec![base_rps, spike_rps], vec![spike_secs]),
+        };
+        if rates.iter().any(|rps| !rps.is_finite() || *rps < 0.) {
+            return Err("rates must be non-negative".to_string());
+        }
+        if periods.iter().any(|secs| !secs.is_finite() || *secs <= 0.) {
+            return Err("periods must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CallKind {
+    Update,
+    Query,
+}
+
+/// How to generate the argument of a call.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PayloadSpec {
+    /// An empty argument.
+    #[default]
+    Empty,
+    /// A fixed argument, given as a hex string.
+    Hex { value: String },
+    /// An argument of `size` zero bytes, e.g. `4KiB`.
+    Zeros { size: String },
+    /// An argument of `size` random bytes, generated anew for every call.
+    Random { size: String },
+}
+
+#[derive(Clone, Debug)]
+enum Payload {
+    Fixed(Vec<u8>),
+    Random(usize),
+}
+
+impl Payload {
+    fn from_spec(spec: &PayloadSpec) -> Result<Self, String> {
+        let size = |size: &str| {
+            Byte::from_str(size.trim())
+                .map(|size| size.get_bytes() as usize)
+                .map_err(|e| format!("invalid payload size {}: {}", size, e))
+        };
+        Ok(match spec {
+            PayloadSpec::Empty => Self::Fixed(vec![]),
+            PayloadSpec::Hex { value } => {
+                Self::Fixed(hex::decode(value).map_err(|e| format!("invalid hex payload: {}", e))?)
+            }
+            PayloadSpec::Zeros { size: s } => Self::Fixed(vec![0; size(s)?]),
+            PayloadSpec::Random { size: s } => Self::Random(size(s)?),
+        })
+    }
+
+    fn generate(&self) -> Vec<u8> {
+        match self {
+            Self::Fixed(payload) => payload.clone(),
+            Self::Random(size) => {
+                let mut payload = vec![0; *size];
+                rand::thread_rng().fill_bytes(&mut payload);
+                payload
+            }
+        }
+    }
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CallSpec {
+    /// The name of the canister in the scenario's `canisters`.
+    canister: String,
+    kind: CallKind,
+    method: String,
+    #[serde(default = "default_weight")]
+    weight: u32,
+    #[serde(default)]
+    payload: PayloadSpec,
+}
+
+/// Criteria the statistics of a phase have to meet. Criteria that are not
+/// given are not checked.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuccessCriteria {
+    min_requests: Option<u32>,
+    min_success_rate: Option<f64>,
+    max_median_latency_ms: Option<u64>,
+    max_p99_latency_ms: Option<u64>,
+}
+
+impl SuccessCriteria {
+    /// Returns a description of each criterion that `summary` does not meet.
+    pub fn violations(&self, summary: &Summary) -> Vec<String> {
+        let mut violations = vec![];
+        if let Some(min) = self.min_requests {
+            if summary.count() < min {
+                violations.push(format!("{} requests < {}", summary.count(), min));
+            }
+        }
+        if let Some(min) = self.min_success_rate {
+            if summary.success_rate() < min {
+                violations.push(format!(
+                    "success rate {:.4} < {}",
+                    summary.success_rate(),
+                    min
+                ));
+            }
+        }
+        if let Some(max) = self.max_median_latency_ms {
+            let median = summary.median();
+            if median > Duration::from_millis(max) {
+                violations.push(format!(
+                    "median latency {} ms > {} ms",
+                    median.as_millis(),
+                    max
+                ));
+            }
+        }
+        if let Some(max) = self.max_p99_latency_ms {
+            match summary.percentile(99) {
+                Some(p99) if p99 <= Duration::from_millis(max) => {}
+                Some(p99) => {
+                    violations.push(format!("p99 latency {} ms > {} ms", p99.as_millis(), max))
+                }
+                None => violations.push("no successful requests to measure p99".to_string()),
+            }
+        }
+        violations
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PhaseSpec {
+    name: String,
+    duration_secs: u64,
+    rate: RateProfile,
+    mix: Vec<CallSpec>,
+    #[serde(default)]
+    success: SuccessCriteria,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioSpec {
+    /// Canister IDs in text format, by the names calls refer to them with.
+    canisters: BTreeMap<String, String>,
+    phases: Vec<PhaseSpec>,
+}
+
+#[derive(Clone, Debug)]
+struct Call {
+    canister_id: CanisterId,
+    kind: CallKind,
+    method: String,
+    payload: Payload,
+}
+
+/// A weighted mix of calls, from which the call of each request of a phase is
+/// drawn at random.
+#[derive(Clone, Debug)]
+pub struct CallMix {
+    calls: Vec<Call>,
+    weights: WeightedIndex<u32>,
+}
+
+impl CallMix {
+    fn new(canisters: &BTreeMap<String, CanisterId>, specs: &[CallSpec]) -> Result<Self, String> {
+        let calls = specs
+            .iter()
+            .map(|spec| {
+                let canister_id = *canisters
+                    .get(&spec.canister)
+                    .ok_or_else(|| format!("unknown canister {}", spec.canister))?;
+                Ok(Call {
+                    canister_id,
+                    kind: spec.kind,
+                    method: spec.method.clone(),
+                    payload: Payload::from_spec(&spec.payload)?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let weights = WeightedIndex::new(specs.iter().map(|spec| spec.weight))
+            .map_err(|e| format!("invalid call weights: {}", e))?;
+        Ok(Self { calls, weights })
+    }
+
+    /// Draws the next call and returns it along with the canister to send it
+    /// to.
+    pub fn generate_call(&self) -> (CanisterId, EngineCall) {
+        let call = &self.calls[self.weights.sample(&mut rand::thread_rng())];
+        let method = call.method.clone();
+        let arg = call.payload.generate();
+        let engine_call = match call.kind {
+            CallKind::Update => EngineCall::Write { method, arg },
+            CallKind::Query => EngineCall::Read { method, arg },
+        };
+        (call.canister_id, engine_call)
+    }
+}
+
+/// A phase of a scenario, ready to be executed.
+#[derive(Clone, Debug)]
+pub struct Phase {
+    pub name: String,
+    pub duration_secs: u64,
+    pub rate: RateProfile,
+    pub mix: CallMix,
+    pub success: SuccessCriteria,
+}
+
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    pub phases: Vec<Phase>,
+}
+
+impl Scenario {
+    /// Reads a scenario from a YAML or JSON file and checks that it is
+    /// well-formed.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let spec: ScenarioSpec = if path.extension().map_or(false, |ext| ext == "json") {
+            serde_json::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            serde_yaml::from_str(&content).map_err(|e| e.to_string())
+        }
+        .map_err(|e| format!("failed to parse scenario {}: {}", path.display(), e))?;
+        Self::from_spec(spec)
+    }
+
+    fn from_spec(spec: ScenarioSpec) -> Result<Self, String> {
+        let canisters = spec
+            .canisters
+            .into_iter()
+            .map(|(name, id)| {
+                PrincipalId::from_str(&id)
+                    .map_err(|e| e.to_string())
+                    .and_then(|id| CanisterId::try_from(id).map_err(|e| e.to_string()))
+                    .map(|id| (name.clone(), id))
+                    .map_err(|e| format!("invalid id of canister {}: {}", name, e))
+            })
+            .collect::<Result<BTreeMap<_, _>, String>>()?;
+        if spec.phases.is_empty() {
+            return Err("the scenario has no phases".to_string());
+        }
+        let phases = spec
+            .phases
+            .into_iter()
+            .map(|phase| {
+                phase
+                    .rate
+                    .validate()
+                    .and_then(|()| CallMix::new(&canisters, &phase.mix))
+                    .map(|mix| Phase {
+                        name: phase.name.clone(),
+                        duration_secs: phase.duration_secs,
+                        rate: phase.rate,
+                        mix,
+                        success: phase.success,
+                    })
+                    .map_err(|e| format!("phase {}: {}", phase.name, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { phases })
+    }
+}
+
+/// The outcome of a phase of a scenario.
+#[derive(Clone, Debug, Serialize)]
+pub struct PhaseReport {
+    pub name: String,
+    pub summary: Summary,
+    pub success: SuccessCriteria,
+    pub violations: Vec<String>,
+}
+
+/// Executes the phases of `scenario` one after the other, printing the summary
+/// of each phase as it completes, and returns their reports.
+pub async fn run(
+    engine: &Engine,
+    scenario: &Scenario,
+    nonce: &str,
+    periodic_output: bool,
+    chart_size: ChartSize,
+) -> Vec<PhaseReport> {
+    let mut reports = vec![];
+    for phase in &scenario.phases {
+        println!(
+            "Running phase {} for {} seconds, rate = {:?}",
+            phase.name, phase.duration_secs, phase.rate
+        );
+        let schedule = phase.rate.schedule(phase.duration_secs);
+        let facts: Vec<Fact> = engine
+            .execute_phase(
+                &schedule,
+                Duration::from_secs(phase.duration_secs),
+                &phase.mix,
+                // Distinct phases must not send identical update calls.
+                format!("{} {}", nonce, phase.name),
+                periodic_output,
+            )
+            .await;
+        let summary = Summary::from_facts(&facts);
+        let violations = phase.success.violations(&summary);
+        println!("Phase {}", phase.name);
+        println!("{}", summary.clone().with_chart_size(chart_size));
+        reports.push(PhaseReport {
+            name: phase.name.clone(),
+            summary,
+            success: phase.success.clone(),
+            violations,
+        });
+    }
+
+    println!("Scenario");
+    for report in &reports {
+        if report.violations.is_empty() {
+            println!(
+                "  ✔ {}: {} requests, success rate {:.4}",
+                report.name,
+                report.summary.count(),
+                report.summary.success_rate()
+            );
+        } else {
+            println!("  ✘ {}: {}", report.name, report.violations.join(", "));
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedules_follow_the_rate_profiles() {
+        let constant = RateProfile::Constant { rps: 4. }.schedule(3);
+        assert_eq!(constant.len(), 12);
+        let gap = constant[2] - constant[1];
+        assert!(gap >= Duration::from_millis(249) && gap <= Duration::from_millis(251));
+
+        let ramp = RateProfile::Ramp {
+            from_rps: 0.,
+            to_rps: 100.,
+        }
+        .schedule(10);
+        assert!((495..=500).contains(&ramp.len()));
+        let first_half = ramp.iter().filter(|t| t.as_secs() < 5).count();
+        assert!(first_half * 2 < ramp.len() - first_half);
+
+        let step = RateProfile::Step {
+            start_rps: 1.,
+            step_rps: 1.,
+            step_secs: 2.,
+        };
+        assert_eq!(step.rps_at(1.9, 10.), 1.);
+        assert_eq!(step.rps_at(4., 10.), 3.);
+
+        let spike = RateProfile::Spike {
+            base_rps: 1.,
+            spike_rps: 100.,
+            at_secs: 5.,
+            spike_secs: 1.,
+        }
+        .schedule(10);
+        assert!((99..=101).contains(&spike.iter().filter(|t| t.as_secs() == 5).count()));
+        assert!((1..=2).contains(&spike.iter().filter(|t| t.as_secs() == 6).count()));
+
+        assert!(RateProfile::Constant { rps: 0. }.schedule(10).is_empty());
+    }
+
+    #[test]
+    fn parses_and_validates_scenarios() {
+        let spec: ScenarioSpec = serde_yaml::from_str(
+            r#"
+canisters:
+  counter: rwlgt-iiaaa-aaaaa-aaaaa-cai
+phases:
+  - name: warmup
+    duration_secs: 60
+    rate: { profile: ramp, from_rps: 1, to_rps: 100 }
+    mix:
+      - { canister: counter, kind: query, method: read, weight: 9 }
+      - canister: counter
+        kind: update
+        method: write
+        payload: { type: zeros, size: 1KiB }
+    success: { min_success_rate: 0.99, max_p99_latency_ms: 2000 }
+"#,
+        )
+        .unwrap();
+        let mut unknown_canister = spec.clone();
+        unknown_canister.phases[0].mix[0].canister = "other".to_string();
+        let mut zero_weights = spec.clone();
+        for call in &mut zero_weights.phases[0].mix {
+            call.weight = 0;
+        }
+
+        let scenario = Scenario::from_spec(spec).unwrap();
+        assert_eq!(scenario.phases.len(), 1);
+        let phase = &scenario.phases[0];
+        assert_eq!(phase.mix.calls.len(), 2);
+        assert!(matches!(&phase.mix.calls[1].payload, Payload::Fixed(p) if p.len() == 1024));
+
+        assert!(Scenario::from_spec(unknown_canister)
+            .unwrap_err()
+            .contains("unknown canister other"));
+        assert!(Scenario::from_spec(zero_weights).is_err());
+    }
+}
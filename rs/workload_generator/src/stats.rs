@@ -176,6 +176,7 @@ pub struct Summary {
     min: Duration,
     stddev: Duration,
     count: u32,
+    success_count: u32,
     content_length: ContentLength,
     percentiles: Vec<Duration>,
     latency_histogram: Vec<u32>,
@@ -208,6 +209,7 @@ impl Summary {
 
         Summary {
             count,
+            success_count: facts.iter().filter(|f| f.is_succ()).count() as u32,
             content_length,
             status_counts,
             succ_rate_histogram: Summary::get_succ_rate_histogram(facts),
@@ -220,6 +222,31 @@ impl Summary {
         self.content_length
     }
 
+    /// The number of requests the summary covers.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// The fraction of requests that succeeded, or 0 if there were none.
+    pub fn success_rate(&self) -> f64 {
+        if self.count == 0 {
+            0.
+        } else {
+            f64::from(self.success_count) / f64::from(self.count)
+        }
+    }
+
+    /// The median latency of the successful requests.
+    pub fn median(&self) -> Duration {
+        self.median
+    }
+
+    /// The latency below which `p` percent of the successful requests
+    /// completed, if there were any.
+    pub fn percentile(&self, p: usize) -> Option<Duration> {
+        self.percentiles.get(p).cloned()
+    }
+
     pub fn with_chart_size(mut self, size: ChartSize) -> Self {
         self.chart_size = size;
         self
@@ -281,6 +308,7 @@ impl Summary {
             max: Duration::new(0, 0),
             min: Duration::new(0, 0),
             count: 0,
+            success_count: 0,
             content_length: ContentLength::zero(),
             percentiles: vec![Duration::new(0, 0); 100],
             latency_histogram: vec![0; 0],
@@ -334,6 +362,7 @@ impl fmt::Display for Summary {
         writeln!(f, "  Longest:   {} ms", self.max.to_ms())?;
         writeln!(f, "  Shortest:  {} ms", self.min.to_ms())?;
         writeln!(f, "  Requests:  {}", self.count)?;
+        writeln!(f, "  Succeeded: {}", self.success_count)?;
         writeln!(f, "  Data:      {}", self.content_length)?;
         writeln!(f)?;
         writeln!(f, "Status codes:")?;